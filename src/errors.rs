@@ -6,6 +6,24 @@ pub enum HikyakuError {
     OAuth2Error(String),
     #[error("Failed to the google drive process: {0}")]
     GoogleDriveError(String),
+    #[error("Failed to the dropbox process: {0}")]
+    DropboxError(String),
+    #[error("Failed to the box process: {0}")]
+    BoxError(String),
+    #[error("Failed to the onedrive process: {0}")]
+    OneDriveError(String),
+    #[error("Failed to the azure blob process: {0}")]
+    AzureBlobError(String),
+    #[error("Failed to the gcs process: {0}")]
+    GcsError(String),
+    #[error("Failed to the sftp process: {0}")]
+    SftpError(String),
+    #[error("Failed to the webdav process: {0}")]
+    WebDavError(String),
+    #[error("Failed to the http process: {0}")]
+    HttpError(String),
+    #[error("Failed to the custom backend process: {0}")]
+    BackendError(String),
     #[error("Failed to the s3 process: {0}")]
     S3Error(String),
     #[error("Failed to parse: {0}")]
@@ -18,6 +36,8 @@ pub enum HikyakuError {
     EnvCredentialError(String),
     #[error("Connection error: {0}")]
     ConnectionError(String),
+    #[error("Archived object error: {0}")]
+    ArchivedObjectError(String),
     #[error("File object not exist error: {0}")]
     NotExistFileError(String),
     #[error("File operation error: {0}")]
@@ -1,3 +1,8 @@
+// The helpers which the backends share are left unused when some of the backends are disabled.
+#![cfg_attr(not(all(feature = "amazon_s3", feature = "google_drive", feature = "dropbox", feature = "box_storage",
+                    feature = "onedrive", feature = "azure_blob", feature = "google_cloud_storage", feature = "sftp",
+                    feature = "webdav", feature = "http")), allow(dead_code))]
+
 pub mod services;
 pub mod utils;
 pub mod errors;
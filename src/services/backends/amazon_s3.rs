@@ -0,0 +1,522 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use aws_sdk_s3::client::Client;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart, MetadataDirective, TaggingDirective};
+use log::{debug, error, warn};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use tokio::sync::Mutex;
+use tokio::task::{JoinError, JoinSet};
+use crate::errors::HikyakuError::{ArchivedObjectError, InvalidArgumentError, NotExistFileError, S3Error, UnknownError, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{client_for_range, downcast, first_client, ParallelWrites};
+use crate::services::file_system::ChunkData;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::amazon_s3::{apply_copy_source_customer_key, apply_customer_key, apply_encryption, apply_upload_options, encode_tagging, s3_copy_part_size, validate_s3_part_size, S3MultipartUpload, S3ObjectAttributes, S3Options, S3PathKind, S3RestoreStatus, S3StorageClass, MAX_S3_PARTS};
+
+// CopyObject can copy the object up to 5 GiB at once. The larger object needs the multipart copy.
+const MAX_COPY_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+// The characters which are not encoded in the copy source(`bucket/key`).
+const COPY_SOURCE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Clone)]
+pub(crate) struct AmazonS3Backend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) bucket: Arc<String>,
+    pub(crate) key: Arc<String>,
+    pub(crate) options: Arc<S3Options>,
+    pub(crate) path_kind: S3PathKind,
+    pub(crate) attributes: Option<Arc<S3ObjectAttributes>>,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl AmazonS3Backend {
+    /// Get the current restore status of the object by HeadObject.
+    pub(crate) async fn restore_status(&self) -> HikyakuResult<S3RestoreStatus> {
+        let Self {clients, bucket, key, options, ..} = self;
+        let request = first_client(clients)
+            .head_object()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .set_version_id(options.version_id.clone());
+        let response = apply_customer_key!(&options.encryption, request)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to head object: {:#?}", e);
+                S3Error(format!("Failed to get the restore status of s3://{}/{}: {:?}", bucket, key, e))
+            })?;
+
+        Ok(S3RestoreStatus::from_restore_header(
+            response.storage_class().map(|storage_class| storage_class.as_str()),
+            response.restore()))
+    }
+}
+
+impl Display for AmazonS3Backend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AmazonS3: bucket: {}, key: {}, version_id: {:?}, file_size: {:?}", self.bucket, self.key, self.options.version_id, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for AmazonS3Backend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.path_kind == S3PathKind::Prefix,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, bucket, key, options, attributes, ..} = self;
+        // The archived object may be restored after the build, so check the latest status.
+        if attributes.as_ref().is_some_and(|attributes| !attributes.restore_status.is_readable())
+            && !self.restore_status().await?.is_readable() {
+            return Err(ArchivedObjectError(format!("s3://{}/{} is archived and must be restored before downloading", bucket, key)));
+        }
+        let client = client_for_range(clients, start, end);
+
+        let request = client
+            .get_object()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .set_version_id(options.version_id.clone())
+            .range(format!("bytes={}-{}", start, end));
+        let part = apply_customer_key!(&options.encryption, request)
+            .send()
+            .await
+            .inspect(|obj| debug!("{:#?}", obj))
+            .map_err(|e| {
+                error!("Failed to request for getting object: {:#?}", e);
+                if e.code() == Some("InvalidObjectState") {
+                    ArchivedObjectError(format!("s3://{}/{} is archived and must be restored before downloading", bucket, key))
+                } else {
+                    S3Error(format!("{:?}", e))
+                }
+            })?;
+
+        let body = part
+            .body
+            .collect()
+            .await
+            .map_err(|e| {
+                error!("Failed to collect body: {:#?}", e);
+                S3Error(format!("{:?}", e))
+            })?;
+
+        Ok(body.to_vec())
+    }
+
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        self.options.ensure_writable(&self.bucket, &self.key)?;
+
+        Ok(Box::new(AmazonS3WriteSession {
+            backend: self.clone(),
+            chunk_size,
+            multipart_upload: Arc::new(Mutex::new(None)),
+            writes: ParallelWrites::new(self.clients.len()),
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        Err(UnsupportedError(format!("List is not supported yet. File system object: {}", self)))
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        Err(UnsupportedError(format!("Delete is not supported yet. File system object: {}", self)))
+    }
+
+    async fn copy_from(&self, source: &dyn StorageBackend, chunk_size: u64) -> HikyakuResult<()> {
+        let Some(source_backend) = downcast::<AmazonS3Backend>(source) else {
+            return Err(UnsupportedError(format!("Server-side copy is not supported from {} to {}", source, self)));
+        };
+        let Self {clients, bucket, key, options, ..} = self;
+        let Self {bucket: source_bucket, key: source_key, options: source_options, file_size: source_file_size, ..} = source_backend;
+
+        options.ensure_writable(bucket, key)?;
+        let source_file_size = source_file_size
+            .ok_or_else(|| NotExistFileError(format!("Copy source s3://{}/{} does not exist", source_bucket, source_key)))?;
+        let copy_source = utf8_percent_encode(
+            &format!("{}/{}", source_bucket, source_key),
+            COPY_SOURCE_ENCODE_SET).to_string();
+        // The specific version of the source is copied when the source has the version id.
+        let copy_source = match &source_options.version_id {
+            Some(version_id) => format!("{}?versionId={}", copy_source, version_id),
+            None => copy_source,
+        };
+        let client = first_client(clients);
+
+        if source_file_size <= MAX_COPY_OBJECT_SIZE {
+            let request = client
+                .copy_object()
+                .bucket(bucket.as_str())
+                .key(key.as_str())
+                .copy_source(&copy_source)
+                .set_storage_class(options.storage_class.as_ref().map(S3StorageClass::to_sdk))
+                .set_acl(options.upload_options.acl());
+            // The metadata and tags of the source are kept unless the options replace them.
+            let request = if options.upload_options.replaces_metadata() {
+                request
+                    .metadata_directive(MetadataDirective::Replace)
+                    .set_metadata(options.upload_options.metadata())
+                    .content_type(options.upload_options.content_type(key))
+                    .set_content_disposition(options.upload_options.content_disposition())
+                    .set_cache_control(options.upload_options.cache_control())
+            } else {
+                request
+            };
+            let request = if options.upload_options.replaces_tags() {
+                request
+                    .tagging_directive(TaggingDirective::Replace)
+                    .set_tagging(options.upload_options.tagging())
+            } else {
+                request
+            };
+            let request = apply_encryption!(&options.encryption, request);
+            apply_copy_source_customer_key!(&source_options.encryption, request)
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Failed to copy object: {:#?}", e);
+                    S3Error(format!("Failed to copy {} to s3://{}/{}: {:?}", copy_source, bucket, key, e))
+                })?;
+
+            return Ok(());
+        }
+
+        let part_size = s3_copy_part_size(source_file_size, chunk_size)?;
+        self.multipart_copy_s3(source_backend, &copy_source, source_file_size, part_size).await
+    }
+}
+
+impl AmazonS3Backend {
+    /// Put the chunk as the whole object or the part of the multipart upload.
+    ///
+    /// The multipart upload is created by the first part, and completed by the part
+    /// which finishes last after the last part arrived.
+    async fn put_chunk(&self, chunk_data: ChunkData, multipart_upload: Arc<Mutex<Option<S3MultipartUpload>>>) -> HikyakuResult<()> {
+        let Self {clients, bucket, key, options, ..} = self;
+        let offset = chunk_data.get_offset();
+        let client = &clients[(offset % clients.len() as u64) as usize];
+
+        // The whole data fits in one chunk so the multipart upload is not needed.
+        if offset == 0 && chunk_data.is_last() {
+            let request = client
+                .put_object()
+                .bucket(bucket.as_str())
+                .key(key.as_str())
+                .body(ByteStream::from(chunk_data.get_raw_data()))
+                .set_storage_class(options.storage_class.as_ref().map(S3StorageClass::to_sdk));
+            let request = apply_upload_options!(&options.upload_options, key, request);
+            apply_encryption!(&options.encryption, request)
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Failed to put object: {:#?}", e);
+                    S3Error(format!("Failed to put object to s3://{}/{}: {:?}", bucket, key, e))
+                })?;
+
+            return Ok(());
+        }
+
+        let upload_id = {
+            let mut multipart_lock = multipart_upload.lock().await;
+            if multipart_lock.is_none() {
+                let request = client
+                    .create_multipart_upload()
+                    .bucket(bucket.as_str())
+                    .key(key.as_str())
+                    .set_storage_class(options.storage_class.as_ref().map(S3StorageClass::to_sdk));
+                let request = apply_upload_options!(&options.upload_options, key, request);
+                let response = apply_encryption!(&options.encryption, request)
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to create multipart upload: {:#?}", e);
+                        S3Error(format!("Failed to create multipart upload to s3://{}/{}: {:?}", bucket, key, e))
+                    })?;
+                let upload_id = response
+                    .upload_id()
+                    .ok_or_else(|| S3Error(format!("Upload ID is not returned for s3://{}/{}", bucket, key)))?;
+                *multipart_lock = Some(S3MultipartUpload::new(upload_id));
+            }
+
+            // SAFETY: The multipart upload is always Some by the above.
+            multipart_lock.as_ref().unwrap().get_upload_id().to_string()
+        };
+
+        // The part number is 1-based.
+        let part_number = offset as i32 + 1;
+        let is_last = chunk_data.is_last();
+        let request = client
+            .upload_part()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .upload_id(&upload_id)
+            .part_number(part_number)
+            .body(ByteStream::from(chunk_data.get_raw_data()));
+        let response = apply_customer_key!(&options.encryption, request)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to upload part {}: {:#?}", part_number, e);
+                S3Error(format!("Failed to upload part {} to s3://{}/{}: {:?}", part_number, bucket, key, e))
+            })?;
+        let completed_part = CompletedPart::builder()
+            .part_number(part_number)
+            .set_e_tag(response.e_tag().map(String::from))
+            .build();
+
+        let mut multipart_lock = multipart_upload.lock().await;
+        // SAFETY: The multipart upload was set before uploading the part.
+        let upload = multipart_lock.as_mut().unwrap();
+        upload.add_part(completed_part, is_last);
+        if upload.is_completed() {
+            let parts = upload.take_sorted_parts();
+            client
+                .complete_multipart_upload()
+                .bucket(bucket.as_str())
+                .key(key.as_str())
+                .upload_id(&upload_id)
+                .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Failed to complete multipart upload: {:#?}", e);
+                    S3Error(format!("Failed to complete multipart upload to s3://{}/{}: {:?}", bucket, key, e))
+                })?;
+            *multipart_lock = None;
+        }
+
+        Ok(())
+    }
+
+    /// Copy the S3 object part by part with UploadPartCopy. The parts are copied in parallel
+    /// up to the concurrency, and the multipart upload is aborted if any part fails.
+    pub(crate) async fn multipart_copy_s3(&self, source: &AmazonS3Backend, copy_source: &str, source_file_size: u64, part_size: u64) -> HikyakuResult<()> {
+        let Self {clients, bucket, key, options, ..} = self;
+        let Self {bucket: source_bucket, key: source_key, options: source_options, ..} = source;
+        let client = first_client(clients);
+
+        let request = client
+            .create_multipart_upload()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .set_storage_class(options.storage_class.as_ref().map(S3StorageClass::to_sdk))
+            .set_acl(options.upload_options.acl());
+        // UploadPartCopy copies only the data, so the metadata and tags of the source are read
+        // and set to the new object like CopyObject keeps them unless the options replace them.
+        let request = if options.upload_options.replaces_metadata() {
+            request
+                .set_metadata(options.upload_options.metadata())
+                .content_type(options.upload_options.content_type(key))
+                .set_content_disposition(options.upload_options.content_disposition())
+                .set_cache_control(options.upload_options.cache_control())
+        } else {
+            let head_request = client
+                .head_object()
+                .bucket(source_bucket.as_str())
+                .key(source_key.as_str())
+                .set_version_id(source_options.version_id.clone());
+            let head = apply_customer_key!(&source_options.encryption, head_request)
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Failed to head object: {:#?}", e);
+                    S3Error(format!("Failed to get the metadata of s3://{}/{}: {:?}", source_bucket, source_key, e))
+                })?;
+            request
+                .set_metadata(head.metadata().cloned())
+                .set_content_type(head.content_type().map(String::from))
+                .set_content_disposition(head.content_disposition().map(String::from))
+                .set_cache_control(head.cache_control().map(String::from))
+                .set_content_encoding(head.content_encoding().map(String::from))
+                .set_content_language(head.content_language().map(String::from))
+        };
+        let request = if options.upload_options.replaces_tags() {
+            request.set_tagging(options.upload_options.tagging())
+        } else {
+            let tagging = client
+                .get_object_tagging()
+                .bucket(source_bucket.as_str())
+                .key(source_key.as_str())
+                .set_version_id(source_options.version_id.clone())
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Failed to get object tagging: {:#?}", e);
+                    S3Error(format!("Failed to get the tags of s3://{}/{}: {:?}", source_bucket, source_key, e))
+                })?;
+            request.set_tagging(encode_tagging(tagging.tag_set().iter().map(|tag| (tag.key(), tag.value()))))
+        };
+        let response = apply_encryption!(&options.encryption, request)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to create multipart upload: {:#?}", e);
+                S3Error(format!("Failed to create multipart upload to s3://{}/{}: {:?}", bucket, key, e))
+            })?;
+        let upload_id = response
+            .upload_id()
+            .ok_or_else(|| S3Error(format!("Upload ID is not returned for s3://{}/{}", bucket, key)))?
+            .to_string();
+
+        let result = self.copy_s3_parts(source_options, copy_source, &upload_id, source_file_size, part_size).await;
+        let result = match result {
+            Ok(parts) => client
+                .complete_multipart_upload()
+                .bucket(bucket.as_str())
+                .key(key.as_str())
+                .upload_id(&upload_id)
+                .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+                .send()
+                .await
+                .map(|_| ())
+                .map_err(|e| {
+                    error!("Failed to complete multipart copy: {:#?}", e);
+                    S3Error(format!("Failed to complete multipart copy to s3://{}/{}: {:?}", bucket, key, e))
+                }),
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            // Abort the upload not to be charged for the copied parts.
+            let _ = client
+                .abort_multipart_upload()
+                .bucket(bucket.as_str())
+                .key(key.as_str())
+                .upload_id(&upload_id)
+                .send()
+                .await;
+        }
+
+        result
+    }
+
+    /// Copy the ranges of the source to the parts and return them sorted by the part number.
+    async fn copy_s3_parts(&self,
+                           source_options: &Arc<S3Options>,
+                           copy_source: &str,
+                           upload_id: &str,
+                           source_file_size: u64,
+                           part_size: u64) -> HikyakuResult<Vec<CompletedPart>> {
+        let Self {clients, bucket, key, options, ..} = self;
+
+        let mut parts = vec![];
+        let mut tasks = JoinSet::new();
+        for offset in 0..source_file_size.div_ceil(part_size) {
+            if tasks.len() >= clients.len() {
+                parts.push(join_copy_task(tasks.join_next().await)?);
+            }
+
+            let start = offset * part_size;
+            let end = (start + part_size).min(source_file_size) - 1;
+            // The part number is 1-based.
+            let part_number = offset as i32 + 1;
+            let client = clients[(offset % clients.len() as u64) as usize].clone();
+            let (bucket, key, options, source_options) = (bucket.clone(), key.clone(), options.clone(), source_options.clone());
+            let (copy_source, upload_id) = (copy_source.to_string(), upload_id.to_string());
+            tasks.spawn(async move {
+                let request = client
+                    .upload_part_copy()
+                    .bucket(bucket.as_str())
+                    .key(key.as_str())
+                    .upload_id(upload_id)
+                    .part_number(part_number)
+                    .copy_source(copy_source)
+                    .copy_source_range(format!("bytes={}-{}", start, end));
+                let request = apply_customer_key!(&options.encryption, request);
+                let response = apply_copy_source_customer_key!(&source_options.encryption, request)
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to copy part {}: {:#?}", part_number, e);
+                        S3Error(format!("Failed to copy part {} to s3://{}/{}: {:?}", part_number, bucket, key, e))
+                    })?;
+
+                Ok(CompletedPart::builder()
+                    .part_number(part_number)
+                    .set_e_tag(response.copy_part_result().and_then(|result| result.e_tag()).map(String::from))
+                    .build())
+            });
+        }
+        while !tasks.is_empty() {
+            parts.push(join_copy_task(tasks.join_next().await)?);
+        }
+        // CompleteMultipartUpload requires the parts in ascending order.
+        parts.sort_by_key(|part| part.part_number());
+
+        Ok(parts)
+    }
+}
+
+fn join_copy_task(result: Option<Result<HikyakuResult<CompletedPart>, JoinError>>) -> HikyakuResult<CompletedPart> {
+    match result {
+        Some(result) => result.map_err(|e| UnknownError(format!("Copy task is failed: {:?}", e)))?,
+        None => Err(UnknownError("No copy task is running".to_string())),
+    }
+}
+
+/// The upload which puts the parts of the multipart upload in parallel.
+struct AmazonS3WriteSession {
+    backend: AmazonS3Backend,
+    chunk_size: u64,
+    multipart_upload: Arc<Mutex<Option<S3MultipartUpload>>>,
+    writes: ParallelWrites,
+}
+
+#[async_trait]
+impl WriteSession for AmazonS3WriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let offset = chunk_data.get_offset();
+        if !(offset == 0 && chunk_data.is_last()) {
+            if offset >= MAX_S3_PARTS {
+                return Err(InvalidArgumentError(format!(
+                    "s3://{}/{} needs more than {} parts. Please increase the chunk size", self.backend.bucket, self.backend.key, MAX_S3_PARTS)));
+            }
+            validate_s3_part_size(self.chunk_size)?;
+        }
+
+        let backend = self.backend.clone();
+        let multipart_upload = Arc::clone(&self.multipart_upload);
+        self.writes.spawn(async move { backend.put_chunk(chunk_data, multipart_upload).await }).await
+    }
+
+    async fn finish(mut self: Box<Self>) -> HikyakuResult<()> {
+        let result = self.writes.join_all().await;
+        if result.is_err() {
+            self.abort().await;
+        }
+
+        result
+    }
+
+    /// Abort the multipart upload left by the failed upload not to be charged for its parts.
+    async fn abort(mut self: Box<Self>) {
+        // The parts in flight finish before the abort not to be left in the aborted upload.
+        self.writes.cancel().await;
+        let Some(upload) = self.multipart_upload.lock().await.take() else {
+            return;
+        };
+        let AmazonS3Backend {clients, bucket, key, ..} = &self.backend;
+
+        let result = first_client(clients)
+            .abort_multipart_upload()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .upload_id(upload.get_upload_id())
+            .send()
+            .await;
+        if let Err(e) = result {
+            warn!("Failed to abort multipart upload {} of s3://{}/{}: {:?}", upload.get_upload_id(), bucket, key, e);
+        }
+    }
+}
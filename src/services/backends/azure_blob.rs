@@ -0,0 +1,188 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, RequestBuilder, Response};
+use reqwest::header::CONTENT_TYPE;
+use crate::errors::HikyakuError::{AzureBlobError, InvalidArgumentError, NotExistFileError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{client_for_range, first_client, ranged_get};
+use crate::services::file_system::ChunkData;
+use crate::services::file_system_builder::azure_blob::list_blobs;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::azure_blob::{block_id, block_list_body, AzureBlobAuthorizer, AzureBlobEndpoint, AzureBlockUpload, MAX_AZURE_BLOCKS};
+
+#[derive(Clone)]
+pub(crate) struct AzureBlobBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) authorizer: Arc<AzureBlobAuthorizer>,
+    pub(crate) endpoint: Arc<AzureBlobEndpoint>,
+    pub(crate) container: Arc<String>,
+    pub(crate) blob: Arc<String>,
+    pub(crate) is_dir: bool,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for AzureBlobBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AzureBlob: container: {}, blob: {}, is_dir: {}, file_size: {:?}", self.container, self.blob, self.is_dir, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for AzureBlobBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, authorizer, endpoint, container, blob, ..} = self;
+        let client = client_for_range(clients, start, end);
+
+        // `x-ms-range` is signed as one of the `x-ms-` headers, so it is used instead of `Range`.
+        let request = client
+            .get(endpoint.blob_url(container, blob))
+            .header("x-ms-range", format!("bytes={}-{}", start, end));
+
+        ranged_get(authorizer.sign(client, request).await?, start, end, AzureBlobError).await
+    }
+
+    async fn start_write(&self, _chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target az://{}/{} is a directory", self.container, self.blob)));
+        }
+
+        Ok(Box::new(AzureBlobWriteSession {
+            backend: self.clone(),
+            block_upload: None,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {clients, authorizer, endpoint, container, blob, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![blob.to_string()]);
+        }
+
+        let prefix = if blob.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", blob)
+        };
+        let client = first_client(clients);
+
+        let mut paths = vec![];
+        let mut marker = None;
+        loop {
+            let page = list_blobs(client, authorizer, endpoint, container, &prefix, marker.as_deref(), None).await?;
+            paths.extend(page.blobs);
+            // The virtual directories are returned with the trailing delimiter.
+            paths.extend(page.prefixes.iter().map(|prefix| prefix.trim_end_matches('/').to_string()));
+
+            marker = page.next_marker;
+            if marker.is_none() {
+                break;
+            }
+        }
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        let Self {clients, authorizer, endpoint, container, blob, is_dir, file_size, ..} = self;
+        if *is_dir {
+            return Err(InvalidArgumentError(format!("az://{}/{} is a directory which is not a blob", container, blob)));
+        }
+        if file_size.is_none() {
+            return Err(NotExistFileError(format!("az://{}/{} does not exist", container, blob)));
+        }
+
+        let client = first_client(clients);
+        let response = authorizer.send(client, client.delete(endpoint.blob_url(container, blob))).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AzureBlobError(format!("Failed to delete az://{}/{}: {}, {}", container, blob, status, body)));
+        }
+
+        Ok(())
+    }
+}
+
+/// The upload of Azure Blob Storage, by the blocks which are committed after the last one.
+struct AzureBlobWriteSession {
+    backend: AzureBlobBackend,
+    block_upload: Option<AzureBlockUpload>,
+}
+
+#[async_trait]
+impl WriteSession for AzureBlobWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let AzureBlobBackend {clients, authorizer, endpoint, container, blob, ..} = &self.backend;
+        let offset = chunk_data.get_offset();
+        let client = &clients[(offset % clients.len() as u64) as usize];
+        let blob_url = endpoint.blob_url(container, blob);
+
+        // The whole data fits in one chunk so the blocks are not needed.
+        if offset == 0 && chunk_data.is_last() {
+            let request = client
+                .put(blob_url)
+                .header("x-ms-blob-type", "BlockBlob")
+                .body(chunk_data.get_raw_data());
+            azure_blob_request(authorizer, client, request, "put blob").await?;
+            return Ok(());
+        }
+        if offset >= MAX_AZURE_BLOCKS {
+            return Err(InvalidArgumentError(format!(
+                "az://{}/{} needs more than {} blocks. Please increase the chunk size", container, blob, MAX_AZURE_BLOCKS)));
+        }
+
+        // The uncommitted blocks are not visible until the block list is committed.
+        let is_last = chunk_data.is_last();
+        let request = client
+            .put(blob_url.as_str())
+            .query(&[("comp", "block".to_string()), ("blockid", block_id(offset))])
+            .body(chunk_data.get_raw_data());
+        azure_blob_request(authorizer, client, request, "put block").await?;
+
+        let upload = self.block_upload.get_or_insert_with(AzureBlockUpload::new);
+        upload.add_block(offset, is_last);
+        if upload.is_completed() {
+            // SAFETY: The total blocks is always Some when the upload is completed.
+            let total_blocks = upload.get_total_blocks().unwrap();
+            let request = client
+                .put(blob_url.as_str())
+                .query(&[("comp", "blocklist")])
+                .header(CONTENT_TYPE, "application/xml")
+                .body(block_list_body(total_blocks));
+            azure_blob_request(authorizer, client, request, "put block list").await?;
+            self.block_upload = None;
+        }
+
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+        Ok(())
+    }
+}
+
+/// Send the authorized request to Azure Blob Storage and check the status.
+async fn azure_blob_request(authorizer: &AzureBlobAuthorizer, client: &Client, request: RequestBuilder, operation: &str) -> HikyakuResult<Response> {
+    let response = authorizer.send(client, request).await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        debug!("Failed to {} to Azure Blob Storage: {}", operation, body);
+        return Err(AzureBlobError(format!("Failed to {}, Azure Blob Storage returned status code: {}, body: {}", operation, status, body)));
+    }
+
+    Ok(response)
+}
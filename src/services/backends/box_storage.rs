@@ -0,0 +1,351 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use log::{debug, error};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use reqwest::header::{CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use reqwest::multipart::{Form, Part};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use crate::errors::HikyakuError::{BoxError, ConnectionError, InvalidArgumentError, NotExistFileError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{cancel_upload_session, client_for_range, first_client, ranged_get};
+use crate::services::file_system::ChunkData;
+use crate::services::file_system_builder::box_storage::list_folder_items;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::box_storage::{sha1_digest, BoxEndpoint, BoxUpload, BoxUploadPart, BoxUploadPartResponse, BoxUploadSessionResponse};
+
+// The upload session accepts the file from 20 MiB, and the smaller file is uploaded by the upload API.
+const MIN_BOX_SESSION_SIZE: u64 = 20 * 1024 * 1024;
+
+#[derive(Clone)]
+pub(crate) struct BoxBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) endpoint: Arc<BoxEndpoint>,
+    pub(crate) path: Arc<String>,
+    pub(crate) file_or_parent_id: Arc<String>,
+    pub(crate) is_dir: bool,
+    pub(crate) not_exist_folders: Arc<Vec<String>>,
+    pub(crate) upload_filename: Option<Arc<String>>,
+    pub(crate) upload_size: Option<u64>,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for BoxBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Self {path, file_or_parent_id, is_dir, not_exist_folders, upload_filename, file_size, ..} = self;
+        write!(f, "Box: path: {}, file_or_parent_id: {}, is_dir: {}, not_exist_folders: {:?}, upload_filename: {:?}, file_size: {:?}", path, file_or_parent_id, is_dir, not_exist_folders, upload_filename, file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for BoxBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, endpoint, file_or_parent_id, ..} = self;
+        let client = client_for_range(clients, start, end);
+
+        // Box redirects to the download URL and the client follows it with the range.
+        let request = client
+            .get(endpoint.file_content_url(file_or_parent_id))
+            .header(RANGE, format!("bytes={}-{}", start, end));
+
+        ranged_get(request, start, end, BoxError).await
+    }
+
+    async fn start_write(&self, _chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target box://{} is a folder", self.path)));
+        }
+
+        Ok(Box::new(BoxWriteSession {
+            backend: self.clone(),
+            upload: None,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {clients, endpoint, path, file_or_parent_id, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![path.to_string()]);
+        }
+
+        let mut paths = list_folder_items(first_client(clients), endpoint, file_or_parent_id)
+            .await?
+            .into_iter()
+            .map(|item| if path.is_empty() {
+                item.name
+            } else {
+                format!("{}/{}", path, item.name)
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        let Self {clients, endpoint, path, file_or_parent_id, is_dir, file_size, ..} = self;
+        let url = if *is_dir {
+            // The folder is deleted only if it is empty.
+            endpoint.folder_url(file_or_parent_id)
+        } else if file_size.is_some() {
+            endpoint.file_url(file_or_parent_id)
+        } else {
+            return Err(NotExistFileError(format!("box://{} does not exist", path)));
+        };
+
+        let response = first_client(clients)
+            .delete(url)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to request for Box API: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Box API: {:?}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(BoxError(format!("Failed to delete box://{}: {}, {}", path, status, body)));
+        }
+
+        Ok(())
+    }
+}
+
+impl BoxBackend {
+    /// Create the folders which do not exist yet and return the id of the folder to upload the file to.
+    async fn create_box_folders(&self) -> HikyakuResult<String> {
+        let Self {clients, endpoint, file_or_parent_id, not_exist_folders, ..} = self;
+        let client = first_client(clients);
+        let mut parent_id = file_or_parent_id.to_string();
+        for folder_name in not_exist_folders.iter() {
+            let response = client
+                .post(endpoint.folders_url())
+                .json(&json!({"name": folder_name, "parent": {"id": parent_id}}))
+                .send()
+                .await
+                .map_err(|e| BoxError(format!("Failed to send request to create folder {}: {:?}", folder_name, e)))?;
+
+            let status = response.status();
+            let body = response
+                .json::<Value>()
+                .await
+                .map_err(|e| BoxError(format!("Failed to parse response from create folder {}: {:?}", folder_name, e)))?;
+            // The folder can be created by the other upload after the build, so reuse it.
+            let id = if status.is_success() {
+                body["id"].as_str()
+            } else if status == StatusCode::CONFLICT {
+                body["context_info"]["conflicts"][0]["id"].as_str()
+            } else {
+                return Err(BoxError(format!("Failed to create folder {} in {}: {}, {}", folder_name, parent_id, status, body)));
+            };
+            parent_id = id
+                .ok_or_else(|| BoxError(format!("Folder id is not returned for {}", folder_name)))?
+                .to_string();
+        }
+
+        Ok(parent_id)
+    }
+}
+
+/// The upload of Box, by the upload API or the upload session for the large file.
+struct BoxWriteSession {
+    backend: BoxBackend,
+    upload: Option<BoxUpload>,
+}
+
+#[async_trait]
+impl WriteSession for BoxWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let BoxBackend {clients, endpoint, path, upload_filename, upload_size, file_size, file_or_parent_id, ..} = &self.backend;
+        let client = first_client(clients);
+        // The existing file is updated as the new version.
+        let file_id = file_size.map(|_| file_or_parent_id.as_str());
+
+        if self.upload.is_none() {
+            // The single chunk tells the file size even if it is not specified.
+            let total_size = upload_size.or(chunk_data.is_last().then_some(chunk_data.len() as u64));
+            let state = match total_size {
+                Some(total_size) if total_size >= MIN_BOX_SESSION_SIZE => {
+                    let mut request = json!({"file_size": total_size});
+                    if file_id.is_none() {
+                        request["folder_id"] = json!(self.backend.create_box_folders().await?);
+                        request["file_name"] = json!(upload_filename.as_deref());
+                    }
+                    let session = box_request(client.post(endpoint.upload_sessions_url(file_id)).json(&request))
+                        .await?
+                        .json::<BoxUploadSessionResponse>()
+                        .await
+                        .map_err(|e| BoxError(format!("Failed to parse the upload session of box://{}: {:?}", path, e)))?;
+
+                    BoxUpload::Session {
+                        upload_part_url: session.session_endpoints.upload_part,
+                        commit_url: session.session_endpoints.commit,
+                        abort_url: session.session_endpoints.abort,
+                        part_size: session.part_size,
+                        file_size: total_size,
+                        buffer: vec![],
+                        uploaded_size: 0,
+                        parts: vec![],
+                        sha1: Box::new(Sha1::new()),
+                    }
+                },
+                Some(_) => BoxUpload::Buffered(vec![]),
+                // Box needs the size to open the upload session, so the file of many chunks is refused
+                // before it is buffered in memory.
+                None => return Err(InvalidArgumentError(format!(
+                    "Box needs the file size to upload box://{} in the chunks. Please set it by `set_upload_size`.", path))),
+            };
+            self.upload = Some(state);
+        }
+
+        let is_last = chunk_data.is_last();
+        // SAFETY: The upload state is always Some by the above.
+        match self.upload.as_mut().unwrap() {
+            BoxUpload::Buffered(buffer) => {
+                buffer.extend_from_slice(chunk_data.get_data());
+                // The file of the single chunk has no upload size.
+                let buffered_size = buffer.len() as u64;
+                if let Some(upload_size) = *upload_size {
+                    if buffered_size > upload_size || (is_last && buffered_size != upload_size) {
+                        return Err(InvalidArgumentError(format!(
+                            "The upload size is set to {} bytes but {} bytes are uploaded", upload_size, buffered_size)));
+                    }
+                }
+                if !is_last {
+                    return Ok(());
+                }
+
+                let data = std::mem::take(buffer);
+                let attributes = match file_id {
+                    Some(_) => json!({}),
+                    None => json!({
+                        "name": upload_filename.as_deref(),
+                        "parent": {"id": self.backend.create_box_folders().await?},
+                    }),
+                };
+                let filename = upload_filename.as_deref().map_or("file", |name| name.as_str());
+                let file = Part::bytes(data)
+                    .file_name(filename.to_string())
+                    .mime_str("application/octet-stream")
+                    .map_err(|e| BoxError(format!("Failed to build the upload of box://{}: {:?}", path, e)))?;
+                // Box reads the attributes before the file, so the order of the parts matters.
+                let form = Form::new()
+                    .text("attributes", attributes.to_string())
+                    .part("file", file);
+                box_request(client.post(endpoint.upload_url(file_id)).multipart(form)).await?;
+            },
+            BoxUpload::Session {
+                upload_part_url,
+                commit_url,
+                part_size,
+                file_size,
+                buffer,
+                uploaded_size,
+                parts,
+                sha1,
+                ..} => {
+                sha1.update(chunk_data.get_data());
+                buffer.extend_from_slice(chunk_data.get_data());
+
+                // Every part except the last must have the part size which the session decided.
+                while buffer.len() as u64 >= *part_size || (is_last && !buffer.is_empty()) {
+                    let size = (*part_size).min(buffer.len() as u64);
+                    let part = buffer.drain(..size as usize).collect::<Vec<_>>();
+                    let response = box_request(client
+                        .put(upload_part_url.as_str())
+                        .header("Digest", sha1_digest(&part))
+                        .header(CONTENT_TYPE, "application/octet-stream")
+                        .header(CONTENT_RANGE, format!("bytes {}-{}/{}", *uploaded_size, *uploaded_size + size - 1, file_size))
+                        .body(part)).await?;
+                    let part = response
+                        .json::<BoxUploadPartResponse>()
+                        .await
+                        .map_err(|e| BoxError(format!("Failed to parse the uploaded part of box://{}: {:?}", path, e)))?;
+                    parts.push(part.part);
+                    *uploaded_size += size;
+                }
+
+                if !is_last {
+                    return Ok(());
+                }
+                if uploaded_size != file_size {
+                    return Err(InvalidArgumentError(format!(
+                        "The upload size is set to {} bytes but {} bytes are uploaded", file_size, uploaded_size)));
+                }
+
+                let digest = format!("sha={}", STANDARD.encode(sha1.clone().finalize()));
+                commit_box_session(client, commit_url, &digest, parts).await?;
+            },
+        }
+        self.upload = None;
+
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) {
+        if let Some(BoxUpload::Session {abort_url, ..}) = &self.upload {
+            cancel_upload_session(first_client(&self.backend.clients), abort_url).await;
+        }
+    }
+}
+
+/// Send the request to Box and check the status.
+async fn box_request(request: RequestBuilder) -> HikyakuResult<Response> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for Box API: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Box API: {:?}", e))
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        debug!("Failed to upload data to Box: {}", body);
+        return Err(BoxError(format!("Box API returned status code: {}, body: {}", status, body)));
+    }
+
+    Ok(response)
+}
+
+/// Commit the upload session with the uploaded parts.
+///
+/// Box may return `202 Accepted` while it is still processing the parts,
+/// then the commit is retried after the `Retry-After` seconds.
+async fn commit_box_session(client: &Client, commit_url: &str, digest: &str, parts: &[BoxUploadPart]) -> HikyakuResult<()> {
+    loop {
+        let response = box_request(client
+            .post(commit_url)
+            .header("Digest", digest)
+            .json(&json!({"parts": parts}))).await?;
+
+        if response.status() != StatusCode::ACCEPTED {
+            return Ok(());
+        }
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(1);
+        debug!("Box is processing the upload session. Retry the commit after {} seconds", retry_after);
+        tokio::time::sleep(std::time::Duration::from_secs(retry_after)).await;
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::{debug, error, warn};
+use reqwest::{Client, Response};
+use reqwest::header::{CONTENT_TYPE, RANGE};
+use serde_json::{json, Value};
+use crate::errors::HikyakuError::{ConnectionError, DropboxError, InvalidArgumentError, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{client_for_range, first_client, ranged_get};
+use crate::services::file_system::ChunkData;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::dropbox::{to_api_arg, DropboxEndpoint, DropboxListFolderResponse, DropboxUploadSession, DropboxUploadSessionStartResponse, DROPBOX_API_ARG};
+
+// Dropbox accepts up to 150 MiB in a request of the upload and the upload session.
+const MAX_DROPBOX_UPLOAD_SIZE: u64 = 150 * 1024 * 1024;
+
+#[derive(Clone)]
+pub(crate) struct DropboxBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) endpoint: Arc<DropboxEndpoint>,
+    pub(crate) path: Arc<String>,
+    pub(crate) is_dir: bool,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for DropboxBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Dropbox: path: {}, is_dir: {}, file_size: {:?}", self.path, self.is_dir, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for DropboxBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, endpoint, path, ..} = self;
+        let client = client_for_range(clients, start, end);
+
+        let request = client
+            .post(endpoint.content_url("files/download"))
+            .header(DROPBOX_API_ARG, to_api_arg(&json!({"path": path.as_str()})))
+            .header(RANGE, format!("bytes={}-{}", start, end));
+
+        ranged_get(request, start, end, DropboxError).await
+    }
+
+    async fn start_write(&self, _chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target {} is a folder", self.path)));
+        }
+
+        Ok(Box::new(DropboxWriteSession {
+            backend: self.clone(),
+            upload_session: None,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {clients, endpoint, path, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![path.to_string()]);
+        }
+
+        let client = first_client(clients);
+        let response = client
+            .post(endpoint.api_url("files/list_folder"))
+            .json(&json!({"path": path.as_str(), "recursive": false}))
+            .send()
+            .await;
+        let mut page = parse_dropbox_list(response, path).await?;
+
+        let mut paths = vec![];
+        loop {
+            paths.extend(page
+                .entries
+                .iter()
+                .filter(|entry| !entry.is_deleted())
+                .filter_map(|entry| entry.get_path_display().map(String::from)));
+            if !page.has_more {
+                break;
+            }
+
+            let response = client
+                .post(endpoint.api_url("files/list_folder/continue"))
+                .json(&json!({"cursor": page.cursor}))
+                .send()
+                .await;
+            page = parse_dropbox_list(response, path).await?;
+        }
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        Err(UnsupportedError(format!("Delete is not supported yet. File system object: {}", self)))
+    }
+}
+
+async fn parse_dropbox_list(response: reqwest::Result<Response>, path: &str) -> HikyakuResult<DropboxListFolderResponse> {
+    let response = response.map_err(|e| {
+        error!("Failed to request for Dropbox API: {:#?}", e);
+        ConnectionError(format!("Failed to send request to Dropbox API: {:?}", e))
+    })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(DropboxError(format!("Failed to list folder {}: {}, {}", path, status, body)));
+    }
+
+    response
+        .json::<DropboxListFolderResponse>()
+        .await
+        .map_err(|e| DropboxError(format!("Failed to parse response from Dropbox API: {:?}", e)))
+}
+
+/// Send the data to the Dropbox content endpoint with the JSON arguments in the header.
+async fn dropbox_upload(client: &Client, url: &str, arg: &Value, data: Vec<u8>) -> HikyakuResult<Response> {
+    let response = client
+        .post(url)
+        .header(DROPBOX_API_ARG, to_api_arg(arg))
+        .header(CONTENT_TYPE, "application/octet-stream")
+        .body(data)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for Dropbox API: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Dropbox API: {:?}", e))
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        debug!("Failed to upload data to Dropbox: {}", body);
+        return Err(DropboxError(format!("Failed to upload to {}: {}, {}", url, status, body)));
+    }
+
+    Ok(response)
+}
+
+/// The upload of Dropbox, by the upload session if the file has more than one chunk.
+struct DropboxWriteSession {
+    backend: DropboxBackend,
+    upload_session: Option<DropboxUploadSession>,
+}
+
+#[async_trait]
+impl WriteSession for DropboxWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let DropboxBackend {clients, endpoint, path, ..} = &self.backend;
+        if chunk_data.len() as u64 > MAX_DROPBOX_UPLOAD_SIZE {
+            return Err(InvalidArgumentError(format!(
+                "Dropbox accepts up to {} bytes per request but the chunk has {} bytes", MAX_DROPBOX_UPLOAD_SIZE, chunk_data.len())));
+        }
+
+        let client = first_client(clients);
+        let commit = json!({
+            "path": path.as_str(),
+            "mode": "overwrite",
+            "autorename": false,
+        });
+
+        // The whole data fits in one chunk so the upload session is not needed.
+        if chunk_data.get_offset() == 0 && chunk_data.is_last() {
+            dropbox_upload(client, &endpoint.content_url("files/upload"), &commit, chunk_data.get_raw_data()).await?;
+            return Ok(());
+        }
+
+        let size = chunk_data.len() as u64;
+        match self.upload_session.as_mut() {
+            None => {
+                let response = dropbox_upload(
+                    client,
+                    &endpoint.content_url("files/upload_session/start"),
+                    &json!({"close": false}),
+                    chunk_data.get_raw_data()).await?;
+                let session = response
+                    .json::<DropboxUploadSessionStartResponse>()
+                    .await
+                    .map_err(|e| DropboxError(format!("Failed to parse the upload session of {}: {:?}", path, e)))?;
+                self.upload_session = Some(DropboxUploadSession::new(&session.session_id, size));
+            },
+            Some(session) => {
+                let cursor = json!({
+                    "session_id": session.get_session_id(),
+                    "offset": session.get_offset(),
+                });
+                if chunk_data.is_last() {
+                    dropbox_upload(
+                        client,
+                        &endpoint.content_url("files/upload_session/finish"),
+                        &json!({"cursor": cursor, "commit": commit}),
+                        chunk_data.get_raw_data()).await?;
+                    self.upload_session = None;
+                } else {
+                    dropbox_upload(
+                        client,
+                        &endpoint.content_url("files/upload_session/append_v2"),
+                        &json!({"cursor": cursor, "close": false}),
+                        chunk_data.get_raw_data()).await?;
+                    session.add_offset(size);
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) {
+        // Dropbox has no API to cancel the upload session, so the session is dropped
+        // without the commit and the appended data expires with it.
+        if let Some(session) = &self.upload_session {
+            warn!("The upload session {} of {} is left until it expires", session.get_session_id(), self.backend.path);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::{debug, error};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use reqwest::header::{CONTENT_TYPE, RANGE};
+use serde_json::json;
+use tokio::sync::Mutex;
+use crate::errors::HikyakuError::{ConnectionError, GcsError, InvalidArgumentError, NotExistFileError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{cancel_upload_session, client_for_range, first_client, put_resumable_chunk, ranged_get};
+use crate::services::file_system::ChunkData;
+use crate::services::file_system_builder::google_cloud_storage::list_objects;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::google_cloud_storage::{calculate_checksums, encode_crc32c, verify_checksums, GcsDownloadChecksum, GcsEndpoint, GcsObject, GcsResumableUpload, RESUMABLE_CHUNK_UNIT};
+
+#[derive(Clone)]
+pub(crate) struct GcsBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) endpoint: Arc<GcsEndpoint>,
+    pub(crate) bucket: Arc<String>,
+    pub(crate) object: Arc<String>,
+    pub(crate) is_dir: bool,
+    pub(crate) crc32c: Option<u32>,
+    pub(crate) download_checksum: Arc<Mutex<GcsDownloadChecksum>>,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for GcsBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GoogleCloudStorage: bucket: {}, object: {}, is_dir: {}, file_size: {:?}", self.bucket, self.object, self.is_dir, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for GcsBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, endpoint, bucket, object, crc32c, download_checksum, file_size, ..} = self;
+        let client = client_for_range(clients, start, end);
+
+        let request = client
+            .get(endpoint.object_url(bucket, object))
+            .query(&[("alt", "media")])
+            .header(RANGE, format!("bytes={}-{}", start, end));
+        let bytes = ranged_get(request, start, end, GcsError).await?;
+
+        // The range which completes the object is held back when the object is corrupted.
+        if let (Some(expected), Some(file_size)) = (crc32c, file_size) {
+            let actual = download_checksum.lock().await.add_part(start, &bytes, *file_size);
+            if let Some(actual) = actual.filter(|actual| actual != expected) {
+                error!("CRC32C of gs://{}/{} does not match", bucket, object);
+                return Err(GcsError(format!(
+                    "CRC32C of gs://{}/{} does not match: expected {}, actual {}",
+                    bucket, object, encode_crc32c(*expected), encode_crc32c(actual))));
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target gs://{}/{} is a directory", self.bucket, self.object)));
+        }
+
+        Ok(Box::new(GcsWriteSession {
+            backend: self.clone(),
+            chunk_size,
+            resumable_upload: None,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {clients, endpoint, bucket, object, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![object.to_string()]);
+        }
+
+        let prefix = if object.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", object)
+        };
+        let client = first_client(clients);
+
+        let mut paths = vec![];
+        let mut page_token = None;
+        loop {
+            let page = list_objects(client, endpoint, bucket, &prefix, page_token.as_deref(), None).await?;
+            // The placeholder object of the directory which the console creates has the name of the prefix.
+            paths.extend(page.items.into_iter().map(|item| item.name).filter(|name| name != &prefix));
+            paths.extend(page.prefixes.iter().map(|prefix| prefix.trim_end_matches('/').to_string()));
+
+            page_token = page.next_page_token;
+            if page_token.is_none() {
+                break;
+            }
+        }
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        let Self {clients, endpoint, bucket, object, is_dir, file_size, ..} = self;
+        if *is_dir {
+            return Err(InvalidArgumentError(format!("gs://{}/{} is a directory which is not an object", bucket, object)));
+        }
+        if file_size.is_none() {
+            return Err(NotExistFileError(format!("gs://{}/{} does not exist", bucket, object)));
+        }
+
+        let response = first_client(clients)
+            .delete(endpoint.object_url(bucket, object))
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to request for Cloud Storage: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Cloud Storage: {:?}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GcsError(format!("Failed to delete gs://{}/{}: {}, {}", bucket, object, status, body)));
+        }
+
+        Ok(())
+    }
+}
+
+/// The upload of Cloud Storage, by the resumable upload if the file has more than one chunk.
+struct GcsWriteSession {
+    backend: GcsBackend,
+    chunk_size: u64,
+    resumable_upload: Option<GcsResumableUpload>,
+}
+
+#[async_trait]
+impl WriteSession for GcsWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let GcsBackend {clients, endpoint, bucket, object, ..} = &self.backend;
+        let client = first_client(clients);
+
+        // The whole data fits in one chunk so the resumable upload is not needed.
+        if chunk_data.get_offset() == 0 && chunk_data.is_last() {
+            let (crc32c, md5_hash) = calculate_checksums(chunk_data.get_data());
+            let request = client
+                .post(endpoint.upload_url(bucket))
+                .query(&[("uploadType", "media"), ("name", object.as_str())])
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .body(chunk_data.get_raw_data());
+            let response = gcs_request(request).await?;
+            return verify_gcs_upload(client, endpoint, bucket, object, response, crc32c, &md5_hash).await;
+        }
+
+        if self.resumable_upload.is_none() {
+            if !self.chunk_size.is_multiple_of(RESUMABLE_CHUNK_UNIT) {
+                return Err(InvalidArgumentError(format!(
+                    "The chunk size of the resumable upload must be a multiple of {} bytes but it is {} bytes", RESUMABLE_CHUNK_UNIT, self.chunk_size)));
+            }
+
+            let request = client
+                .post(endpoint.upload_url(bucket))
+                .query(&[("uploadType", "resumable"), ("name", object.as_str())])
+                .json(&json!({"name": object.as_str()}));
+            let response = gcs_request(request).await?;
+            let session_url = response
+                .headers()
+                .get("Location")
+                .and_then(|location| location.to_str().ok())
+                .ok_or_else(|| GcsError(format!("Failed to get resumable URL for gs://{}/{}: Location header is not found", bucket, object)))?;
+            self.resumable_upload = Some(GcsResumableUpload::new(session_url));
+        }
+
+        // SAFETY: The resumable upload is always Some by the above.
+        let upload = self.resumable_upload.as_mut().unwrap();
+        let session_url = upload.get_session_url().to_string();
+        let start = upload.get_uploaded_size();
+        let is_last = chunk_data.is_last();
+        upload.add_chunk(chunk_data.get_data());
+
+        let res = put_resumable_chunk(client, &session_url, chunk_data, start)
+            .await
+            .map_err(|e| {
+                error!("Failed to request for Cloud Storage: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Cloud Storage: {:?}", e))
+            })?;
+        if !res.status().is_success() && res.status() != StatusCode::PERMANENT_REDIRECT {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(GcsError(format!("Cloud Storage returned status code: {}, body: {}", status, body)));
+        }
+        if !is_last {
+            return Ok(());
+        }
+
+        // SAFETY: The resumable upload is always Some by the above.
+        let (crc32c, md5_hash) = self.resumable_upload.take().unwrap().finalize();
+        verify_gcs_upload(client, endpoint, bucket, object, res, crc32c, &md5_hash).await
+    }
+
+    async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) {
+        if let Some(upload) = &self.resumable_upload {
+            cancel_upload_session(first_client(&self.backend.clients), upload.get_session_url()).await;
+        }
+    }
+}
+
+/// Send the request to Cloud Storage and check the status.
+async fn gcs_request(request: RequestBuilder) -> HikyakuResult<Response> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for Cloud Storage: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Cloud Storage: {:?}", e))
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        debug!("Failed to upload data to Cloud Storage: {}", body);
+        return Err(GcsError(format!("Cloud Storage returned status code: {}, body: {}", status, body)));
+    }
+
+    Ok(response)
+}
+
+/// Verify the checksums of the uploaded object with the ones of the sent data.
+/// The corrupted object is removed not to be read as the valid one.
+async fn verify_gcs_upload(client: &Client,
+                           endpoint: &GcsEndpoint,
+                           bucket: &str,
+                           object: &str,
+                           response: Response,
+                           crc32c: u32,
+                           md5_hash: &str) -> HikyakuResult<()> {
+    let uploaded = response
+        .json::<GcsObject>()
+        .await
+        .map_err(|e| GcsError(format!("Failed to parse the metadata of gs://{}/{}: {:?}", bucket, object, e)))?;
+
+    if let Err(e) = verify_checksums(&uploaded, crc32c, md5_hash) {
+        error!("The uploaded gs://{}/{} is corrupted: {}", bucket, object, e);
+        let _ = client.delete(endpoint.object_url(bucket, object)).send().await;
+        return Err(e);
+    }
+
+    Ok(())
+}
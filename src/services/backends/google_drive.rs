@@ -0,0 +1,396 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::{debug, error};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, RANGE};
+use serde_json::json;
+use crate::errors::HikyakuError::{ConnectionError, GoogleDriveError, InvalidArgumentError, UnsupportedError};
+use crate::errors::{HikyakuError, HikyakuResult};
+use crate::services::backends::{cancel_upload_session, client_for_range, downcast, first_client, put_resumable_chunk, ranged_get, ResponseStream};
+use crate::services::file_system::ChunkData;
+use crate::services::storage_backend::{BackendStat, ReadStream, StorageBackend, WriteSession};
+use crate::types::google_drive::{FileId, GoogleDriveConversion, GoogleDriveEndpoint, GoogleDriveExport, GoogleDriveShortcut};
+use crate::utils::credential::oauth2_tokens::OAuth2Tokens;
+use crate::utils::file_type::FileType;
+use crate::utils::reqwest::AuthType::Bearer;
+use crate::utils::reqwest::get_client_with_token;
+
+#[derive(Clone)]
+pub(crate) struct GoogleDriveBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) google_drive_token: Arc<OAuth2Tokens>,
+    pub(crate) endpoint: Arc<GoogleDriveEndpoint>,
+    pub(crate) queryable_file_or_parent_id: Arc<String>,
+    pub(crate) not_exist_file_paths: Arc<Vec<String>>,
+    pub(crate) upload_filename: Option<Arc<String>>,
+    pub(crate) mime_type: Arc<String>,
+    pub(crate) export: Option<Arc<GoogleDriveExport>>,
+    pub(crate) shortcut: Option<Arc<GoogleDriveShortcut>>,
+    pub(crate) conversion: Option<Arc<GoogleDriveConversion>>,
+    pub(crate) upload_size: Option<u64>,
+    pub(crate) is_dir: bool,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for GoogleDriveBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let export_mime_type = self.export.as_ref().map(|export| export.file_type.mime());
+        let shortcut_target_id = self.shortcut.as_ref().map(|shortcut| shortcut.target_id.as_str());
+        let conversion_mime_type = self.conversion.as_ref().map(|conversion| conversion.target.mime());
+        write!(f, "GoogleDrive: queryable_file_or_parent_id: {}, not_exist_file_paths: {:?}, upload_filename: {:?}, mime_type: {}, export_mime_type: {:?}, shortcut_target_id: {:?}, conversion_mime_type: {:?}, file_size: {:?}",
+               self.queryable_file_or_parent_id, self.not_exist_file_paths, self.upload_filename, self.mime_type, export_mime_type, shortcut_target_id, conversion_mime_type, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for GoogleDriveBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            // The exported size of Google Workspace document is unknown until the export.
+            is_streamed: self.export.is_some(),
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, google_drive_token, endpoint, queryable_file_or_parent_id, ..} = self;
+        let client = client_for_range(clients, start, end);
+        let url = endpoint.file_url(queryable_file_or_parent_id);
+
+        let request = client
+            .get(url)
+            .header(AUTHORIZATION, format!("Bearer {}", google_drive_token.get_access_token()))
+            .header(RANGE, format!("bytes={}-{}", start, end))
+            .query(&[
+                ("alt", "media"),
+                ("supportsAllDrives", "true"),
+            ]);
+
+        ranged_get(request, start, end, GoogleDriveError).await
+    }
+
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.export.is_some() {
+            return Err(UnsupportedError(format!("Google Workspace document cannot be overwritten. File system object: {}", self)));
+        }
+        if self.shortcut.is_some() {
+            return Err(UnsupportedError(format!("Google Drive shortcut cannot be overwritten. File system object: {}", self)));
+        }
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target is a folder. File system object: {}", self)));
+        }
+        if self.upload_filename.is_none() {
+            return Err(InvalidArgumentError(
+                "The upload filename is not specified".to_string()));
+        }
+        if let (Some(conversion), Some(upload_size)) = (&self.conversion, self.upload_size) {
+            if upload_size > conversion.size_limit() {
+                return Err(conversion_size_error(self, conversion));
+            }
+        }
+
+        Ok(Box::new(GoogleDriveWriteSession {
+            backend: self.clone(),
+            chunk_size,
+            resumable_url: None,
+            uploaded_size: 0,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        Err(UnsupportedError(format!("List is not supported yet. File system object: {}", self)))
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        Err(UnsupportedError(format!("Delete is not supported yet. File system object: {}", self)))
+    }
+
+    /// The size of the exported Google Workspace document is unknown, so it is streamed.
+    async fn open_stream(&self) -> HikyakuResult<Box<dyn ReadStream>> {
+        let res = self.export_response().await?;
+
+        Ok(Box::new(ResponseStream::new(res, None, GoogleDriveError)))
+    }
+
+    /// The kept shortcut is copied as the shortcut to the same target.
+    async fn copy_from(&self, source: &dyn StorageBackend, _chunk_size: u64) -> HikyakuResult<()> {
+        let Some(shortcut) = downcast::<GoogleDriveBackend>(source).and_then(|source| source.shortcut.as_ref()) else {
+            return Err(UnsupportedError(format!("Server-side copy is not supported from {} to {}", source, self)));
+        };
+        let Self {google_drive_token, endpoint, upload_filename, ..} = self;
+
+        let filename = upload_filename
+            .as_ref()
+            .ok_or_else(|| InvalidArgumentError("The upload filename is not specified".to_string()))?;
+        let parent_id = self.create_google_drive_parent_dirs().await?;
+
+        let mut metadata = json!({
+            "name": filename.as_str(),
+            "mimeType": FileType::GoogleShortcut.mime(),
+            "shortcutDetails": {"targetId": shortcut.target_id},
+        });
+        if !parent_id.is_empty() {
+            metadata["parents"] = json!([parent_id]);
+        }
+
+        let client = get_client_with_token(google_drive_token.get_access_token(), Bearer)?;
+        let response = client
+            .post(endpoint.files_url())
+            .json(&metadata)
+            .query(&[("supportsAllDrives", "true")])
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to request for Google Drive API: {:#?}", e);
+                ConnectionError(format!("Failed to send request to create shortcut {}: {:?}", filename, e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(GoogleDriveError(format!("Failed to create shortcut {}: {}, body: {}", filename, status, body)));
+        }
+
+        Ok(())
+    }
+}
+
+impl GoogleDriveBackend {
+    /// Request the Google Workspace document exported to the type of the export.
+    ///
+    /// The document over the size limit of `files.export` is requested from the link of `exportLinks`.
+    async fn export_response(&self) -> HikyakuResult<Response> {
+        let Self {clients, google_drive_token, endpoint, queryable_file_or_parent_id, export: Some(export), ..} = self else {
+            return Err(GoogleDriveError(format!("File system object is not Google Workspace document. File system object: {}", self)));
+        };
+        let client = first_client(clients);
+        let bearer = format!("Bearer {}", google_drive_token.get_access_token());
+
+        let res = send_google_drive_request(client
+            .get(endpoint.export_url(queryable_file_or_parent_id))
+            .header(AUTHORIZATION, &bearer)
+            .query(&[("mimeType", export.file_type.mime())]))
+            .await?;
+        if res.status().is_success() {
+            return Ok(res);
+        }
+
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        if status != StatusCode::FORBIDDEN || !body.contains("exportSizeLimitExceeded") {
+            error!("Failed to export Google Workspace document: {}, body: {}", status, body);
+            return Err(GoogleDriveError(format!("Google Drive API returned status code: {}, body: {}", status, body)));
+        }
+
+        let export_link = export.export_link.as_ref().ok_or_else(|| GoogleDriveError(format!(
+            "The document is over the export size limit and has no export link to {}. File system object: {}", export.file_type.mime(), self)))?;
+        let res = send_google_drive_request(client.get(export_link).header(AUTHORIZATION, &bearer)).await?;
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            error!("Failed to download from export link: {}, body: {}", status, body);
+            return Err(GoogleDriveError(format!("Export link returned status code: {}, body: {}", status, body)));
+        }
+
+        Ok(res)
+    }
+
+    /// Create the folders of the path which do not exist on Google Drive yet.
+    ///
+    /// # Returns
+    ///
+    /// `HikyakuResult<String>` - The id of the folder which the file is created in("" for My Drive root).
+    async fn create_google_drive_parent_dirs(&self) -> HikyakuResult<String> {
+        let Self {queryable_file_or_parent_id, not_exist_file_paths, ..} = self;
+        if not_exist_file_paths.is_empty() {
+            return Ok(queryable_file_or_parent_id.to_string());
+        }
+
+        let mut parent_id = if queryable_file_or_parent_id.is_empty() {
+            None
+        } else {
+            Some(queryable_file_or_parent_id.as_str().to_string())
+        };
+        for dir_name in not_exist_file_paths.iter() {
+            let created_parent_id = self.create_dir(dir_name, &parent_id).await?;
+            parent_id = Some(created_parent_id);
+        }
+
+        Ok(parent_id.unwrap_or("".to_string()))
+    }
+
+    async fn create_dir(&self, dir_name: &str, parent_id: &Option<String>) -> HikyakuResult<String> {
+        let Self {google_drive_token, endpoint, ..} = self;
+        let access_token = google_drive_token.get_access_token();
+        // TODO: We should check if the client should create newly or use generated client from performance.
+        let client = get_client_with_token(access_token, Bearer)?;
+        let mut metadata = json!({
+            "name": dir_name,
+            "mimeType": FileType::GoogleDriveFolder.mime(),
+        });
+
+        if let Some(parent_id) = parent_id {
+            metadata["parents"] = json!([parent_id.to_string()]);
+        }
+
+        let response = client
+            .post(endpoint.files_url())
+            .header("Content-Type", "application/json")
+            .json(&metadata)
+            .query(&[("supportsAllDrives", "true")])
+            .send()
+            .await
+            .map_err(|e| {
+                GoogleDriveError(format!("Failed to send request to create directory {}: {:?}", dir_name, e))
+            })?;
+
+        if !response.status().is_success() {
+            let parent_id = parent_id.as_ref().map_or("RootDir", |id| id.as_str());
+            return Err(GoogleDriveError(format!("Failed to create directory {} in {}: {:?}", dir_name, parent_id, response.status())));
+        }
+
+        let file_info = response
+            .json::<FileId>()
+            .await
+            .map_err(|e| {
+                GoogleDriveError(format!("Failed to parse response to id from create directory {}: {:?}", dir_name, e))
+            })?;
+
+        Ok(file_info.get_id())
+    }
+}
+
+fn conversion_size_error(backend: &GoogleDriveBackend, conversion: &GoogleDriveConversion) -> HikyakuError {
+    InvalidArgumentError(format!(
+        "{:?} is over {} bytes which can be converted to {}", backend.upload_filename, conversion.size_limit(), conversion.target.mime()))
+}
+
+async fn send_google_drive_request(request: RequestBuilder) -> HikyakuResult<Response> {
+    request
+        .send()
+        .await
+        .inspect(|obj| debug!("{:#?}", obj))
+        .map_err(|e| {
+            error!("Failed to request for Google Drive API: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Google Drive API: {:?}", e))
+        })
+}
+
+/// The resumable upload of Google Drive.
+struct GoogleDriveWriteSession {
+    backend: GoogleDriveBackend,
+    chunk_size: u64,
+    resumable_url: Option<String>,
+    // The size of the file of the unknown size is checked against the conversion limit as the chunks arrive.
+    uploaded_size: u64,
+}
+
+#[async_trait]
+impl WriteSession for GoogleDriveWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        self.uploaded_size += chunk_data.len() as u64;
+        if let Some(conversion) = &self.backend.conversion {
+            // The session which is opened by the earlier chunks is cancelled by `abort`.
+            if self.uploaded_size > conversion.size_limit() {
+                return Err(conversion_size_error(&self.backend, conversion));
+            }
+        }
+
+        self.put_chunk(chunk_data).await
+    }
+
+    async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) {
+        if let Some(resumable_url) = &self.resumable_url {
+            cancel_upload_session(first_client(&self.backend.clients), resumable_url).await;
+        }
+    }
+}
+
+impl GoogleDriveWriteSession {
+    async fn put_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let GoogleDriveBackend {clients, google_drive_token, endpoint, upload_filename, conversion, ..} = &self.backend;
+
+        let offset = chunk_data.get_offset();
+        let start = offset * self.chunk_size;
+
+        if self.resumable_url.is_none() {
+            let parent_dir_id = self.backend.create_google_drive_parent_dirs().await?;
+
+            let url = endpoint.upload_files_url();
+            // Google Drive converts the uploaded data when the metadata has Google Workspace type.
+            // TODO: Implement the infer mime_type
+            let (mime_type, content_type) = match conversion {
+                Some(conversion) => (conversion.target.mime(), conversion.source.mime()),
+                None => ("application/octet-stream", "application/octet-stream"),
+            };
+            // SAFETY: The upload_filename is always Some because the None was filtered by `start_write`.
+            let filename = upload_filename.clone().unwrap();
+
+            let mut metadata = json!({
+                "name": filename.as_str(),
+                "mimeType": mime_type
+            });
+
+            if !parent_dir_id.is_empty() {
+                metadata["parents"] = json!([parent_dir_id]);
+            }
+
+            let client = get_client_with_token(google_drive_token.get_access_token(), Bearer)?;
+            let response = client
+                .post(url)
+                .header(CONTENT_TYPE, "application/json")
+                .header("X-Upload-Content-Type", content_type)
+                .json(&metadata)
+                .query(&[("uploadType", "resumable"), ("supportsAllDrives", "true")])
+                .send()
+                .await
+                .map_err(|e| {
+                    GoogleDriveError(format!("Failed to send request to get resumable URL for {}: {:?}", filename, e))
+                })?;
+
+            if !response.status().is_success() {
+                return Err(GoogleDriveError(format!("Failed to get resumable URL for {}: {:?}", filename, response.status())));
+            }
+
+            let resumable_url = response
+                .headers()
+                .get("Location")
+                .ok_or_else(|| {
+                    GoogleDriveError(format!("Failed to get resumable URL for {}: {}", filename, "Location header is not found"))
+                })?
+                .to_str()
+                .map_err(|e| {
+                    GoogleDriveError(format!("Failed to convert resumable URL for {}: {:?}", filename, e))
+                })?
+                .to_string();
+
+            self.resumable_url = Some(resumable_url);
+        }
+
+        // SAFETY: The resumable URL is always Some by the above.
+        let resumable_url = self.resumable_url.as_ref().unwrap();
+        // Note: Google Drive resumable upload requires alignment bytes so it cannot parallelize.
+        let res = put_resumable_chunk(first_client(clients), resumable_url, chunk_data, start)
+            .await
+            .map_err(|e| {
+                GoogleDriveError(format!("Failed to send request to upload {}: {:?}", resumable_url, e))
+            })?;
+
+        if !res.status().is_success() && res.status() != StatusCode::PERMANENT_REDIRECT {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            let message = format!("Google Drive API returned status code: {}, body: {}", status, body);
+            debug!("Failed to put chunk data to Google Drive: {}", message);
+            error!("Failed to put chunk data part of {}", offset);
+            return Err(GoogleDriveError(format!("Failed to upload part: {}", status)));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::header::RANGE;
+use crate::errors::HikyakuError::{HttpError, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{client_for_range, first_client, ranged_get, ResponseStream};
+use crate::services::file_system_builder::http::http_request;
+use crate::services::storage_backend::{BackendStat, ReadStream, StorageBackend, WriteSession};
+
+#[derive(Clone)]
+pub(crate) struct HttpBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) url: Arc<String>,
+    pub(crate) is_exist: bool,
+    pub(crate) accepts_ranges: bool,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for HttpBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Http: url: {}, accepts_ranges: {}, file_size: {:?}", self.url, self.accepts_ranges, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for HttpBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            // The file on the HTTP(S) server is always a file.
+            is_dir: false,
+            // The server which does not accept the range or tells no length sends the whole file in one response.
+            is_streamed: self.is_exist && !self.accepts_ranges,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, url, ..} = self;
+        let client = client_for_range(clients, start, end);
+
+        let request = client
+            .get(url.as_str())
+            .header(RANGE, format!("bytes={}-{}", start, end));
+
+        ranged_get(request, start, end, HttpError).await
+    }
+
+    async fn open_stream(&self) -> HikyakuResult<Box<dyn ReadStream>> {
+        let res = http_request(first_client(&self.clients).get(self.url.as_str())).await?;
+
+        Ok(Box::new(ResponseStream::new(res, self.file_size, HttpError)))
+    }
+
+    async fn start_write(&self, _chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        Err(UnsupportedError(format!("Upload is not supported by the read-only HTTP(S) server. File system object: {}", self)))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        Err(UnsupportedError(format!("List is not supported by the read-only HTTP(S) server. File system object: {}", self)))
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        Err(UnsupportedError(format!("Delete is not supported by the read-only HTTP(S) server. File system object: {}", self)))
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt::{Display, Formatter};
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::{error, warn};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use crate::errors::HikyakuError::{ConnectionError, FileOperationError, InvalidArgumentError, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::downcast;
+use crate::services::file_system::ChunkData;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+
+#[derive(Clone)]
+pub(crate) struct LocalBackend {
+    pub(crate) path: Arc<PathBuf>,
+    pub(crate) file: Arc<Mutex<Option<File>>>,
+    pub(crate) is_dir: bool,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for LocalBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Local: path: {}, file_size: {:?}", self.path.display(), self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let mut file_lock = self.file.lock().await;
+        if file_lock.is_none() {
+            let f = File::open(self.path.as_path())
+                .await
+                .map_err(|e| {
+                    error!("Failed to open file: {:#?}", e);
+                    ConnectionError(format!("Failed to open file: {:?}", e))
+                })?;
+            *file_lock = Some(f);
+        }
+        // SAFETY: The file is always opened by the above.
+        let file = file_lock.as_mut().unwrap();
+
+        let mut buf = vec![0u8; (end - start + 1) as usize];
+        file.seek(SeekFrom::Start(start))
+            .await
+            .map_err(|e| {
+                error!("Failed to seek file: {:#?}", e);
+                FileOperationError(format!("Failed to seek file: {:?}", e))
+            })?;
+        file.read_exact(&mut buf)
+            .await
+            .map_err(|e| {
+                error!("Failed to read file: {:#?}", e);
+                FileOperationError(format!("Failed to read file: {:?}", e))
+            })?;
+
+        Ok(buf)
+    }
+
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        // The existing local file is never overwritten.
+        if self.file_size.is_some() {
+            error!("The same name file is already exist. Please rename it.");
+            return Err(InvalidArgumentError(
+                "The same name file is already exist. Please rename it.".to_string()))
+        }
+
+        Ok(Box::new(LocalWriteSession {
+            path: Arc::clone(&self.path),
+            chunk_size,
+            file: None,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {path, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![path.display().to_string()]);
+        }
+
+        let mut entries = tokio::fs::read_dir(path.as_path())
+            .await
+            .map_err(|e| {
+                error!("Failed to read directory: {:#?}", e);
+                FileOperationError(format!("Failed to read directory {}: {:?}", path.display(), e))
+            })?;
+
+        let mut paths = vec![];
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| FileOperationError(format!("Failed to read directory entry in {}: {:?}", path.display(), e)))? {
+            paths.push(entry.path().display().to_string());
+        }
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        // Release the opened handle before removing the file.
+        *self.file.lock().await = None;
+
+        tokio::fs::remove_file(self.path.as_path())
+            .await
+            .map_err(|e| {
+                error!("Failed to remove file: {:#?}", e);
+                FileOperationError(format!("Failed to remove file {}: {:?}", self.path.display(), e))
+            })
+    }
+
+    async fn mkdir(&self) -> HikyakuResult<()> {
+        tokio::fs::create_dir_all(self.path.as_path())
+            .await
+            .map_err(|e| {
+                error!("Failed to create directory: {:#?}", e);
+                FileOperationError(format!("Failed to create directory {}: {:?}", self.path.display(), e))
+            })
+    }
+
+    async fn move_to(&self, destination: &dyn StorageBackend) -> HikyakuResult<()> {
+        let Some(LocalBackend {path: destination_path, ..}) = downcast::<LocalBackend>(destination) else {
+            return Err(UnsupportedError(format!("Move is not supported yet. Source: {}, destination: {}", self, destination)));
+        };
+        // Release the opened handle before moving the file.
+        *self.file.lock().await = None;
+
+        tokio::fs::rename(self.path.as_path(), destination_path.as_path())
+            .await
+            .map_err(|e| {
+                error!("Failed to move file: {:#?}", e);
+                FileOperationError(format!("Failed to move {} to {}: {:?}", self.path.display(), destination_path.display(), e))
+            })
+    }
+}
+
+/// The upload of the local file, which writes the chunks at their offsets.
+struct LocalWriteSession {
+    path: Arc<PathBuf>,
+    chunk_size: u64,
+    file: Option<File>,
+}
+
+#[async_trait]
+impl WriteSession for LocalWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let start = chunk_data.get_offset() * self.chunk_size;
+
+        if self.file.is_none() {
+            let f = File::create(self.path.as_path()).await
+                .map_err(|e| {
+                    FileOperationError(format!("Failed to create file to {}: {:?}", self.path.display(), e))
+                })?;
+            self.file = Some(f);
+        }
+        // SAFETY: The file is always created by the above.
+        let file = self.file.as_mut().unwrap();
+
+        file.seek(SeekFrom::Start(start))
+            .await
+            .map_err(|e| {
+                FileOperationError(format!("Failed to seek file {}: {:?}", start, e))
+            })?;
+        file.write_all(chunk_data.get_data())
+            .await
+            .map_err(|e| {
+                FileOperationError(format!("Failed to write file {}: {:?}", start, e))
+            })
+    }
+
+    async fn finish(mut self: Box<Self>) -> HikyakuResult<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()
+                .await
+                .map_err(|e| FileOperationError(format!("Failed to flush file {}: {:?}", self.path.display(), e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the partially written file not to be read as the whole file.
+    /// The session never overwrites the existing file, so the removed file is always created by it.
+    async fn abort(mut self: Box<Self>) {
+        if self.file.take().is_none() {
+            return;
+        }
+        if let Err(e) = tokio::fs::remove_file(self.path.as_path()).await {
+            warn!("Failed to remove the partially written {}: {:?}", self.path.display(), e);
+        }
+    }
+}
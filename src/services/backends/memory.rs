@@ -0,0 +1,98 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::errors::HikyakuError::InvalidArgumentError;
+use crate::errors::HikyakuResult;
+use crate::services::file_system::ChunkData;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::utils::memory_store::MemoryStore;
+
+#[derive(Clone)]
+pub(crate) struct MemoryBackend {
+    pub(crate) store: MemoryStore,
+    pub(crate) namespace: Arc<String>,
+    pub(crate) path: Arc<String>,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for MemoryBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Memory: namespace: {}, path: {}, file_size: {:?}", self.namespace, self.path, self.file_size)
+    }
+}
+
+impl MemoryBackend {
+    /// The stat of the path, which is known without waiting because the store is in the memory.
+    /// The path which has the children and no data is a directory.
+    pub(crate) fn current_stat(&self) -> BackendStat {
+        BackendStat {
+            file_size: self.file_size,
+            is_dir: self.file_size.is_none() && self.store.is_dir(&self.namespace, &self.path),
+            is_streamed: false,
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for MemoryBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(self.current_stat())
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        self.store.read_range(&self.namespace, &self.path, start, end).await
+    }
+
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.current_stat().is_dir {
+            return Err(InvalidArgumentError(format!("The upload target mem://{}/{} is a directory", self.namespace, self.path)));
+        }
+
+        Ok(Box::new(MemoryWriteSession {
+            backend: self.clone(),
+            chunk_size,
+            is_written: false,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        self.store.list(&self.namespace, &self.path).await
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        self.store.delete(&self.namespace, &self.path).await
+    }
+}
+
+/// The upload of the in-memory store, which writes the chunks at their offsets.
+struct MemoryWriteSession {
+    backend: MemoryBackend,
+    chunk_size: u64,
+    is_written: bool,
+}
+
+#[async_trait]
+impl WriteSession for MemoryWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let MemoryBackend {store, namespace, path, ..} = &self.backend;
+        let start = chunk_data.get_offset() * self.chunk_size;
+
+        store.write_at(namespace, path, start, chunk_data.get_data(), chunk_data.is_last()).await?;
+        self.is_written = true;
+
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+        Ok(())
+    }
+
+    /// Remove the partially written object not to be read as the whole file.
+    /// The removal skips the hooks, so the injected failures never leave the partial object.
+    async fn abort(self: Box<Self>) {
+        let MemoryBackend {store, namespace, path, ..} = &self.backend;
+        if self.is_written {
+            store.remove(namespace, path);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! The [StorageBackend] of the built-in file systems.
+//!
+//! [StorageBackend]: crate::services::storage_backend::StorageBackend
+
+#[cfg(feature = "amazon_s3")]
+pub(crate) mod amazon_s3;
+#[cfg(feature = "google_drive")]
+pub(crate) mod google_drive;
+#[cfg(feature = "dropbox")]
+pub(crate) mod dropbox;
+#[cfg(feature = "box_storage")]
+pub(crate) mod box_storage;
+#[cfg(feature = "onedrive")]
+pub(crate) mod onedrive;
+#[cfg(feature = "azure_blob")]
+pub(crate) mod azure_blob;
+#[cfg(feature = "google_cloud_storage")]
+pub(crate) mod google_cloud_storage;
+#[cfg(feature = "sftp")]
+pub(crate) mod sftp;
+#[cfg(feature = "webdav")]
+pub(crate) mod webdav;
+#[cfg(feature = "http")]
+pub(crate) mod http;
+pub(crate) mod local;
+pub(crate) mod memory;
+
+use std::any::Any;
+use std::future::Future;
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::{error, warn};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use tokio::task::JoinSet;
+use crate::errors::{HikyakuError, HikyakuResult};
+use crate::errors::HikyakuError::{ConnectionError, UnknownError};
+use crate::services::file_system::ChunkData;
+use crate::services::storage_backend::{ReadStream, StorageBackend};
+
+/// Get the concrete backend, so the operation between the same kind of backends(e.x. the server-side copy)
+/// can use its fields.
+pub(crate) fn downcast<B: StorageBackend>(backend: &dyn StorageBackend) -> Option<&B> {
+    (backend as &dyn Any).downcast_ref::<B>()
+}
+
+/// Pick the client for the range, so the parallel reads of the chunks are spread over the clients.
+pub(crate) fn client_for_range<T>(clients: &[Arc<T>], start: u64, end: u64) -> &Arc<T> {
+    let index = start / (end - start + 1);
+    &clients[(index % clients.len() as u64) as usize]
+}
+
+/// Pick the client for the single request which is not spread over the clients.
+pub(crate) fn first_client<T>(clients: &[Arc<T>]) -> &Arc<T> {
+    // SAFETY: The builder always creates the clients more than 1 by NonZero concurrency.
+    clients.first().unwrap()
+}
+
+/// The writes of the chunks which run in parallel up to the concurrency.
+pub(crate) struct ParallelWrites {
+    tasks: JoinSet<HikyakuResult<()>>,
+    concurrency: usize,
+}
+
+impl ParallelWrites {
+    pub(crate) fn new(concurrency: usize) -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            concurrency,
+        }
+    }
+
+    /// Start the write after one of the running writes finishes if the writes are up to the concurrency.
+    pub(crate) async fn spawn<F>(&mut self, write: F) -> HikyakuResult<()>
+    where
+        F: Future<Output = HikyakuResult<()>> + Send + 'static,
+    {
+        if self.tasks.len() >= self.concurrency {
+            self.join_next().await?;
+        }
+        self.tasks.spawn(write);
+
+        Ok(())
+    }
+
+    /// Wait for all running writes and return the first failure.
+    pub(crate) async fn join_all(&mut self) -> HikyakuResult<()> {
+        while !self.tasks.is_empty() {
+            self.join_next().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the running writes ignoring their results, so they are not left in the aborted upload.
+    pub(crate) async fn cancel(&mut self) {
+        while self.tasks.join_next().await.is_some() {}
+    }
+
+    async fn join_next(&mut self) -> HikyakuResult<()> {
+        match self.tasks.join_next().await {
+            Some(result) => result.map_err(|e| UnknownError(format!("Upload task is failed: {:?}", e)))?,
+            None => Ok(()),
+        }
+    }
+}
+
+/// The body of the response which is read from the beginning.
+pub(crate) struct ResponseStream {
+    response: Response,
+    // The size which the server told, or [None] if it is unknown.
+    expected_size: Option<u64>,
+    received_size: u64,
+    map_err: fn(String) -> HikyakuError,
+}
+
+impl ResponseStream {
+    pub(crate) fn new(response: Response, expected_size: Option<u64>, map_err: fn(String) -> HikyakuError) -> Self {
+        Self {
+            response,
+            expected_size,
+            received_size: 0,
+            map_err,
+        }
+    }
+}
+
+#[async_trait]
+impl ReadStream for ResponseStream {
+    async fn next_bytes(&mut self) -> HikyakuResult<Option<Vec<u8>>> {
+        let bytes = self.response
+            .chunk()
+            .await
+            .map_err(|e| {
+                error!("Failed to read body: {:#?}", e);
+                (self.map_err)(format!("{:?}", e))
+            })?;
+
+        let url = self.response.url();
+        match (bytes, self.expected_size) {
+            (Some(bytes), expected_size) => {
+                self.received_size += bytes.len() as u64;
+                if let Some(expected_size) = expected_size.filter(|expected_size| self.received_size > *expected_size) {
+                    return Err((self.map_err)(format!("{} is longer than {} bytes", url, expected_size)));
+                }
+                Ok(Some(bytes.to_vec()))
+            },
+            (None, Some(expected_size)) if self.received_size != expected_size => {
+                Err((self.map_err)(format!("{} is shorter than {} bytes", url, expected_size)))
+            },
+            (None, _) => Ok(None),
+        }
+    }
+}
+
+/// Put the chunk to the resumable upload session of Google Drive or Cloud Storage.
+///
+/// The total size is unknown until the last chunk comes, and the session returns
+/// `308 Resume Incomplete` for the chunks before it.
+/// The empty last chunk(e.x. of the empty file) has no bytes, so it only tells the total size(`bytes */0`).
+pub(crate) async fn put_resumable_chunk(client: &Client, resumable_url: &str, chunk_data: ChunkData, start: u64) -> reqwest::Result<Response> {
+    let total_size = if chunk_data.is_last() {
+        (start + chunk_data.len() as u64).to_string()
+    } else {
+        "*".to_string()
+    };
+    let content_range = match chunk_data.len() {
+        0 => format!("bytes */{}", total_size),
+        len => format!("bytes {}-{}/{}", start, start + len as u64 - 1, total_size),
+    };
+
+    client
+        .put(resumable_url)
+        .header(CONTENT_LENGTH, chunk_data.len())
+        .header(CONTENT_RANGE, content_range)
+        .body(chunk_data.get_raw_data())
+        .send()
+        .await
+}
+
+/// Get the bytes from `start` to `end`(inclusive) of the file by the request which has the range.
+///
+/// The server which ignores the range returns the whole file with `200 OK`, so the range is cut out of it.
+///
+/// # Arguments
+///
+/// * `request` - The GET request with the `Range` header(or the alternative, e.x. `x-ms-range`).
+/// * `start` - The first byte of the range.
+/// * `end` - The last byte of the range.
+/// * `map_err` - The error of the backend(e.x. `DropboxError`) for the failed status and the wrong length.
+///
+/// # Errors
+///
+/// Returns a `ConnectionError` if the request cannot be sent, or the error by `map_err` if the status
+/// is not successful or the response does not have the whole range.
+pub(crate) async fn ranged_get(request: RequestBuilder,
+                               start: u64,
+                               end: u64,
+                               map_err: fn(String) -> HikyakuError) -> HikyakuResult<Vec<u8>> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for the range {}-{}: {:#?}", start, end, e);
+            ConnectionError(format!("Failed to send request for the range {}-{}: {:?}", start, end, e))
+        })?;
+
+    let status = response.status();
+    let url = response.url().to_string();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(map_err(format!("{} returned status code: {} for the range {}-{}, body: {}", url, status, start, end, body)));
+    }
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| {
+            error!("Failed to collect body: {:#?}", e);
+            map_err(format!("Failed to collect the range {}-{} of {}: {:?}", start, end, url, e))
+        })?;
+    let range = if status == StatusCode::PARTIAL_CONTENT {
+        Some(bytes.as_ref())
+    } else {
+        bytes.get(start as usize..=end as usize)
+    };
+
+    match range {
+        Some(range) if range.len() as u64 == end - start + 1 => Ok(range.to_vec()),
+        _ => Err(map_err(format!("{} returned {} bytes for the range {}-{}", url, bytes.len(), start, end))),
+    }
+}
+
+/// Cancel the upload session by DELETE to its URL not to leave the uploaded data until it expires.
+///
+/// The failure is only logged, because the upload has already failed.
+pub(crate) async fn cancel_upload_session(client: &Client, session_url: &str) {
+    match client.delete(session_url).send().await {
+        // Google Drive and Cloud Storage return 499 Client Closed Request for the cancelled session.
+        Ok(response) if response.status().is_success() || response.status().as_u16() == 499 => {},
+        Ok(response) => warn!("Failed to cancel the upload session {}: {}", session_url, response.status()),
+        Err(e) => warn!("Failed to cancel the upload session {}: {:?}", session_url, e),
+    }
+}
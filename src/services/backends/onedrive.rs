@@ -0,0 +1,253 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::{debug, error};
+use reqwest::{Client, RequestBuilder, Response};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use serde_json::json;
+use crate::errors::HikyakuError::{ConnectionError, InvalidArgumentError, NotExistFileError, OneDriveError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{cancel_upload_session, client_for_range, first_client, ranged_get};
+use crate::services::file_system::ChunkData;
+use crate::services::file_system_builder::onedrive::get_all_pages;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::onedrive::{fragment_size, OneDriveEndpoint, OneDriveItem, OneDriveUpload, OneDriveUploadSessionResponse};
+
+// Microsoft Graph accepts up to 4 MiB by the simple upload, the larger file needs the upload session.
+const MAX_ONEDRIVE_SIMPLE_UPLOAD_SIZE: u64 = 4 * 1024 * 1024;
+
+#[derive(Clone)]
+pub(crate) struct OneDriveBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) endpoint: Arc<OneDriveEndpoint>,
+    pub(crate) drive_id: Arc<String>,
+    pub(crate) path: Arc<String>,
+    pub(crate) is_dir: bool,
+    pub(crate) upload_size: Option<u64>,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for OneDriveBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OneDrive: drive_id: {}, path: {}, is_dir: {}, file_size: {:?}", self.drive_id, self.path, self.is_dir, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for OneDriveBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, endpoint, drive_id, path, ..} = self;
+        let client = client_for_range(clients, start, end);
+
+        // Microsoft Graph redirects to the pre-authenticated download URL and the client follows it with the range.
+        let request = client
+            .get(endpoint.item_url(drive_id, path, Some("content")))
+            .header(RANGE, format!("bytes={}-{}", start, end));
+
+        ranged_get(request, start, end, OneDriveError).await
+    }
+
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target {} in the drive {} is a folder", self.path, self.drive_id)));
+        }
+
+        Ok(Box::new(OneDriveWriteSession {
+            backend: self.clone(),
+            chunk_size,
+            upload: None,
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {clients, endpoint, drive_id, path, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![path.to_string()]);
+        }
+
+        let request_url = format!("{}?$select=name", endpoint.item_url(drive_id, path, Some("children")));
+        let mut paths = get_all_pages::<OneDriveItem>(first_client(clients), &request_url)
+            .await?
+            .into_iter()
+            .map(|item| if path.is_empty() {
+                item.name
+            } else {
+                format!("{}/{}", path, item.name)
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        let Self {clients, endpoint, drive_id, path, is_dir, file_size, ..} = self;
+        if !*is_dir && file_size.is_none() {
+            return Err(NotExistFileError(format!("{} does not exist in the drive {}", path, drive_id)));
+        }
+        if path.is_empty() {
+            return Err(InvalidArgumentError(format!("The root folder of the drive {} cannot be deleted", drive_id)));
+        }
+
+        // The folder is deleted with its children and moved to the recycle bin.
+        let response = first_client(clients)
+            .delete(endpoint.item_url(drive_id, path, None))
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to request for Microsoft Graph: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Microsoft Graph: {:?}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(OneDriveError(format!("Failed to delete {} in the drive {}: {}, {}", path, drive_id, status, body)));
+        }
+
+        Ok(())
+    }
+}
+
+impl OneDriveBackend {
+    /// Create the upload session of the file at the path of the OneDrive object.
+    ///
+    /// The missing folders in the path are created by Microsoft Graph, and the existing file is replaced.
+    async fn create_onedrive_session(&self, file_size: u64) -> HikyakuResult<OneDriveUpload> {
+        let Self {clients, endpoint, drive_id, path, ..} = self;
+        let client = first_client(clients);
+        let session = onedrive_request(client
+            .post(endpoint.item_url(drive_id, path, Some("createUploadSession")))
+            .json(&json!({"item": {"@microsoft.graph.conflictBehavior": "replace"}})))
+            .await?
+            .json::<OneDriveUploadSessionResponse>()
+            .await
+            .map_err(|e| OneDriveError(format!("Failed to parse the upload session of {}: {:?}", path, e)))?;
+
+        Ok(OneDriveUpload::Session {
+            client: Client::new(),
+            upload_url: session.upload_url,
+            file_size,
+            buffer: vec![],
+            uploaded_size: 0,
+        })
+    }
+}
+
+/// The upload of OneDrive, by the simple upload or the upload session for the large file.
+struct OneDriveWriteSession {
+    backend: OneDriveBackend,
+    chunk_size: u64,
+    upload: Option<OneDriveUpload>,
+}
+
+#[async_trait]
+impl WriteSession for OneDriveWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let OneDriveBackend {clients, endpoint, drive_id, path, upload_size, ..} = &self.backend;
+        let client = first_client(clients);
+        let content_url = endpoint.item_url(drive_id, path, Some("content"));
+
+        // The small file in one chunk does not need the upload session.
+        if chunk_data.get_offset() == 0 && chunk_data.is_last() && chunk_data.len() as u64 <= MAX_ONEDRIVE_SIMPLE_UPLOAD_SIZE {
+            onedrive_request(client.put(content_url).body(chunk_data.get_raw_data())).await?;
+            return Ok(());
+        }
+
+        if self.upload.is_none() {
+            let state = match upload_size {
+                Some(upload_size) => self.backend.create_onedrive_session(*upload_size).await?,
+                None => OneDriveUpload::Buffered(vec![]),
+            };
+            self.upload = Some(state);
+        }
+
+        let is_last = chunk_data.is_last();
+        // SAFETY: The upload state is always Some by the above.
+        if let OneDriveUpload::Buffered(buffer) = self.upload.as_mut().unwrap() {
+            buffer.extend_from_slice(chunk_data.get_data());
+            if !is_last {
+                return Ok(());
+            }
+
+            let data = std::mem::take(buffer);
+            if data.len() as u64 <= MAX_ONEDRIVE_SIMPLE_UPLOAD_SIZE {
+                onedrive_request(client.put(content_url).body(data)).await?;
+                self.upload = None;
+                return Ok(());
+            }
+            let mut state = self.backend.create_onedrive_session(data.len() as u64).await?;
+            if let OneDriveUpload::Session {buffer, ..} = &mut state {
+                *buffer = data;
+            }
+            self.upload = Some(state);
+        } else if let Some(OneDriveUpload::Session {buffer, ..}) = self.upload.as_mut() {
+            buffer.extend_from_slice(chunk_data.get_data());
+        }
+
+        if let Some(OneDriveUpload::Session {client, upload_url, file_size, buffer, uploaded_size}) = self.upload.as_mut() {
+            let fragment_size = fragment_size(self.chunk_size);
+            // Every fragment except the last must be a multiple of 320 KiB.
+            while buffer.len() as u64 >= fragment_size || (is_last && !buffer.is_empty()) {
+                let size = fragment_size.min(buffer.len() as u64);
+                let fragment = buffer.drain(..size as usize).collect::<Vec<_>>();
+                let request = client
+                    .put(upload_url.as_str())
+                    .header(CONTENT_LENGTH, size)
+                    .header(CONTENT_RANGE, format!("bytes {}-{}/{}", *uploaded_size, *uploaded_size + size - 1, file_size))
+                    .body(fragment);
+                onedrive_request(request).await?;
+                *uploaded_size += size;
+            }
+
+            if !is_last {
+                return Ok(());
+            }
+            if uploaded_size != file_size {
+                return Err(InvalidArgumentError(format!(
+                    "The upload size is set to {} bytes but {} bytes are uploaded", file_size, uploaded_size)));
+            }
+        }
+        self.upload = None;
+
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) {
+        if let Some(OneDriveUpload::Session {client, upload_url, ..}) = &self.upload {
+            cancel_upload_session(client, upload_url).await;
+        }
+    }
+}
+
+/// Send the request to Microsoft Graph and check the status.
+async fn onedrive_request(request: RequestBuilder) -> HikyakuResult<Response> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for Microsoft Graph: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Microsoft Graph: {:?}", e))
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        debug!("Failed to upload data to OneDrive: {}", body);
+        return Err(OneDriveError(format!("Microsoft Graph returned status code: {}, body: {}", status, body)));
+    }
+
+    Ok(response)
+}
@@ -0,0 +1,206 @@
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+use async_trait::async_trait;
+use log::error;
+use ssh2::{OpenFlags, OpenType};
+use crate::errors::HikyakuError::{InvalidArgumentError, NotExistFileError, SftpError, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{client_for_range, downcast, first_client, ParallelWrites};
+use crate::services::file_system::ChunkData;
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::sftp::{create_dirs, remote_path, sftp_error, SftpAuthority, SftpConnection, FILE_MODE};
+
+#[derive(Clone)]
+pub(crate) struct SftpBackend {
+    pub(crate) connections: Vec<Arc<SftpConnection>>,
+    pub(crate) authority: Arc<SftpAuthority>,
+    pub(crate) path: Arc<String>,
+    pub(crate) is_dir: bool,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for SftpBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sftp: host: {}, user: {}, path: {}, is_dir: {}, file_size: {:?}", self.authority.known_hosts_name(), self.authority.user, self.path, self.is_dir, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for SftpBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let connection = client_for_range(&self.connections, start, end);
+
+        let remote_path = remote_path(&self.path);
+        let mut bytes = vec![0u8; (end - start + 1) as usize];
+        // Each session opens its own handle, so the ranges are read in parallel.
+        connection.run(move |sftp| {
+            let mut file = sftp
+                .open(&remote_path)
+                .map_err(|e| sftp_error(&format!("Failed to open {}", remote_path.display()), e))?;
+            file.seek(SeekFrom::Start(start))
+                .and_then(|_| file.read_exact(&mut bytes))
+                .map_err(|e| {
+                    error!("Failed to read {}: {:#?}", remote_path.display(), e);
+                    SftpError(format!("Failed to read {}: {:?}", remote_path.display(), e))
+                })?;
+            Ok(bytes)
+        }).await
+    }
+
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target {}:{} is a directory", self.authority.known_hosts_name(), self.path)));
+        }
+
+        Ok(Box::new(SftpWriteSession {
+            backend: self.clone(),
+            chunk_size,
+            is_created: false,
+            writes: ParallelWrites::new(self.connections.len()),
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {connections, path, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![path.to_string()]);
+        }
+
+        let connection = first_client(connections);
+        let remote_path = remote_path(path);
+        let names = connection.run(move |sftp| {
+            let entries = sftp
+                .readdir(&remote_path)
+                .map_err(|e| sftp_error(&format!("Failed to read directory {}", remote_path.display()), e))?;
+            Ok(entries
+                .into_iter()
+                .filter_map(|(entry, _)| entry.file_name().map(|name| name.to_string_lossy().to_string()))
+                .collect::<Vec<_>>())
+        }).await?;
+
+        // The paths are the same form as the path of `sftp://` except the namespace.
+        let mut paths = names
+            .into_iter()
+            .map(|name| if path.is_empty() { name } else { format!("{}/{}", path, name) })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        let Self {connections, authority, path, is_dir, file_size} = self;
+        if !is_dir && file_size.is_none() {
+            return Err(NotExistFileError(format!("sftp://{}@{}/{} does not exist", authority.user, authority.known_hosts_name(), path)));
+        }
+
+        let connection = first_client(connections);
+        let remote_path = remote_path(path);
+        let is_dir = *is_dir;
+        connection.run(move |sftp| {
+            // The directory is removed only if it is empty.
+            let result = if is_dir {
+                sftp.rmdir(&remote_path)
+            } else {
+                sftp.unlink(&remote_path)
+            };
+            result.map_err(|e| sftp_error(&format!("Failed to remove {}", remote_path.display()), e))
+        }).await
+    }
+
+    async fn mkdir(&self) -> HikyakuResult<()> {
+        let connection = first_client(&self.connections);
+        let remote_path = remote_path(&self.path);
+        connection.run(move |sftp| create_dirs(sftp, &remote_path)).await
+    }
+
+    async fn move_to(&self, destination: &dyn StorageBackend) -> HikyakuResult<()> {
+        let Some(SftpBackend {authority: destination_authority, path: destination_path, ..}) = downcast::<SftpBackend>(destination) else {
+            return Err(UnsupportedError(format!("Move is not supported yet. Source: {}, destination: {}", self, destination)));
+        };
+        let Self {connections, authority, path, is_dir, file_size} = self;
+        if !is_dir && file_size.is_none() {
+            return Err(NotExistFileError(format!("Move source {}:{} does not exist", authority.known_hosts_name(), path)));
+        }
+        if authority != destination_authority {
+            return Err(InvalidArgumentError(format!(
+                "Move across the servers is not supported: {} to {}", authority.known_hosts_name(), destination_authority.known_hosts_name())));
+        }
+        let source_path = remote_path(path);
+        let destination_path = remote_path(destination_path);
+
+        first_client(connections).run(move |sftp| {
+            // The existing destination is overwritten like `mv`.
+            if sftp.stat(&destination_path).is_ok_and(|stat| !stat.is_dir()) {
+                sftp.unlink(&destination_path)
+                    .map_err(|e| sftp_error(&format!("Failed to remove {}", destination_path.display()), e))?;
+            }
+            sftp.rename(&source_path, &destination_path, None)
+                .map_err(|e| sftp_error(&format!("Failed to move {} to {}", source_path.display(), destination_path.display()), e))
+        }).await
+    }
+}
+
+/// The upload of SFTP, which writes the chunks at their offsets over the sessions in parallel.
+struct SftpWriteSession {
+    backend: SftpBackend,
+    chunk_size: u64,
+    is_created: bool,
+    writes: ParallelWrites,
+}
+
+#[async_trait]
+impl WriteSession for SftpWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let SftpBackend {connections, path, ..} = &self.backend;
+        let offset = chunk_data.get_offset();
+        let connection = Arc::clone(&connections[(offset % connections.len() as u64) as usize]);
+        let remote_path = remote_path(path);
+
+        // The file is created(or truncated) once before any chunk is written.
+        if !self.is_created {
+            let remote_path = remote_path.clone();
+            connection.run(move |sftp| {
+                if let Some(parent) = remote_path.parent() {
+                    create_dirs(sftp, parent)?;
+                }
+                sftp.create(&remote_path)
+                    .map(|_| ())
+                    .map_err(|e| sftp_error(&format!("Failed to create {}", remote_path.display()), e))
+            }).await?;
+            self.is_created = true;
+        }
+
+        let start = offset * self.chunk_size;
+        self.writes.spawn(async move {
+            connection.run(move |sftp| {
+                let mut file = sftp
+                    .open_mode(&remote_path, OpenFlags::WRITE, FILE_MODE, OpenType::File)
+                    .map_err(|e| sftp_error(&format!("Failed to open {}", remote_path.display()), e))?;
+                file.seek(SeekFrom::Start(start))
+                    .and_then(|_| file.write_all(chunk_data.get_data()))
+                    .map_err(|e| {
+                        error!("Failed to write {}: {:#?}", remote_path.display(), e);
+                        SftpError(format!("Failed to write {} at {}: {:?}", remote_path.display(), start, e))
+                    })
+            }).await
+        }).await
+    }
+
+    async fn finish(mut self: Box<Self>) -> HikyakuResult<()> {
+        self.writes.join_all().await
+    }
+
+    async fn abort(mut self: Box<Self>) {
+        self.writes.cancel().await;
+    }
+}
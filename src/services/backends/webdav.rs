@@ -0,0 +1,250 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::header::RANGE;
+use time::OffsetDateTime;
+use crate::errors::HikyakuError::{InvalidArgumentError, NotExistFileError, UnsupportedError, WebDavError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::{client_for_range, downcast, first_client, ranged_get, ParallelWrites};
+use crate::services::file_system::ChunkData;
+use crate::services::file_system_builder::webdav::{create_collections, propfind, webdav_method, webdav_request};
+use crate::services::storage_backend::{BackendStat, StorageBackend, WriteSession};
+use crate::types::webdav::{chunk_url, WebDavEndpoint, WebDavUpload, MAX_NEXTCLOUD_CHUNKS};
+
+// The header of WebDAV which points to the target of MOVE. Nextcloud requires it on the chunked upload too.
+const DESTINATION: &str = "Destination";
+
+#[derive(Clone)]
+pub(crate) struct WebDavBackend {
+    pub(crate) clients: Vec<Arc<Client>>,
+    pub(crate) endpoint: Arc<WebDavEndpoint>,
+    pub(crate) path: Arc<String>,
+    pub(crate) is_dir: bool,
+    pub(crate) nextcloud_uploads: Option<Arc<String>>,
+    pub(crate) file_size: Option<u64>,
+}
+
+impl Display for WebDavBackend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WebDav: url: {}, path: {}, is_dir: {}, file_size: {:?}", self.endpoint.url(""), self.path, self.is_dir, self.file_size)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for WebDavBackend {
+    async fn stat(&self) -> HikyakuResult<BackendStat> {
+        Ok(BackendStat {
+            file_size: self.file_size,
+            is_dir: self.is_dir,
+            is_streamed: false,
+        })
+    }
+
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        let Self {clients, endpoint, path, ..} = self;
+        let client = client_for_range(clients, start, end);
+
+        let request = client
+            .get(endpoint.url(path))
+            .header(RANGE, format!("bytes={}-{}", start, end));
+
+        ranged_get(request, start, end, WebDavError).await
+    }
+
+    async fn start_write(&self, _chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+        if self.is_dir {
+            return Err(InvalidArgumentError(format!("The upload target {} is a collection", self.endpoint.url(&self.path))));
+        }
+
+        Ok(Box::new(WebDavWriteSession {
+            backend: self.clone(),
+            upload: None,
+            writes: ParallelWrites::new(self.clients.len()),
+        }))
+    }
+
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        let Self {clients, endpoint, path, is_dir, ..} = self;
+        if !is_dir {
+            return Ok(vec![path.to_string()]);
+        }
+
+        let client = first_client(clients);
+        let resources = propfind(client, &endpoint.collection_url(path), 1)
+            .await?
+            .ok_or_else(|| NotExistFileError(format!("{} does not exist", endpoint.url(path))))?;
+
+        // The response has the collection itself too.
+        let mut paths = resources
+            .into_iter()
+            .map(|resource| resource.path)
+            .filter(|member| member != path.as_str())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        Ok(paths)
+    }
+
+    async fn delete(&self) -> HikyakuResult<()> {
+        let Self {clients, endpoint, path, is_dir, file_size, ..} = self;
+        if !is_dir && file_size.is_none() {
+            return Err(NotExistFileError(format!("{} does not exist", endpoint.url(path))));
+        }
+        let client = first_client(clients);
+
+        // DELETE of the collection removes its members too, so only the empty collection is deleted
+        // like the other file systems.
+        let url = if *is_dir {
+            let url = endpoint.collection_url(path);
+            let members = propfind(client, &url, 1).await?.unwrap_or_default();
+            if members.iter().any(|member| member.path != path.as_str()) {
+                return Err(InvalidArgumentError(format!("{} is not an empty collection", url)));
+            }
+            url
+        } else {
+            endpoint.url(path)
+        };
+
+        webdav_request(client.delete(url)).await.map(|_| ())
+    }
+
+    async fn mkdir(&self) -> HikyakuResult<()> {
+        create_collections(first_client(&self.clients), &self.endpoint, &self.path).await
+    }
+
+    async fn move_to(&self, destination: &dyn StorageBackend) -> HikyakuResult<()> {
+        let Some(WebDavBackend {endpoint: destination_endpoint, path: destination_path, ..}) = downcast::<WebDavBackend>(destination) else {
+            return Err(UnsupportedError(format!("Move is not supported yet. Source: {}, destination: {}", self, destination)));
+        };
+        let Self {clients, endpoint, path, is_dir, file_size, ..} = self;
+        if !is_dir && file_size.is_none() {
+            return Err(NotExistFileError(format!("Move source {} does not exist", endpoint.url(path))));
+        }
+        if endpoint != destination_endpoint {
+            return Err(InvalidArgumentError(format!(
+                "Move across the servers is not supported: {} to {}", endpoint.url(""), destination_endpoint.url(""))));
+        }
+        let (source_url, destination_url) = if *is_dir {
+            (endpoint.collection_url(path), endpoint.collection_url(destination_path))
+        } else {
+            (endpoint.url(path), endpoint.url(destination_path))
+        };
+
+        let request = first_client(clients)
+            .request(webdav_method("MOVE"), source_url)
+            .header(DESTINATION, destination_url)
+            .header("Overwrite", "T");
+        webdav_request(request).await.map(|_| ())
+    }
+}
+
+/// The upload of WebDAV, which puts the chunks in the transfer collection of Nextcloud in parallel,
+/// or holds them until all arrive.
+struct WebDavWriteSession {
+    backend: WebDavBackend,
+    upload: Option<WebDavUpload>,
+    writes: ParallelWrites,
+}
+
+impl WebDavWriteSession {
+    /// Cancel the transfer not to leave the uploaded chunks on the server.
+    async fn cancel_transfer(&mut self) {
+        self.writes.cancel().await;
+        let Some(transfer_url) = self.upload.take().and_then(|upload| upload.get_transfer_url().map(str::to_string)) else {
+            return;
+        };
+        let _ = first_client(&self.backend.clients).delete(&transfer_url).send().await;
+    }
+
+    /// Assemble the chunks of Nextcloud by MOVE, or put the held chunks at once.
+    async fn complete(&mut self) -> HikyakuResult<()> {
+        let WebDavBackend {clients, endpoint, path, ..} = &self.backend;
+        // Nothing is uploaded if no chunk is received.
+        let Some(webdav_upload) = self.upload.take() else {
+            return Ok(());
+        };
+        let client = first_client(clients);
+
+        match webdav_upload.get_transfer_url().map(str::to_string) {
+            Some(transfer_url) => {
+                let request = client
+                    .request(webdav_method("MOVE"), format!("{}/.file", transfer_url))
+                    .header(DESTINATION, endpoint.url(path))
+                    .header("Overwrite", "T");
+                let result = webdav_request(request).await;
+                if result.is_err() {
+                    let _ = client.delete(&transfer_url).send().await;
+                }
+                result.map(|_| ())
+            },
+            None => {
+                let request = client
+                    .put(endpoint.url(path))
+                    .body(webdav_upload.into_data());
+                webdav_request(request).await.map(|_| ())
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl WriteSession for WebDavWriteSession {
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+        let WebDavBackend {clients, endpoint, path, nextcloud_uploads, ..} = &self.backend;
+        let offset = chunk_data.get_offset();
+        let client = Arc::clone(&clients[(offset % clients.len() as u64) as usize]);
+
+        if self.upload.is_none() {
+            if let Some((parent, _)) = path.rsplit_once('/') {
+                create_collections(&client, endpoint, parent).await?;
+            }
+            // The whole data fits in one chunk so the chunked upload is not needed.
+            let transfer_url = match nextcloud_uploads {
+                Some(uploads_url) if !(offset == 0 && chunk_data.is_last()) => {
+                    let transfer_url = format!("{}/hikyaku-{}", uploads_url, OffsetDateTime::now_utc().unix_timestamp_nanos());
+                    let request = client
+                        .request(webdav_method("MKCOL"), &transfer_url)
+                        .header(DESTINATION, endpoint.url(path));
+                    webdav_request(request).await?;
+                    Some(transfer_url)
+                },
+                _ => None,
+            };
+            self.upload = Some(WebDavUpload::new(transfer_url));
+        }
+
+        // SAFETY: The upload is always Some by the above.
+        let webdav_upload = self.upload.as_mut().unwrap();
+        let Some(transfer_url) = webdav_upload.get_transfer_url().map(str::to_string) else {
+            webdav_upload.add_chunk(offset, chunk_data.get_raw_data());
+            return Ok(());
+        };
+        if offset >= MAX_NEXTCLOUD_CHUNKS {
+            return Err(InvalidArgumentError(format!(
+                "Nextcloud accepts up to {} chunks. Please increase the chunk size", MAX_NEXTCLOUD_CHUNKS)));
+        }
+
+        let destination = endpoint.url(path);
+        self.writes.spawn(async move {
+            let request = client
+                .put(chunk_url(&transfer_url, offset))
+                .header(DESTINATION, destination)
+                .body(chunk_data.get_raw_data());
+            webdav_request(request).await.map(|_| ())
+        }).await
+    }
+
+    async fn finish(mut self: Box<Self>) -> HikyakuResult<()> {
+        if let Err(e) = self.writes.join_all().await {
+            self.cancel_transfer().await;
+            return Err(e);
+        }
+
+        self.complete().await
+    }
+
+    async fn abort(mut self: Box<Self>) {
+        self.cancel_transfer().await;
+    }
+}
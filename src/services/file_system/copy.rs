@@ -0,0 +1,181 @@
+use async_trait::async_trait;
+use crate::errors::HikyakuResult;
+use crate::services::file_system::FileSystemObject;
+
+#[async_trait]
+pub trait ServerSideCopy {
+    /// Copy the `source` to this file system object inside the storage service
+    /// without transferring the data through the local machine.
+    async fn copy_from(&self, source: &FileSystemObject) -> HikyakuResult<()>;
+}
+
+#[async_trait]
+impl ServerSideCopy for FileSystemObject {
+    async fn copy_from(&self, source: &FileSystemObject) -> HikyakuResult<()> {
+        self.backend.copy_from(source.backend.as_ref(), self.chunk_size()).await
+    }
+}
+
+#[cfg(all(test, feature = "amazon_s3"))]
+mod tests {
+    use crate::services::backends::amazon_s3::AmazonS3Backend;
+    use crate::services::file_system_builder::FileSystemBuilder;
+    use crate::types::amazon_s3::{S3CustomerKey, S3Encryption, S3UploadOptions};
+    use crate::utils::test_support::fake_s3::FakeS3;
+    use crate::utils::memory_store::MemoryStore;
+    use super::*;
+
+    fn s3_backend(file_obj: &FileSystemObject) -> &AmazonS3Backend {
+        file_obj.backend::<AmazonS3Backend>().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_copy_amazon_s3_encryption() {
+        let s3 = FakeS3::start().await;
+        s3.put_object("bucket", "dir/source file.txt", b"hello".to_vec());
+
+        let customer_key = S3CustomerKey::new(&[1u8; 32]).unwrap();
+        let src = FileSystemBuilder::from(s3.credential())
+            .set_encryption(S3Encryption::CustomerKey(customer_key.clone()))
+            .set_file_path("s3://bucket/dir/source file.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let dst = FileSystemBuilder::from(s3.credential())
+            .set_encryption(S3Encryption::S3Managed)
+            .set_file_path("s3://bucket/copied.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        s3.clear_requests();
+
+        dst.copy_from(&src).await.unwrap();
+
+        let requests = s3.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/bucket/copied.txt");
+        assert_eq!(requests[0].header("x-amz-copy-source"), Some("bucket/dir/source%20file.txt"));
+        assert_eq!(requests[0].header("x-amz-server-side-encryption"), Some("AES256"));
+        assert_eq!(
+            requests[0].header("x-amz-copy-source-server-side-encryption-customer-key-md5"),
+            Some(customer_key.key_md5()));
+        assert_eq!(s3.get_object("bucket", "copied.txt").unwrap().data, b"hello".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_copy_unsupported() {
+        let store = MemoryStore::new();
+        store.insert("ns", "file", b"data".to_vec());
+        let src = FileSystemBuilder::from(store.clone())
+            .set_file_path("mem://ns/file")
+            .unwrap()
+            .build()
+            .unwrap();
+        let dst = FileSystemBuilder::from(store)
+            .set_file_path("mem://ns/copied")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert!(dst.copy_from(&src).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_copy_amazon_s3_upload_options() {
+        let s3 = FakeS3::start().await;
+        s3.put_object_with_headers("bucket", "source.txt", b"hello".to_vec(), &[("x-amz-meta-origin", "source")]);
+
+        let src = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/source.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let kept = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/kept.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let replaced = FileSystemBuilder::from(s3.credential())
+            .set_upload_options(S3UploadOptions::new().set_metadata("origin", "copy").set_tag("team", "data"))
+            .set_file_path("s3://bucket/replaced.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        kept.copy_from(&src).await.unwrap();
+        replaced.copy_from(&src).await.unwrap();
+
+        assert_eq!(s3.get_object("bucket", "kept.txt").unwrap().headers["x-amz-meta-origin"], "source");
+        let replaced = s3.get_object("bucket", "replaced.txt").unwrap();
+        assert_eq!(replaced.headers["x-amz-meta-origin"], "copy");
+        assert_eq!(replaced.headers["x-amz-metadata-directive"], "REPLACE");
+        assert_eq!(replaced.headers["x-amz-tagging-directive"], "REPLACE");
+        assert_eq!(replaced.headers["content-type"], "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_multipart_copy_amazon_s3() {
+        let s3 = FakeS3::start().await;
+        let data = (0..100u8).collect::<Vec<_>>();
+        s3.put_object_with_headers("bucket", "source.bin", data.clone(), &[
+            ("x-amz-meta-origin", "source"),
+            ("content-type", "text/csv"),
+            ("cache-control", "no-cache"),
+            ("x-amz-tagging", "team=data%20ml"),
+        ]);
+
+        let src = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let dst = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/copied.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        s3.clear_requests();
+
+        // The small part size stands in for the parts of the object larger than 5 GiB.
+        s3_backend(&dst).multipart_copy_s3(s3_backend(&src), "bucket/source.bin", 100, 30).await.unwrap();
+
+        // The metadata and tags of the source are kept like CopyObject.
+        let copied = s3.get_object("bucket", "copied.bin").unwrap();
+        assert_eq!(copied.data, data);
+        assert_eq!(copied.headers["x-amz-meta-origin"], "source");
+        assert_eq!(copied.headers["content-type"], "text/csv");
+        assert_eq!(copied.headers["cache-control"], "no-cache");
+        assert_eq!(copied.headers["x-amz-tagging"], "team=data%20ml");
+        let ranges = s3.requests()
+            .iter()
+            .filter_map(|request| request.header("x-amz-copy-source-range").map(String::from))
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(ranges, ["bytes=0-29", "bytes=30-59", "bytes=60-89", "bytes=90-99"].map(String::from).into());
+
+        let replaced = FileSystemBuilder::from(s3.credential())
+            .set_upload_options(S3UploadOptions::new().set_metadata("origin", "copy").set_tag("team", "web"))
+            .set_file_path("s3://bucket/replaced.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        s3_backend(&replaced).multipart_copy_s3(s3_backend(&src), "bucket/source.bin", 100, 30).await.unwrap();
+        let replaced = s3.get_object("bucket", "replaced.txt").unwrap();
+        assert_eq!(replaced.headers["x-amz-meta-origin"], "copy");
+        assert_eq!(replaced.headers["content-type"], "text/plain");
+        assert!(!replaced.headers.contains_key("cache-control"));
+        assert_eq!(replaced.headers["x-amz-tagging"], "team=web");
+
+        // The failed part aborts the upload.
+        s3.clear_requests();
+        assert!(s3_backend(&dst).multipart_copy_s3(s3_backend(&src), "bucket/missing.bin", 100, 30).await.is_err());
+        assert!(s3.requests().iter().any(|request| request.method == "DELETE" && request.query.contains_key("uploadId")));
+    }
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use crate::errors::HikyakuResult;
+use crate::services::file_system::FileSystemObject;
+
+#[async_trait]
+pub trait Delete {
+    /// Delete the file of the file system object.
+    async fn delete(&self) -> HikyakuResult<()>;
+}
+
+#[async_trait]
+impl Delete for FileSystemObject {
+    async fn delete(&self) -> HikyakuResult<()> {
+        self.backend.delete().await
+    }
+}
@@ -1,15 +1,12 @@
 use std::cmp::min;
-use std::io::SeekFrom;
 use std::sync::Arc;
 use async_trait::async_trait;
-use log::{debug, error};
-use reqwest::header::{AUTHORIZATION, RANGE};
-use tokio::fs::File;
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::sync::mpsc::Sender;
-use crate::errors::HikyakuError::{ConnectionError, FileOperationError, GoogleDriveError, NotExistFileError, S3Error};
+use tokio::task::{JoinError, JoinSet};
+use crate::errors::HikyakuError::{BackendError, NotExistFileError, UnknownError};
 use crate::errors::HikyakuResult;
 use crate::services::file_system::{ChunkData, FileSystemObject};
+use crate::services::storage_backend::ReadStream;
 
 #[async_trait]
 pub trait Download {
@@ -23,28 +20,83 @@ impl Download for FileSystemObject {
             return Err(NotExistFileError(format!("File system object is not downloadable. File system object: {}", self)));
         }
 
-        let last_offset = (self.file_size().unwrap() + self.chunk_size() - 1) / self.chunk_size();
+        // The file which cannot be read by the ranges(e.x. the exported Google Workspace document) is streamed.
+        if self.stat.is_streamed {
+            let stream = self.backend.open_stream().await?;
+            return stream_download(stream, self.chunk_size() as usize, sender).await;
+        }
+
+        // The empty file is sent as one empty chunk, so the target is created too.
+        // SAFETY: The downloadable file which is not streamed always has the size.
+        let file_size = self.file_size().unwrap();
+        if file_size == 0 {
+            return send_chunk(&sender, ChunkData::new(vec![], 0, true)).await;
+        }
+
+        let last_offset = file_size.div_ceil(self.chunk_size());
 
-        let mut tasks = vec![];
+        // The chunks are downloaded in parallel up to the concurrency, and the first failure stops the others.
+        let mut tasks = JoinSet::new();
         let arc_sender = Arc::new(sender);
         for offset in 0..last_offset {
+            if tasks.len() >= self.concurrency() as usize {
+                join_download_task(tasks.join_next().await)?;
+            }
             let arc_sender = Arc::clone(&arc_sender);
             let clone_me = self.clone();
 
-            let task: tokio::task::JoinHandle<HikyakuResult<()>> = tokio::spawn(async move {
+            tasks.spawn(async move {
                 let chunk_data = clone_me.partial_download(offset).await?;
-                arc_sender.send(chunk_data).await.unwrap();
-                Ok(())
+                send_chunk(&arc_sender, chunk_data).await
             });
-            tasks.push(task);
+        }
+        while let Some(result) = tasks.join_next().await {
+            join_download_task(Some(result))?;
         }
 
         Ok(())
     }
 }
 
+fn join_download_task(result: Option<Result<HikyakuResult<()>, JoinError>>) -> HikyakuResult<()> {
+    match result {
+        Some(result) => result.map_err(|e| UnknownError(format!("Download task is failed: {:?}", e)))?,
+        None => Ok(()),
+    }
+}
+
+/// Split the streamed file into the chunks and send them in order.
+///
+/// The last chunk is sent when the stream ends, so the empty file is sent as one empty chunk.
+async fn stream_download(mut stream: Box<dyn ReadStream>, chunk_size: usize, sender: Sender<ChunkData>) -> HikyakuResult<()> {
+    let mut buffer = Vec::with_capacity(chunk_size);
+    let mut offset = 0;
+    while let Some(bytes) = stream.next_bytes().await? {
+        buffer.extend_from_slice(&bytes);
+        // The chunk of the just size is kept until it is known whether more data follows.
+        while buffer.len() > chunk_size {
+            let rest = buffer.split_off(chunk_size);
+            let chunk_data = ChunkData::new(std::mem::replace(&mut buffer, rest), offset, false);
+            send_chunk(&sender, chunk_data).await?;
+            offset += 1;
+        }
+    }
+
+    send_chunk(&sender, ChunkData::new(buffer, offset, true)).await
+}
+
+/// Send the chunk to the receiver. The dropped receiver fails the download,
+/// because the rest of the file can never be delivered.
+async fn send_chunk(sender: &Sender<ChunkData>, chunk_data: ChunkData) -> HikyakuResult<()> {
+    let offset = chunk_data.get_offset();
+    sender
+        .send(chunk_data)
+        .await
+        .map_err(|_| UnknownError(format!("The receiver was dropped before the chunk {} was sent", offset)))
+}
+
 impl FileSystemObject {
-    async fn partial_download(&self, offset: u64) -> HikyakuResult<ChunkData> {
+    pub(crate) async fn partial_download(&self, offset: u64) -> HikyakuResult<ChunkData> {
         let chunk_size = self.chunk_size();
         // SAFETY: This method called in download func and it guaranties the filesize is always Some.
         let file_size = self.file_size().unwrap();
@@ -52,138 +104,11 @@ impl FileSystemObject {
         let end = min((offset + 1) * chunk_size - 1, file_size - 1);
         let is_last = end == file_size - 1;
 
-        match self {
-            Self::AmazonS3 {
-                clients,
-                bucket,
-                key,
-                ..
-            } => {
-                let client = clients[(self.concurrency() as u64 % offset) as usize].clone();
-
-                let part = client
-                    .get_object()
-                    .bucket(bucket.as_str())
-                    .key(key.as_str())
-                    .range(format!("bytes={}-{}", start, end))
-                    .send()
-                    .await
-                    .inspect(|obj| debug!("{:#?}", obj))
-                    .map_err(|e| {
-                        error!("Failed to request for getting object: {:#?}", e);
-                        S3Error(format!("{:?}", e))
-                    })?;
-
-                let body = part
-                    .body
-                    .collect()
-                    .await
-                    .map_err(|e| {
-                        error!("Failed to collect body: {:#?}", e);
-                        S3Error(format!("{:?}", e))
-                    })?;
-
-                let bytes = body.to_vec();
-
-                let bytes = if end == file_size - 1 {
-                    bytes[..(end - start + 1) as usize].to_vec()
-                } else {
-                    bytes
-                };
-
-                Ok(ChunkData::new(bytes, offset, is_last))
-            },
-            Self::GoogleDrive {
-                clients,
-                google_drive_token,
-                queryable_file_or_parent_id,
-                ..
-            } => {
-                let client = clients[(self.concurrency() as u64 % offset) as usize].clone();
-                let url = format!("https://www.googleapis.com/drive/v3/files/{}?alt=media", queryable_file_or_parent_id);
-
-                let res = client
-                    .get(url)
-                    .header(AUTHORIZATION, format!("Bearer {}", google_drive_token.get_access_token()))
-                    .header(RANGE, format!("bytes={}-{}", start, end))
-                    .query(&[
-                        ("supportAllDrives", "true")
-                    ])
-                    .send()
-                    .await
-                    .inspect(|obj| debug!("{:#?}", obj))
-                    .map_err(|e| {
-                        error!("Failed to request for Google Drive API: {:#?}", e);
-                        ConnectionError(format!("Failed to send request to Google Drive API: {:?}", e))
-                    })?;
-
-                if !res.status().is_success() {
-                    let status = res.status();
-                    let body = res.text().await.unwrap_or_default();
-                    let message = format!("Google Drive API returned status code: {}, body: {}", status, body);
-                    return Err(ConnectionError(message));
-                }
-
-                let bytes = res
-                    .bytes()
-                    .await
-                    .map_err(|e| {
-                        error!("Failed to collect body: {:#?}", e);
-                        GoogleDriveError(format!("{:?}", e))
-                    })?
-                    .to_vec();
-
-                let bytes = if end == file_size - 1 {
-                    bytes[..(end - start + 1) as usize].to_vec()
-                } else {
-                    bytes
-                };
-
-                Ok(ChunkData::new(bytes, offset, is_last))
-            },
-            Self::Local {
-                path,
-                file,
-                ..
-            } => {
-                let mut file_lock = file.lock().await;
-                if file_lock.is_none() {
-                    let f = File::open(path.as_path())
-                        .await
-                        .map_err(|e| {
-                            error!("Failed to open file: {:#?}", e);
-                            ConnectionError(format!("Failed to open file: {:?}", e))
-                        })?;
-                    *file_lock = Some(f);
-                }
-
-                let file = file_lock.as_mut().unwrap();
-
-                let part_size = min(chunk_size, end - start);
-                let mut buf = vec![0u8; part_size as usize];
-                file.seek(SeekFrom::Start(start))
-                    .await
-                    .map_err(|e| {
-                        error!("Failed to seek file: {:#?}", e);
-                        FileOperationError(format!("Failed to seek file: {:?}", e))
-                    })?;
-                file.read_exact(&mut buf)
-                    .await
-                    .map_err(|e| {
-                        error!("Failed to read file: {:#?}", e);
-                        FileOperationError(format!("Failed to read file: {:?}", e))
-                    })?;
-
-                let bytes = if end == file_size - 1 {
-                    buf[..(end - start + 1) as usize].to_vec()
-                } else {
-                    buf
-                };
-
-                drop(file_lock);
-
-                Ok(ChunkData::new(bytes, offset, is_last))
-            },
+        let bytes = self.backend.read_range(start, end).await?;
+        if bytes.len() as u64 != end - start + 1 {
+            return Err(BackendError(format!("{} returned {} bytes for the range {}-{}", self, bytes.len(), start, end)));
         }
+
+        Ok(ChunkData::new(bytes, offset, is_last))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+use crate::errors::HikyakuResult;
+use crate::services::file_system::FileSystemObject;
+
+#[async_trait]
+pub trait List {
+    /// List the file paths under the path of the file system object.
+    async fn list(&self) -> HikyakuResult<Vec<String>>;
+}
+
+#[async_trait]
+impl List for FileSystemObject {
+    async fn list(&self) -> HikyakuResult<Vec<String>> {
+        self.backend.list().await
+    }
+}
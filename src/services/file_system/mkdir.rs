@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use crate::errors::HikyakuResult;
+use crate::services::file_system::FileSystemObject;
+
+#[async_trait]
+pub trait Mkdir {
+    /// Create the directory at the path of the file system object with its missing parents.
+    ///
+    /// The existing directory is treated as success.
+    async fn mkdir(&self) -> HikyakuResult<()>;
+}
+
+#[async_trait]
+impl Mkdir for FileSystemObject {
+    async fn mkdir(&self) -> HikyakuResult<()> {
+        self.backend.mkdir().await
+    }
+}
@@ -1,114 +1,110 @@
-mod download;
-mod upload;
+pub mod download;
+pub mod upload;
+pub mod list;
+pub mod delete;
+pub mod copy;
+#[cfg(feature = "amazon_s3")]
+pub mod restore;
+#[cfg(feature = "amazon_s3")]
+pub mod presign;
+#[cfg(feature = "amazon_s3")]
+pub mod versions;
+pub mod mkdir;
+pub mod rename;
 
 use std::fmt::{Display, Formatter};
-use std::path::PathBuf;
 use std::sync::Arc;
-use reqwest::Client;
-use aws_sdk_s3::client::Client as S3Client;
-use tokio::fs::File;
-use tokio::sync::Mutex;
-use crate::utils::credential::google_drive_credential::GoogleDriveTokens;
+#[cfg(feature = "amazon_s3")]
+use crate::services::backends::amazon_s3::AmazonS3Backend;
+use crate::services::backends::downcast;
+#[cfg(feature = "google_drive")]
+use crate::services::backends::google_drive::GoogleDriveBackend;
+use crate::services::storage_backend::{BackendStat, StorageBackend};
+#[cfg(feature = "amazon_s3")]
+use crate::types::amazon_s3::{S3ObjectAttributes, S3PathKind};
 
+/// The file(or directory) on one of the file systems, which the transfers and the other operations run on.
+///
+/// The file system is the [StorageBackend] behind it, so the built-in file systems and
+/// the backends registered in [BackendRegistry](crate::services::storage_backend::BackendRegistry)
+/// are handled in the same way.
 #[derive(Clone)]
-pub enum FileSystemObject {
-    AmazonS3 {
-        clients: Vec<Arc<S3Client>>,
-        bucket: Arc<String>,
-        key: Arc<String>,
-        file_size: Option<u64>,
-        chunk_size: u64,
-    },
-    GoogleDrive {
-        clients: Vec<Arc<Client>>,
-        google_drive_token: Arc<GoogleDriveTokens>,
-        queryable_file_or_parent_id: Arc<String>,
-        not_exist_file_paths: Arc<Vec<String>>,
-        upload_filename: Option<Arc<String>>,
-        mime_type: Arc<String>,
-        resumable_upload_url: Arc<Mutex<Option<String>>>,
-        file_size: Option<u64>,
-        chunk_size: u64,
-    },
-    Local {
-        path: Arc<PathBuf>,
-        file: Arc<Mutex<Option<File>>>,
-        is_dir: bool,
-        file_size: Option<u64>,
-        concurrency: u16,
-        chunk_size: u64,
-    },
+pub struct FileSystemObject {
+    backend: Arc<dyn StorageBackend>,
+    stat: BackendStat,
+    concurrency: u16,
+    chunk_size: u64,
 }
 
 impl FileSystemObject {
-    pub(crate) fn is_downloadable(&self) -> bool {
-        match self {
-            Self::AmazonS3 { file_size, .. } |
-            Self::GoogleDrive { file_size, .. } |
-            Self::Local { file_size, .. }=> {
-                match file_size {
-                    Some(_) => true,
-                    None => false,
-                }
-            },
+    pub(crate) fn new(backend: Arc<dyn StorageBackend>, stat: BackendStat, concurrency: u16, chunk_size: u64) -> Self {
+        Self {
+            backend,
+            stat,
+            concurrency,
+            chunk_size,
         }
     }
 
+    /// Get the backend of the file system, which can be used directly for the operations
+    /// which the file system object does not have.
+    pub fn storage_backend(&self) -> Arc<dyn StorageBackend> {
+        Arc::clone(&self.backend)
+    }
+
+    /// Get the backend as the built-in backend `B`, or [None] if it is the other file system.
+    pub(crate) fn backend<B: StorageBackend>(&self) -> Option<&B> {
+        downcast::<B>(self.backend.as_ref())
+    }
+
+    pub(crate) fn is_downloadable(&self) -> bool {
+        // The size of the streamed file(e.x. the exported Google Workspace document) is unknown until it is read.
+        self.stat.file_size.is_some() || self.stat.is_streamed
+    }
+
     pub(crate) fn chunk_size(&self) -> u64 {
-        match self {
-            Self::AmazonS3 { chunk_size, .. } |
-            Self::GoogleDrive { chunk_size, .. } |
-            Self::Local { chunk_size, .. }=> {
-                *chunk_size
-            },
-        }
+        self.chunk_size
     }
 
     pub(crate) fn concurrency(&self) -> u16 {
-        match self {
-            Self::AmazonS3 {clients, ..} => clients.len() as u16,
-            Self::GoogleDrive {clients, ..} => clients.len() as u16,
-            Self::Local {concurrency, ..} => *concurrency,
-        }
+        self.concurrency
     }
 
     pub(crate) fn file_size(&self) -> Option<u64> {
-        match self {
-            Self::AmazonS3 {file_size, ..} |
-            Self::GoogleDrive {file_size, ..} |
-            Self::Local {file_size, ..} => file_size.clone(),
-        }
+        self.stat.file_size
+    }
+
+    /// Get the filename of the exported Google Workspace document with the extension of the export
+    /// format(e.x. `Report.docx` for the Google Docs `Report`). Other files return [None].
+    #[cfg(feature = "google_drive")]
+    pub fn export_filename(&self) -> Option<String> {
+        let GoogleDriveBackend {export: Some(export), upload_filename, ..} = self.backend::<GoogleDriveBackend>()? else {
+            return None;
+        };
+        upload_filename.as_ref().map(|filename| export.filename(filename))
+    }
+
+    /// Get what the Amazon S3 path points to. Other file systems return [None].
+    #[cfg(feature = "amazon_s3")]
+    pub fn s3_path_kind(&self) -> Option<S3PathKind> {
+        self.backend::<AmazonS3Backend>().map(|backend| backend.path_kind)
+    }
+
+    /// Get the attributes(ETag, Content-Type, last-modified and storage class) of the Amazon S3 object.
+    /// If the object does not exist or the file system is not Amazon S3, return [None].
+    #[cfg(feature = "amazon_s3")]
+    pub fn s3_attributes(&self) -> Option<&S3ObjectAttributes> {
+        self.backend::<AmazonS3Backend>()?.attributes.as_deref()
     }
 
     pub fn set_chunk_size(&mut self, size: u64) {
-        match self {
-            Self::AmazonS3 {chunk_size, ..} |
-            Self::GoogleDrive {chunk_size, ..} |
-            Self::Local {chunk_size, ..} => {
-                *chunk_size = size;
-            }
-        }
+        self.chunk_size = size;
     }
 }
 
 impl Display for FileSystemObject {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::AmazonS3 {bucket, key, file_size, ..} => {
-                write!(f, "AmazonS3: bucket: {}, key: {}, file_size: {:?}", bucket, key, file_size)
-            },
-            Self::GoogleDrive {
-                queryable_file_or_parent_id,
-                not_exist_file_paths,
-                upload_filename,
-                mime_type,
-                file_size, ..} => {
-                write!(f, "GoogleDrive: queryable_file_or_parent_id: {}, not_exist_file_paths: {:?}, upload_filename: {:?}, mime_type: {}, file_size: {:?}", queryable_file_or_parent_id, not_exist_file_paths, upload_filename, mime_type, file_size)
-            },
-            Self::Local {path, file_size, ..} => {
-                write!(f, "Local: path: {}, file_size: {:?}", path.display(), file_size)
-            }
-        }
+        Display::fmt(&self.backend, f)
     }
 }
 
@@ -129,15 +125,15 @@ impl ChunkData {
         }
     }
 
-    pub(crate) fn get_data(&self) -> &[u8] {
+    pub fn get_data(&self) -> &[u8] {
         &self.data
     }
 
-    pub(crate) fn get_offset(&self) -> u64 {
+    pub fn get_offset(&self) -> u64 {
         self.offset
     }
 
-    pub(crate) fn is_last(&self) -> bool {
+    pub fn is_last(&self) -> bool {
         self.is_last
     }
 
@@ -145,7 +141,7 @@ impl ChunkData {
         self.data.len()
     }
     
-    pub(crate) fn get_raw_data(self) -> Vec<u8> {
+    pub fn get_raw_data(self) -> Vec<u8> {
         self.data
     }
 }
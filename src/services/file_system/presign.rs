@@ -0,0 +1,266 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use aws_sdk_s3::presigning::{PresignedRequest, PresigningConfig};
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use log::error;
+use crate::errors::HikyakuError::{InvalidArgumentError, S3Error, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::amazon_s3::AmazonS3Backend;
+use crate::services::file_system::FileSystemObject;
+use crate::types::amazon_s3::{apply_customer_key, apply_encryption, apply_upload_options, S3PresignedMultipartUpload, S3PresignedRequest, S3StorageClass, MAX_S3_PARTS};
+
+#[async_trait]
+pub trait Presign {
+    /// Create the presigned GetObject request which is valid for `expires_in`(up to 7 days).
+    async fn presign_get(&self, expires_in: Duration) -> HikyakuResult<S3PresignedRequest>;
+
+    /// Create the presigned PutObject request which is valid for `expires_in`(up to 7 days).
+    ///
+    /// When `content_type` is [None], the Content-Type of the upload options is signed.
+    async fn presign_put(&self, expires_in: Duration, content_type: Option<&str>) -> HikyakuResult<S3PresignedRequest>;
+
+    /// Start the multipart upload and create the presigned UploadPart requests of `part_count` parts.
+    async fn presign_multipart(&self, part_count: i32, expires_in: Duration) -> HikyakuResult<S3PresignedMultipartUpload>;
+
+    /// Complete the presigned multipart upload with the ETags of the parts in the part number order.
+    async fn complete_presigned_multipart(&self, upload_id: &str, e_tags: &[String]) -> HikyakuResult<()>;
+
+    /// Abort the presigned multipart upload and discard the uploaded parts.
+    async fn abort_presigned_multipart(&self, upload_id: &str) -> HikyakuResult<()>;
+}
+
+fn presigning_config(expires_in: Duration) -> HikyakuResult<PresigningConfig> {
+    PresigningConfig::expires_in(expires_in)
+        .map_err(|e| InvalidArgumentError(format!("Invalid presigned URL expiration {:?}: {}", expires_in, e)))
+}
+
+fn to_presigned_request(request: PresignedRequest) -> S3PresignedRequest {
+    let headers = request
+        .headers()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+    S3PresignedRequest::new(request.method(), request.uri(), headers)
+}
+
+#[async_trait]
+impl Presign for FileSystemObject {
+    async fn presign_get(&self, expires_in: Duration) -> HikyakuResult<S3PresignedRequest> {
+        let Some(AmazonS3Backend {clients, bucket, key, options, ..}) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Presigned URL is only supported for Amazon S3. File system object: {}", self)));
+        };
+        let request = first_client(clients)
+            .get_object()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .set_version_id(options.version_id.clone());
+        let presigned = apply_customer_key!(&options.encryption, request)
+            .presigned(presigning_config(expires_in)?)
+            .await
+            .map_err(|e| {
+                error!("Failed to presign get object: {:#?}", e);
+                S3Error(format!("Failed to presign GetObject of s3://{}/{}: {:?}", bucket, key, e))
+            })?;
+
+        Ok(to_presigned_request(presigned))
+    }
+
+    async fn presign_put(&self, expires_in: Duration, content_type: Option<&str>) -> HikyakuResult<S3PresignedRequest> {
+        let Some(AmazonS3Backend {clients, bucket, key, options, ..}) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Presigned URL is only supported for Amazon S3. File system object: {}", self)));
+        };
+        options.ensure_writable(bucket, key)?;
+        let request = first_client(clients)
+            .put_object()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .set_storage_class(options.storage_class.as_ref().map(S3StorageClass::to_sdk));
+        let request = apply_upload_options!(&options.upload_options, key, request);
+        let request = match content_type {
+            Some(content_type) => request.content_type(content_type),
+            None => request,
+        };
+        let presigned = apply_encryption!(&options.encryption, request)
+            .presigned(presigning_config(expires_in)?)
+            .await
+            .map_err(|e| {
+                error!("Failed to presign put object: {:#?}", e);
+                S3Error(format!("Failed to presign PutObject of s3://{}/{}: {:?}", bucket, key, e))
+            })?;
+
+        Ok(to_presigned_request(presigned))
+    }
+
+    async fn presign_multipart(&self, part_count: i32, expires_in: Duration) -> HikyakuResult<S3PresignedMultipartUpload> {
+        // Amazon S3 accepts the part number from 1 to 10,000.
+        if !(1..=MAX_S3_PARTS as i32).contains(&part_count) {
+            return Err(InvalidArgumentError(format!("Part count must be between 1 and {} but got {}", MAX_S3_PARTS, part_count)));
+        }
+
+        let Some(AmazonS3Backend {clients, bucket, key, options, ..}) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Presigned URL is only supported for Amazon S3. File system object: {}", self)));
+        };
+        options.ensure_writable(bucket, key)?;
+        let config = presigning_config(expires_in)?;
+        let client = first_client(clients);
+
+        let request = client
+            .create_multipart_upload()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .set_storage_class(options.storage_class.as_ref().map(S3StorageClass::to_sdk));
+        let request = apply_upload_options!(&options.upload_options, key, request);
+        let response = apply_encryption!(&options.encryption, request)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to create multipart upload: {:#?}", e);
+                S3Error(format!("Failed to create multipart upload to s3://{}/{}: {:?}", bucket, key, e))
+            })?;
+        let upload_id = response
+            .upload_id()
+            .ok_or_else(|| S3Error(format!("Upload ID is not returned for s3://{}/{}", bucket, key)))?;
+
+        let mut parts = vec![];
+        for part_number in 1..=part_count {
+            let request = client
+                .upload_part()
+                .bucket(bucket.as_str())
+                .key(key.as_str())
+                .upload_id(upload_id)
+                .part_number(part_number);
+            let presigned = apply_customer_key!(&options.encryption, request)
+                .presigned(config.clone())
+                .await
+                .map_err(|e| {
+                    error!("Failed to presign upload part: {:#?}", e);
+                    S3Error(format!("Failed to presign UploadPart {} of s3://{}/{}: {:?}", part_number, bucket, key, e))
+                })?;
+            parts.push(to_presigned_request(presigned));
+        }
+
+        Ok(S3PresignedMultipartUpload::new(upload_id, parts))
+    }
+
+    async fn complete_presigned_multipart(&self, upload_id: &str, e_tags: &[String]) -> HikyakuResult<()> {
+        let Some(AmazonS3Backend {clients, bucket, key, ..}) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Presigned URL is only supported for Amazon S3. File system object: {}", self)));
+        };
+        let parts = e_tags
+            .iter()
+            .enumerate()
+            .map(|(index, e_tag)| CompletedPart::builder()
+                .part_number(index as i32 + 1)
+                .e_tag(e_tag)
+                .build())
+            .collect::<Vec<_>>();
+
+        first_client(clients)
+            .complete_multipart_upload()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .upload_id(upload_id)
+            .multipart_upload(CompletedMultipartUpload::builder().set_parts(Some(parts)).build())
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to complete multipart upload: {:#?}", e);
+                S3Error(format!("Failed to complete multipart upload to s3://{}/{}: {:?}", bucket, key, e))
+            })?;
+
+        Ok(())
+    }
+
+    async fn abort_presigned_multipart(&self, upload_id: &str) -> HikyakuResult<()> {
+        let Some(AmazonS3Backend {clients, bucket, key, ..}) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Presigned URL is only supported for Amazon S3. File system object: {}", self)));
+        };
+        first_client(clients)
+            .abort_multipart_upload()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .upload_id(upload_id)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to abort multipart upload: {:#?}", e);
+                S3Error(format!("Failed to abort multipart upload to s3://{}/{}: {:?}", bucket, key, e))
+            })?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::services::file_system_builder::FileSystemBuilder;
+    use crate::types::amazon_s3::S3UploadOptions;
+    use crate::utils::test_support::fake_s3::FakeS3;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_presign_amazon_s3() {
+        let s3 = FakeS3::start().await;
+        s3.put_object("bucket", "shared/report.csv", b"a,b,c".to_vec());
+
+        let file_obj = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/shared/report.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let get = file_obj.presign_get(Duration::from_secs(600)).await.unwrap();
+        assert_eq!(get.get_method(), "GET");
+        assert!(get.get_url().contains("/bucket/shared/report.csv?"));
+        assert!(get.get_url().contains("X-Amz-Expires=600"));
+        assert!(get.get_url().contains("X-Amz-Signature="));
+        let body = reqwest::get(get.get_url()).await.unwrap().bytes().await.unwrap();
+        assert_eq!(body.as_ref(), b"a,b,c");
+
+        let put = file_obj.presign_put(Duration::from_secs(600), Some("text/csv")).await.unwrap();
+        assert_eq!(put.get_method(), "PUT");
+        let mut request = reqwest::Client::new().put(put.get_url()).body("x,y,z");
+        for (name, value) in put.get_headers() {
+            request = request.header(name, value);
+        }
+        assert!(request.send().await.unwrap().status().is_success());
+
+        let object = s3.get_object("bucket", "shared/report.csv").unwrap();
+        assert_eq!(object.data, b"x,y,z".to_vec());
+        assert_eq!(object.headers["content-type"], "text/csv");
+
+        assert!(file_obj.presign_get(Duration::from_secs(8 * 24 * 60 * 60)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_presign_multipart_amazon_s3() {
+        let s3 = FakeS3::start().await;
+        let file_obj = FileSystemBuilder::from(s3.credential())
+            .set_upload_options(S3UploadOptions::new().set_metadata("source", "browser"))
+            .set_file_path("s3://bucket/upload.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let upload = file_obj.presign_multipart(2, Duration::from_secs(600)).await.unwrap();
+        assert_eq!(upload.get_parts().len(), 2);
+
+        let mut e_tags = vec![];
+        for (part, data) in upload.get_parts().iter().zip(["first-", "second"]) {
+            let response = reqwest::Client::new().put(part.get_url()).body(data).send().await.unwrap();
+            e_tags.push(response.headers()["etag"].to_str().unwrap().to_string());
+        }
+        file_obj.complete_presigned_multipart(upload.get_upload_id(), &e_tags).await.unwrap();
+
+        let object = s3.get_object("bucket", "upload.bin").unwrap();
+        assert_eq!(object.data, b"first-second".to_vec());
+        assert_eq!(object.headers["x-amz-meta-source"], "browser");
+
+        let upload = file_obj.presign_multipart(1, Duration::from_secs(600)).await.unwrap();
+        file_obj.abort_presigned_multipart(upload.get_upload_id()).await.unwrap();
+        assert!(file_obj.complete_presigned_multipart(upload.get_upload_id(), &e_tags).await.is_err());
+    }
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use log::warn;
+use crate::errors::HikyakuResult;
+use crate::services::file_system::FileSystemObject;
+
+#[async_trait]
+pub trait ServerSideMove {
+    /// Move(rename) this file system object to the `destination` inside the server
+    /// without transferring the data through the local machine.
+    ///
+    /// The existing file at the `destination` is overwritten.
+    async fn move_to(&self, destination: &FileSystemObject) -> HikyakuResult<()>;
+}
+
+#[async_trait]
+impl ServerSideMove for FileSystemObject {
+    async fn move_to(&self, destination: &FileSystemObject) -> HikyakuResult<()> {
+        if destination.is_downloadable() {
+            warn!("The same name file is already exist. Please caution.");
+        }
+
+        self.backend.move_to(destination.backend.as_ref()).await
+    }
+}
@@ -0,0 +1,155 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use aws_sdk_s3::error::ProvideErrorMetadata;
+use aws_sdk_s3::types::{GlacierJobParameters, RestoreRequest};
+use log::{debug, error};
+use tokio::time::Instant;
+use crate::errors::HikyakuError::{ArchivedObjectError, InvalidArgumentError, NotExistFileError, S3Error, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::amazon_s3::AmazonS3Backend;
+use crate::services::file_system::FileSystemObject;
+use crate::types::amazon_s3::{S3RestoreStatus, S3RestoreTier};
+
+#[async_trait]
+pub trait Restore {
+    /// Request to restore the archived object for `days` days with the retrieval `tier`.
+    ///
+    /// When the restore is already in progress, the request is treated as success.
+    async fn restore(&self, days: i32, tier: S3RestoreTier) -> HikyakuResult<()>;
+
+    /// Get the current restore status of the object.
+    async fn restore_status(&self) -> HikyakuResult<S3RestoreStatus>;
+
+    /// Wait until the object becomes readable by polling the restore status every `poll_interval`.
+    ///
+    /// Returns an `ArchivedObjectError` if the restore is not requested or
+    /// it is not completed within the `timeout`.
+    async fn wait_for_restore(&self, poll_interval: Duration, timeout: Option<Duration>) -> HikyakuResult<()>;
+}
+
+#[async_trait]
+impl Restore for FileSystemObject {
+    async fn restore(&self, days: i32, tier: S3RestoreTier) -> HikyakuResult<()> {
+        if days < 1 {
+            return Err(InvalidArgumentError(format!("Restore days must be 1 or more but got {}", days)));
+        }
+
+        let Some(AmazonS3Backend {clients, bucket, key, options, file_size, ..}) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Restore is only supported for Amazon S3. File system object: {}", self)));
+        };
+        if file_size.is_none() {
+            return Err(NotExistFileError(format!("s3://{}/{} does not exist", bucket, key)));
+        }
+
+        let glacier_job_parameters = GlacierJobParameters::builder()
+            .tier(tier.to_sdk())
+            .build()
+            .map_err(|e| S3Error(format!("Failed to build the restore request: {:?}", e)))?;
+        let restore_request = RestoreRequest::builder()
+            .days(days)
+            .glacier_job_parameters(glacier_job_parameters)
+            .build();
+
+        let result = first_client(clients)
+            .restore_object()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .set_version_id(options.version_id.clone())
+            .restore_request(restore_request)
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) if e.code() == Some("RestoreAlreadyInProgress") => {
+                debug!("Restore is already in progress: s3://{}/{}", bucket, key);
+                Ok(())
+            },
+            Err(e) => {
+                error!("Failed to restore object: {:#?}", e);
+                Err(S3Error(format!("Failed to restore s3://{}/{}: {:?}", bucket, key, e)))
+            },
+        }
+    }
+
+    async fn restore_status(&self) -> HikyakuResult<S3RestoreStatus> {
+        let Some(backend) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Restore is only supported for Amazon S3. File system object: {}", self)));
+        };
+
+        backend.restore_status().await
+    }
+
+    async fn wait_for_restore(&self, poll_interval: Duration, timeout: Option<Duration>) -> HikyakuResult<()> {
+        let started_at = Instant::now();
+
+        loop {
+            match self.restore_status().await? {
+                S3RestoreStatus::NotArchived | S3RestoreStatus::Restored {..} => return Ok(()),
+                S3RestoreStatus::Archived => {
+                    return Err(ArchivedObjectError(format!("Restore is not requested. File system object: {}", self)));
+                },
+                S3RestoreStatus::InProgress => {
+                    if let Some(timeout) = timeout {
+                        if started_at.elapsed() + poll_interval > timeout {
+                            return Err(ArchivedObjectError(format!("Restore is not completed within {:?}. File system object: {}", timeout, self)));
+                        }
+                    }
+                    debug!("Restore is in progress. Wait {:?}", poll_interval);
+                    tokio::time::sleep(poll_interval).await;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::channel;
+    use crate::errors::HikyakuError;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system_builder::FileSystemBuilder;
+    use crate::utils::test_support::fake_s3::FakeS3;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_restore_amazon_s3() {
+        let s3 = FakeS3::start().await;
+        s3.put_object_with_headers("bucket", "archive.bin", b"archived".to_vec(), &[("x-amz-storage-class", "DEEP_ARCHIVE")]);
+
+        let file_obj = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/archive.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(file_obj.s3_attributes().unwrap().get_restore_status(), &S3RestoreStatus::Archived);
+
+        let (sender, _receiver) = channel(1);
+        assert!(matches!(file_obj.download(sender).await, Err(HikyakuError::ArchivedObjectError(_))));
+        // The archived object is detected by HeadObject without requesting the object data.
+        assert!(!s3.requests().iter().any(|request| request.method == "GET"));
+        assert_eq!(file_obj.restore_status().await.unwrap(), S3RestoreStatus::Archived);
+        assert!(file_obj.wait_for_restore(Duration::from_millis(10), None).await.is_err());
+
+        file_obj.restore(3, S3RestoreTier::Bulk).await.unwrap();
+        // The second request is accepted while the restore is in progress.
+        file_obj.restore(3, S3RestoreTier::Bulk).await.unwrap();
+        assert_eq!(file_obj.restore_status().await.unwrap(), S3RestoreStatus::InProgress);
+        assert!(file_obj
+            .wait_for_restore(Duration::from_millis(10), Some(Duration::from_millis(30)))
+            .await
+            .is_err());
+
+        s3.complete_restore("bucket", "archive.bin");
+        file_obj.wait_for_restore(Duration::from_millis(10), None).await.unwrap();
+
+        let (sender, mut receiver) = channel(1);
+        file_obj.download(sender).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap().get_data(), b"archived");
+
+        let request = s3.requests().into_iter().find(|request| request.query.contains_key("restore")).unwrap();
+        assert_eq!(request.method, "POST");
+    }
+}
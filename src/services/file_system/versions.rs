@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use std::time::SystemTime;
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::DateTime;
+use log::error;
+use crate::errors::HikyakuError::{NotExistFileError, S3Error, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::amazon_s3::AmazonS3Backend;
+use crate::services::file_system::FileSystemObject;
+use crate::types::amazon_s3::{apply_customer_key, S3ObjectVersion, S3Options, S3PathKind};
+
+#[async_trait]
+pub trait Versions {
+    /// List the versions and the delete markers of the object from the newest.
+    async fn list_versions(&self) -> HikyakuResult<Vec<S3ObjectVersion>>;
+
+    /// Copy the `version_id` of the object over the current version.
+    ///
+    /// The copied data becomes the new current version and the older versions are kept.
+    async fn promote_version(&self, version_id: &str) -> HikyakuResult<()>;
+}
+
+fn to_system_time(date_time: Option<&DateTime>) -> Option<SystemTime> {
+    date_time.and_then(|date_time| SystemTime::try_from(*date_time).ok())
+}
+
+#[async_trait]
+impl Versions for FileSystemObject {
+    async fn list_versions(&self) -> HikyakuResult<Vec<S3ObjectVersion>> {
+        let Some(AmazonS3Backend {clients, bucket, key, ..}) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Versioning is only supported for Amazon S3. File system object: {}", self)));
+        };
+        let client = first_client(clients);
+        let mut versions = vec![];
+        let mut key_marker = None;
+        let mut version_id_marker = None;
+
+        loop {
+            let output = client
+                .list_object_versions()
+                .bucket(bucket.as_str())
+                .prefix(key.as_str())
+                .set_key_marker(key_marker)
+                .set_version_id_marker(version_id_marker)
+                .send()
+                .await
+                .map_err(|e| {
+                    error!("Failed to list object versions: {:#?}", e);
+                    S3Error(format!("Failed to list versions of s3://{}/{}: {:?}", bucket, key, e))
+                })?;
+
+            // The prefix also matches the other keys(e.x. `key.bak`), so keep only the exact key.
+            versions.extend(output
+                .versions()
+                .iter()
+                .filter(|version| version.key() == Some(key.as_str()))
+                .map(|version| S3ObjectVersion {
+                    key: key.to_string(),
+                    version_id: version.version_id().unwrap_or("null").to_string(),
+                    is_latest: version.is_latest().unwrap_or(false),
+                    is_delete_marker: false,
+                    size: version.size().map(|size| size as u64),
+                    last_modified: to_system_time(version.last_modified()),
+                    e_tag: version.e_tag().map(String::from),
+                }));
+            versions.extend(output
+                .delete_markers()
+                .iter()
+                .filter(|marker| marker.key() == Some(key.as_str()))
+                .map(|marker| S3ObjectVersion {
+                    key: key.to_string(),
+                    version_id: marker.version_id().unwrap_or("null").to_string(),
+                    is_latest: marker.is_latest().unwrap_or(false),
+                    is_delete_marker: true,
+                    size: None,
+                    last_modified: to_system_time(marker.last_modified()),
+                    e_tag: None,
+                }));
+
+            if !output.is_truncated().unwrap_or(false) {
+                break;
+            }
+            key_marker = output.next_key_marker().map(String::from);
+            version_id_marker = output.next_version_id_marker().map(String::from);
+        }
+
+        // The versions and the delete markers are returned separately, so merge them by the time.
+        versions.sort_by_key(|version| std::cmp::Reverse(version.last_modified));
+
+        Ok(versions)
+    }
+
+    async fn promote_version(&self, version_id: &str) -> HikyakuResult<()> {
+        let Some(backend) = self.backend::<AmazonS3Backend>() else {
+            return Err(UnsupportedError(format!("Versioning is only supported for Amazon S3. File system object: {}", self)));
+        };
+        let AmazonS3Backend {clients, bucket, key, options, ..} = backend;
+        let request = first_client(clients)
+            .head_object()
+            .bucket(bucket.as_str())
+            .key(key.as_str())
+            .version_id(version_id);
+        let output = apply_customer_key!(&options.encryption, request)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to head object version: {:#?}", e);
+                NotExistFileError(format!("Version {} of s3://{}/{} cannot be read: {:?}", version_id, bucket, key, e))
+            })?;
+
+        let source = AmazonS3Backend {
+            options: Arc::new(S3Options {
+                version_id: Some(version_id.to_string()),
+                ..options.as_ref().clone()
+            }),
+            path_kind: S3PathKind::Object,
+            attributes: None,
+            file_size: output.content_length().map(|size| size as u64),
+            ..backend.clone()
+        };
+
+        self.backend.copy_from(&source, self.chunk_size()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::channel;
+    use crate::errors::HikyakuError;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::upload::Upload;
+    use crate::services::file_system::ChunkData;
+    use crate::services::file_system_builder::FileSystemBuilder;
+    use crate::utils::test_support::fake_s3::FakeS3;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_versions_amazon_s3() {
+        let s3 = FakeS3::start().await;
+        let first = s3.put_object("bucket", "data.txt", b"first".to_vec());
+        s3.put_object("bucket", "data.txt.bak", b"backup".to_vec());
+        s3.delete_object("bucket", "data.txt");
+        let second = s3.put_object("bucket", "data.txt", b"second!".to_vec());
+
+        let current = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/data.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let versions = current.list_versions().await.unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[0].get_version_id(), second);
+        assert!(versions[0].is_latest());
+        assert!(versions[1].is_delete_marker());
+        assert_eq!(versions[2].get_version_id(), first);
+        assert_eq!(versions[2].get_size(), Some(5));
+
+        // The version id in the path reads the old version.
+        let old = FileSystemBuilder::from(s3.credential())
+            .set_file_path(&format!("s3://bucket/data.txt?versionId={}", first))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(old.to_string().contains("file_size: Some(5)"));
+        let (sender, mut receiver) = channel(1);
+        old.download(sender).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap().get_data(), b"first");
+        let request = s3.requests().into_iter().rev().find(|request| request.method == "GET").unwrap();
+        assert_eq!(request.query["versionId"], first);
+
+        // The specific version is immutable.
+        let (sender, receiver) = channel(1);
+        sender.send(ChunkData::new(b"new".to_vec(), 0, true)).await.unwrap();
+        drop(sender);
+        assert!(old.upload(receiver).await.is_err());
+
+        current.promote_version(&first).await.unwrap();
+        assert_eq!(s3.get_object("bucket", "data.txt").unwrap().data, b"first".to_vec());
+        assert_eq!(current.list_versions().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_version_id_conflict_amazon_s3() {
+        let s3 = FakeS3::start().await;
+        let result = FileSystemBuilder::from(s3.credential())
+            .set_version_id("version-a")
+            .set_file_path("s3://bucket/data.txt?versionId=version-b")
+            .unwrap()
+            .build()
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_version_id_in_key_amazon_s3() {
+        let s3 = FakeS3::start().await;
+        let version_id = s3.put_object("bucket", "data.txt?versionId=draft", b"draft".to_vec());
+
+        let empty = FileSystemBuilder::from(s3.credential())
+            .set_file_path("s3://bucket/data.txt?versionId=")
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(empty, Err(HikyakuError::InvalidArgumentError(_))));
+
+        // The key which contains `?versionId=` is split at the last one.
+        let file_obj = FileSystemBuilder::from(s3.credential())
+            .set_file_path(&format!("s3://bucket/data.txt?versionId=draft?versionId={}", version_id))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let (sender, mut receiver) = channel(1);
+        file_obj.download(sender).await.unwrap();
+        assert_eq!(receiver.recv().await.unwrap().get_data(), b"draft");
+    }
+}
@@ -1,21 +1,101 @@
 use std::sync::Arc;
-use aws_config::BehaviorVersion;
+use std::time::SystemTime;
+use aws_config::{BehaviorVersion, SdkConfig};
 use aws_sdk_s3::Client;
-use crate::errors::HikyakuError::{BuilderError, InvalidArgumentError};
+use aws_sdk_s3::config::{Builder as S3ConfigBuilder, Config as S3Config, RequestChecksumCalculation, ResponseChecksumValidation};
+use crate::errors::HikyakuError::{BuilderError, InvalidArgumentError, NotExistFileError};
 use crate::errors::{HikyakuError, HikyakuResult};
+use crate::services::backends::amazon_s3::AmazonS3Backend;
 use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
 use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::amazon_s3::{apply_customer_key, S3Encryption, S3ObjectAttributes, S3Options, S3PathKind, S3RestoreStatus, S3StorageClass, S3UploadOptions};
 use crate::types::FileInfo;
 use crate::utils::credential::Credential;
 use crate::utils::credential::s3_credential::S3Credential;
 use crate::utils::parser::FileSystemParseResult;
 
 impl FileSystemBuilder<S3Credential, FileSystemParseResult> {
+    /// Sets the server-side encryption for the objects written by the built `FileSystemObject`.
+    ///
+    /// The setting applies to PutObject, multipart upload and server-side copy. When the
+    /// encryption is SSE-C, the same key is also sent to read the object.
+    ///
+    /// # Arguments
+    ///
+    /// * `encryption` - A [S3Encryption] which specifies the encryption method and key.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_encryption(mut self, encryption: S3Encryption) -> Self {
+        self.options.encryption = Some(encryption);
+        self
+    }
+
+    /// Sets the storage class of the objects written by the built `FileSystemObject`.
+    ///
+    /// The setting applies to PutObject, multipart upload and server-side copy.
+    /// If it is not set, the bucket default(usually `STANDARD`) is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage_class` - A [S3StorageClass] like `S3StorageClass::DeepArchive`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_storage_class(mut self, storage_class: S3StorageClass) -> Self {
+        self.options.storage_class = Some(storage_class);
+        self
+    }
+
+    /// Sets the object headers(user metadata, tags, Content-Type, Content-Disposition,
+    /// Cache-Control and canned ACL) of the objects written by the built `FileSystemObject`.
+    ///
+    /// # Arguments
+    ///
+    /// * `upload_options` - A [S3UploadOptions] which holds the headers.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_upload_options(mut self, upload_options: S3UploadOptions) -> Self {
+        self.options.upload_options = upload_options;
+        self
+    }
+
+    /// Sets the version id of the object to read in the versioned bucket.
+    ///
+    /// The version id can also be given in the path like `s3://bucket/key?versionId=...`.
+    /// The path is split at the last `?versionId=`, so the key which contains `?versionId=` itself
+    /// is given with the version id suffix(e.x. `s3://bucket/a?versionId=b?versionId=...`) and
+    /// cannot be read without the version by the path.
+    /// The built `FileSystemObject` reads the specific version and cannot be the upload destination.
+    ///
+    /// # Arguments
+    ///
+    /// * `version_id` - A string slice of the version id.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_version_id(mut self, version_id: &str) -> Self {
+        self.options.version_id = Some(version_id.to_string());
+        self
+    }
+
     /// Builds a `FileSystemObject` for Amazon S3 using specified credentials and file information.
     ///
     /// This function validates the file path to ensure it has the "s3://" prefix and then
     /// extracts the bucket and key information. It loads AWS configuration using the given
-    /// credentials, creates S3 clients and retrieves the file size for the given object.
+    /// credentials, creates S3 clients and looks up the path by HeadObject. When the object exists,
+    /// its size, ETag, Content-Type, last-modified and restore status are retrieved so that the
+    /// archived objects(GLACIER, DEEP_ARCHIVE) are detected before downloading. Otherwise, the path
+    /// is resolved as a prefix with children or absent. When the path has the `?versionId=` suffix
+    /// or the version id is set, the specific version is looked up.
+    /// When the credential has a custom endpoint URL, the clients connect to it instead of
+    /// the standard AWS endpoint so that S3-compatible storages can be used.
     ///
     /// # Returns
     ///
@@ -24,7 +104,8 @@ impl FileSystemBuilder<S3Credential, FileSystemParseResult> {
     ///
     /// # Errors
     ///
-    /// Returns an `InvalidArgumentError` if the file prefix is not "s3://".
+    /// Returns an `InvalidArgumentError` if the file prefix is not "s3://", the version id is empty or
+    /// the version id in the path conflicts with the one set on the builder.
     /// Returns a `BuilderError` if the bucket name cannot be found or the path is not set.
     ///
     /// # Example
@@ -45,7 +126,7 @@ impl FileSystemBuilder<S3Credential, FileSystemParseResult> {
     ///     assert!(file_obj.to_string().contains("AmazonS3"));
     /// }
     /// ```
-    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+    pub async fn build(mut self) -> HikyakuResult<FileSystemObject> {
         let (bucket, key) = match self.file_info.borrow().as_ref() {
             Some(file_info) => {
                 if file_info.get_prefix() != "s3://" {
@@ -62,58 +143,166 @@ impl FileSystemBuilder<S3Credential, FileSystemParseResult> {
             }
         };
 
+        // The version id can be given as the query of the path(s3://bucket/key?versionId=...).
+        let key = match key.rsplit_once("?versionId=") {
+            Some((_, "")) => {
+                return Err(InvalidArgumentError(format!("Version id is empty in the path: {}", key)));
+            },
+            Some((key, version_id)) => {
+                let s3_options = &mut self.options;
+                match &s3_options.version_id {
+                    Some(set_version_id) if set_version_id != version_id => {
+                        return Err(InvalidArgumentError(format!(
+                            "Version id in the path({}) conflicts with the builder setting({})", version_id, set_version_id)));
+                    },
+                    _ => s3_options.version_id = Some(version_id.to_string()),
+                }
+
+                key.to_string()
+            },
+            None => key,
+        };
+        if self.options.version_id.as_deref() == Some("") {
+            return Err(InvalidArgumentError("Version id is empty".to_string()));
+        }
+
         let file_system_credential = self.file_system_credential;
 
+        // The newer behavior versions change the defaults of S3(e.x. the flexible checksums),
+        // so the version is pinned until they are verified with the S3-compatible storages.
+        #[allow(deprecated)]
         let shared_config = aws_config::defaults(BehaviorVersion::v2024_03_28())
             .region(file_system_credential.get_region())
             .credentials_provider(file_system_credential.get_credential())
             .load()
             .await;
+        let s3_config = Self::s3_config(&file_system_credential, &shared_config);
         let concurrency = self.concurrency.borrow().to_owned();
         let clients = (0..concurrency)
-            .map(|_| Arc::new(Client::new(&shared_config)))
+            .map(|_| Arc::new(Client::from_conf(s3_config.clone())))
             .collect::<Vec<_>>();
-        let client = Client::new(&shared_config);
+        let client = Client::from_conf(s3_config);
 
-        let file_size = Self::get_file_size(client, &bucket, &key).await?;
+        let options = self.options;
+        let (path_kind, attributes, file_size) = Self::head_path(client, &bucket, &key, &options).await?;
 
-        let file_obj = FileSystemObject::AmazonS3 {
+        let backend = AmazonS3Backend {
             clients,
             bucket: Arc::new(bucket),
             key: Arc::new(key),
+            options: Arc::new(options),
+            path_kind,
+            attributes: attributes.map(Arc::new),
             file_size,
-            chunk_size: self.chunk_size.into_inner(),
         };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
 
         Ok(file_obj)
     }
 
-    async fn get_file_size(client: Client, bucket: &str, key: &str) -> HikyakuResult<Option<u64>> {
+    /// Creates the S3 client configuration from the shared configuration and
+    /// the S3-compatible storage settings of the credential.
+    fn s3_config(credential: &S3Credential, shared_config: &SdkConfig) -> S3Config {
+        let mut builder = S3ConfigBuilder::from(shared_config)
+            .force_path_style(credential.is_force_path_style());
+
+        if let Some(endpoint_url) = credential.get_endpoint_url() {
+            builder = builder.endpoint_url(endpoint_url);
+        }
+
+        if credential.is_checksum_compat() {
+            builder = builder
+                .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+                .response_checksum_validation(ResponseChecksumValidation::WhenRequired);
+        }
+
+        builder.build()
+    }
+
+    /// Look up the path by HeadObject. If the object does not exist, check whether the path is
+    /// a prefix which has children(a.k.a. directory) by ListObjectsV2.
+    async fn head_path(client: Client,
+                       bucket: &str,
+                       key: &str,
+                       options: &S3Options) -> HikyakuResult<(S3PathKind, Option<S3ObjectAttributes>, Option<u64>)> {
+        // The bucket root is not an object.
+        if !key.is_empty() {
+            let request = client
+                .head_object()
+                .bucket(bucket)
+                .key(key)
+                .set_version_id(options.version_id.clone());
+            let result = apply_customer_key!(&options.encryption, request)
+                .send()
+                .await;
+
+            match result {
+                Ok(output) => {
+                    let storage_class = output.storage_class().map(|storage_class| storage_class.as_str());
+                    let attributes = S3ObjectAttributes {
+                        e_tag: output.e_tag().map(String::from),
+                        content_type: output.content_type().map(String::from),
+                        last_modified: output.last_modified().and_then(|date_time| SystemTime::try_from(*date_time).ok()),
+                        storage_class: storage_class.map(S3StorageClass::from_name),
+                        restore_status: S3RestoreStatus::from_restore_header(storage_class, output.restore()),
+                    };
+
+                    return Ok((
+                        S3PathKind::Object,
+                        Some(attributes),
+                        output.content_length().map(|size| size as u64)));
+                },
+                Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {},
+                // HeadObject has no body so the delete marker is returned as 405 without the error code.
+                Err(e) if e.raw_response().is_some_and(|response| response.status().as_u16() == 405) => {
+                    return Err(NotExistFileError(format!(
+                        "Version {:?} of s3://{}/{} is a delete marker", options.version_id, bucket, key)));
+                },
+                Err(e) => {
+                    return Err(HikyakuError::ConnectionError(format!("Failed to head object: {}", e)));
+                },
+            }
+        }
+
+        // The specific version never be a prefix.
+        if options.version_id.is_some() {
+            return Ok((S3PathKind::Absent, None, None));
+        }
+
+        let prefix = if key.is_empty() { String::new() } else { format!("{}/", key) };
         let result = client
             .list_objects_v2()
             .bucket(bucket)
-            .prefix(key)
+            .prefix(prefix)
+            .max_keys(1)
             .send()
             .await
             .map_err(|e| {
                 HikyakuError::ConnectionError(format!("Failed to get objects: {}", e))
             })?;
 
-        let objects = result.contents();
-        if objects.len() != 1 {
-            Ok(None)
-        }
-        else {
-            // This objects always has 1 object.
-            let object = objects.get(0).unwrap();
-
-            Ok(object.size().map(|size| size as u64))
+        if result.contents().is_empty() {
+            Ok((S3PathKind::Absent, None, None))
+        } else {
+            Ok((S3PathKind::Prefix, None, None))
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::ChunkData;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::upload::Upload;
+    use crate::errors::HikyakuError::InvalidArgumentError;
+    use crate::types::amazon_s3::{S3CannedAcl, S3CustomerKey, MIN_S3_PART_SIZE};
+    use crate::utils::test_support::fake_s3::FakeS3;
+    use crate::utils::region::aws::AWSRegion;
     use super::*;
 
     #[tokio::test]
@@ -129,4 +318,236 @@ mod tests {
         assert!(file_obj.to_string().contains("AmazonS3"));
         assert!(file_obj.to_string().contains("train.csv"));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_build_amazon_s3_custom_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 8192];
+            let n = stream.read(&mut buf).await.unwrap();
+            let response = "HTTP/1.1 200 OK\r\nContent-Type: text/csv\r\nContent-Length: 42\r\n\
+                ETag: \"abc\"\r\nConnection: close\r\n\r\n";
+            stream.write_all(response.as_bytes()).await.unwrap();
+
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+
+        let cred = S3Credential::new("access_key", "secret_key", None, None, AWSRegion::Tokyo)
+            .set_endpoint_url(&endpoint)
+            .set_force_path_style(true)
+            .set_checksum_compat(true);
+        let file_obj = FileSystemBuilder::from(cred)
+            .set_file_path("s3://test-bucket/datas/train.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(file_obj.to_string().contains("file_size: Some(42)"));
+        let request = server.await.unwrap();
+        assert!(request.starts_with("HEAD /test-bucket/datas/train.csv"));
+    }
+
+    #[tokio::test]
+    async fn test_build_amazon_s3_path_kind() {
+        let s3 = FakeS3::start().await;
+        s3.put_object_with_headers("bucket", "data.csv", b"a,b".to_vec(), &[("content-type", "text/csv")]);
+        s3.put_object("bucket", "data.csv.bak", b"old".to_vec());
+        s3.put_object("bucket", "dir/child.txt", b"child".to_vec());
+
+        let build = |path: &'static str| {
+            let credential = s3.credential();
+            async move {
+                FileSystemBuilder::from(credential)
+                    .set_file_path(path)
+                    .unwrap()
+                    .build()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // The other key which has the same prefix does not hide the object.
+        let object = build("s3://bucket/data.csv").await;
+        assert_eq!(object.s3_path_kind(), Some(S3PathKind::Object));
+        assert!(object.to_string().contains("file_size: Some(3)"));
+        let attributes = object.s3_attributes().unwrap();
+        assert_eq!(attributes.get_content_type(), Some("text/csv"));
+        assert!(attributes.get_e_tag().is_some());
+        assert!(attributes.get_last_modified().is_some());
+
+        let prefix = build("s3://bucket/dir").await;
+        assert_eq!(prefix.s3_path_kind(), Some(S3PathKind::Prefix));
+        assert!(prefix.s3_attributes().is_none());
+        assert!(prefix.to_string().contains("file_size: None"));
+
+        let absent = build("s3://bucket/missing.csv").await;
+        assert_eq!(absent.s3_path_kind(), Some(S3PathKind::Absent));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_amazon_s3_encryption_and_storage_class() {
+        let s3 = FakeS3::start().await;
+        // Every part except the last must be 5 MiB or larger.
+        let data = (0..3 * MIN_S3_PART_SIZE + 8).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        s3.put_object("bucket", "source.bin", data.clone());
+
+        let customer_key = S3CustomerKey::new(&[7u8; 32]).unwrap();
+        let builder = FileSystemBuilder::from(s3.credential())
+            .set_encryption(S3Encryption::CustomerKey(customer_key.clone()));
+        builder.chunk_size(MIN_S3_PART_SIZE);
+        let src = builder
+            .set_file_path("s3://bucket/source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let builder = FileSystemBuilder::from(s3.credential())
+            .set_encryption(S3Encryption::Kms {
+                key_id: Some("my-key".to_string()),
+                encryption_context: BTreeMap::from([("project".to_string(), "hikyaku".to_string())]),
+                bucket_key_enabled: Some(true),
+            })
+            .set_storage_class(S3StorageClass::Glacier);
+        builder.chunk_size(MIN_S3_PART_SIZE);
+        let dst = builder
+            .set_file_path("s3://bucket/copied.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        s3.clear_requests();
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+
+        assert_eq!(s3.get_object("bucket", "copied.bin").unwrap().data, data);
+
+        let requests = s3.requests();
+        let gets = requests.iter().filter(|request| request.method == "GET").collect::<Vec<_>>();
+        assert_eq!(gets.len(), 4);
+        assert!(gets.iter().all(|request| {
+            request.header("x-amz-server-side-encryption-customer-key-md5") == Some(customer_key.key_md5())
+        }));
+
+        let create = requests.iter().find(|request| request.query.contains_key("uploads")).unwrap();
+        assert_eq!(create.header("x-amz-server-side-encryption"), Some("aws:kms"));
+        assert_eq!(create.header("x-amz-server-side-encryption-aws-kms-key-id"), Some("my-key"));
+        assert_eq!(create.header("x-amz-server-side-encryption-context"), Some("eyJwcm9qZWN0IjoiaGlreWFrdSJ9"));
+        assert_eq!(create.header("x-amz-server-side-encryption-bucket-key-enabled"), Some("true"));
+        assert_eq!(create.header("x-amz-storage-class"), Some("GLACIER"));
+
+        let parts = requests.iter().filter(|request| request.query.contains_key("partNumber")).count();
+        assert_eq!(parts, 4);
+    }
+
+    #[tokio::test]
+    async fn test_upload_amazon_s3_single_part_encryption() {
+        let s3 = FakeS3::start().await;
+        let dst = FileSystemBuilder::from(s3.credential())
+            .set_encryption(S3Encryption::S3Managed)
+            .set_file_path("s3://bucket/small.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        s3.clear_requests();
+
+        let (sender, receiver) = channel(1);
+        sender.send(ChunkData::new(b"hello".to_vec(), 0, true)).await.unwrap();
+        drop(sender);
+        dst.upload(receiver).await.unwrap();
+
+        let requests = s3.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].header("x-amz-server-side-encryption"), Some("AES256"));
+        assert_eq!(s3.get_object("bucket", "small.txt").unwrap().data, b"hello".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_upload_amazon_s3_upload_options() {
+        let s3 = FakeS3::start().await;
+        let upload_options = S3UploadOptions::new()
+            .set_metadata("project", "hikyaku")
+            .set_tag("team", "data")
+            .set_content_disposition("inline")
+            .set_cache_control("max-age=3600")
+            .set_acl(S3CannedAcl::PublicRead);
+
+        for (path, chunks) in [("site/index.html", 1), ("site/app.js", 3)] {
+            let builder = FileSystemBuilder::from(s3.credential())
+                .set_upload_options(upload_options.clone());
+            builder.chunk_size(MIN_S3_PART_SIZE);
+            let dst = builder
+                .set_file_path(&format!("s3://bucket/{}", path))
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            let (sender, receiver) = channel(chunks);
+            for offset in 0..chunks {
+                let data = if chunks == 1 { b"<p>".to_vec() } else { vec![b'a' + offset as u8; MIN_S3_PART_SIZE as usize] };
+                sender.send(ChunkData::new(data, offset as u64, offset == chunks - 1)).await.unwrap();
+            }
+            drop(sender);
+            dst.upload(receiver).await.unwrap();
+        }
+
+        let html = s3.get_object("bucket", "site/index.html").unwrap();
+        assert_eq!(html.headers["content-type"], "text/html");
+        assert_eq!(html.headers["x-amz-meta-project"], "hikyaku");
+        assert_eq!(html.headers["x-amz-tagging"], "team=data");
+        assert_eq!(html.headers["content-disposition"], "inline");
+        assert_eq!(html.headers["cache-control"], "max-age=3600");
+        assert_eq!(html.headers["x-amz-acl"], "public-read");
+
+        let js = s3.get_object("bucket", "site/app.js").unwrap();
+        let expected = [b'a', b'b', b'c'].map(|byte| vec![byte; MIN_S3_PART_SIZE as usize]).concat();
+        assert_eq!(js.data, expected);
+        assert_eq!(js.headers["content-type"], "application/javascript");
+        assert_eq!(js.headers["x-amz-meta-project"], "hikyaku");
+    }
+
+    #[tokio::test]
+    async fn test_upload_amazon_s3_part_limits() {
+        let s3 = FakeS3::start().await;
+        let builder = FileSystemBuilder::from(s3.credential());
+        builder.chunk_size(4);
+        let dst = builder
+            .set_file_path("s3://bucket/small_parts.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        s3.clear_requests();
+
+        // The parts smaller than 5 MiB are rejected before the multipart upload is created.
+        let (sender, receiver) = channel(2);
+        sender.send(ChunkData::new(b"abcd".to_vec(), 0, false)).await.unwrap();
+        sender.send(ChunkData::new(b"ef".to_vec(), 1, true)).await.unwrap();
+        drop(sender);
+        assert!(matches!(dst.upload(receiver).await, Err(InvalidArgumentError(_))));
+        assert!(s3.requests().is_empty());
+
+        // The part over the 10,000th fails the upload and the multipart upload is aborted.
+        let builder = FileSystemBuilder::from(s3.credential());
+        builder.chunk_size(MIN_S3_PART_SIZE);
+        let dst = builder
+            .set_file_path("s3://bucket/many_parts.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let mut session = dst.storage_backend().start_write(MIN_S3_PART_SIZE).await.unwrap();
+        session.write_chunk(ChunkData::new(vec![0; MIN_S3_PART_SIZE as usize], 0, false)).await.unwrap();
+        let result = session.write_chunk(ChunkData::new(vec![0; MIN_S3_PART_SIZE as usize], 10_000, true)).await;
+        assert!(matches!(result, Err(InvalidArgumentError(_))));
+        session.abort().await;
+        assert!(s3.requests().iter().any(|request| request.method == "DELETE" && request.query.contains_key("uploadId")));
+        assert!(s3.get_object("bucket", "many_parts.bin").is_none());
+    }
+}
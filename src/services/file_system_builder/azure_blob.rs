@@ -0,0 +1,376 @@
+use std::sync::Arc;
+use log::error;
+use reqwest::{Client, StatusCode};
+use reqwest::header::CONTENT_LENGTH;
+use crate::errors::HikyakuError::{AzureBlobError, BuilderError, InvalidArgumentError, NotExistFileError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::azure_blob::AzureBlobBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::azure_blob::{parse_blob_list, AzureBlobAuthorizer, AzureBlobEndpoint, AzureBlobList};
+use crate::utils::credential::Credential;
+use crate::utils::credential::azure_blob_credential::AzureBlobCredential;
+use crate::utils::parser::FileSystemParseResult;
+
+impl FileSystemBuilder<AzureBlobCredential, FileSystemParseResult> {
+    /// Sets the base URL of the Blob service.
+    ///
+    /// By default, the public endpoint of the account(`https://{account}.blob.core.windows.net`) is used.
+    /// This is useful to point at the Azurite emulator(`AzureBlobEndpoint::azurite()`) or a private endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - An [AzureBlobEndpoint] which holds the base URL of the account.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_endpoint(mut self, endpoint: AzureBlobEndpoint) -> Self {
+        self.options.endpoint = Some(endpoint);
+        self
+    }
+
+
+    /// Builds a `FileSystemObject` for Azure Blob Storage using the specified credentials and file path.
+    ///
+    /// This function validates the file path to ensure it has the "az://" prefix and the container,
+    /// then looks up the blob. The path which is not a blob but the prefix of blobs is treated as a directory.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError` or `AzureBlobError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use hikyaku::types::azure_blob::AzureBlobEndpoint;
+    /// use hikyaku::utils::credential::azure_blob_credential::AzureBlobCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let file_obj = FileSystemBuilder::from(AzureBlobCredential::azurite())
+    ///         .set_endpoint(AzureBlobEndpoint::azurite())
+    ///         .set_file_path("az://datas/titanic/train.csv")
+    ///         .unwrap()
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("AzureBlob"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let (container, blob) = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                if file_info.get_prefix() != "az://" {
+                    return Err(InvalidArgumentError("File system prefix is not az://".to_string()));
+                }
+                // SAFETY: The parser always sets the namespace of `az://`.
+                (file_info.get_namespace().unwrap().to_string(), file_info.get_path().to_string())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let token = self.file_system_credential.get_credential();
+        let endpoint = self.options
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| AzureBlobEndpoint::from_account(token.get_account_name()));
+        let authorizer = token.get_authorizer().await?;
+        // The authorization is signed per request, so the clients have no default header.
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
+            .map(|_| Arc::new(Client::new()))
+            .collect::<Vec<_>>();
+        let client = first_client(&clients);
+
+        // The container itself is the root directory.
+        let (is_dir, file_size) = if blob.is_empty() {
+            (true, None)
+        } else {
+            match get_blob_size(client, &authorizer, &endpoint, &container, &blob).await? {
+                Some(size) => (false, Some(size)),
+                None => {
+                    let prefix = format!("{}/", blob);
+                    let page = list_blobs(client, &authorizer, &endpoint, &container, &prefix, None, Some(1)).await?;
+                    (!page.blobs.is_empty() || !page.prefixes.is_empty(), None)
+                },
+            }
+        };
+
+        let backend = AzureBlobBackend {
+            clients,
+            authorizer: Arc::new(authorizer),
+            endpoint: Arc::new(endpoint),
+            container: Arc::new(container),
+            blob: Arc::new(blob),
+            is_dir,
+            file_size,
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+/// Retrieves the size of the blob by Get Blob Properties.
+///
+/// # Returns
+///
+/// `HikyakuResult<Option<u64>>` - The size of the blob, or [None] if the blob does not exist.
+async fn get_blob_size(client: &Client,
+                       authorizer: &AzureBlobAuthorizer,
+                       endpoint: &AzureBlobEndpoint,
+                       container: &str,
+                       blob: &str) -> HikyakuResult<Option<u64>> {
+    let response = authorizer.send(client, client.head(endpoint.blob_url(container, blob))).await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        error!("Failed to get blob properties for Azure Blob Storage: {}", response.status());
+        return Err(AzureBlobError(format!("Failed to get the properties of az://{}/{}: {}", container, blob, response.status())));
+    }
+
+    // The HEAD response has no body, so the size is read from the header.
+    let size = response
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .ok_or_else(|| AzureBlobError(format!("Content-Length is not returned for az://{}/{}", container, blob)))?;
+
+    Ok(Some(size))
+}
+
+/// Lists a page of the blobs and the prefixes under the prefix with the `/` delimiter.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to Azure Blob Storage.
+/// * `authorizer` - The authorizer of the request.
+/// * `endpoint` - The base URL of the account.
+/// * `container` - The name of the container.
+/// * `prefix` - The prefix of the blob names. The empty prefix lists the root of the container.
+/// * `marker` - The marker of the page which the previous page returns.
+/// * `max_results` - The maximum number of the items in the page.
+///
+/// # Returns
+///
+/// `HikyakuResult<AzureBlobList>` - The page, or a `NotExistFileError` if the container does not exist.
+pub(crate) async fn list_blobs(client: &Client,
+                               authorizer: &AzureBlobAuthorizer,
+                               endpoint: &AzureBlobEndpoint,
+                               container: &str,
+                               prefix: &str,
+                               marker: Option<&str>,
+                               max_results: Option<u64>) -> HikyakuResult<AzureBlobList> {
+    let mut query = vec![
+        ("restype", "container".to_string()),
+        ("comp", "list".to_string()),
+        ("delimiter", "/".to_string()),
+        ("prefix", prefix.to_string()),
+    ];
+    if let Some(marker) = marker {
+        query.push(("marker", marker.to_string()));
+    }
+    if let Some(max_results) = max_results {
+        query.push(("maxresults", max_results.to_string()));
+    }
+    let response = authorizer.send(client, client.get(endpoint.container_url(container)).query(&query)).await?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(NotExistFileError(format!("Container {} does not exist", container)));
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        error!("Failed to list blobs for Azure Blob Storage: {}", status);
+        return Err(AzureBlobError(format!("Failed to list blobs in az://{}/{}: {}, {}", container, prefix, status, body)));
+    }
+
+    let xml = response
+        .text()
+        .await
+        .map_err(|e| AzureBlobError(format!("Failed to read response from Azure Blob Storage: {:?}", e)))?;
+
+    parse_blob_list(&xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_azure_blob::FakeAzureBlob;
+    use crate::utils::test_support::{assert_read_range, collect, range_data, send_data};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_build_azure_blob_fake() {
+        let azure = FakeAzureBlob::start().await;
+        azure.add_blob("datas", "titanic/train.csv", b"a,b,c".to_vec());
+        azure.add_blob("datas", "titanic/test.csv", b"a,b".to_vec());
+        azure.add_blob("datas", "titanic/raw/2024.csv", b"a".to_vec());
+
+        let credentials = [
+            AzureBlobCredential::azurite(),
+            AzureBlobCredential::from_sas("devstoreaccount1", &format!("?{}", azure.sas_token())),
+            AzureBlobCredential::from_client_secret("devstoreaccount1", "tenant", "client", "secret")
+                .set_authority_host(&azure.authority_host()),
+        ];
+        for credential in credentials {
+            let file_obj = FileSystemBuilder::from(credential)
+                .set_endpoint(azure.endpoint())
+                .set_file_path("az://datas/titanic/train.csv")
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            assert!(file_obj.to_string().contains("container: datas, blob: titanic/train.csv, is_dir: false, file_size: Some(5)"));
+        }
+
+        let folder = FileSystemBuilder::from(AzureBlobCredential::azurite())
+            .set_endpoint(azure.endpoint())
+            .set_file_path("az://datas/titanic/")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(folder.to_string().contains("is_dir: true"));
+        assert_eq!(folder.list().await.unwrap(), vec!["titanic/raw", "titanic/test.csv", "titanic/train.csv"]);
+
+        let absent = FileSystemBuilder::from(AzureBlobCredential::azurite())
+            .set_endpoint(azure.endpoint())
+            .set_file_path("az://datas/titanic/valid.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(absent.to_string().contains("is_dir: false, file_size: None"));
+        assert!(matches!(absent.delete().await, Err(NotExistFileError(_))));
+
+        let wrong_key = AzureBlobCredential::from_account_key("devstoreaccount1", "AAAA").unwrap();
+        let denied = FileSystemBuilder::from(wrong_key)
+            .set_endpoint(azure.endpoint())
+            .set_file_path("az://datas/titanic/train.csv")
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(denied, Err(AzureBlobError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_azure_blob_fake() {
+        let azure = FakeAzureBlob::start().await;
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        azure.add_blob("datas", "source.bin", data.clone());
+
+        let build = |path: &str| {
+            let builder = FileSystemBuilder::from(AzureBlobCredential::azurite())
+                .set_endpoint(azure.endpoint());
+            builder.chunk_size(64);
+            builder.set_file_path(path).unwrap().build()
+        };
+        let src = build("az://datas/source.bin").await.unwrap();
+        let dst = build("az://datas/copied/copied.bin").await.unwrap();
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(azure.get_blob("datas", "copied/copied.bin").unwrap(), data);
+        // Every chunk is put as a block and they are committed at once.
+        assert_eq!(azure.put_blocks(), 16);
+
+        let copied = build("az://datas/copied/copied.bin").await.unwrap();
+        copied.delete().await.unwrap();
+        assert!(azure.get_blob("datas", "copied/copied.bin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_entra_id_token_azure_blob_fake() {
+        let azure = FakeAzureBlob::start().await;
+        azure.add_blob("datas", "source.bin", b"abc".to_vec());
+        let build = || {
+            let credential = AzureBlobCredential::from_client_secret("devstoreaccount1", "tenant", "client", "secret")
+                .set_authority_host(&azure.authority_host());
+            FileSystemBuilder::from(credential)
+                .set_endpoint(azure.endpoint())
+                .set_file_path("az://datas/source.bin")
+                .unwrap()
+                .build()
+        };
+
+        // The token which is valid for an hour is reused.
+        let file_obj = build().await.unwrap();
+        file_obj.storage_backend().read_range(0, 1).await.unwrap();
+        assert_eq!(azure.issued_tokens(), 1);
+
+        // The token which expires soon is requested again before the request.
+        azure.set_token_expires_in(60);
+        let file_obj = build().await.unwrap();
+        let issued_tokens = azure.issued_tokens();
+        assert_eq!(file_obj.storage_backend().read_range(0, 1).await.unwrap(), b"ab");
+        assert_eq!(azure.issued_tokens(), issued_tokens + 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Azurite listening on 127.0.0.1:10000"]
+    async fn test_transfer_azurite() {
+        // The container is created by the well-known account, and the existing one is reused.
+        let authorizer = AzureBlobCredential::azurite().get_credential().get_authorizer().await.unwrap();
+        let client = Client::new();
+        let request = client.put(AzureBlobEndpoint::azurite().container_url("hikyaku")).query(&[("restype", "container")]);
+        let status = authorizer.send(&client, request).await.unwrap().status();
+        assert!(status.is_success() || status == StatusCode::CONFLICT);
+
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        let build = |path: &str| {
+            let builder = FileSystemBuilder::from(AzureBlobCredential::azurite()).set_endpoint(AzureBlobEndpoint::azurite());
+            builder.chunk_size(64);
+            builder.set_file_path(path).unwrap().build()
+        };
+        let dst = build("az://hikyaku/emulator/source.bin").await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::join!(send_data(sender, &data, 64), dst.upload(receiver)).1.unwrap();
+
+        let src = build("az://hikyaku/emulator/source.bin").await.unwrap();
+        assert!(src.to_string().contains("file_size: Some(1000)"));
+        let (sender, receiver) = channel(4);
+        let (downloaded, received) = tokio::join!(src.download(sender), collect(receiver));
+        downloaded.unwrap();
+        assert_eq!(received, data);
+        assert_eq!(src.storage_backend().read_range(100, 199).await.unwrap(), data[100..200]);
+
+        let folder = build("az://hikyaku/emulator/").await.unwrap();
+        assert!(folder.list().await.unwrap().contains(&"emulator/source.bin".to_string()));
+        src.delete().await.unwrap();
+        let deleted = build("az://hikyaku/emulator/source.bin").await.unwrap();
+        assert!(deleted.to_string().contains("file_size: None"));
+    }
+
+    #[tokio::test]
+    async fn test_read_range_azure_blob_fake() {
+        let azure = FakeAzureBlob::start().await;
+        azure.add_blob("datas", "source.bin", range_data());
+        let file_obj = FileSystemBuilder::from(AzureBlobCredential::azurite())
+            .set_endpoint(azure.endpoint())
+            .set_file_path("az://datas/source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        // The range is requested by the signed `x-ms-range`.
+        assert_read_range(file_obj.storage_backend(), |e| matches!(e, AzureBlobError(_))).await;
+    }
+}
@@ -0,0 +1,476 @@
+use std::sync::Arc;
+use log::error;
+use reqwest::{Client, StatusCode};
+use crate::errors::HikyakuError::{BoxError, BuilderError, ConnectionError, InvalidArgumentError, NotExistFileError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::box_storage::BoxBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::box_storage::{BoxEndpoint, BoxFileInfo, BoxItem, BoxItemsResponse, BOX_ROOT_FOLDER_ID};
+use crate::utils::credential::Credential;
+use crate::utils::credential::box_credential::BoxCredential;
+use crate::utils::parser::path_to_names_vec;
+use crate::utils::reqwest::AuthType::Bearer;
+use crate::utils::reqwest::get_client_with_token;
+
+// The maximum number of the items which a page of the folder items returns.
+const ITEMS_PAGE_LIMIT: u64 = 1000;
+
+impl FileSystemBuilder<BoxCredential, BoxFileInfo> {
+    /// Sets the folder ID and the file path under the folder for the Box file operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_id` - A string slice that holds the id of the folder to start the path from.
+    /// * `path` - A string slice that represents the file path under the folder.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_folder_id_and_key(self, folder_id: &str, path: &str) -> Self {
+        let file_info = BoxFileInfo::FolderId {
+            folder_id: folder_id.to_string(),
+            file_path: path.to_string(),
+        };
+
+        *self.file_info.borrow_mut() = Some(file_info);
+        self
+    }
+
+
+    /// Sets the target file ID for the Box file operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - A string slice that holds the id of the target file.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_file_id(self, file_id: &str) -> Self {
+        *self.file_info.borrow_mut() = Some(BoxFileInfo::FileId(file_id.to_string()));
+        self
+    }
+
+
+    /// Sets the base URLs of the Box API.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - A [BoxEndpoint] which holds the API base URLs.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_endpoint(mut self, endpoint: BoxEndpoint) -> Self {
+        self.options.endpoint = endpoint;
+        self
+    }
+
+
+    /// Sets the size of the file which will be uploaded.
+    ///
+    /// Box needs the file size to open the chunked upload session for the file from 20 MiB.
+    /// The smaller file is kept in memory and uploaded at once. Without the size, only the file
+    /// of a single chunk can be uploaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `upload_size` - The size of the whole file in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_upload_size(mut self, upload_size: u64) -> Self {
+        self.options.upload_size = Some(upload_size);
+        self
+    }
+
+
+    /// Builds a `FileSystemObject` for Box using the specified credentials and file information.
+    ///
+    /// The path is resolved from the root folder(or the specified folder) to the deepest existing
+    /// folder. The folders which do not exist yet are created when the file is uploaded.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError` or `BoxError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use time::{Duration, OffsetDateTime};
+    /// use hikyaku::utils::credential::box_credential::BoxCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let cred = BoxCredential::new("access_token", "refresh_token", OffsetDateTime::now_utc() + Duration::hours(1));
+    ///     let file_obj = FileSystemBuilder::from(cred)
+    ///         .set_file_path("box://datas/train.csv")
+    ///         .unwrap()
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("Box"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let token = self.file_system_credential.get_credential();
+        let options = self.options.clone();
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
+            .map(|_| get_client_with_token(token.get_access_token(), Bearer).map(Arc::new))
+            .collect::<HikyakuResult<Vec<_>>>()?;
+        let client = first_client(&clients);
+
+        let resolved = match self.file_info.take() {
+            Some(BoxFileInfo::Parsed(info)) => {
+                if info.get_prefix() != "box://" {
+                    return Err(InvalidArgumentError("File system prefix is not box://".to_string()));
+                }
+                resolve_path(client, &options.endpoint, BOX_ROOT_FOLDER_ID, info.get_path()).await?
+            },
+            Some(BoxFileInfo::FolderId {folder_id, file_path}) => {
+                resolve_path(client, &options.endpoint, &folder_id, &file_path).await?
+            },
+            Some(BoxFileInfo::FileId(file_id)) => {
+                let item = get_file(client, &options.endpoint, &file_id).await?;
+                ResolvedPath {
+                    path: item.name.clone(),
+                    upload_filename: Some(item.name.clone()),
+                    item: Some(item),
+                    parent_id: String::new(),
+                    not_exist_folders: vec![],
+                }
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            },
+        };
+
+        let (file_or_parent_id, is_dir, file_size) = match &resolved.item {
+            Some(item) if item.is_folder() => (item.id.clone(), true, None),
+            Some(item) => (item.id.clone(), false, item.size),
+            None => (resolved.parent_id, false, None),
+        };
+
+        let backend = BoxBackend {
+            clients,
+            endpoint: Arc::new(options.endpoint),
+            path: Arc::new(resolved.path),
+            file_or_parent_id: Arc::new(file_or_parent_id),
+            is_dir,
+            not_exist_folders: Arc::new(resolved.not_exist_folders),
+            upload_filename: resolved.upload_filename.map(Arc::new),
+            upload_size: options.upload_size,
+            file_size,
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+struct ResolvedPath {
+    path: String,
+    // The file or folder at the path. [None] if it does not exist.
+    item: Option<BoxItem>,
+    // The deepest existing folder id when the item does not exist.
+    parent_id: String,
+    not_exist_folders: Vec<String>,
+    upload_filename: Option<String>,
+}
+
+/// Resolves the path to the file or folder from the folder.
+///
+/// Box does not allow the same name items in a folder, so the path is never ambiguous.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to Box which has token header as default.
+/// * `endpoint` - The base URLs of the Box API.
+/// * `folder_id` - The id of the folder to start the path from.
+/// * `path` - The path under the folder.
+async fn resolve_path(client: &Client, endpoint: &BoxEndpoint, folder_id: &str, path: &str) -> HikyakuResult<ResolvedPath> {
+    let path_names = path_to_names_vec(path, false)?;
+    let mut parent_id = folder_id.to_string();
+    let mut item = None;
+
+    for (depth, name) in path_names.iter().enumerate() {
+        let found = list_folder_items(client, endpoint, &parent_id)
+            .await?
+            .into_iter()
+            // Box compares the names case-insensitively.
+            .find(|item| item.name.to_lowercase() == name.to_lowercase());
+
+        match found {
+            Some(found) if found.is_folder() => {
+                parent_id = found.id.clone();
+                item = Some(found);
+            },
+            Some(found) if depth == path_names.len() - 1 => {
+                item = Some(found);
+            },
+            Some(_) => {
+                return Err(InvalidArgumentError(format!("'{}' in the path '{}' is a file, not a folder", name, path)));
+            },
+            None => {
+                // The remaining folders will be created when the file is uploaded.
+                let mut not_exist_folders = path_names[depth..].to_vec();
+                let upload_filename = not_exist_folders.pop();
+
+                return Ok(ResolvedPath {
+                    path: path.to_string(),
+                    item: None,
+                    parent_id,
+                    not_exist_folders,
+                    upload_filename,
+                });
+            },
+        }
+    }
+
+    let item = match item {
+        Some(item) => item,
+        // The empty path points to the start folder itself.
+        None => BoxItem {
+            item_type: "folder".to_string(),
+            id: folder_id.to_string(),
+            name: String::new(),
+            size: None,
+        },
+    };
+
+    Ok(ResolvedPath {
+        path: path.to_string(),
+        upload_filename: path_names.last().cloned(),
+        item: Some(item),
+        parent_id,
+        not_exist_folders: vec![],
+    })
+}
+
+/// Lists all items in the folder by following the pages.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to Box which has token header as default.
+/// * `endpoint` - The base URLs of the Box API.
+/// * `folder_id` - The id of the folder.
+///
+/// # Returns
+///
+/// `HikyakuResult<Vec<BoxItem>>` - The files and folders in the folder.
+pub(crate) async fn list_folder_items(client: &Client, endpoint: &BoxEndpoint, folder_id: &str) -> HikyakuResult<Vec<BoxItem>> {
+    let mut items = vec![];
+    loop {
+        let response = client
+            .get(endpoint.folder_items_url(folder_id))
+            .query(&[
+                ("fields", "id,type,name,size".to_string()),
+                ("limit", ITEMS_PAGE_LIMIT.to_string()),
+                ("offset", items.len().to_string()),
+            ])
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to send request to Box API: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Box API: {:?}", e))
+            })?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(NotExistFileError(format!("Box folder {} does not exist", folder_id)));
+        }
+        if !response.status().is_success() {
+            error!("Failed to list folder items for Box API: {}", response.status());
+            return Err(ConnectionError(format!("Failed to list folder items for Box API: {}", response.status())));
+        }
+
+        let page = response
+            .json::<BoxItemsResponse>()
+            .await
+            .map_err(|e| BoxError(format!("Failed to parse response from Box API: {:?}", e)))?;
+        let is_empty = page.entries.is_empty();
+        items.extend(page.entries);
+
+        if is_empty || items.len() as u64 >= page.total_count {
+            return Ok(items);
+        }
+    }
+}
+
+/// Retrieves a Box file by its ID.
+async fn get_file(client: &Client, endpoint: &BoxEndpoint, file_id: &str) -> HikyakuResult<BoxItem> {
+    let response = client
+        .get(endpoint.file_url(file_id))
+        .query(&[("fields", "id,type,name,size")])
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to send request to Box API: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Box API: {:?}", e))
+        })?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(NotExistFileError(format!("Box file {} does not exist", file_id)));
+    }
+    if !response.status().is_success() {
+        error!("Failed to get file by ID for Box API: {}", response.status());
+        return Err(ConnectionError(format!("Failed to get file by ID for Box API: {}", response.status())));
+    }
+
+    response
+        .json::<BoxItem>()
+        .await
+        .map_err(|e| BoxError(format!("Failed to parse response from Box API: {:?}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_box::FakeBox;
+    use crate::utils::test_support::{assert_read_range, range_data};
+    use super::*;
+
+    fn fake_credential() -> BoxCredential {
+        BoxCredential::new("fake_access_token", "", OffsetDateTime::now_utc() + Duration::hours(1))
+    }
+
+    #[tokio::test]
+    async fn test_build_box_fake() {
+        let box_server = FakeBox::start().await;
+        let folder_id = box_server.add_folder("datas", BOX_ROOT_FOLDER_ID);
+        let file_id = box_server.add_file("train.csv", &folder_id, b"a,b,c".to_vec());
+
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(box_server.endpoint())
+            .set_file_path("box://Datas/train.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(file_obj.to_string().contains(&format!("file_or_parent_id: {}, is_dir: false", file_id)));
+        assert!(file_obj.to_string().contains("file_size: Some(5)"));
+
+        let by_id = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(box_server.endpoint())
+            .set_file_id(&file_id)
+            .build()
+            .await
+            .unwrap();
+        assert!(by_id.to_string().contains("file_size: Some(5)"));
+
+        let absent = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(box_server.endpoint())
+            .set_folder_id_and_key(&folder_id, "new/test.csv")
+            .build()
+            .await
+            .unwrap();
+        assert!(absent.to_string().contains(&format!("file_or_parent_id: {}", folder_id)));
+        assert!(absent.to_string().contains("not_exist_folders: [\"new\"], upload_filename: Some(\"test.csv\")"));
+
+        assert_eq!(
+            FileSystemBuilder::from(fake_credential())
+                .set_endpoint(box_server.endpoint())
+                .set_file_path("box://datas")
+                .unwrap()
+                .build()
+                .await
+                .unwrap()
+                .list()
+                .await
+                .unwrap(),
+            vec!["datas/train.csv"]);
+
+        file_obj.delete().await.unwrap();
+        assert!(box_server.find_file("train.csv").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_box_fake() {
+        let box_server = FakeBox::start().await;
+        let large = (0..=255u8).cycle().take(20 * 1024 * 1024 + 1000).collect::<Vec<_>>();
+        let small = large[..1000].to_vec();
+        box_server.add_file("large.bin", BOX_ROOT_FOLDER_ID, large.clone());
+        box_server.add_file("small.bin", BOX_ROOT_FOLDER_ID, small.clone());
+
+        let build = |path: &str, chunk_size: u64, upload_size: Option<u64>| {
+            let builder = FileSystemBuilder::from(fake_credential())
+                .set_endpoint(box_server.endpoint());
+            let builder = match upload_size {
+                Some(size) => builder.set_upload_size(size),
+                None => builder,
+            };
+            builder.chunk_size(chunk_size);
+            builder.set_file_path(path).unwrap().build()
+        };
+
+        // The file size is known, so the upload session is used.
+        let src = build("box://large.bin", 1024 * 1024, None).await.unwrap();
+        let dst = build("box://uploaded/large.bin", 1024 * 1024, Some(large.len() as u64)).await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        let uploaded = box_server.find_file("large.bin").unwrap();
+        assert!(uploaded.data == large);
+        // The chunks are re-split by the part size(8 MiB) of the session.
+        assert_eq!(box_server.uploaded_parts(), 3);
+
+        // The small file is uploaded at once, and the single chunk tells the file size.
+        let src = build("box://small.bin", 64, None).await.unwrap();
+        let dst = build("box://uploaded/small.bin", 64, Some(small.len() as u64)).await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        let src = build("box://small.bin", 1024, None).await.unwrap();
+        let dst = build("box://uploaded/single.bin", 1024, None).await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        let uploaded = box_server.find_files_by_parent(&box_server.find_file("uploaded").unwrap().id);
+        assert_eq!(uploaded.len(), 3);
+        assert!(uploaded.iter().any(|file| file.name == "small.bin" && file.data == small));
+        assert!(uploaded.iter().any(|file| file.name == "single.bin" && file.data == small));
+
+        // The file of many chunks without the size is refused at the first chunk.
+        let dst = build("box://uploaded/unknown.bin", 64, None).await.unwrap();
+        let (sender, receiver) = channel(1);
+        sender.send(crate::services::file_system::ChunkData::new(small[..64].to_vec(), 0, false)).await.unwrap();
+        drop(sender);
+        assert!(matches!(dst.upload(receiver).await, Err(InvalidArgumentError(_))));
+        assert!(box_server.find_file("unknown.bin").is_none());
+
+        // The upload session whose last chunk never arrives is aborted.
+        let dst = build("box://uploaded/interrupted.bin", 1024 * 1024, Some(large.len() as u64)).await.unwrap();
+        let (sender, receiver) = channel(1);
+        sender.send(crate::services::file_system::ChunkData::new(large[..1024 * 1024].to_vec(), 0, false)).await.unwrap();
+        drop(sender);
+        assert!(dst.upload(receiver).await.is_err());
+        assert_eq!(box_server.open_sessions(), 0);
+        assert!(box_server.find_file("interrupted.bin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_range_box_fake() {
+        let box_server = FakeBox::start().await;
+        let folder_id = box_server.add_folder("datas", BOX_ROOT_FOLDER_ID);
+        box_server.add_file("source.bin", &folder_id, range_data());
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(box_server.endpoint())
+            .set_file_path("box://datas/source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_read_range(file_obj.storage_backend(), |e| matches!(e, BoxError(_))).await;
+    }
+}
@@ -0,0 +1,264 @@
+use std::sync::Arc;
+use log::error;
+use reqwest::{Client, StatusCode};
+use serde_json::json;
+use crate::errors::HikyakuError::{BuilderError, ConnectionError, DropboxError, InvalidArgumentError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::dropbox::DropboxBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::dropbox::{to_dropbox_path, DropboxEndpoint, DropboxMetadata};
+use crate::utils::credential::Credential;
+use crate::utils::credential::dropbox_credential::DropboxCredential;
+use crate::utils::parser::FileSystemParseResult;
+use crate::utils::reqwest::AuthType::Bearer;
+use crate::utils::reqwest::get_client_with_token;
+
+impl FileSystemBuilder<DropboxCredential, FileSystemParseResult> {
+    /// Sets the base URLs of the Dropbox API.
+    ///
+    /// By default, the builder and the built `FileSystemObject` talk to `https://api.dropboxapi.com`
+    /// and `https://content.dropboxapi.com`. This is useful to point them at a mock server in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - A [DropboxEndpoint] which holds the API base URLs.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_endpoint(mut self, endpoint: DropboxEndpoint) -> Self {
+        self.options.endpoint = endpoint;
+        self
+    }
+
+
+    /// Builds a `FileSystemObject` for Dropbox using the specified credentials and file path.
+    ///
+    /// This function validates the file path to ensure it has the "dbx://" prefix and looks up
+    /// the metadata of the path to decide whether it is a file, a folder or not existing yet.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError` or `DropboxError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use time::{Duration, OffsetDateTime};
+    /// use hikyaku::utils::credential::dropbox_credential::DropboxCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let cred = DropboxCredential::new("access_token", "refresh_token", OffsetDateTime::now_utc() + Duration::hours(4));
+    ///     let file_obj = FileSystemBuilder::from(cred)
+    ///         .set_file_path("dbx://datas/train.csv")
+    ///         .unwrap()
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("Dropbox"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let path = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                if file_info.get_prefix() != "dbx://" {
+                    return Err(InvalidArgumentError("File system prefix is not dbx://".to_string()));
+                }
+                to_dropbox_path(file_info.get_path())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let token = self.file_system_credential.get_credential();
+        let endpoint = self.options.endpoint.clone();
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
+            .map(|_| get_client_with_token(token.get_access_token(), Bearer).map(Arc::new))
+            .collect::<HikyakuResult<Vec<_>>>()?;
+
+        // The root folder has no metadata in Dropbox.
+        let (is_dir, file_size) = if path.is_empty() {
+            (true, None)
+        } else {
+            match get_metadata(first_client(&clients), &endpoint, &path).await? {
+                Some(metadata) if metadata.is_folder() => (true, None),
+                Some(metadata) => (false, metadata.get_size()),
+                None => (false, None),
+            }
+        };
+
+        let backend = DropboxBackend {
+            clients,
+            endpoint: Arc::new(endpoint),
+            path: Arc::new(path),
+            is_dir,
+            file_size,
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+
+/// Retrieves the metadata of the file or folder at the path.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to Dropbox which has token header as default.
+/// * `endpoint` - The base URLs of the Dropbox API.
+/// * `path` - The Dropbox path which starts with `/`.
+///
+/// # Returns
+///
+/// `HikyakuResult<Option<DropboxMetadata>>` - The metadata, or [None] if nothing exists at the path.
+async fn get_metadata(client: &Client, endpoint: &DropboxEndpoint, path: &str) -> HikyakuResult<Option<DropboxMetadata>> {
+    let response = client
+        .post(endpoint.api_url("files/get_metadata"))
+        .json(&json!({"path": path}))
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to send request to Dropbox API: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Dropbox API: {:?}", e))
+        })?;
+
+    let status = response.status();
+    if status == StatusCode::CONFLICT {
+        // The endpoint specific errors are returned with 409 and the summary like `path/not_found/..`.
+        let body = response.text().await.unwrap_or_default();
+        if body.contains("path/not_found") {
+            return Ok(None);
+        }
+        return Err(DropboxError(format!("Failed to get metadata of {}: {}", path, body)));
+    }
+    if !status.is_success() {
+        error!("Failed to get metadata for Dropbox API: {}", status);
+        return Err(ConnectionError(format!("Failed to get metadata for Dropbox API: {}", status)));
+    }
+
+    let metadata = response
+        .json::<DropboxMetadata>()
+        .await
+        .map_err(|e| DropboxError(format!("Failed to parse response from Dropbox API: {:?}", e)))?;
+
+    Ok(Some(metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_dropbox::FakeDropbox;
+    use crate::utils::test_support::{assert_read_range, range_data};
+    use super::*;
+
+    fn fake_credential() -> DropboxCredential {
+        DropboxCredential::new("fake_access_token", "", OffsetDateTime::now_utc() + Duration::hours(4))
+    }
+
+    #[tokio::test]
+    async fn test_build_dropbox_fake() {
+        let dropbox = FakeDropbox::start().await;
+        dropbox.add_file("/datas/train.csv", b"a,b,c".to_vec());
+
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(dropbox.endpoint())
+            .set_file_path("dbx://datas/train.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(file_obj.to_string().contains("path: /datas/train.csv, is_dir: false, file_size: Some(5)"));
+
+        let folder = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(dropbox.endpoint())
+            .set_file_path("dbx://datas/")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(folder.to_string().contains("is_dir: true"));
+
+        let absent = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(dropbox.endpoint())
+            .set_file_path("dbx://datas/test.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(absent.to_string().contains("is_dir: false, file_size: None"));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_dropbox_fake() {
+        let dropbox = FakeDropbox::start().await;
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        dropbox.add_file("/source.bin", data.clone());
+        dropbox.add_file("/copied/other.bin", vec![1]);
+
+        let builder = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(dropbox.endpoint());
+        builder.chunk_size(64);
+        let src = builder
+            .set_file_path("dbx://source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let builder = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(dropbox.endpoint());
+        builder.chunk_size(64);
+        let dst = builder
+            .set_file_path("dbx://copied/copied.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(dropbox.get_file("/copied/copied.bin").unwrap(), data);
+        // The first chunk starts the session, the last chunk finishes it and the others are appended.
+        assert_eq!(dropbox.requests("files/upload_session/start"), 1);
+        assert_eq!(dropbox.requests("files/upload_session/append_v2"), 14);
+        assert_eq!(dropbox.requests("files/upload_session/finish"), 1);
+
+        let folder = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(dropbox.endpoint())
+            .set_file_path("dbx://copied")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(folder.list().await.unwrap(), vec!["/copied/copied.bin", "/copied/other.bin"]);
+    }
+
+    #[tokio::test]
+    async fn test_read_range_dropbox_fake() {
+        let dropbox = FakeDropbox::start().await;
+        dropbox.add_file("/datas/source.bin", range_data());
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(dropbox.endpoint())
+            .set_file_path("dbx://datas/source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_read_range(file_obj.storage_backend(), |e| matches!(e, DropboxError(_))).await;
+    }
+}
@@ -0,0 +1,406 @@
+use std::sync::Arc;
+use log::error;
+use reqwest::{Client, StatusCode};
+use tokio::sync::Mutex;
+use crate::errors::HikyakuError::{BuilderError, ConnectionError, GcsError, InvalidArgumentError, NotExistFileError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::google_cloud_storage::GcsBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::google_cloud_storage::{GcsDownloadChecksum, GcsEndpoint, GcsObject, GcsObjectList};
+use crate::utils::credential::Credential;
+use crate::utils::credential::gcs_credential::GcsCredential;
+use crate::utils::parser::FileSystemParseResult;
+use crate::utils::reqwest::AuthType::Bearer;
+use crate::utils::reqwest::get_client_with_token;
+
+impl FileSystemBuilder<GcsCredential, FileSystemParseResult> {
+    /// Sets the base URL of the Cloud Storage JSON API.
+    ///
+    /// By default, `https://storage.googleapis.com` is used. This is useful to point at
+    /// fake-gcs-server(`GcsEndpoint::fake_gcs_server()`) or a private endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - A [GcsEndpoint] which holds the base URL of the API.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_endpoint(mut self, endpoint: GcsEndpoint) -> Self {
+        self.options.endpoint = endpoint;
+        self
+    }
+
+
+    /// Builds a `FileSystemObject` for Google Cloud Storage using the specified credentials and file path.
+    ///
+    /// This function validates the file path to ensure it has the "gs://" prefix and the bucket,
+    /// then gets the metadata of the object. The path which is not an object but the prefix of objects
+    /// is treated as a directory.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError` or `GcsError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use hikyaku::types::google_cloud_storage::GcsEndpoint;
+    /// use hikyaku::utils::credential::gcs_credential::GcsCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let file_obj = FileSystemBuilder::from(GcsCredential::anonymous())
+    ///         .set_endpoint(GcsEndpoint::fake_gcs_server())
+    ///         .set_file_path("gs://datas/titanic/train.csv")
+    ///         .unwrap()
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("GoogleCloudStorage"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let (bucket, object) = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                if file_info.get_prefix() != "gs://" {
+                    return Err(InvalidArgumentError("File system prefix is not gs://".to_string()));
+                }
+                // SAFETY: The parser always sets the namespace of `gs://`.
+                (file_info.get_namespace().unwrap().to_string(), file_info.get_path().to_string())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let endpoint = self.options.endpoint.clone();
+        let access_token = self.file_system_credential.get_credential().get_access_token().await?;
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
+            .map(|_| match &access_token {
+                Some(access_token) => get_client_with_token(access_token, Bearer).map(Arc::new),
+                None => Ok(Arc::new(Client::new())),
+            })
+            .collect::<HikyakuResult<Vec<_>>>()?;
+        let client = first_client(&clients);
+
+        // The bucket itself is the root directory.
+        let (is_dir, metadata) = if object.is_empty() {
+            (true, None)
+        } else {
+            match get_object(client, &endpoint, &bucket, &object).await? {
+                Some(metadata) => (false, Some(metadata)),
+                None => {
+                    let prefix = format!("{}/", object);
+                    let page = list_objects(client, &endpoint, &bucket, &prefix, None, Some(1)).await?;
+                    (!page.items.is_empty() || !page.prefixes.is_empty(), None)
+                },
+            }
+        };
+        let file_size = metadata.as_ref().map(GcsObject::get_size).transpose()?;
+
+        let backend = GcsBackend {
+            clients,
+            endpoint: Arc::new(endpoint),
+            bucket: Arc::new(bucket),
+            object: Arc::new(object),
+            is_dir,
+            crc32c: metadata.as_ref().and_then(GcsObject::get_crc32c),
+            download_checksum: Arc::new(Mutex::new(GcsDownloadChecksum::default())),
+            file_size,
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+/// Gets the metadata of the object.
+///
+/// # Returns
+///
+/// `HikyakuResult<Option<GcsObject>>` - The metadata, or [None] if the object does not exist.
+async fn get_object(client: &Client, endpoint: &GcsEndpoint, bucket: &str, object: &str) -> HikyakuResult<Option<GcsObject>> {
+    let response = client
+        .get(endpoint.object_url(bucket, object))
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for Cloud Storage: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Cloud Storage: {:?}", e))
+        })?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(GcsError(format!("Failed to get the metadata of gs://{}/{}: {}, {}", bucket, object, status, body)));
+    }
+
+    response
+        .json::<GcsObject>()
+        .await
+        .map(Some)
+        .map_err(|e| GcsError(format!("Failed to parse the metadata of gs://{}/{}: {:?}", bucket, object, e)))
+}
+
+/// Lists a page of the objects and the prefixes under the prefix with the `/` delimiter.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to Cloud Storage.
+/// * `endpoint` - The base URL of the API.
+/// * `bucket` - The name of the bucket.
+/// * `prefix` - The prefix of the object names. The empty prefix lists the root of the bucket.
+/// * `page_token` - The token of the page which the previous page returns.
+/// * `max_results` - The maximum number of the items in the page.
+///
+/// # Returns
+///
+/// `HikyakuResult<GcsObjectList>` - The page, or a `NotExistFileError` if the bucket does not exist.
+pub(crate) async fn list_objects(client: &Client,
+                                 endpoint: &GcsEndpoint,
+                                 bucket: &str,
+                                 prefix: &str,
+                                 page_token: Option<&str>,
+                                 max_results: Option<u64>) -> HikyakuResult<GcsObjectList> {
+    let mut query = vec![
+        ("delimiter", "/".to_string()),
+        ("prefix", prefix.to_string()),
+    ];
+    if let Some(page_token) = page_token {
+        query.push(("pageToken", page_token.to_string()));
+    }
+    if let Some(max_results) = max_results {
+        query.push(("maxResults", max_results.to_string()));
+    }
+    let response = client
+        .get(endpoint.objects_url(bucket))
+        .query(&query)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for Cloud Storage: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Cloud Storage: {:?}", e))
+        })?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(NotExistFileError(format!("Bucket {} does not exist", bucket)));
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        error!("Failed to list objects for Cloud Storage: {}", status);
+        return Err(GcsError(format!("Failed to list objects in gs://{}/{}: {}, {}", bucket, prefix, status, body)));
+    }
+
+    response
+        .json::<GcsObjectList>()
+        .await
+        .map_err(|e| GcsError(format!("Failed to parse the objects in gs://{}/{}: {:?}", bucket, prefix, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_gcs::FakeGcs;
+    use crate::utils::test_support::{assert_read_range, collect, range_data, send_data};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_build_gcs_fake() {
+        let gcs = FakeGcs::start().await;
+        gcs.add_object("datas", "titanic/train.csv", b"a,b,c".to_vec());
+        gcs.add_object("datas", "titanic/test.csv", b"a,b".to_vec());
+        gcs.add_object("datas", "titanic/raw/2024.csv", b"a".to_vec());
+
+        let credentials = [
+            GcsCredential::anonymous(),
+            GcsCredential::from_service_account_key(&gcs.service_account_key()).unwrap(),
+        ];
+        for credential in credentials {
+            let file_obj = FileSystemBuilder::from(credential)
+                .set_endpoint(gcs.endpoint())
+                .set_file_path("gs://datas/titanic/train.csv")
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            assert!(file_obj.to_string().contains("bucket: datas, object: titanic/train.csv, is_dir: false, file_size: Some(5)"));
+        }
+
+        let folder = FileSystemBuilder::from(GcsCredential::anonymous())
+            .set_endpoint(gcs.endpoint())
+            .set_file_path("gs://datas/titanic/")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(folder.to_string().contains("is_dir: true"));
+        assert_eq!(folder.list().await.unwrap(), vec!["titanic/raw", "titanic/test.csv", "titanic/train.csv"]);
+
+        let absent = FileSystemBuilder::from(GcsCredential::anonymous())
+            .set_endpoint(gcs.endpoint())
+            .set_file_path("gs://datas/titanic/valid.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(absent.to_string().contains("is_dir: false, file_size: None"));
+        assert!(matches!(absent.delete().await, Err(NotExistFileError(_))));
+
+        let denied = FileSystemBuilder::from(GcsCredential::new("expired", "refresh", OffsetDateTime::now_utc()))
+            .set_endpoint(gcs.endpoint())
+            .set_file_path("gs://datas/titanic/train.csv")
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(denied, Err(GcsError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_gcs_fake() {
+        let gcs = FakeGcs::start().await;
+        // 2 chunks of 256 KiB and the rest.
+        let data = (0..=255u8).cycle().take(600 * 1024).collect::<Vec<_>>();
+        gcs.add_object("datas", "source.bin", data.clone());
+
+        let build = |path: &str| {
+            let builder = FileSystemBuilder::from(GcsCredential::from_service_account_key(&gcs.service_account_key()).unwrap())
+                .set_endpoint(gcs.endpoint());
+            builder.chunk_size(256 * 1024);
+            builder.set_file_path(path).unwrap().build()
+        };
+        let src = build("gs://datas/source.bin").await.unwrap();
+        let dst = build("gs://datas/copied/copied.bin").await.unwrap();
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(gcs.get_object("datas", "copied/copied.bin").unwrap(), data);
+        assert_eq!(gcs.uploaded_chunks(), 3);
+
+        // The single chunk is uploaded without the resumable upload.
+        let small = build("gs://datas/small.bin").await.unwrap();
+        let (sender, receiver) = channel(1);
+        sender.send(crate::services::file_system::ChunkData::new(b"small".to_vec(), 0, true)).await.unwrap();
+        drop(sender);
+        small.upload(receiver).await.unwrap();
+        assert_eq!(gcs.get_object("datas", "small.bin").unwrap(), b"small");
+        assert_eq!(gcs.uploaded_chunks(), 3);
+
+        let copied = build("gs://datas/copied/copied.bin").await.unwrap();
+        copied.delete().await.unwrap();
+        assert!(gcs.get_object("datas", "copied/copied.bin").is_none());
+
+        // The resumable upload whose last chunk never arrives is cancelled.
+        let (sender, receiver) = channel(1);
+        sender.send(crate::services::file_system::ChunkData::new(data[..256 * 1024].to_vec(), 0, false)).await.unwrap();
+        drop(sender);
+        assert!(dst.upload(receiver).await.is_err());
+        assert_eq!(gcs.open_sessions(), 0);
+        assert_eq!(gcs.uploaded_chunks(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_verify_gcs_checksums_fake() {
+        let gcs = FakeGcs::start().await;
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        gcs.add_object("datas", "source.bin", data.clone());
+        gcs.corrupt_crc32c("datas", "source.bin");
+
+        let builder = FileSystemBuilder::from(GcsCredential::anonymous()).set_endpoint(gcs.endpoint());
+        builder.chunk_size(100);
+        let src = builder.set_file_path("gs://datas/source.bin").unwrap().build().await.unwrap();
+
+        // The chunk which completes the object is not sent when the CRC32C does not match.
+        let (sender, mut receiver) = channel(16);
+        assert!(matches!(src.download(sender).await, Err(GcsError(_))));
+        while let Some(chunk_data) = receiver.recv().await {
+            assert!(!chunk_data.is_last());
+        }
+
+        // The object whose checksums do not match is removed.
+        gcs.corrupt_uploads();
+        let dst = FileSystemBuilder::from(GcsCredential::anonymous())
+            .set_endpoint(gcs.endpoint())
+            .set_file_path("gs://datas/broken.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let (sender, receiver) = channel(1);
+        sender.send(crate::services::file_system::ChunkData::new(data, 0, true)).await.unwrap();
+        drop(sender);
+        assert!(matches!(dst.upload(receiver).await, Err(GcsError(_))));
+        assert!(gcs.get_object("datas", "broken.bin").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs fake-gcs-server listening on localhost:4443 with -scheme http"]
+    async fn test_transfer_fake_gcs_server() {
+        // The bucket is created by the JSON API of the emulator, and the existing one is reused.
+        let status = Client::new()
+            .post("http://localhost:4443/storage/v1/b")
+            .json(&serde_json::json!({"name": "hikyaku"}))
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert!(status.is_success() || status == StatusCode::CONFLICT);
+
+        // 2 chunks of 256 KiB and the rest.
+        let data = (0..=255u8).cycle().take(600 * 1024).collect::<Vec<_>>();
+        let build = |path: &str| {
+            let builder = FileSystemBuilder::from(GcsCredential::anonymous()).set_endpoint(GcsEndpoint::fake_gcs_server());
+            builder.chunk_size(256 * 1024);
+            builder.set_file_path(path).unwrap().build()
+        };
+        let dst = build("gs://hikyaku/emulator/source.bin").await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::join!(send_data(sender, &data, 256 * 1024), dst.upload(receiver)).1.unwrap();
+
+        let src = build("gs://hikyaku/emulator/source.bin").await.unwrap();
+        assert!(src.to_string().contains(&format!("file_size: Some({})", data.len())));
+        let (sender, receiver) = channel(4);
+        let (downloaded, received) = tokio::join!(src.download(sender), collect(receiver));
+        downloaded.unwrap();
+        assert_eq!(received, data);
+        assert_eq!(src.storage_backend().read_range(100, 199).await.unwrap(), data[100..200]);
+
+        let folder = build("gs://hikyaku/emulator/").await.unwrap();
+        assert!(folder.list().await.unwrap().contains(&"emulator/source.bin".to_string()));
+        src.delete().await.unwrap();
+        let deleted = build("gs://hikyaku/emulator/source.bin").await.unwrap();
+        assert!(deleted.to_string().contains("file_size: None"));
+    }
+
+    #[tokio::test]
+    async fn test_read_range_gcs_fake() {
+        let gcs = FakeGcs::start().await;
+        gcs.add_object("datas", "source.bin", range_data());
+        let file_obj = FileSystemBuilder::from(GcsCredential::from_service_account_key(&gcs.service_account_key()).unwrap())
+            .set_endpoint(gcs.endpoint())
+            .set_file_path("gs://datas/source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_read_range(file_obj.storage_backend(), |e| matches!(e, GcsError(_))).await;
+    }
+}
@@ -1,13 +1,15 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 use log::{error};
 use reqwest::{Client};
-use tokio::sync::Mutex;
 use crate::errors::HikyakuError::{BuilderError, ConnectionError, GoogleDriveError, InvalidArgumentError, UnknownError, UnsupportedError};
 use crate::errors::HikyakuResult;
+use crate::services::backends::google_drive::GoogleDriveBackend;
 use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
 use crate::services::file_system_builder::FileSystemBuilder;
 use crate::types::FileInfo;
-use crate::types::google_drive::{DriveFileInfo, DriveFileQueryResponse, GoogleDriveFile, GoogleDriveFileInfo, SharedDriveInfo, SharedDriveQueryResponse};
+use crate::types::google_drive::{AmbiguityStrategy, DocsExportFormat, GoogleDriveConversion, DrawingsExportFormat, DriveFileInfo, DriveFileQueryResponse, DriveQuery, escape_query_value, SheetsExportFormat, SlidesExportFormat, GoogleDriveEndpoint, GoogleDriveFile, GoogleDriveFileInfo, SharedDriveInfo, SharedDriveQueryResponse};
 use crate::utils::credential::Credential;
 use crate::utils::credential::google_drive_credential::GoogleDriveCredential;
 use crate::utils::file_type::FileType;
@@ -15,6 +17,12 @@ use crate::utils::parser::path_to_names_vec;
 use crate::utils::reqwest::AuthType::Bearer;
 use crate::utils::reqwest::get_client_with_token;
 
+// The max number of the files and the shared drives in a page of `files.list` and `drives.list`.
+const FILES_PAGE_SIZE: u32 = 1000;
+const DRIVES_PAGE_SIZE: u32 = 100;
+// The fields of the file metadata which the builder gets from Google Drive API.
+const DRIVE_FILE_FIELDS: &str = "id, name, mimeType, size, exportLinks, shortcutDetails, createdTime, modifiedTime, ownedByMe, owners(emailAddress)";
+
 impl FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
     /// Sets the parent IDs and the file path key for the Google Drive file operation.
     ///
@@ -63,13 +71,170 @@ impl FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
         self
     }
 
+
+    /// Sets the base URLs of the Google Drive API.
+    ///
+    /// By default, the builder and the built `FileSystemObject` talk to `https://www.googleapis.com`.
+    /// This is useful to point them at a mock server in tests or a private endpoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - A [GoogleDriveEndpoint] which holds the API base URLs.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_endpoint(mut self, endpoint: GoogleDriveEndpoint) -> Self {
+        self.options.endpoint = endpoint;
+        self
+    }
+
     
+    /// Sets the format which Google Docs are exported to. The default is `Docx`.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - A [DocsExportFormat] of the exported files.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_docs_export_format(mut self, format: DocsExportFormat) -> Self {
+        self.options.export_formats.docs = format;
+        self
+    }
+
+
+    /// Sets the format which Google Sheets are exported to. The default is `Xlsx`.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - A [SheetsExportFormat] of the exported files.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_sheets_export_format(mut self, format: SheetsExportFormat) -> Self {
+        self.options.export_formats.sheets = format;
+        self
+    }
+
+
+    /// Sets the format which Google Slides are exported to. The default is `Pptx`.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - A [SlidesExportFormat] of the exported files.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_slides_export_format(mut self, format: SlidesExportFormat) -> Self {
+        self.options.export_formats.slides = format;
+        self
+    }
+
+
+    /// Sets the format which Google Drawings are exported to. The default is `Png`.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - A [DrawingsExportFormat] of the exported files.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_drawings_export_format(mut self, format: DrawingsExportFormat) -> Self {
+        self.options.export_formats.drawings = format;
+        self
+    }
+
+    
+    /// Sets whether the shortcut which the path points to is kept as the shortcut.
+    ///
+    /// By default, the shortcuts are followed to their targets, so the shortcut is read as its target file.
+    /// When this is `true`, the shortcut at the end of the path is not followed and
+    /// [ServerSideCopy::copy_from](crate::services::file_system::copy::ServerSideCopy::copy_from) to
+    /// another Google Drive path creates the shortcut to the same target. The shortcuts in the middle
+    /// of the path are always followed.
+    ///
+    /// # Arguments
+    ///
+    /// * `keep_shortcuts` - Whether the shortcut is kept as the shortcut.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_keep_shortcuts(mut self, keep_shortcuts: bool) -> Self {
+        self.options.keep_shortcuts = keep_shortcuts;
+        self
+    }
+
+
+    /// Sets whether the uploaded file is converted to Google Workspace document.
+    ///
+    /// When this is `true`, the uploaded Word, Excel, PowerPoint(and their OpenDocument versions),
+    /// CSV and text files become Google Docs, Sheets or Slides by the filename extension.
+    /// Google Drive converts the file up to 50 MB for Docs and 100 MB for Sheets and Slides.
+    /// The size set by [set_upload_size](Self::set_upload_size) is checked before the upload starts,
+    /// and the upload of the unknown size is cancelled when it goes over the limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `convert` - Whether the uploaded file is converted.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_convert_to_google_workspace(mut self, convert: bool) -> Self {
+        self.options.convert_to_google_workspace = convert;
+        self
+    }
+
+
+    /// Sets the size of the file which will be uploaded.
+    ///
+    /// The size is used to refuse the file which Google Drive cannot convert before the upload starts.
+    ///
+    /// # Arguments
+    ///
+    /// * `upload_size` - The size of the whole file in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_upload_size(mut self, upload_size: u64) -> Self {
+        self.options.upload_size = Some(upload_size);
+        self
+    }
+
+
+    /// Sets how the file is chosen when Google Drive has the files of the same name at a level of the path.
+    ///
+    /// The default is [AmbiguityStrategy::Error] which fails the build. The strategy is applied to
+    /// the intermediate folders as well as the last file or folder of the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - An [AmbiguityStrategy] to choose one of the files of the same name.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_ambiguity_strategy(mut self, strategy: AmbiguityStrategy) -> Self {
+        self.options.ambiguity_strategy = strategy;
+        self
+    }
+
+
     /// Builds a `FileSystemObject` for Google Drive using the specified credentials and file information.
     ///
     /// This function validates the file path to ensure it corresponds to a Google Drive location (either "gd://" or "gds://") 
     /// and resolves the file path to the deepest existing path in Google Drive if you set path as identity of the file. 
     /// It creates HTTP clients for operations, and prepares necessary data 
     /// such as file ID, MIME type, and upload filename for Google Drive interactions.
+    /// Google Docs, Sheets, Slides and Drawings are downloaded by exporting them to the set formats.
+    /// The shortcuts are followed to their targets unless they are kept by [Self::set_keep_shortcuts].
     ///
     /// # Returns
     ///
@@ -105,7 +270,9 @@ impl FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
             }
         };
 
-        let (google_drive_file, not_exist_paths, upload_filename) = match self.file_info.borrow().as_ref() {
+        let file_info = self.file_info.take();
+        let endpoint = self.options.endpoint.clone();
+        let (google_drive_file, not_exist_paths, upload_filename, is_resolved) = match file_info.as_ref() {
             Some(GoogleDriveFileInfo::Parsed(info)) => {
                 if !["gd://", "gds://"].contains(&info.get_prefix()) {
                     return Err(InvalidArgumentError("File system prefix is not gd:// or gds".to_string()));
@@ -115,20 +282,29 @@ impl FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
                     self.file_system_credential.get_credential().get_access_token(),
                     Bearer)?;
 
-                let shared_drive_ids = match info.get_namespace().map(String::from) {
-                    Some(name) => get_shared_drive(&client, &name).await?,
+                // The shared drives of the same name are narrowed down to one as well as the files.
+                let shared_drive_ids = match info.get_namespace() {
+                    Some(name) => {
+                        let mut shared_drives = get_shared_drive(&client, &endpoint, name).await?;
+                        let shared_drive = if shared_drives.len() >= 2 {
+                            self.choose_candidate(&format!("gds://{}/{}", name, info.get_path()), shared_drives)?
+                        } else {
+                            shared_drives.remove(0)
+                        };
+                        vec![shared_drive.get_id().to_string()]
+                    },
                     None => vec![]
                 };
                 let res = self.resolve_path_to_existing_depth(
                     &shared_drive_ids, info.get_path()).await?;
                 let upload_filename = get_upload_filename(info.get_path());
-                (res.0, res.1, upload_filename)
+                (res.0, res.1, upload_filename, res.2)
             },
             Some(GoogleDriveFileInfo::ParentId { parent_ids, file_path }) => {
                 let res = self.resolve_path_to_existing_depth(
-                    &parent_ids, file_path).await?;
+                    parent_ids, file_path).await?;
                 let upload_filename = get_upload_filename(file_path);
-                (res.0, res.1, upload_filename)
+                (res.0, res.1, upload_filename, res.2)
             },
             Some(GoogleDriveFileInfo::FileId(file_id)) => {
                 let client = get_client_with_token(
@@ -144,48 +320,77 @@ impl FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
                         );
                         (drive_file, None)
                     }
-                    else if let Ok(info) = get_drive_from_id(&client, file_id).await {
+                    else if let Ok(info) = get_drive_from_id(&client, &endpoint, file_id).await {
                         // The file id can be Shared Drive ID.
                         (info, None)
                     } else {
-                        let (info, filename) = get_file_from_id(&client, file_id).await?;
+                        let (info, filename) = get_file_from_id(&client, &endpoint, file_id).await?;
+                        let info = if self.options.keep_shortcuts {
+                            info
+                        } else {
+                            resolve_shortcut(&client, &endpoint, info).await?
+                        };
                         (info, Some(Arc::new(filename)))
                     };
-                (Some(file_info), vec![], filename)
+                (Some(file_info), vec![], filename, true)
             }
             None => {
                 return Err(BuilderError("Path is not set".to_string()));
             },
         };
 
-        let clients = (0..self.concurrency.into_inner())
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
             .map(|_| Arc::new(Client::new()))
             .collect::<Vec<_>>();
-        let (queryable_file_or_parent_id, mime_type, file_size) = match google_drive_file {
+        let (queryable_file_or_parent_id, mime_type, file_size, export, shortcut) = match google_drive_file {
             Some(file) => {
-                if file.is_google_workspace_file() || file.is_invalid() {
+                // Google Docs, Sheets, Slides and Drawings have no content, so they are exported.
+                let export = file.export(&self.options.export_formats);
+                let shortcut = file.get_shortcut().cloned();
+                if export.is_none() && shortcut.is_none() && (file.is_google_workspace_file() || file.is_invalid()) {
                     return Err(UnsupportedError(format!("The {} file is currently unsupported.", file.get_mime())));
                 }
 
-                (file.get_id().to_string(), file.get_mime().to_string(), file.get_size())
+                (file.get_id().to_string(), file.get_mime().to_string(), file.get_size(), export.map(Arc::new), shortcut.map(Arc::new))
             },
             None => (
                 "".to_string(),
                 FileType::Unknown.mime().to_string(),
+                None,
+                None,
                 None),
         };
 
-        let file_obj = FileSystemObject::GoogleDrive {
+        // The fully resolved folder(including My Drive and the Shared Drive root) is a directory.
+        let is_dir = is_resolved && mime_type == FileType::GoogleDriveFolder.mime();
+
+        let conversion = match upload_filename.as_ref() {
+            Some(filename) if self.options.convert_to_google_workspace => {
+                let conversion = GoogleDriveConversion::from_filename(filename)
+                    .ok_or_else(|| InvalidArgumentError(format!("{} cannot be converted to Google Workspace document", filename)))?;
+                Some(Arc::new(conversion))
+            },
+            _ => None,
+        };
+
+        let backend = GoogleDriveBackend {
             clients,
             google_drive_token: Arc::new(self.file_system_credential.get_credential()),
+            endpoint: Arc::new(endpoint),
             queryable_file_or_parent_id: Arc::new(queryable_file_or_parent_id),
             not_exist_file_paths: Arc::new(not_exist_paths),
             upload_filename,
             mime_type: Arc::new(mime_type),
-            resumable_upload_url: Arc::new(Mutex::new(None)),
+            export,
+            shortcut,
+            conversion,
+            upload_size: self.options.upload_size,
+            is_dir,
             file_size,
-            chunk_size: self.chunk_size.into_inner(),
         };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
 
         Ok(file_obj)
     }
@@ -201,43 +406,63 @@ impl FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
     /// # Arguments
     ///
     /// * `parent_ids` - Slice of the parent ids when the slice is empty, it represents
-    /// the specified path has no parent (start from root place).
+    ///   the specified path has no parent (start from root place).
     /// * `path` - The path to be resolved, represented as a string.
     ///
     /// # Returns
     ///
-    /// `HikyakuResult<(Option<GoogleDriveFile>, Vec<String>)>` - A result containing a tuple.
+    /// `HikyakuResult<(Option<GoogleDriveFile>, Vec<String>, bool)>` - A result containing a tuple.
     /// The first element is an `Option` with the `GoogleDriveFile` corresponding to the most deeply
     /// existing file or folder. The second element is a vector of the path component names that do not exist on the current GoogleDrive.
-    async fn resolve_path_to_existing_depth(&self, parent_ids: &[String], path: &str) -> HikyakuResult<(Option<GoogleDriveFile>, Vec<String>)> {
+    /// The third element is whether the whole path exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the ambiguity strategy cannot choose one of the same name files.
+    async fn resolve_path_to_existing_depth(&self, parent_ids: &[String], path: &str) -> HikyakuResult<(Option<GoogleDriveFile>, Vec<String>, bool)> {
         let client = get_client_with_token(
             self.file_system_credential.get_credential().get_access_token(),
             Bearer)?;
 
+        let endpoint = self.options.endpoint.clone();
+        let keep_shortcuts = self.options.keep_shortcuts;
         let path_names = path_to_names_vec(path, false)?;
 
         // Store the explored paths nums to skip paths when collect not exist paths.
         let mut complete_explore_path_num = 0;
         let mut parent_infos = initial_parents(parent_ids);
 
-        for name in &path_names {
-            let query_response = query_drive_files(&client, name, &parent_infos).await?;
+        for (depth, name) in path_names.iter().enumerate() {
+            let query_response = query_drive_files(&client, &endpoint, name, &parent_infos).await?;
             if query_response.is_empty() {
                 break
             }
             complete_explore_path_num += 1;
 
-            parent_infos = query_response;
+            // The shortcuts are replaced with their targets, so the next name is searched in the target folder.
+            let is_last = depth == path_names.len() - 1;
+            let mut resolved = vec![];
+            for file in query_response {
+                if keep_shortcuts && is_last {
+                    resolved.push(file);
+                } else {
+                    resolved.push(resolve_shortcut(&client, &endpoint, file).await?);
+                }
+            }
+            // The same name files are narrowed down to one at every level, so the next name is searched in one folder.
+            if resolved.len() >= 2 {
+                resolved = vec![self.choose_candidate(path, resolved)?];
+            }
+            parent_infos = resolved;
         }
         
-        // In the above loop, the most match(most deep match path on the current drive) treat as 
-        // the user input path, even if the partway paths are multiple.
-        // However, if there is 2 or more the most match path exists, it cannot identify the user input actually.
+        // The multiple parents given by `set_parents_ids_and_key` remain when no name of the path is found in them.
         if parent_infos.len() >= 2 {
             return Err(InvalidArgumentError(format!("File path '{}' is ambiguous. There is multiple candidate on the same depth of the path in Google Drive.", path)));
         }
 
         let res = parent_infos.into_iter().next();
+        let is_resolved = complete_explore_path_num == path_names.len();
         
         // In this function, the not exist path not create due to the builder is originally gather information
         // to create object for Hikyaku. Therefore, collect the not exist path to pass it to FileSystemObject.
@@ -249,48 +474,83 @@ impl FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
             .collect::<Vec<_>>();
         remain_path.pop();
 
-        Ok((res, remain_path))
+        Ok((res, remain_path, is_resolved))
+    }
+
+
+    /// Chooses one of the files of the same name by the ambiguity strategy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the strategy cannot choose one.
+    fn choose_candidate(&self, path: &str, mut files: Vec<GoogleDriveFile>) -> HikyakuResult<GoogleDriveFile> {
+        let candidates = files.iter().map(|file| file.get_candidate().clone()).collect::<Vec<_>>();
+        let strategy = self.options.ambiguity_strategy.clone();
+        match strategy.choose(&candidates) {
+            Some(index) => Ok(files.swap_remove(index)),
+            None => {
+                let ids = candidates.iter().map(|candidate| candidate.get_id()).collect::<Vec<_>>();
+                Err(InvalidArgumentError(format!(
+                    "File path '{}' is ambiguous. There is multiple candidate {:?} of '{}' in Google Drive and {:?} strategy cannot choose one.",
+                    path, ids, candidates[0].get_name(), strategy)))
+            },
+        }
     }
 }
 
 
-/// Fetches the IDs of shared drives with the given name from Google Drive.
+/// Fetches the shared drives with the given name from Google Drive.
 ///
 /// # Arguments
 ///
 /// * `client` - The client used to send the request to Google Drive which has token header as default.
+/// * `endpoint` - The base URLs of the Google Drive API.
 /// * `shared_drive_name` - The name of the shared drive to search for.
 ///
 /// # Returns
 ///
-/// `HikyakuResult<Vec<String>>` - A result containing a vector of shared drive IDs, or an error if the operation fails.
-async fn get_shared_drive(client: &Client, shared_drive_name: &str) -> HikyakuResult<Vec<String>> {
-    let response = client
-        .get("https://www.googleapis.com/drive/v3/drives")
-        .query(&[("q", format!("name = '{}'", shared_drive_name))])
-        .send()
-        .await
-        .map_err(|e| {
-            error!("Failed to send request to Google Drive API: {:#?}", e);
-            ConnectionError(format!("Failed to send request to Google Drive API: {:?}", e))
-        })?;
+/// `HikyakuResult<Vec<GoogleDriveFile>>` - A result containing a vector of shared drives of all pages(which are not empty),
+/// or an error if the operation fails.
+async fn get_shared_drive(client: &Client, endpoint: &GoogleDriveEndpoint, shared_drive_name: &str) -> HikyakuResult<Vec<GoogleDriveFile>> {
+    let query = format!("name = '{}'", escape_query_value(shared_drive_name));
+    let mut drives = vec![];
+    let mut page_token = None;
+    loop {
+        let mut params = vec![
+            ("q", query.clone()),
+            ("pageSize", DRIVES_PAGE_SIZE.to_string()),
+            ("fields", "nextPageToken, drives(id, name, createdTime)".to_string()),
+        ];
+        if let Some(page_token) = page_token {
+            params.push(("pageToken", page_token));
+        }
+        let response = client
+            .get(endpoint.drives_url())
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to send request to Google Drive API: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Google Drive API: {:?}", e))
+            })?;
 
-    let shared_drive_ids = response
-        .json::<SharedDriveQueryResponse>()
-        .await
-        .map_err(|e| GoogleDriveError(format!("Failed to parse response from Google Drive API: {:?}", e)))?;
+        let shared_drives = response
+            .json::<SharedDriveQueryResponse>()
+            .await
+            .map_err(|e| GoogleDriveError(format!("Failed to parse response from Google Drive API: {:?}", e)))?;
 
-    if shared_drive_ids.is_empty() {
-        return Err(InvalidArgumentError(format!("Shared drive name: '{}' is not found", shared_drive_name)));
+        drives.extend(shared_drives.get_drives().iter().map(GoogleDriveFile::from_shared_drive));
+        match shared_drives.next_page_token() {
+            Some(next_page_token) => page_token = Some(next_page_token.to_string()),
+            None => break,
+        }
     }
 
-    let ids = shared_drive_ids
-        .get_drives()
-        .iter()
-        .map(|shared_drive| shared_drive.id.clone())
-        .collect::<Vec<_>>();
+    if drives.is_empty() {
+        return Err(InvalidArgumentError(format!("Shared drive name: '{}' is not found", shared_drive_name)));
+    }
 
-    Ok(ids)
+    Ok(drives)
 }
 
 /// Get initial parents as [GoogleDriveFile] from the drives ids. 
@@ -315,81 +575,105 @@ fn initial_parents(drives: &[String]) -> Vec<GoogleDriveFile> {
 /// # Arguments
 ///
 /// * `client` - The client used to send the request to Google Drive which has token header as default.
+/// * `endpoint` - The base URLs of the Google Drive API.
 /// * `file_or_folder_name` - The name of the file or folder to search for.
 /// * `parents` - A slice of parent([GoogleDriveFile]) directories to search within.
 ///
 /// # Returns
 ///
-/// `HikyakuResult<Vec<GoogleDriveFile>>` - A result containing a vector of found Google Drive files, or an error if the operation fails.
-async fn query_drive_files(client: &Client, file_or_folder_name: &str, parents: &[GoogleDriveFile]) -> HikyakuResult<Vec<GoogleDriveFile>> {
-    let query = query_statement_builder(file_or_folder_name, parents);
+/// `HikyakuResult<Vec<GoogleDriveFile>>` - A result containing a vector of found Google Drive files
+/// of all pages except the trashed files, or an error if the operation fails.
+async fn query_drive_files(client: &Client, endpoint: &GoogleDriveEndpoint, file_or_folder_name: &str, parents: &[GoogleDriveFile]) -> HikyakuResult<Vec<GoogleDriveFile>> {
+    let parent_ids = parents.iter().map(GoogleDriveFile::get_id).collect::<Vec<_>>();
+    let query = DriveQuery::new()
+        .set_name(file_or_folder_name)
+        .set_parents(&parent_ids)
+        .build();
 
-    let response = client
-        .get("https://www.googleapis.com/drive/v3/files")
-        .query(&[
-            ("q", &query),
-            ("supportsAllDrives", &"true".to_string()),
-            ("includeItemsFromAllDrives", &"true".to_string()),
-            ("fields", &"files(id, mimeType, size)".to_string()),
-        ])
-        .send()
-        .await
-        .map_err(|e| {
-            error!("Failed to send request to Google Drive API: {:#?}", e);
-            ConnectionError(format!("Failed to send request to Google Drive API: {:?}", e))
-        })?;
+    let mut query_result = vec![];
+    let mut page_token = None;
+    loop {
+        let mut params = vec![
+            ("q", query.clone()),
+            ("supportsAllDrives", "true".to_string()),
+            ("includeItemsFromAllDrives", "true".to_string()),
+            ("pageSize", FILES_PAGE_SIZE.to_string()),
+            ("fields", format!("nextPageToken, files({})", DRIVE_FILE_FIELDS)),
+        ];
+        if let Some(page_token) = page_token {
+            params.push(("pageToken", page_token));
+        }
+        let response = client
+            .get(endpoint.files_url())
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to send request to Google Drive API: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Google Drive API: {:?}", e))
+            })?;
 
-    if !response.status().is_success() {
-        error!("Failed to query files for Google Drive API: {}", response.status());
-        return Err(ConnectionError(format!("Failed to query files for Google Drive API: {}", response.status())));
-    }
+        if !response.status().is_success() {
+            error!("Failed to query files for Google Drive API: {}", response.status());
+            return Err(ConnectionError(format!("Failed to query files for Google Drive API: {}", response.status())));
+        }
 
-    let query_response = response
-        .json::<DriveFileQueryResponse>()
-        .await
-        .map_err(|e| UnknownError(format!("Failed to parse response from Google Drive API: {:#?}", e)))?;
+        let query_response = response
+            .json::<DriveFileQueryResponse>()
+            .await
+            .map_err(|e| UnknownError(format!("Failed to parse response from Google Drive API: {:#?}", e)))?;
 
-    let mut query_result = vec![];
-    for file in query_response.files() {
-        let size = if let Some(size) = file.size() {
-            // Google Drive API returns the file size via JSON string. When it cannot parse to i64, it treats as -1 for handling.
-            if size < 0 {
-                return Err(GoogleDriveError("Google Drive returns invalid size information. If this issue occurs, please report to the author.".to_string()));
-            }
+        for file in query_response.files() {
+            let size = if let Some(size) = file.size() {
+                // Google Drive API returns the file size via JSON string. When it cannot parse to i64, it treats as -1 for handling.
+                if size < 0 {
+                    return Err(GoogleDriveError("Google Drive returns invalid size information. If this issue occurs, please report to the author.".to_string()));
+                }
 
-            Some(size as u64)
-        } else {
-            None
-        };
-        query_result.push(GoogleDriveFile::new(&file.id, &file.mime_type, size))
+                Some(size as u64)
+            } else {
+                None
+            };
+            query_result.push(GoogleDriveFile::from_info(file, size))
+        }
+
+        match query_response.next_page_token() {
+            Some(next_page_token) => page_token = Some(next_page_token.to_string()),
+            None => break,
+        }
     }
 
     Ok(query_result)
 }
 
 
-/// Builds a query statement to search for files or folders in Google Drive.
+/// Follows the shortcut to its target file.
 ///
 /// # Arguments
 ///
-/// * `file_folder_name` - The name of the file or folder to search for.
-/// * `parents` - A slice of parent directories (GoogleDriveFile) to search within.
+/// * `client` - The client used to send the request to Google Drive which has token header as default.
+/// * `endpoint` - The base URLs of the Google Drive API.
+/// * `file` - The file which may be a shortcut.
 ///
 /// # Returns
 ///
-/// `String` - The constructed query statement to be used in Google Drive API requests.
-fn query_statement_builder(file_folder_name: &str, parents: &[GoogleDriveFile]) -> String {
-    let query = format!("name = '{}'", file_folder_name);
-    let mut parents_query = vec![];
-    for parent_info in parents {
-        parents_query.push(format!("'{}' in parents", parent_info.get_id()));
-    }
-    if parents_query.len() > 0 {
-        format!("{} and ({})", query, parents_query.join(" or "))
-    }
-    else {
-        query
+/// `HikyakuResult<GoogleDriveFile>` - The target file, or the `file` itself if it is not shortcut.
+///
+/// # Errors
+///
+/// Returns an `InvalidArgumentError` if the shortcuts point to each other.
+async fn resolve_shortcut(client: &Client, endpoint: &GoogleDriveEndpoint, file: GoogleDriveFile) -> HikyakuResult<GoogleDriveFile> {
+    let mut visited = HashSet::new();
+    let mut file = file;
+    while let Some(shortcut) = file.get_shortcut() {
+        if !visited.insert(file.get_id().to_string()) {
+            return Err(InvalidArgumentError(format!("Shortcut {} is circular", file.get_id())));
+        }
+        let (target, _) = get_file_from_id(client, endpoint, &shortcut.target_id).await?;
+        file = target;
     }
+
+    Ok(file)
 }
 
 
@@ -397,8 +681,8 @@ fn query_statement_builder(file_folder_name: &str, parents: &[GoogleDriveFile])
 ///
 /// This function sends a request to the Google Drive API to obtain details about a shared drive
 /// identified by the specified file ID.
-async fn get_drive_from_id(client: &Client, drive_id: &str) -> HikyakuResult<GoogleDriveFile> {
-    let request_uri = format!("https://www.googleapis.com/drive/v3/drives/{drive_id}");
+async fn get_drive_from_id(client: &Client, endpoint: &GoogleDriveEndpoint, drive_id: &str) -> HikyakuResult<GoogleDriveFile> {
+    let request_uri = endpoint.drive_url(drive_id);
     let response = client
         .get(request_uri)
         .send()
@@ -418,26 +702,21 @@ async fn get_drive_from_id(client: &Client, drive_id: &str) -> HikyakuResult<Goo
         .await
         .map_err(|e| UnknownError(format!("Failed to parse response from Google Drive API: {:#?}", e)))?;
 
-    let google_drive_file = GoogleDriveFile::new(
-        get_response.id.as_ref(),
-        "application/vnd.google-apps.folder",
-        None,
-    );
-
-    Ok(google_drive_file)
+    Ok(GoogleDriveFile::from_shared_drive(&get_response))
 }
 
 /// Retrieves a Google Drive file by its ID.
 ///
 /// This function sends a request to the Google Drive API to obtain details about a file
 /// identified by the specified file ID.
-async fn get_file_from_id(client: &Client, file_id: &str) -> HikyakuResult<(GoogleDriveFile, String)> {
-    let request_uri = format!("https://www.googleapis.com/drive/v3/files/{file_id}");
+async fn get_file_from_id(client: &Client, endpoint: &GoogleDriveEndpoint, file_id: &str) -> HikyakuResult<(GoogleDriveFile, String)> {
+    let request_uri = endpoint.file_url(file_id);
 
     let response = client
         .get(request_uri)
         .query(&[
             ("supportsAllDrives", &"true".to_string()),
+            ("fields", &DRIVE_FILE_FIELDS.to_string()),
         ])
         .send()
         .await
@@ -456,10 +735,7 @@ async fn get_file_from_id(client: &Client, file_id: &str) -> HikyakuResult<(Goog
         .await
         .map_err(|e| UnknownError(format!("Failed to parse response from Google Drive API: {:#?}", e)))?;
 
-    let google_drive_file = GoogleDriveFile::new(
-        get_response.id.as_ref(),
-        get_response.mime_type.as_ref(),
-        get_response.size().map(|size| size as u64));
+    let google_drive_file = GoogleDriveFile::from_info(&get_response, get_response.size().map(|size| size as u64));
 
     Ok((google_drive_file, get_response.name))
 }
@@ -468,8 +744,23 @@ async fn get_file_from_id(client: &Client, file_id: &str) -> HikyakuResult<(Goog
 mod tests {
     use std::env;
     use time::{Duration, OffsetDateTime};
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::ChunkData;
+    use crate::services::file_system::copy::ServerSideCopy;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_google_drive::FakeGoogleDrive;
+    use crate::utils::test_support::{assert_read_range, range_data};
     use super::*;
 
+    fn fake_credential() -> GoogleDriveCredential {
+        GoogleDriveCredential::new(
+            "fake_access_token",
+            "",
+            OffsetDateTime::now_utc() + Duration::hours(1),
+        )
+    }
+
     #[tokio::test]
     async fn test_build_google_drive() {
         let access_token = env::var("GOOGLE_DRIVE_TOKEN").unwrap();
@@ -488,4 +779,371 @@ mod tests {
 
         assert!(file_obj.to_string().contains("1rmRBMDEMurxCBwmpVj47THuYuDVDsco"));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_build_google_drive_fake_shared_drive() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let drive_id = drive.add_shared_drive("datas");
+        let folder_id = drive.add_folder("titanic", Some(&drive_id));
+        let file_id = drive.add_file("train.csv", Some(&folder_id), "text/csv", b"a,b,c".to_vec());
+
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(drive.endpoint())
+            .set_file_path("gds://datas/titanic/train.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(file_obj.to_string().contains(&file_id));
+        assert!(file_obj.to_string().contains("file_size: Some(5)"));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        drive.add_file("source.bin", None, "application/octet-stream", data.clone());
+
+        let builder = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(drive.endpoint());
+        builder.chunk_size(64);
+        let src = builder
+            .set_file_path("gd://source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let builder = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(drive.endpoint());
+        builder.chunk_size(64);
+        let dst = builder
+            .set_file_path("gd://uploaded/copied.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+
+        let folders = drive.find_files("uploaded");
+        let copied = drive.find_files("copied.bin");
+        assert_eq!(folders.len(), 1);
+        assert_eq!(copied.len(), 1);
+        assert_eq!(copied[0].parents, vec![folders[0].id.clone()]);
+        assert_eq!(copied[0].data, data);
+
+        // The resumable upload whose last chunk never arrives is cancelled.
+        let (sender, receiver) = channel(1);
+        sender.send(ChunkData::new(data[..64].to_vec(), 0, false)).await.unwrap();
+        drop(sender);
+        assert!(dst.upload(receiver).await.is_err());
+        assert_eq!(drive.open_uploads(), 0);
+        assert_eq!(drive.find_files("copied.bin").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_empty_file_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        drive.add_file("empty.bin", None, "application/octet-stream", vec![]);
+        let build = |path: &str| {
+            FileSystemBuilder::from(fake_credential())
+                .set_endpoint(drive.endpoint())
+                .set_file_path(path)
+                .unwrap()
+                .build()
+        };
+
+        // The empty file is sent as the empty last chunk, which only tells the total size.
+        let src = build("gd://empty.bin").await.unwrap();
+        let dst = build("gd://uploaded/empty.bin").await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+
+        let folder = drive.find_files("uploaded");
+        let uploaded = drive.find_files("empty.bin")
+            .into_iter()
+            .filter(|file| file.parents == vec![folder[0].id.clone()])
+            .collect::<Vec<_>>();
+        assert_eq!(uploaded.len(), 1);
+        assert!(uploaded[0].data.is_empty());
+        assert_eq!(drive.open_uploads(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_export_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let docx = b"docx of report".to_vec();
+        let markdown = b"# Report".to_vec();
+        let xlsx = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        drive.add_workspace_file("Report", None, FileType::GoogleDocs.mime(), &[
+            (FileType::MSWord.mime(), docx.clone()),
+            (FileType::Markdown.mime(), markdown.clone()),
+        ]);
+        drive.add_workspace_file("Budget", None, FileType::GoogleSheets.mime(), &[(FileType::MSExcel.mime(), xlsx.clone())]);
+        drive.add_file("Form", None, FileType::GoogleForm.mime(), vec![]);
+        // The sheet is over the limit and downloaded from `exportLinks`.
+        drive.set_export_size_limit(500);
+
+        let build = |path: &str, docs_format: DocsExportFormat| {
+            let builder = FileSystemBuilder::from(fake_credential())
+                .set_endpoint(drive.endpoint())
+                .set_docs_export_format(docs_format);
+            builder.chunk_size(64);
+            builder.set_file_path(path).unwrap().build()
+        };
+        let download = |file_obj: FileSystemObject| async move {
+            let (sender, mut receiver) = channel(4);
+            let receive = async move {
+                let mut chunks = vec![];
+                while let Some(chunk_data) = receiver.recv().await {
+                    chunks.push(chunk_data);
+                }
+                chunks
+            };
+            let (downloaded, chunks) = tokio::join!(file_obj.download(sender), receive);
+            downloaded.unwrap();
+            assert!(chunks.last().unwrap().is_last());
+            chunks.into_iter().flat_map(|chunk_data| chunk_data.get_raw_data()).collect::<Vec<_>>()
+        };
+
+        let report = build("gd://Report", DocsExportFormat::Docx).await.unwrap();
+        assert!(report.to_string().contains("file_size: None"));
+        assert_eq!(report.export_filename(), Some("Report.docx".to_string()));
+        assert_eq!(download(report.clone()).await, docx);
+        let (_sender, receiver) = channel(1);
+        assert!(matches!(report.upload(receiver).await, Err(UnsupportedError(_))));
+
+        let report = build("gd://Report", DocsExportFormat::Markdown).await.unwrap();
+        assert_eq!(report.export_filename(), Some("Report.md".to_string()));
+        assert_eq!(download(report).await, markdown);
+
+        let budget = build("gd://Budget", DocsExportFormat::Docx).await.unwrap();
+        assert_eq!(budget.export_filename(), Some("Budget.xlsx".to_string()));
+        assert_eq!(download(budget).await, xlsx);
+
+        // Drive has no PDF of the document, so the export fails.
+        let report = build("gd://Report", DocsExportFormat::Pdf).await.unwrap();
+        let (sender, _receiver) = channel(1);
+        assert!(matches!(report.download(sender).await, Err(GoogleDriveError(_))));
+
+        // The export which is cut off fails the download instead of looking like the short document.
+        drive.set_interrupt_exports(true);
+        let report = build("gd://Report", DocsExportFormat::Markdown).await.unwrap();
+        let (sender, mut receiver) = channel(4);
+        let receive = async move { while receiver.recv().await.is_some() {} };
+        let (downloaded, _) = tokio::join!(report.download(sender), receive);
+        assert!(matches!(downloaded, Err(GoogleDriveError(_))));
+
+        assert!(matches!(build("gd://Form", DocsExportFormat::Docx).await, Err(UnsupportedError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_shortcut_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let datasets_id = drive.add_folder("datasets", None);
+        let train_id = drive.add_file("train.csv", Some(&datasets_id), "text/csv", b"a,b,c".to_vec());
+        let links_id = drive.add_folder("links", None);
+        drive.add_shortcut("datasets", Some(&links_id), &datasets_id);
+        let shortcut_id = drive.add_shortcut("latest.csv", Some(&links_id), &train_id);
+        // The shortcuts which point to each other.
+        let loop_id = drive.add_shortcut("loop", None, &links_id);
+        let back_id = drive.add_shortcut("loop back", None, &loop_id);
+        drive.set_shortcut_target(&loop_id, &back_id);
+        let builder = || FileSystemBuilder::from(fake_credential()).set_endpoint(drive.endpoint());
+
+        // The shortcut in the middle and at the end of the path, and the shortcut by id are followed.
+        for file_obj in [
+            builder().set_file_path("gd://links/datasets/train.csv").unwrap().build().await.unwrap(),
+            builder().set_file_path("gd://links/latest.csv").unwrap().build().await.unwrap(),
+            builder().set_file_id(&shortcut_id).build().await.unwrap(),
+        ] {
+            assert!(file_obj.to_string().contains(&format!("queryable_file_or_parent_id: {}", train_id)));
+            assert!(file_obj.to_string().contains("file_size: Some(5)"));
+        }
+        let dst = builder().set_file_path("gd://links/datasets/valid.csv").unwrap().build().await.unwrap();
+        assert!(dst.to_string().contains(&format!("queryable_file_or_parent_id: {}", datasets_id)));
+        assert!(!dst.storage_backend().stat().await.unwrap().is_dir);
+        // The folder behind the shortcut is a directory, and the file is not.
+        let folder = builder().set_file_path("gd://links/datasets").unwrap().build().await.unwrap();
+        assert!(folder.storage_backend().stat().await.unwrap().is_dir);
+        let file = builder().set_file_path("gd://links/latest.csv").unwrap().build().await.unwrap();
+        assert!(!file.storage_backend().stat().await.unwrap().is_dir);
+
+        // The kept shortcut is copied as the shortcut.
+        let src = builder().set_keep_shortcuts(true).set_file_path("gd://links/latest.csv").unwrap().build().await.unwrap();
+        assert!(src.to_string().contains(&format!("shortcut_target_id: Some(\"{}\")", train_id)));
+        let (_sender, receiver) = channel(1);
+        assert!(matches!(builder().set_keep_shortcuts(true).set_file_id(&shortcut_id).build().await.unwrap().upload(receiver).await, Err(UnsupportedError(_))));
+        let dst = builder().set_file_path("gd://copies/latest.csv").unwrap().build().await.unwrap();
+        dst.copy_from(&src).await.unwrap();
+        let copied = drive.find_files("latest.csv").into_iter().find(|file| file.id != shortcut_id).unwrap();
+        assert_eq!(copied.shortcut_target, Some((train_id.clone(), "text/csv".to_string())));
+        assert_eq!(copied.parents, vec![drive.find_files("copies")[0].id.clone()]);
+
+        assert!(matches!(builder().set_file_path("gd://loop").unwrap().build().await, Err(InvalidArgumentError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_convert_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let build_with_size = |path: &str, upload_size: Option<u64>| {
+            let builder = FileSystemBuilder::from(fake_credential())
+                .set_endpoint(drive.endpoint())
+                .set_convert_to_google_workspace(true);
+            let builder = match upload_size {
+                Some(size) => builder.set_upload_size(size),
+                None => builder,
+            };
+            builder.chunk_size(1024 * 1024);
+            builder.set_file_path(path).unwrap().build()
+        };
+        let build = |path: &str| build_with_size(path, None);
+
+        let dst = build("gd://reports/budget.csv").await.unwrap();
+        assert!(dst.to_string().contains(&format!("conversion_mime_type: Some(\"{}\")", FileType::GoogleSheets.mime())));
+        let (sender, receiver) = channel(2);
+        // The chunks which arrive out of order are uploaded in order.
+        sender.send(ChunkData::new(b",c\n".to_vec(), 1, true)).await.unwrap();
+        sender.send(ChunkData::new(vec![b'a'; 1024 * 1024], 0, false)).await.unwrap();
+        drop(sender);
+        dst.upload(receiver).await.unwrap();
+        let budget = drive.find_files("budget.csv");
+        assert_eq!(budget[0].mime_type, FileType::GoogleSheets.mime());
+        assert_eq!(budget[0].data.len(), 1024 * 1024 + 3);
+
+        // The document over 50 MB is rejected before the upload starts when the size is known.
+        let dst = build_with_size("gd://drafts/minutes.docx", Some(51 * 1024 * 1024)).await.unwrap();
+        let (_sender, receiver) = channel(1);
+        assert!(matches!(dst.upload(receiver).await, Err(InvalidArgumentError(_))));
+        assert!(drive.find_files("drafts").is_empty());
+
+        // The upload of the unknown size is cancelled when it goes over the limit.
+        let dst = build("gd://reports/minutes.docx").await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::spawn(async move {
+            for offset in 0..51 {
+                let _ = sender.send(ChunkData::new(vec![0; 1024 * 1024], offset, false)).await;
+            }
+        });
+        assert!(matches!(dst.upload(receiver).await, Err(InvalidArgumentError(_))));
+        assert!(drive.find_files("reports").len() == 1 && drive.find_files("minutes.docx").is_empty());
+        assert_eq!(drive.open_uploads(), 0);
+
+        assert!(matches!(build("gd://reports/archive.zip").await, Err(InvalidArgumentError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ambiguity_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let old_id = drive.add_folder("data", None);
+        let old_file_id = drive.add_file("train.csv", Some(&old_id), "text/csv", b"old".to_vec());
+        let new_id = drive.add_folder("data", None);
+        let new_file_id = drive.add_file("train.csv", Some(&new_id), "text/csv", b"new".to_vec());
+        drive.update_file(&new_id, |folder| {
+            folder.owners = vec!["other@example.com".to_string()];
+            folder.owned_by_me = false;
+        });
+        let build = |strategy: AmbiguityStrategy| FileSystemBuilder::from(fake_credential())
+            .set_endpoint(drive.endpoint())
+            .set_ambiguity_strategy(strategy)
+            .set_file_path("gd://data/train.csv")
+            .unwrap()
+            .build();
+
+        // The intermediate folders of the same name are ambiguous as well as the last file.
+        assert!(matches!(build(AmbiguityStrategy::Error).await, Err(InvalidArgumentError(_))));
+        for (strategy, file_id) in [
+            (AmbiguityStrategy::MostRecentlyModified, &new_file_id),
+            (AmbiguityStrategy::OldestCreated, &old_file_id),
+            (AmbiguityStrategy::OwnedByMe, &old_file_id),
+        ] {
+            let file_obj = build(strategy).await.unwrap();
+            assert!(file_obj.to_string().contains(&format!("queryable_file_or_parent_id: {}", file_id)));
+        }
+
+        let other_id = new_id.clone();
+        let callback = AmbiguityStrategy::callback(move |candidates| {
+            assert_eq!(candidates.len(), 2);
+            assert!(candidates.iter().all(|candidate| candidate.get_name() == "data" && candidate.get_modified_time().is_some()));
+            candidates.iter().position(|candidate| candidate.get_owners() == ["other@example.com"] && candidate.get_id() == other_id)
+        });
+        let file_obj = build(callback).await.unwrap();
+        assert!(file_obj.to_string().contains(&format!("queryable_file_or_parent_id: {}", new_file_id)));
+        assert!(matches!(build(AmbiguityStrategy::callback(|_| None)).await, Err(InvalidArgumentError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_shared_drives_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let old_drive_id = drive.add_shared_drive("datas");
+        let old_file_id = drive.add_file("train.csv", Some(&old_drive_id), "text/csv", b"old".to_vec());
+        let new_drive_id = drive.add_shared_drive("datas");
+        let new_file_id = drive.add_file("train.csv", Some(&new_drive_id), "text/csv", b"new".to_vec());
+        let build = |strategy: AmbiguityStrategy| FileSystemBuilder::from(fake_credential())
+            .set_endpoint(drive.endpoint())
+            .set_ambiguity_strategy(strategy)
+            .set_file_path("gds://datas/train.csv")
+            .unwrap()
+            .build();
+
+        // The shared drives of the same name are not searched together.
+        assert!(matches!(build(AmbiguityStrategy::Error).await, Err(InvalidArgumentError(_))));
+        // The shared drives have no modified time.
+        assert!(matches!(build(AmbiguityStrategy::MostRecentlyModified).await, Err(InvalidArgumentError(_))));
+        let file_obj = build(AmbiguityStrategy::OldestCreated).await.unwrap();
+        assert!(file_obj.to_string().contains(&format!("queryable_file_or_parent_id: {}", old_file_id)));
+
+        let chosen_id = new_drive_id.clone();
+        let callback = AmbiguityStrategy::callback(move |candidates| {
+            assert!(candidates.iter().all(|candidate| candidate.get_name() == "datas" && candidate.get_created_time().is_some()));
+            candidates.iter().position(|candidate| candidate.get_id() == chosen_id)
+        });
+        let file_obj = build(callback).await.unwrap();
+        assert!(file_obj.to_string().contains(&format!("queryable_file_or_parent_id: {}", new_file_id)));
+    }
+
+    #[tokio::test]
+    async fn test_query_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        let drive_id = drive.add_shared_drive("datas");
+        drive.add_shared_drive("datas");
+        let folder_id = drive.add_folder("it's a \\ folder", Some(&drive_id));
+        drive.add_file("it's a folder", Some(&drive_id), "text/csv", vec![]);
+        // The trashed file does not shadow the live ones.
+        let trashed_id = drive.add_file("train.csv", Some(&folder_id), "text/csv", b"trashed".to_vec());
+        drive.update_file(&trashed_id, |file| file.trashed = true);
+        let file_id = drive.add_file("train.csv", Some(&folder_id), "text/csv", b"a,b,c".to_vec());
+        drive.add_file("train.csv", Some(&folder_id), "text/csv", b"new".to_vec());
+        // The second shared drive and the newer file are found on the second pages.
+        drive.set_max_page_size(1);
+
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(drive.endpoint())
+            .set_ambiguity_strategy(AmbiguityStrategy::OldestCreated)
+            .set_file_path("gds://datas/it's a \\ folder/train.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert!(file_obj.to_string().contains(&format!("queryable_file_or_parent_id: {}", file_id)));
+        assert!(file_obj.to_string().contains("file_size: Some(5)"));
+    }
+
+    #[tokio::test]
+    async fn test_read_range_google_drive_fake() {
+        let drive = FakeGoogleDrive::start().await.unwrap();
+        drive.add_file("source.bin", None, "application/octet-stream", range_data());
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(drive.endpoint())
+            .set_file_path("gd://source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_read_range(file_obj.storage_backend(), |e| matches!(e, GoogleDriveError(_))).await;
+    }
+}
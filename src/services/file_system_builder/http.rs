@@ -0,0 +1,371 @@
+use std::sync::Arc;
+use log::error;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use reqwest::header::{HeaderMap, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use crate::errors::HikyakuError::{BuilderError, ConnectionError, HttpError, InvalidArgumentError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::http::HttpBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::http::parse_content_range_length;
+use crate::utils::credential::Credential;
+use crate::utils::credential::http_credential::HttpCredential;
+use crate::utils::parser::FileSystemParseResult;
+
+impl FileSystemBuilder<HttpCredential, FileSystemParseResult> {
+    /// Sets the header which is sent on every request to the server.
+    ///
+    /// The same name can be set more than once to send the header with the multiple values.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header(e.x. `X-Api-Key`).
+    /// * `value` - The value of the header.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_header(mut self, name: &str, value: &str) -> Self {
+        self.options.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+
+    /// Builds a read-only `FileSystemObject` for the file on the HTTP(S) server using the specified credentials and URL.
+    ///
+    /// This function validates the file path to ensure it has the "http://" or "https://" prefix and the host,
+    /// then discovers the size of the file by HEAD. If the server does not answer HEAD with the length,
+    /// the size is taken from `Content-Range` of the request of the first byte.
+    ///
+    /// The file is downloaded in parallel ranged chunks if the server advertises `Accept-Ranges: bytes`,
+    /// otherwise by a single stream.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError`, `ConnectionError` or `HttpError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use hikyaku::utils::credential::http_credential::HttpCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let file_obj = FileSystemBuilder::from(HttpCredential::from_bearer("token"))
+    ///         .set_header("X-Api-Key", "key")
+    ///         .set_file_path("https://datasets.example.com/titanic/train.csv")
+    ///         .unwrap()
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("Http"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let url = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                if !["http://", "https://"].contains(&file_info.get_prefix()) {
+                    return Err(InvalidArgumentError("File system prefix is not http:// or https://".to_string()));
+                }
+                // SAFETY: The parser always sets the host of `http://` and `https://` as the namespace.
+                format!("{}{}/{}", file_info.get_prefix(), file_info.get_namespace().unwrap(), file_info.get_path())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let headers = self.options.header_map()?;
+        let auth = self.file_system_credential.get_credential();
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
+            .map(|_| auth.create_client(headers.clone()).map(Arc::new))
+            .collect::<HikyakuResult<Vec<_>>>()?;
+        let client = first_client(&clients);
+
+        let discovered = discover_size(client, &url).await?;
+
+        let backend = HttpBackend {
+            clients,
+            url: Arc::new(url),
+            is_exist: discovered.is_some(),
+            accepts_ranges: discovered.as_ref().is_some_and(|file| file.accepts_ranges),
+            file_size: discovered.and_then(|file| file.size),
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+/// Send the request to the HTTP(S) server and check the status.
+///
+/// # Errors
+///
+/// Returns a `ConnectionError` if the request cannot be sent, or a `HttpError` if the status is not success.
+pub(crate) async fn http_request(request: RequestBuilder) -> HikyakuResult<Response> {
+    let response = send(request).await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let url = response.url().to_string();
+        error!("HTTP server returned status code: {} for {}", status, url);
+        return Err(HttpError(format!("HTTP server returned status code: {} for {}", status, url)));
+    }
+
+    Ok(response)
+}
+
+async fn send(request: RequestBuilder) -> HikyakuResult<Response> {
+    request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for HTTP server: {:#?}", e);
+            ConnectionError(format!("Failed to send request to HTTP server: {:?}", e))
+        })
+}
+
+fn header_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)?.to_str().ok()?.parse::<u64>().ok()
+}
+
+fn content_range_length(headers: &HeaderMap) -> Option<u64> {
+    parse_content_range_length(headers.get(CONTENT_RANGE)?.to_str().ok()?)
+}
+
+/// The file which is found on the server.
+struct DiscoveredFile {
+    /// The size, or [None] if the server tells no length(e.x. the chunked response).
+    size: Option<u64>,
+    /// Whether the file can be downloaded by the ranged requests.
+    accepts_ranges: bool,
+}
+
+impl DiscoveredFile {
+    fn new(size: u64, accepts_ranges: bool) -> Self {
+        Self {
+            size: Some(size),
+            accepts_ranges,
+        }
+    }
+
+    /// The file whose length is unknown is downloaded by one GET to the end of the response.
+    fn unknown_size() -> Self {
+        Self {
+            size: None,
+            accepts_ranges: false,
+        }
+    }
+}
+
+/// Discovers the size of the file and whether the server accepts the byte ranges.
+///
+/// # Returns
+///
+/// `HikyakuResult<Option<DiscoveredFile>>` - The size and whether the server accepts the ranges,
+/// or [None] if the file does not exist.
+///
+/// # Errors
+///
+/// Returns a `HttpError` if the access is denied or the server returns the failed status.
+async fn discover_size(client: &Client, url: &str) -> HikyakuResult<Option<DiscoveredFile>> {
+    let head = send(client.head(url)).await?;
+    match head.status() {
+        StatusCode::NOT_FOUND | StatusCode::GONE => return Ok(None),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            return Err(HttpError(format!("Access to {} is denied: {}", url, head.status())));
+        },
+        status if status.is_success() => {
+            // The body of HEAD is always empty, so the length is read from the header itself.
+            if let Some(length) = header_length(head.headers()) {
+                let accepts_ranges = head
+                    .headers()
+                    .get(ACCEPT_RANGES)
+                    .and_then(|value| value.to_str().ok())
+                    .is_some_and(|value| value.split(',').any(|unit| unit.trim() == "bytes"));
+                return Ok(Some(DiscoveredFile::new(length, accepts_ranges)));
+            }
+        },
+        // Some servers do not implement HEAD(e.x. 405 Method Not Allowed), so try GET below.
+        _ => {},
+    }
+
+    // The server which accepts the range returns the complete length in `Content-Range`.
+    let probe = send(client.get(url).header(RANGE, "bytes=0-0")).await?;
+    // The server which tells no length(e.x. `Content-Range: bytes 0-0/*`) still sends the whole file by GET.
+    let file = match probe.status() {
+        StatusCode::PARTIAL_CONTENT => content_range_length(probe.headers())
+            .map_or_else(DiscoveredFile::unknown_size, |length| DiscoveredFile::new(length, true)),
+        // The empty file cannot satisfy the range of the first byte.
+        StatusCode::RANGE_NOT_SATISFIABLE => content_range_length(probe.headers())
+            .map_or_else(DiscoveredFile::unknown_size, |length| DiscoveredFile::new(length, false)),
+        StatusCode::NOT_FOUND | StatusCode::GONE => return Ok(None),
+        status if status.is_success() => header_length(probe.headers())
+            .map_or_else(DiscoveredFile::unknown_size, |length| DiscoveredFile::new(length, false)),
+        status => return Err(HttpError(format!("HTTP server returned status code: {} for {}", status, url))),
+    };
+
+    Ok(Some(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::{channel, Receiver};
+    use crate::errors::HikyakuError::UnsupportedError;
+    use crate::services::file_system::ChunkData;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_http::{FakeHttp, FakeHttpMode, API_KEY, PASSWORD, TOKEN, USER};
+    use crate::utils::test_support::{assert_read_range, collect, range_data};
+    use super::*;
+
+    async fn collect_partial(mut receiver: Receiver<ChunkData>) {
+        while let Some(chunk_data) = receiver.recv().await {
+            assert!(!chunk_data.is_last());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_http_fake() {
+        let server = FakeHttp::start(FakeHttpMode::Ranges).await;
+        server.add_file("datasets/train.csv", b"a,b,c".to_vec());
+        server.add_file("private/train.csv", b"a,b".to_vec());
+        let build = |credential: HttpCredential, path: &str| {
+            FileSystemBuilder::from(credential)
+                .set_header("X-Api-Key", API_KEY)
+                .set_file_path(&format!("http://{}/{}", server.host(), path))
+                .unwrap()
+                .build()
+        };
+
+        let file_obj = build(HttpCredential::anonymous(), "datasets/train.csv").await.unwrap();
+        assert!(file_obj.to_string().contains("accepts_ranges: true, file_size: Some(5)"));
+        assert!(matches!(file_obj.list().await, Err(UnsupportedError(_))));
+        assert!(matches!(file_obj.delete().await, Err(UnsupportedError(_))));
+        let (_sender, receiver) = channel(1);
+        assert!(matches!(file_obj.upload(receiver).await, Err(UnsupportedError(_))));
+
+        let absent = build(HttpCredential::anonymous(), "datasets/valid.csv").await.unwrap();
+        assert!(absent.to_string().contains("file_size: None"));
+
+        for credential in [HttpCredential::from_bearer(TOKEN), HttpCredential::from_basic(USER, PASSWORD)] {
+            let private = build(credential, "private/train.csv").await.unwrap();
+            assert!(private.to_string().contains("file_size: Some(3)"));
+        }
+        let denied = build(HttpCredential::from_bearer("wrong"), "private/train.csv").await;
+        assert!(matches!(denied, Err(HttpError(_))));
+
+        let without_key = FileSystemBuilder::from(HttpCredential::anonymous())
+            .set_file_path(&format!("http://{}/datasets/train.csv", server.host()))
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(without_key, Err(HttpError(_))));
+
+        let invalid_header = FileSystemBuilder::from(HttpCredential::anonymous())
+            .set_header("X Api Key", API_KEY)
+            .set_file_path(&format!("http://{}/datasets/train.csv", server.host()))
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(invalid_header, Err(InvalidArgumentError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_download_http_fake() {
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        // The size is found by HEAD, or by `Content-Range` if the server does not implement HEAD.
+        for (mode, accepts_ranges, range_gets, full_gets) in [
+            (FakeHttpMode::Ranges, true, 16, 0),
+            (FakeHttpMode::RangesWithoutHead, true, 17, 0),
+            (FakeHttpMode::NoRanges, false, 0, 1),
+        ] {
+            let server = FakeHttp::start(mode).await;
+            server.add_file("datasets/source.bin", data.clone());
+            let builder = FileSystemBuilder::from(HttpCredential::anonymous()).set_header("X-Api-Key", API_KEY);
+            builder.chunk_size(64);
+            let src = builder
+                .set_file_path(&format!("http://{}/datasets/source.bin", server.host()))
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            assert!(src.to_string().contains(&format!("accepts_ranges: {}, file_size: Some(1000)", accepts_ranges)));
+
+            let (sender, receiver) = channel(4);
+            let (downloaded, received) = tokio::join!(src.download(sender), collect(receiver));
+            downloaded.unwrap();
+            assert_eq!(received, data);
+            assert_eq!((server.range_gets(), server.full_gets()), (range_gets, full_gets));
+
+            // The response which ends before the file size fails the download.
+            if !accepts_ranges {
+                server.set_interrupt_full_gets(true);
+                let (sender, receiver) = channel(4);
+                let (downloaded, _) = tokio::join!(src.download(sender), collect_partial(receiver));
+                assert!(matches!(downloaded, Err(HttpError(_))));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_http_fake_unknown_size() {
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        let server = FakeHttp::start(FakeHttpMode::Chunked).await;
+        server.add_file("datasets/source.bin", data.clone());
+        let src = FileSystemBuilder::from(HttpCredential::anonymous())
+            .set_header("X-Api-Key", API_KEY)
+            .set_file_path(&format!("http://{}/datasets/source.bin", server.host()))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(src.to_string().contains("accepts_ranges: false, file_size: None"));
+
+        // The whole file is streamed by one GET after the probe.
+        let (sender, receiver) = channel(4);
+        let (downloaded, received) = tokio::join!(src.download(sender), collect(receiver));
+        downloaded.unwrap();
+        assert_eq!(received, data);
+        assert_eq!((server.range_gets(), server.full_gets()), (0, 2));
+
+        // The missing file is still not found.
+        let missing = FileSystemBuilder::from(HttpCredential::anonymous())
+            .set_header("X-Api-Key", API_KEY)
+            .set_file_path(&format!("http://{}/datasets/missing.bin", server.host()))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(!missing.to_string().contains("file_size: Some"));
+        let (sender, _receiver) = channel(4);
+        assert!(missing.download(sender).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_range_http_fake() {
+        // The range is cut out of the whole file when the server ignores the range.
+        for mode in [FakeHttpMode::Ranges, FakeHttpMode::NoRanges] {
+            let server = FakeHttp::start(mode).await;
+            server.add_file("datasets/source.bin", range_data());
+            let file_obj = FileSystemBuilder::from(HttpCredential::anonymous())
+                .set_header("X-Api-Key", API_KEY)
+                .set_file_path(&format!("http://{}/datasets/source.bin", server.host()))
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            assert_read_range(file_obj.storage_backend(), |e| matches!(e, HttpError(_))).await;
+        }
+    }
+}
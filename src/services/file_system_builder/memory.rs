@@ -0,0 +1,236 @@
+use std::sync::Arc;
+use crate::errors::HikyakuError::{BuilderError, InvalidArgumentError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::memory::MemoryBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::utils::memory_store::MemoryStore;
+use crate::utils::parser::FileSystemParseResult;
+
+impl FileSystemBuilder<MemoryStore, FileSystemParseResult> {
+    /// Builds a `FileSystemObject` for the in-memory store.
+    ///
+    /// This function validates the file path to ensure it has the "mem://" prefix and then
+    /// extracts the namespace and path information. The file size is looked up from the store
+    /// which the builder was created from.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError` or `BuilderError` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the file prefix is not "mem://".
+    /// Returns a `BuilderError` if the namespace cannot be found or the path is not set.
+    pub fn build(self) -> HikyakuResult<FileSystemObject> {
+        let (namespace, path) = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                if file_info.get_prefix() != "mem://" {
+                    return Err(InvalidArgumentError("File system prefix is not mem://".to_string()));
+                }
+                let namespace = file_info.get_namespace()
+                    .ok_or(BuilderError("Namespace cannot found".to_string()))?
+                    .to_string();
+
+                (namespace, file_info.get_path().to_string())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let file_size = self.file_system_credential.size(&namespace, &path);
+
+        let backend = MemoryBackend {
+            store: self.file_system_credential,
+            namespace: Arc::new(namespace),
+            path: Arc::new(path),
+            file_size,
+        };
+        let stat = backend.current_stat();
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, self.concurrency.into_inner(), self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::ChunkData;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::errors::HikyakuError::{ConnectionError, UnknownError};
+    use crate::utils::memory_store::MemoryOperation;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_build_memory() {
+        let store = MemoryStore::new();
+        store.insert("bucket", "datas/train.csv", b"a,b,c".to_vec());
+
+        let file_obj = FileSystemBuilder::from(store)
+            .set_file_path("mem://bucket/datas/train.csv")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert!(file_obj.to_string().contains("Memory"));
+        assert!(file_obj.to_string().contains("file_size: Some(5)"));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_memory() {
+        let store = MemoryStore::new();
+        let data = (0..100u8).collect::<Vec<_>>();
+        store.insert("src", "file.bin", data.clone());
+
+        let builder = FileSystemBuilder::from(store.clone());
+        builder.chunk_size(7);
+        let src = builder
+            .set_file_path("mem://src/file.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+        let builder = FileSystemBuilder::from(store.clone());
+        builder.chunk_size(7);
+        let dst = builder
+            .set_file_path("mem://dst/copied/file.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+
+        assert_eq!(store.get("dst", "copied/file.bin").unwrap(), data);
+        assert_eq!(dst.list().await.unwrap(), vec!["copied/file.bin"]);
+
+        dst.delete().await.unwrap();
+        assert!(store.get("dst", "copied/file.bin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_empty_memory() {
+        let store = MemoryStore::new();
+        store.insert("src", "empty.bin", vec![]);
+
+        let src = FileSystemBuilder::from(store.clone())
+            .set_file_path("mem://src/empty.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+        let dst = FileSystemBuilder::from(store.clone())
+            .set_file_path("mem://dst/empty.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+
+        assert_eq!(store.get("dst", "empty.bin").unwrap(), Vec::<u8>::new());
+    }
+
+    #[tokio::test]
+    async fn test_download_memory_failure() {
+        let store = MemoryStore::new();
+        let data = (0..100u8).collect::<Vec<_>>();
+        store.insert("src", "file.bin", data.clone());
+
+        let builder = FileSystemBuilder::from(store.clone());
+        builder.chunk_size(7);
+        let src = builder
+            .set_file_path("mem://src/file.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+        let builder = FileSystemBuilder::from(store.clone());
+        builder.chunk_size(7);
+        let dst = builder
+            .set_file_path("mem://dst/file.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // The failed read fails the download, so the caller can retry it.
+        store.fail_next(MemoryOperation::Read, 1);
+        let (sender, mut receiver) = channel(16);
+        let receive = async move { while receiver.recv().await.is_some() {} };
+        let (downloaded, _) = tokio::join!(src.download(sender), receive);
+        assert!(matches!(downloaded, Err(ConnectionError(_))));
+
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(store.get("dst", "file.bin").unwrap(), data);
+
+        // Nobody receives the chunks, so the download fails instead of panicking.
+        let (sender, receiver) = channel(4);
+        drop(receiver);
+        assert!(matches!(src.download(sender).await, Err(UnknownError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_upload_memory_incomplete() {
+        let store = MemoryStore::new();
+        let builder = FileSystemBuilder::from(store.clone());
+        builder.chunk_size(2);
+        let dst = builder
+            .set_file_path("mem://dst/file.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // The chunk 1 is missing, so the chunk 2 can never be written.
+        let (sender, receiver) = channel(4);
+        sender.send(ChunkData::new(b"ab".to_vec(), 0, false)).await.unwrap();
+        sender.send(ChunkData::new(b"e".to_vec(), 2, true)).await.unwrap();
+        drop(sender);
+        assert!(matches!(dst.upload(receiver).await, Err(UnknownError(_))));
+        assert!(store.get("dst", "file.bin").is_none());
+
+        // The sender is closed before the last chunk.
+        let (sender, receiver) = channel(4);
+        sender.send(ChunkData::new(b"ab".to_vec(), 0, false)).await.unwrap();
+        drop(sender);
+        assert!(matches!(dst.upload(receiver).await, Err(UnknownError(_))));
+        assert!(store.get("dst", "file.bin").is_none());
+
+        // Nothing is sent at all.
+        let (sender, receiver) = channel(4);
+        drop(sender);
+        assert!(matches!(dst.upload(receiver).await, Err(UnknownError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_upload_memory_abort_with_failures() {
+        let store = MemoryStore::new();
+        let builder = FileSystemBuilder::from(store.clone());
+        builder.chunk_size(2);
+        let dst = builder
+            .set_file_path("mem://dst/file.bin")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let (sender, receiver) = channel(4);
+        let upload = tokio::spawn(async move { dst.upload(receiver).await });
+        sender.send(ChunkData::new(b"ab".to_vec(), 0, false)).await.unwrap();
+        while store.get("dst", "file.bin").is_none() {
+            tokio::task::yield_now().await;
+        }
+
+        // The write of the last chunk fails after the first chunk is written, and the injected
+        // failure of the delete does not leave the partial object either.
+        store.fail_next(MemoryOperation::Write, 1);
+        store.fail_next(MemoryOperation::Delete, 1);
+        sender.send(ChunkData::new(b"cd".to_vec(), 1, true)).await.unwrap();
+        drop(sender);
+        assert!(matches!(upload.await.unwrap(), Err(ConnectionError(_))));
+        assert!(store.get("dst", "file.bin").is_none());
+    }
+}
@@ -8,16 +8,61 @@ use log::error;
 use tokio::sync::Mutex;
 use crate::errors::HikyakuError::{InvalidArgumentError};
 use crate::errors::HikyakuResult;
+use crate::services::backends::local::LocalBackend;
 use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::BackendStat;
 use crate::types::FileInfo;
+#[cfg(feature = "box_storage")]
+use crate::types::box_storage::BoxFileInfo;
+#[cfg(feature = "google_drive")]
 use crate::types::google_drive::GoogleDriveFileInfo;
 use crate::utils::credential::{Credential, NoCredential};
+#[cfg(feature = "azure_blob")]
+use crate::utils::credential::azure_blob_credential::AzureBlobCredential;
+#[cfg(feature = "box_storage")]
+use crate::utils::credential::box_credential::BoxCredential;
+#[cfg(feature = "dropbox")]
+use crate::utils::credential::dropbox_credential::DropboxCredential;
+#[cfg(feature = "google_cloud_storage")]
+use crate::utils::credential::gcs_credential::GcsCredential;
+#[cfg(feature = "google_drive")]
 use crate::utils::credential::google_drive_credential::GoogleDriveCredential;
+#[cfg(feature = "onedrive")]
+use crate::utils::credential::onedrive_credential::OneDriveCredential;
+#[cfg(feature = "amazon_s3")]
 use crate::utils::credential::s3_credential::S3Credential;
+#[cfg(feature = "sftp")]
+use crate::utils::credential::sftp_credential::SftpCredential;
+#[cfg(feature = "webdav")]
+use crate::utils::credential::webdav_credential::WebDavCredential;
+#[cfg(feature = "http")]
+use crate::utils::credential::http_credential::HttpCredential;
+use crate::utils::memory_store::MemoryStore;
+use crate::services::storage_backend::BackendRegistry;
 use crate::utils::parser::{file_system_prefix_parser, FileSystemParseResult};
 
+#[cfg(feature = "amazon_s3")]
 pub(crate) mod amazon_s3;
+#[cfg(feature = "google_drive")]
 pub(crate) mod google_drive;
+#[cfg(feature = "dropbox")]
+pub(crate) mod dropbox;
+#[cfg(feature = "box_storage")]
+pub(crate) mod box_storage;
+#[cfg(feature = "onedrive")]
+pub(crate) mod onedrive;
+#[cfg(feature = "azure_blob")]
+pub(crate) mod azure_blob;
+#[cfg(feature = "google_cloud_storage")]
+pub(crate) mod google_cloud_storage;
+#[cfg(feature = "sftp")]
+pub(crate) mod sftp;
+#[cfg(feature = "webdav")]
+pub(crate) mod webdav;
+#[cfg(feature = "http")]
+pub(crate) mod http;
+pub(crate) mod memory;
+pub(crate) mod registry;
 
 
 /// A builder for constructing instances of a file system with a specified
@@ -38,6 +83,7 @@ where
     file_system_credential: C,
     concurrency: RefCell<u16>,
     chunk_size: RefCell<u64>,
+    options: C::Options,
 }
 
 impl<C, FI> FileSystemBuilder<C, FI>
@@ -63,6 +109,7 @@ where
             file_system_credential,
             concurrency,
             chunk_size,
+            options: C::Options::default(),
         }
     }
 
@@ -84,7 +131,7 @@ where
     /// An error is returned if the `file_system_prefix_parser` fails to parse the provided `path`.
     ///
     pub fn set_file_path(self, path: &str) -> HikyakuResult<Self> {
-        let parse_res = file_system_prefix_parser(path)?;
+        let parse_res = file_system_prefix_parser(path, &self.file_system_credential.registered_prefixes())?;
         let info = FI::from(parse_res);
         *self.file_info.borrow_mut() = Some(info);
 
@@ -207,31 +254,105 @@ impl FileSystemBuilder<NoCredential, FileSystemParseResult> {
             }
         };
 
-        let file_obj = FileSystemObject::Local {
+        let backend = LocalBackend {
             path: Arc::new(PathBuf::from(path)),
             file: Arc::new(Mutex::new(None)),
             is_dir,
             file_size,
-            concurrency: self.concurrency.into_inner(),
-            chunk_size: self.chunk_size.into_inner(),
         };
+        let stat = BackendStat {
+            file_size,
+            is_dir,
+            is_streamed: false,
+        };
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, self.concurrency.into_inner(), self.chunk_size.into_inner());
 
         Ok(file_obj)
     }
 }
 
+#[cfg(feature = "amazon_s3")]
 impl From<S3Credential> for FileSystemBuilder<S3Credential, FileSystemParseResult> {
     fn from(value: S3Credential) -> Self {
         Self::new(value)
     }
 }
 
+#[cfg(feature = "google_drive")]
 impl From<GoogleDriveCredential> for FileSystemBuilder<GoogleDriveCredential, GoogleDriveFileInfo> {
     fn from(value: GoogleDriveCredential) -> Self {
         Self::new(value)
     }
 }
 
+#[cfg(feature = "dropbox")]
+impl From<DropboxCredential> for FileSystemBuilder<DropboxCredential, FileSystemParseResult> {
+    fn from(value: DropboxCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "box_storage")]
+impl From<BoxCredential> for FileSystemBuilder<BoxCredential, BoxFileInfo> {
+    fn from(value: BoxCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "onedrive")]
+impl From<OneDriveCredential> for FileSystemBuilder<OneDriveCredential, FileSystemParseResult> {
+    fn from(value: OneDriveCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "azure_blob")]
+impl From<AzureBlobCredential> for FileSystemBuilder<AzureBlobCredential, FileSystemParseResult> {
+    fn from(value: AzureBlobCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "google_cloud_storage")]
+impl From<GcsCredential> for FileSystemBuilder<GcsCredential, FileSystemParseResult> {
+    fn from(value: GcsCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "sftp")]
+impl From<SftpCredential> for FileSystemBuilder<SftpCredential, FileSystemParseResult> {
+    fn from(value: SftpCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "webdav")]
+impl From<WebDavCredential> for FileSystemBuilder<WebDavCredential, FileSystemParseResult> {
+    fn from(value: WebDavCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "http")]
+impl From<HttpCredential> for FileSystemBuilder<HttpCredential, FileSystemParseResult> {
+    fn from(value: HttpCredential) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<BackendRegistry> for FileSystemBuilder<BackendRegistry, FileSystemParseResult> {
+    fn from(value: BackendRegistry) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<MemoryStore> for FileSystemBuilder<MemoryStore, FileSystemParseResult> {
+    fn from(value: MemoryStore) -> Self {
+        Self::new(value)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::env;
@@ -0,0 +1,425 @@
+use std::sync::Arc;
+use log::error;
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
+use crate::errors::HikyakuError::{BuilderError, ConnectionError, InvalidArgumentError, NotExistFileError, OneDriveError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::onedrive::OneDriveBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::onedrive::{encode_path, OneDriveCollection, OneDriveDrive, OneDriveEndpoint, OneDriveItem, OneDriveOptions, OneDriveSite};
+use crate::utils::credential::Credential;
+use crate::utils::credential::onedrive_credential::OneDriveCredential;
+use crate::utils::parser::{path_to_names_vec, FileSystemParseResult};
+use crate::utils::reqwest::AuthType::Bearer;
+use crate::utils::reqwest::get_client_with_token;
+
+impl FileSystemBuilder<OneDriveCredential, FileSystemParseResult> {
+    /// Sets the base URL of the Microsoft Graph API.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - A [OneDriveEndpoint] which holds the Graph API base URL.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_endpoint(mut self, endpoint: OneDriveEndpoint) -> Self {
+        self.options.endpoint = endpoint;
+        self
+    }
+
+
+    /// Sets the id of the drive to address instead of resolving it from the path.
+    ///
+    /// By default, `od://` addresses the OneDrive of the signed-in user and `sp://` addresses
+    /// the document library of the site. The drive id addresses any drive which the token can access.
+    ///
+    /// # Arguments
+    ///
+    /// * `drive_id` - A string slice that holds the id of the drive.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_drive_id(mut self, drive_id: &str) -> Self {
+        self.options.drive_id = Some(drive_id.to_string());
+        self
+    }
+
+
+    /// Sets the name of the document library of the SharePoint site(e.x. `Documents`).
+    ///
+    /// Without the name, `sp://` addresses the default document library of the site.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - A string slice that holds the name of the document library.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_document_library(mut self, name: &str) -> Self {
+        self.options.document_library = Some(name.to_string());
+        self
+    }
+
+
+    /// Sets the size of the file which will be uploaded.
+    ///
+    /// The upload session needs the file size in every fragment. Without the size,
+    /// the chunks are kept in memory until the last chunk arrives.
+    ///
+    /// # Arguments
+    ///
+    /// * `upload_size` - The size of the whole file in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_upload_size(mut self, upload_size: u64) -> Self {
+        self.options.upload_size = Some(upload_size);
+        self
+    }
+
+
+    /// Builds a `FileSystemObject` for OneDrive or SharePoint using the specified credentials and file path.
+    ///
+    /// The path with "od://" addresses the OneDrive of the signed-in user, and the path with "sp://"
+    /// addresses the document library of the SharePoint site whose name is the first component of the path.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError` or `OneDriveError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use time::{Duration, OffsetDateTime};
+    /// use hikyaku::utils::credential::onedrive_credential::OneDriveCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let cred = OneDriveCredential::new("access_token", "refresh_token", OffsetDateTime::now_utc() + Duration::hours(1));
+    ///     let file_obj = FileSystemBuilder::from(cred)
+    ///         .set_file_path("sp://Marketing/plans/2024.xlsx")
+    ///         .unwrap()
+    ///         .set_document_library("Documents")
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("OneDrive"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let token = self.file_system_credential.get_credential();
+        let options = self.options.clone();
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
+            .map(|_| get_client_with_token(token.get_access_token(), Bearer).map(Arc::new))
+            .collect::<HikyakuResult<Vec<_>>>()?;
+        let client = first_client(&clients);
+
+        let (drive_id, path) = match self.file_info.take() {
+            Some(info) => {
+                let drive_id = match (info.get_prefix(), info.get_namespace(), &options.drive_id) {
+                    ("od://" | "sp://", _, Some(drive_id)) => drive_id.clone(),
+                    ("od://", _, None) => get_json::<OneDriveDrive>(client, &options.endpoint.my_drive_url()).await?.id,
+                    ("sp://", Some(site_name), None) => get_site_drive_id(client, &options, site_name).await?,
+                    _ => return Err(InvalidArgumentError("File system prefix is not od:// or sp://".to_string())),
+                };
+                // Validate the path components before putting them in the URL.
+                let path = path_to_names_vec(info.get_path(), false)?.join("/");
+                (drive_id, path)
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            },
+        };
+
+        let (is_dir, file_size) = match get_item(client, &options.endpoint, &drive_id, &path).await? {
+            Some(item) if item.is_folder() => (true, None),
+            Some(item) => (false, item.size),
+            None => (false, None),
+        };
+
+        let backend = OneDriveBackend {
+            clients,
+            endpoint: Arc::new(options.endpoint),
+            drive_id: Arc::new(drive_id),
+            path: Arc::new(path),
+            is_dir,
+            upload_size: options.upload_size,
+            file_size,
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+/// Resolves the drive id of the document library of the SharePoint site.
+///
+/// The site is searched by the name, and the name is compared with both of the name in the URL
+/// and the display name. The site id(`{hostname},{site collection id},{site id}`) is also accepted as is.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to Microsoft Graph which has token header as default.
+/// * `options` - The options which have the endpoint and the name of the document library.
+/// * `site_name` - The name or the id of the site.
+///
+/// # Returns
+///
+/// `HikyakuResult<String>` - The drive id, or an `InvalidArgumentError` if the site or the library
+/// is not found or the site name is ambiguous.
+async fn get_site_drive_id(client: &Client, options: &OneDriveOptions, site_name: &str) -> HikyakuResult<String> {
+    let site_id = if site_name.contains(',') {
+        site_name.to_string()
+    } else {
+        let request_url = format!("{}?search={}", options.endpoint.sites_url(), encode_path(site_name));
+        let lowercase_name = site_name.to_lowercase();
+        let sites = get_all_pages::<OneDriveSite>(client, &request_url)
+            .await?
+            .into_iter()
+            .filter(|site| [&site.name, &site.display_name]
+                .iter()
+                .any(|name| name.as_ref().is_some_and(|name| name.to_lowercase() == lowercase_name)))
+            .collect::<Vec<_>>();
+
+        match sites.as_slice() {
+            [site] => site.id.clone(),
+            [] => return Err(InvalidArgumentError(format!("SharePoint site '{}' is not found", site_name))),
+            _ => {
+                let ids = sites.iter().map(|site| site.id.as_str()).collect::<Vec<_>>();
+                return Err(InvalidArgumentError(format!(
+                    "SharePoint site name '{}' is ambiguous. Please specify the site id from {:?}", site_name, ids)));
+            },
+        }
+    };
+
+    match &options.document_library {
+        Some(library) => get_all_pages::<OneDriveDrive>(client, &options.endpoint.site_drives_url(&site_id))
+            .await?
+            .into_iter()
+            .find(|drive| drive.name.as_ref().is_some_and(|name| name.to_lowercase() == library.to_lowercase()))
+            .map(|drive| drive.id)
+            .ok_or_else(|| InvalidArgumentError(format!("Document library '{}' is not found in the site '{}'", library, site_name))),
+        None => Ok(get_json::<OneDriveDrive>(client, &options.endpoint.site_drive_url(&site_id)).await?.id),
+    }
+}
+
+/// Retrieves the item at the path in the drive.
+///
+/// # Returns
+///
+/// `HikyakuResult<Option<OneDriveItem>>` - The file or folder, or [None] if nothing exists at the path.
+async fn get_item(client: &Client, endpoint: &OneDriveEndpoint, drive_id: &str, path: &str) -> HikyakuResult<Option<OneDriveItem>> {
+    match get_json::<OneDriveItem>(client, &endpoint.item_url(drive_id, path, None)).await {
+        Ok(item) => Ok(Some(item)),
+        Err(NotExistFileError(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Lists all items of the collection by following `@odata.nextLink`.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to Microsoft Graph which has token header as default.
+/// * `request_url` - The URL of the first page.
+pub(crate) async fn get_all_pages<T: DeserializeOwned>(client: &Client, request_url: &str) -> HikyakuResult<Vec<T>> {
+    let mut values = vec![];
+    let mut next_link = Some(request_url.to_string());
+    while let Some(request_url) = next_link {
+        let page = get_json::<OneDriveCollection<T>>(client, &request_url).await?;
+        values.extend(page.value);
+        next_link = page.next_link;
+    }
+
+    Ok(values)
+}
+
+/// Sends the GET request to Microsoft Graph and parses the response.
+///
+/// # Errors
+///
+/// Returns `NotExistFileError` if the resource is not found.
+async fn get_json<T: DeserializeOwned>(client: &Client, request_url: &str) -> HikyakuResult<T> {
+    let response = client
+        .get(request_url)
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to send request to Microsoft Graph: {:#?}", e);
+            ConnectionError(format!("Failed to send request to Microsoft Graph: {:?}", e))
+        })?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND {
+        return Err(NotExistFileError(format!("{} is not found", request_url)));
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        error!("Failed to get {} from Microsoft Graph: {}", request_url, status);
+        return Err(OneDriveError(format!("Failed to get {}: {}, {}", request_url, status, body)));
+    }
+
+    response
+        .json::<T>()
+        .await
+        .map_err(|e| OneDriveError(format!("Failed to parse response from Microsoft Graph: {:?}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+    use tokio::sync::mpsc::channel;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_onedrive::FakeOneDrive;
+    use crate::utils::test_support::{assert_read_range, range_data};
+    use super::*;
+
+    fn fake_credential() -> OneDriveCredential {
+        OneDriveCredential::new("fake_access_token", "", OffsetDateTime::now_utc() + Duration::hours(1))
+    }
+
+    #[tokio::test]
+    async fn test_build_onedrive_fake() {
+        let graph = FakeOneDrive::start().await;
+        graph.add_file("me", "datas/train.csv", b"a,b,c".to_vec());
+        graph.add_site("Sales Archive", "sales-documents");
+        graph.add_site("Marketing", "marketing-documents");
+        graph.add_file("marketing-documents", "plans/2024 plan.xlsx", vec![1; 10]);
+
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(graph.endpoint())
+            .set_file_path("od://datas/train.csv")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(file_obj.to_string().contains("drive_id: me, path: datas/train.csv, is_dir: false, file_size: Some(5)"));
+
+        let site_file = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(graph.endpoint())
+            .set_file_path("sp://marketing/plans/2024 plan.xlsx")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(site_file.to_string().contains("drive_id: marketing-documents"));
+        assert!(site_file.to_string().contains("file_size: Some(10)"));
+
+        let library = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(graph.endpoint())
+            .set_file_path("sp://Marketing/plans")
+            .unwrap()
+            .set_document_library("Archive")
+            .build()
+            .await;
+        assert!(matches!(library, Err(InvalidArgumentError(_))));
+
+        let absent = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(graph.endpoint())
+            .set_file_path("sp://Sales/plans")
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(absent, Err(InvalidArgumentError(_))));
+
+        let folder = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(graph.endpoint())
+            .set_file_path("od://datas")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(folder.to_string().contains("is_dir: true"));
+        assert_eq!(folder.list().await.unwrap(), vec!["datas/train.csv"]);
+
+        file_obj.delete().await.unwrap();
+        assert!(graph.get_file("me", "datas/train.csv").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_onedrive_fake() {
+        let graph = FakeOneDrive::start().await;
+        let data = (0..=255u8).cycle().take(4 * 1024 * 1024 + 1000).collect::<Vec<_>>();
+        graph.add_file("me", "source.bin", data.clone());
+
+        let build = |path: &str, upload_size: Option<u64>| {
+            let builder = FileSystemBuilder::from(fake_credential())
+                .set_endpoint(graph.endpoint());
+            let builder = match upload_size {
+                Some(size) => builder.set_upload_size(size),
+                None => builder,
+            };
+            builder.chunk_size(1024 * 1024);
+            builder.set_file_path(path).unwrap().build()
+        };
+
+        // The chunks of 1 MiB are re-split into the fragments of 960 KiB(3 times 320 KiB).
+        let src = build("od://source.bin", None).await.unwrap();
+        let dst = build("od://copied/session.bin", Some(data.len() as u64)).await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(graph.get_file("me", "copied/session.bin").unwrap(), data);
+        assert_eq!(graph.uploaded_fragments(), 5);
+
+        // Without the upload size, the chunks are kept until the last chunk.
+        let dst = build("od://copied/buffered.bin", None).await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(graph.get_file("me", "copied/buffered.bin").unwrap(), data);
+        assert_eq!(graph.uploaded_fragments(), 10);
+
+        let small = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(graph.endpoint())
+            .set_file_path("od://copied/small.txt")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let (sender, receiver) = channel(1);
+        sender.send(crate::services::file_system::ChunkData::new(b"small".to_vec(), 0, true)).await.unwrap();
+        drop(sender);
+        small.upload(receiver).await.unwrap();
+        assert_eq!(graph.get_file("me", "copied/small.txt").unwrap(), b"small");
+        assert_eq!(graph.uploaded_fragments(), 10);
+
+        // The upload session whose last chunk never arrives is cancelled.
+        let dst = build("od://copied/interrupted.bin", Some(data.len() as u64)).await.unwrap();
+        let (sender, receiver) = channel(1);
+        sender.send(crate::services::file_system::ChunkData::new(data[..1024 * 1024].to_vec(), 0, false)).await.unwrap();
+        drop(sender);
+        assert!(dst.upload(receiver).await.is_err());
+        assert_eq!(graph.open_sessions(), 0);
+        assert!(graph.get_file("me", "copied/interrupted.bin").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_range_onedrive_fake() {
+        let graph = FakeOneDrive::start().await;
+        graph.add_file("me", "datas/source.bin", range_data());
+        let file_obj = FileSystemBuilder::from(fake_credential())
+            .set_endpoint(graph.endpoint())
+            .set_file_path("od://datas/source.bin")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        // The content is read from the pre-authenticated download URL.
+        assert_read_range(file_obj.storage_backend(), |e| matches!(e, OneDriveError(_))).await;
+    }
+}
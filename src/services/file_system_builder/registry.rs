@@ -0,0 +1,202 @@
+use crate::errors::HikyakuError::{BuilderError, InvalidArgumentError};
+use crate::errors::HikyakuResult;
+use crate::services::file_system::FileSystemObject;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::services::storage_backend::{BackendLocation, BackendRegistry};
+use crate::types::FileInfo;
+use crate::utils::parser::FileSystemParseResult;
+
+impl FileSystemBuilder<BackendRegistry, FileSystemParseResult> {
+    /// Builds a `FileSystemObject` for the backend registered for the scheme of the path.
+    ///
+    /// This function looks up the factory of the prefix(e.x. "acme://") in the registry which
+    /// the builder was created from, creates the backend for the path and gets its state.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError` or the error of the backend on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if no backend is registered for the prefix.
+    /// Returns a `BuilderError` if the namespace cannot be found or the path is not set.
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let location = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                let namespace = file_info.get_namespace()
+                    .ok_or(BuilderError("Namespace cannot found".to_string()))?;
+
+                BackendLocation::new(file_info.get_prefix(), namespace, file_info.get_path())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let factory = self.file_system_credential
+            .factory(location.get_prefix())
+            .ok_or_else(|| InvalidArgumentError(format!("No backend is registered for {}", location.get_prefix())))?;
+        let backend = factory.create(&location).await?;
+        let stat = backend.stat().await?;
+
+        let file_obj = FileSystemObject::new(backend, stat, self.concurrency.into_inner(), self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fmt::{Display, Formatter};
+    use std::sync::Arc;
+    use std::sync::Mutex as StdMutex;
+    use async_trait::async_trait;
+    use tokio::sync::mpsc::channel;
+    use crate::errors::HikyakuError::NotExistFileError;
+    use crate::services::file_system::ChunkData;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::upload::Upload;
+    use crate::services::storage_backend::{BackendFactory, BackendStat, StorageBackend, WriteSession};
+    use crate::utils::memory_store::MemoryStore;
+    use super::*;
+
+    // The key-value storage of the tests. The keys are `namespace/path`.
+    type Objects = Arc<StdMutex<BTreeMap<String, Vec<u8>>>>;
+
+    struct KvFactory {
+        objects: Objects,
+    }
+
+    struct KvBackend {
+        objects: Objects,
+        key: String,
+    }
+
+    struct KvWriteSession {
+        objects: Objects,
+        key: String,
+        data: Vec<u8>,
+        next_offset: u64,
+    }
+
+    #[async_trait]
+    impl BackendFactory for KvFactory {
+        async fn create(&self, location: &BackendLocation) -> HikyakuResult<Arc<dyn StorageBackend>> {
+            Ok(Arc::new(KvBackend {
+                objects: Arc::clone(&self.objects),
+                key: format!("{}/{}", location.get_namespace().unwrap(), location.get_path()),
+            }))
+        }
+    }
+
+    impl Display for KvBackend {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Kv: key: {}", self.key)
+        }
+    }
+
+    #[async_trait]
+    impl StorageBackend for KvBackend {
+        async fn stat(&self) -> HikyakuResult<BackendStat> {
+            let objects = self.objects.lock().unwrap();
+            Ok(BackendStat {
+                file_size: objects.get(&self.key).map(|data| data.len() as u64),
+                is_dir: objects.keys().any(|key| key.starts_with(&format!("{}/", self.key))),
+                is_streamed: false,
+            })
+        }
+
+        async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+            let objects = self.objects.lock().unwrap();
+            let data = objects.get(&self.key).ok_or_else(|| NotExistFileError(self.key.clone()))?;
+            Ok(data[start as usize..=end as usize].to_vec())
+        }
+
+        async fn start_write(&self, _chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+            Ok(Box::new(KvWriteSession {
+                objects: Arc::clone(&self.objects),
+                key: self.key.clone(),
+                data: vec![],
+                next_offset: 0,
+            }))
+        }
+
+        async fn list(&self) -> HikyakuResult<Vec<String>> {
+            let prefix = format!("{}/", self.key);
+            Ok(self.objects.lock().unwrap().keys().filter(|key| key.starts_with(&prefix)).cloned().collect())
+        }
+
+        async fn delete(&self) -> HikyakuResult<()> {
+            self.objects.lock().unwrap().remove(&self.key).map(|_| ()).ok_or_else(|| NotExistFileError(self.key.clone()))
+        }
+    }
+
+    #[async_trait]
+    impl WriteSession for KvWriteSession {
+        async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+            assert_eq!(chunk_data.get_offset(), self.next_offset);
+            self.next_offset += 1;
+            self.data.extend_from_slice(chunk_data.get_data());
+            Ok(())
+        }
+
+        async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+            self.objects.lock().unwrap().insert(self.key, self.data);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_registered_backend() {
+        let objects = Objects::default();
+        let registry = BackendRegistry::new();
+        registry.register("kv", KvFactory { objects: Arc::clone(&objects) }).unwrap();
+        let build = |path: &str| {
+            let builder = FileSystemBuilder::from(registry.clone());
+            builder.chunk_size(64);
+            builder.set_file_path(path).unwrap().build()
+        };
+
+        let store = MemoryStore::new();
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        store.insert("bucket", "source.bin", data.clone());
+        let memory = |path: &str| {
+            let builder = FileSystemBuilder::from(store.clone());
+            builder.chunk_size(64);
+            builder.set_file_path(path).unwrap().build().unwrap()
+        };
+
+        // The chunks arrive out of order but the write session gets them in order.
+        let dst = build("kv://vault/datasets/copied.bin").await.unwrap();
+        assert_eq!(dst.to_string(), "Kv: key: vault/datasets/copied.bin");
+        assert_eq!(dst.file_size(), None);
+        let (sender, receiver) = channel(4);
+        let src = memory("mem://bucket/source.bin");
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(objects.lock().unwrap().get("vault/datasets/copied.bin").unwrap(), &data);
+
+        let src = build("kv://vault/datasets/copied.bin").await.unwrap();
+        assert_eq!(src.file_size(), Some(1000));
+        let (sender, receiver) = channel(4);
+        let dst = memory("mem://bucket/restored.bin");
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(store.get("bucket", "restored.bin").unwrap(), data);
+
+        let folder = build("kv://vault/datasets").await.unwrap();
+        assert!(folder.storage_backend().stat().await.unwrap().is_dir);
+        assert_eq!(List::list(&folder).await.unwrap(), vec!["vault/datasets/copied.bin"]);
+        let (sender, receiver) = channel(1);
+        sender.send(ChunkData::new(b"a".to_vec(), 0, true)).await.unwrap();
+        drop(sender);
+        assert!(matches!(folder.upload(receiver).await, Err(InvalidArgumentError(_))));
+
+        Delete::delete(&src).await.unwrap();
+        assert!(objects.lock().unwrap().is_empty());
+
+        assert!(matches!(FileSystemBuilder::from(BackendRegistry::new()).set_file_path("kv://vault/copied.bin"), Err(InvalidArgumentError(_))));
+    }
+}
@@ -0,0 +1,218 @@
+use std::path::Path;
+use std::sync::Arc;
+use crate::errors::HikyakuError::{BuilderError, InvalidArgumentError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::sftp::SftpBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::sftp::{is_not_found, remote_path, sftp_error, SftpAuthority, SftpConnection, SftpConnector, SftpHostKeyCheck};
+use crate::utils::credential::Credential;
+use crate::utils::credential::sftp_credential::SftpCredential;
+use crate::utils::parser::FileSystemParseResult;
+
+impl FileSystemBuilder<SftpCredential, FileSystemParseResult> {
+    /// Sets the known_hosts file which the host key of the server is verified with.
+    ///
+    /// By default, `~/.ssh/known_hosts` is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The known_hosts file of the OpenSSH format.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_known_hosts_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.options.known_hosts_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+
+    /// Sets how the host key of the unknown server is treated.
+    ///
+    /// By default, the server which is not in the known_hosts file is refused([SftpHostKeyCheck::Strict]).
+    /// The server whose key is changed is always refused.
+    ///
+    /// # Arguments
+    ///
+    /// * `host_key_check` - A [SftpHostKeyCheck] of the unknown server.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_host_key_check(mut self, host_key_check: SftpHostKeyCheck) -> Self {
+        self.options.host_key_check = host_key_check;
+        self
+    }
+
+
+    /// Builds a `FileSystemObject` for the SFTP server using the specified credentials and file path.
+    ///
+    /// This function validates the file path to ensure it has the "sftp://" prefix and `user@host[:port]`,
+    /// then connects to the server and looks up the path. The path starting with `~/` is relative to
+    /// the home directory of the user, and others are absolute.
+    /// The object has the SSH sessions as many as the concurrency and they are opened on the first use,
+    /// except the first one which is opened here.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError`, `ConnectionError` or `SftpError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use hikyaku::types::sftp::SftpHostKeyCheck;
+    /// use hikyaku::utils::credential::sftp_credential::SftpCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let file_obj = FileSystemBuilder::from(SftpCredential::from_private_key("/home/hikyaku/.ssh/id_ed25519", None))
+    ///         .set_host_key_check(SftpHostKeyCheck::AcceptNew)
+    ///         .set_file_path("sftp://hikyaku@example.com/~/titanic/train.csv")
+    ///         .unwrap()
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("Sftp"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let (authority, path) = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                if file_info.get_prefix() != "sftp://" {
+                    return Err(InvalidArgumentError("File system prefix is not sftp://".to_string()));
+                }
+                // SAFETY: The parser always sets the namespace of `sftp://`.
+                (SftpAuthority::parse(file_info.get_namespace().unwrap())?, file_info.get_path().to_string())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let connector = Arc::new(SftpConnector::new(authority.clone(),
+                                                    self.file_system_credential.get_credential(),
+                                                    self.options.clone()));
+        let concurrency = self.concurrency.into_inner();
+        let connections = (0..concurrency)
+            .map(|_| Arc::new(SftpConnection::new(Arc::clone(&connector))))
+            .collect::<Vec<_>>();
+        let connection = first_client(&connections);
+
+        let stat_path = remote_path(&path);
+        let (is_dir, file_size) = connection.run(move |sftp| {
+            match sftp.stat(&stat_path) {
+                Ok(stat) if stat.is_dir() => Ok((true, None)),
+                Ok(stat) => Ok((false, Some(stat.size.unwrap_or_default()))),
+                Err(e) if is_not_found(&e) => Ok((false, None)),
+                Err(e) => Err(sftp_error(&format!("Failed to stat {}", stat_path.display()), e)),
+            }
+        }).await?;
+
+        let backend = SftpBackend {
+            connections,
+            authority: Arc::new(authority),
+            path: Arc::new(path),
+            is_dir,
+            file_size,
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::num::NonZero;
+    use tokio::sync::mpsc::channel;
+    use crate::errors::HikyakuError::SftpError;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::mkdir::Mkdir;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::{collect, send_data};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_build_sftp_not_ssh_server() {
+        // The server which speaks not SSH fails the handshake.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").unwrap();
+        });
+
+        let result = FileSystemBuilder::from(SftpCredential::from_password("password"))
+            .set_file_path(&format!("sftp://hikyaku@127.0.0.1:{}/~/train.csv", port))
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(result, Err(SftpError(_))));
+        server.join().unwrap();
+
+        let result = FileSystemBuilder::from(SftpCredential::from_agent())
+            .set_file_path("sftp://127.0.0.1/train.csv")
+            .unwrap()
+            .build()
+            .await;
+        assert!(matches!(result, Err(InvalidArgumentError(_))));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs an SSH server at HIKYAKU_SFTP_HOST(localhost:2222 by default)"]
+    async fn test_transfer_sshd() {
+        // e.x. `docker run -p 2222:22 atmoz/sftp hikyaku:password:::upload`, with HIKYAKU_SFTP_USER, HIKYAKU_SFTP_PASSWORD
+        // and HIKYAKU_SFTP_DIR(the writable directory) for the other server.
+        let host = std::env::var("HIKYAKU_SFTP_HOST").unwrap_or("localhost:2222".to_string());
+        let user = std::env::var("HIKYAKU_SFTP_USER").unwrap_or("hikyaku".to_string());
+        let password = std::env::var("HIKYAKU_SFTP_PASSWORD").unwrap_or("password".to_string());
+        let dir = std::env::var("HIKYAKU_SFTP_DIR").unwrap_or("~/upload".to_string());
+        let known_hosts_file = std::env::temp_dir().join("hikyaku-sftp-known_hosts");
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        let build = |path: &str| {
+            let builder = FileSystemBuilder::from(SftpCredential::from_password(&password))
+                .set_known_hosts_file(&known_hosts_file)
+                .set_host_key_check(SftpHostKeyCheck::AcceptNew);
+            // The chunks are written at their offsets over the 4 sessions in parallel.
+            builder.concurrency(NonZero::new(4).unwrap());
+            builder.chunk_size(64);
+            builder.set_file_path(&format!("sftp://{}@{}/{}/{}", user, host, dir, path)).unwrap().build()
+        };
+
+        // The parent directories are created too.
+        build("hikyaku/nested/emulator").await.unwrap().mkdir().await.unwrap();
+        assert!(build("hikyaku/nested").await.unwrap().to_string().contains("is_dir: true"));
+        let dst = build("hikyaku/nested/emulator/source.bin").await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::join!(send_data(sender, &data, 64), dst.upload(receiver)).1.unwrap();
+
+        let src = build("hikyaku/nested/emulator/source.bin").await.unwrap();
+        assert!(src.to_string().contains("file_size: Some(1000)"));
+        let (sender, receiver) = channel(4);
+        let (downloaded, received) = tokio::join!(src.download(sender), collect(receiver));
+        downloaded.unwrap();
+        assert_eq!(received, data);
+        assert_eq!(src.storage_backend().read_range(100, 199).await.unwrap(), data[100..200]);
+
+        let folder = build("hikyaku/nested/emulator").await.unwrap();
+        assert_eq!(folder.list().await.unwrap(), vec![format!("{}/hikyaku/nested/emulator/source.bin", dir)]);
+        src.delete().await.unwrap();
+        let deleted = build("hikyaku/nested/emulator/source.bin").await.unwrap();
+        assert!(deleted.to_string().contains("file_size: None"));
+        // The directories are removed only if they are empty.
+        for path in ["hikyaku/nested/emulator", "hikyaku/nested", "hikyaku"] {
+            build(path).await.unwrap().delete().await.unwrap();
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use std::sync::Arc;
+use log::error;
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use reqwest::header::CONTENT_TYPE;
+use crate::errors::HikyakuError::{BuilderError, ConnectionError, InvalidArgumentError, WebDavError};
+use crate::errors::HikyakuResult;
+use crate::services::backends::first_client;
+use crate::services::backends::webdav::WebDavBackend;
+use crate::services::file_system::FileSystemObject;
+use crate::services::storage_backend::StorageBackend;
+use crate::services::file_system_builder::FileSystemBuilder;
+use crate::types::FileInfo;
+use crate::types::webdav::{nextcloud_uploads_url, parse_multistatus, WebDavEndpoint, WebDavResource, PROPFIND_BODY};
+use crate::utils::credential::Credential;
+use crate::utils::credential::webdav_credential::WebDavCredential;
+use crate::utils::parser::FileSystemParseResult;
+
+impl FileSystemBuilder<WebDavCredential, FileSystemParseResult> {
+    /// Sets whether Nextcloud chunked upload v2 is used.
+    ///
+    /// By default, the chunks are uploaded in parallel by Nextcloud chunked upload v2 if the path is
+    /// in the files collection of Nextcloud(`/remote.php/dav/files/{user}/...`) and the server has
+    /// the uploads collection. Otherwise the file is put by a single PUT.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunked_upload` - `false` to always put the file by a single PUT.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the builder instance for further chaining of method calls.
+    pub fn set_chunked_upload(mut self, chunked_upload: bool) -> Self {
+        self.options.chunked_upload = chunked_upload;
+        self
+    }
+
+
+    /// Builds a `FileSystemObject` for the WebDAV server using the specified credentials and file path.
+    ///
+    /// This function validates the file path to ensure it has the "dav://" or "davs://" prefix and the host,
+    /// then looks up the resource by PROPFIND. `dav://` is served over HTTP and `davs://` over HTTPS.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<FileSystemObject>` - A result containing the `FileSystemObject` if successful,
+    ///   otherwise an `InvalidArgumentError`, `BuilderError`, `ConnectionError` or `WebDavError` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use hikyaku::utils::credential::webdav_credential::WebDavCredential;
+    /// use hikyaku::services::file_system_builder::FileSystemBuilder;
+    ///
+    /// async fn example() {
+    ///     let file_obj = FileSystemBuilder::from(WebDavCredential::from_basic("alice", "app-password"))
+    ///         .set_file_path("davs://cloud.example.com/remote.php/dav/files/alice/titanic/train.csv")
+    ///         .unwrap()
+    ///         .build()
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     assert!(file_obj.to_string().contains("WebDav"));
+    /// }
+    /// ```
+    pub async fn build(self) -> HikyakuResult<FileSystemObject> {
+        let (endpoint, path) = match self.file_info.borrow().as_ref() {
+            Some(file_info) => {
+                if !["dav://", "davs://"].contains(&file_info.get_prefix()) {
+                    return Err(InvalidArgumentError("File system prefix is not dav:// or davs://".to_string()));
+                }
+                // SAFETY: The parser always sets the namespace of `dav://` and `davs://`.
+                (WebDavEndpoint::new(file_info.get_prefix(), file_info.get_namespace().unwrap()),
+                 file_info.get_path().to_string())
+            },
+            None => {
+                return Err(BuilderError("Path is not set".to_string()));
+            }
+        };
+
+        let auth = self.file_system_credential.get_credential();
+        let concurrency = self.concurrency.into_inner();
+        let clients = (0..concurrency)
+            .map(|_| auth.create_client().map(Arc::new))
+            .collect::<HikyakuResult<Vec<_>>>()?;
+        let client = first_client(&clients);
+
+        let (is_dir, file_size) = match propfind(client, &endpoint.url(&path), 0).await? {
+            Some(resources) => {
+                let resource = resources
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| WebDavError(format!("PROPFIND of {} returned no resource", endpoint.url(&path))))?;
+                if resource.is_collection {
+                    (true, None)
+                } else {
+                    (false, Some(resource.content_length.unwrap_or_default()))
+                }
+            },
+            None => (false, None),
+        };
+
+        // The uploads collection exists only on Nextcloud.
+        let nextcloud_uploads = match nextcloud_uploads_url(&endpoint, &path) {
+            Some(uploads_url) if self.options.chunked_upload => {
+                propfind(client, &uploads_url, 0).await?.map(|_| Arc::new(uploads_url))
+            },
+            _ => None,
+        };
+
+        let backend = WebDavBackend {
+            clients,
+            endpoint: Arc::new(endpoint),
+            path: Arc::new(path),
+            is_dir,
+            nextcloud_uploads,
+            file_size,
+        };
+        let stat = backend.stat().await?;
+        let file_obj = FileSystemObject::new(Arc::new(backend), stat, concurrency, self.chunk_size.into_inner());
+
+        Ok(file_obj)
+    }
+}
+
+/// The method of the WebDAV extension(e.x. `PROPFIND`, `MKCOL` and `MOVE`).
+pub(crate) fn webdav_method(name: &'static str) -> Method {
+    // SAFETY: The method names are the const tokens.
+    Method::from_bytes(name.as_bytes()).unwrap()
+}
+
+/// Send the request to the WebDAV server and check the status.
+///
+/// # Errors
+///
+/// Returns a `ConnectionError` if the request cannot be sent, or a `WebDavError` if the status is not success.
+pub(crate) async fn webdav_request(request: RequestBuilder) -> HikyakuResult<Response> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for WebDAV server: {:#?}", e);
+            ConnectionError(format!("Failed to send request to WebDAV server: {:?}", e))
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let url = response.url().to_string();
+        let body = response.text().await.unwrap_or_default();
+        error!("WebDAV server returned status code: {} for {}", status, url);
+        return Err(WebDavError(format!("WebDAV server returned status code: {} for {}, body: {}", status, url, body)));
+    }
+
+    Ok(response)
+}
+
+/// Gets the properties of the resource, and its members if `depth` is `1`.
+///
+/// # Arguments
+///
+/// * `client` - The client used to send the request to the WebDAV server.
+/// * `url` - The URL of the resource.
+/// * `depth` - `0` for the resource itself, `1` for the resource and its members.
+///
+/// # Returns
+///
+/// `HikyakuResult<Option<Vec<WebDavResource>>>` - The resources, or [None] if the resource does not exist.
+pub(crate) async fn propfind(client: &Client, url: &str, depth: u8) -> HikyakuResult<Option<Vec<WebDavResource>>> {
+    let request = client
+        .request(webdav_method("PROPFIND"), url)
+        .header("Depth", depth.to_string())
+        .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(PROPFIND_BODY);
+    let response = request
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request for WebDAV server: {:#?}", e);
+            ConnectionError(format!("Failed to send request to WebDAV server: {:?}", e))
+        })?;
+
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if response.status() != StatusCode::MULTI_STATUS {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        error!("Failed to PROPFIND for WebDAV server: {}", status);
+        return Err(WebDavError(format!("Failed to get the properties of {}: {}, {}", url, status, body)));
+    }
+
+    let xml = response
+        .text()
+        .await
+        .map_err(|e| WebDavError(format!("Failed to read response from WebDAV server: {:?}", e)))?;
+
+    parse_multistatus(&xml).map(Some)
+}
+
+/// Creates the collection and its missing parents like `mkdir -p`. The existing collection is left as is.
+///
+/// # Errors
+///
+/// Returns a `WebDavError` if the collection cannot be created, or the path exists as a file.
+pub(crate) async fn create_collections(client: &Client, endpoint: &WebDavEndpoint, path: &str) -> HikyakuResult<()> {
+    let segments = path.split('/').filter(|segment| !segment.is_empty()).collect::<Vec<_>>();
+
+    // Find the deepest existing collection first, because the collections near the root
+    // (e.x. `/remote.php` of Nextcloud) may not accept MKCOL even if they exist.
+    let mut existing = segments.len();
+    while existing > 0 {
+        let ancestor = segments[..existing].join("/");
+        match propfind(client, &endpoint.collection_url(&ancestor), 0).await? {
+            Some(resources) if resources.first().is_some_and(|resource| resource.is_collection) => break,
+            Some(_) => return Err(WebDavError(format!("{} is not a collection", ancestor))),
+            None => existing -= 1,
+        }
+    }
+
+    for depth in existing + 1..=segments.len() {
+        let collection = segments[..depth].join("/");
+        webdav_request(client.request(webdav_method("MKCOL"), endpoint.collection_url(&collection))).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::channel;
+    use crate::errors::HikyakuError::NotExistFileError;
+    use crate::services::file_system::delete::Delete;
+    use crate::services::file_system::download::Download;
+    use crate::services::file_system::list::List;
+    use crate::services::file_system::mkdir::Mkdir;
+    use crate::services::file_system::rename::ServerSideMove;
+    use crate::services::file_system::upload::Upload;
+    use crate::utils::test_support::fake_webdav::FakeWebDav;
+    use crate::utils::test_support::{assert_read_range, collect, range_data, send_data};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_build_webdav_fake() {
+        let dav = FakeWebDav::start().await;
+        dav.add_file("share/titanic/train.csv", b"a,b,c".to_vec());
+        dav.add_file("share/titanic/test.csv", b"a,b".to_vec());
+        dav.add_file("share/titanic/raw/2024.csv", b"a".to_vec());
+        let build = |credential: WebDavCredential, path: &str| {
+            FileSystemBuilder::from(credential)
+                .set_file_path(&format!("dav://{}/{}", dav.host(), path))
+                .unwrap()
+                .build()
+        };
+
+        let file_obj = build(dav.credential(), "share/titanic/train.csv").await.unwrap();
+        assert!(file_obj.to_string().contains("path: share/titanic/train.csv, is_dir: false, file_size: Some(5)"));
+
+        let folder = build(dav.credential(), "share/titanic/").await.unwrap();
+        assert!(folder.to_string().contains("is_dir: true"));
+        assert_eq!(folder.list().await.unwrap(), vec!["share/titanic/raw", "share/titanic/test.csv", "share/titanic/train.csv"]);
+        // The collection which is not empty is not deleted not to remove its members recursively.
+        assert!(matches!(folder.delete().await, Err(InvalidArgumentError(_))));
+
+        let absent = build(dav.credential(), "share/titanic/valid.csv").await.unwrap();
+        assert!(absent.to_string().contains("is_dir: false, file_size: None"));
+        assert!(matches!(absent.delete().await, Err(NotExistFileError(_))));
+
+        let new_folder = build(dav.credential(), "share/titanic/2025/01").await.unwrap();
+        new_folder.mkdir().await.unwrap();
+        let created = build(dav.credential(), "share/titanic/2025/01").await.unwrap();
+        assert!(created.to_string().contains("is_dir: true"));
+        created.delete().await.unwrap();
+        assert!(!dav.is_empty_collection("share/titanic/2025/01"));
+
+        let denied = build(WebDavCredential::from_basic("alice", "wrong"), "share/titanic/train.csv").await;
+        assert!(matches!(denied, Err(WebDavError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_webdav_fake() {
+        let dav = FakeWebDav::start().await;
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        dav.add_file("remote.php/dav/files/alice/source.bin", data.clone());
+        dav.add_file("share/source.bin", data.clone());
+        let build = |path: String, chunked_upload: bool| {
+            let builder = FileSystemBuilder::from(dav.credential()).set_chunked_upload(chunked_upload);
+            builder.chunk_size(64);
+            builder.set_file_path(&format!("dav://{}/{}", dav.host(), path)).unwrap().build()
+        };
+
+        // Nextcloud puts the chunks in the uploads collection and assembles them by MOVE.
+        let src = build("remote.php/dav/files/alice/source.bin".to_string(), true).await.unwrap();
+        let dst = build("remote.php/dav/files/alice/copied/copied.bin".to_string(), true).await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+        assert_eq!(dav.get_file("remote.php/dav/files/alice/copied/copied.bin").unwrap(), data);
+        assert_eq!((dav.chunk_puts(), dav.file_puts()), (16, 0));
+        assert!(dav.is_empty_collection("remote.php/dav/uploads/alice"));
+
+        // The plain WebDAV server gets the whole file by a single PUT.
+        for (src_path, dst_path, chunked_upload) in [
+            ("share/source.bin", "share/copied/copied.bin", true),
+            ("remote.php/dav/files/alice/source.bin", "remote.php/dav/files/alice/single.bin", false),
+        ] {
+            let src = build(src_path.to_string(), chunked_upload).await.unwrap();
+            let dst = build(dst_path.to_string(), chunked_upload).await.unwrap();
+            let (sender, receiver) = channel(4);
+            tokio::try_join!(src.download(sender), dst.upload(receiver)).unwrap();
+            assert_eq!(dav.get_file(dst_path).unwrap(), data);
+        }
+        assert_eq!((dav.chunk_puts(), dav.file_puts()), (16, 2));
+
+        let copied = build("share/copied/copied.bin".to_string(), true).await.unwrap();
+        let moved = build("share/moved/moved.bin".to_string(), true).await.unwrap();
+        build("share/moved".to_string(), true).await.unwrap().mkdir().await.unwrap();
+        copied.move_to(&moved).await.unwrap();
+        assert!(dav.get_file("share/copied/copied.bin").is_none());
+        assert_eq!(dav.get_file("share/moved/moved.bin").unwrap(), data);
+
+        let moved = build("share/moved/moved.bin".to_string(), true).await.unwrap();
+        moved.delete().await.unwrap();
+        assert!(dav.get_file("share/moved/moved.bin").is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a WebDAV server at HIKYAKU_WEBDAV_HOST(localhost:8080 by default)"]
+    async fn test_transfer_webdav_server() {
+        // e.x. `rclone serve webdav --addr :8080 /tmp/dav`, with HIKYAKU_WEBDAV_USER and HIKYAKU_WEBDAV_PASSWORD if it needs them.
+        let host = std::env::var("HIKYAKU_WEBDAV_HOST").unwrap_or("localhost:8080".to_string());
+        let credential = || match (std::env::var("HIKYAKU_WEBDAV_USER"), std::env::var("HIKYAKU_WEBDAV_PASSWORD")) {
+            (Ok(user), Ok(password)) => WebDavCredential::from_basic(&user, &password),
+            _ => WebDavCredential::anonymous(),
+        };
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        let build = |path: &str| {
+            let builder = FileSystemBuilder::from(credential());
+            builder.chunk_size(64);
+            builder.set_file_path(&format!("dav://{}/{}", host, path)).unwrap().build()
+        };
+
+        build("hikyaku/emulator").await.unwrap().mkdir().await.unwrap();
+        let dst = build("hikyaku/emulator/source.bin").await.unwrap();
+        let (sender, receiver) = channel(4);
+        tokio::join!(send_data(sender, &data, 64), dst.upload(receiver)).1.unwrap();
+
+        let src = build("hikyaku/emulator/source.bin").await.unwrap();
+        assert!(src.to_string().contains("file_size: Some(1000)"));
+        let (sender, receiver) = channel(4);
+        let (downloaded, received) = tokio::join!(src.download(sender), collect(receiver));
+        downloaded.unwrap();
+        assert_eq!(received, data);
+        assert_eq!(src.storage_backend().read_range(100, 199).await.unwrap(), data[100..200]);
+
+        let folder = build("hikyaku/emulator/").await.unwrap();
+        assert!(folder.to_string().contains("is_dir: true"));
+        assert_eq!(folder.list().await.unwrap(), vec!["hikyaku/emulator/source.bin"]);
+        src.delete().await.unwrap();
+        let deleted = build("hikyaku/emulator/source.bin").await.unwrap();
+        assert!(deleted.to_string().contains("file_size: None"));
+        build("hikyaku/emulator").await.unwrap().delete().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_range_webdav_fake() {
+        let dav = FakeWebDav::start().await;
+        dav.add_file("share/source.bin", range_data());
+        let file_obj = FileSystemBuilder::from(dav.credential())
+            .set_file_path(&format!("dav://{}/share/source.bin", dav.host()))
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        assert_read_range(file_obj.storage_backend(), |e| matches!(e, WebDavError(_))).await;
+    }
+}
@@ -1,2 +1,4 @@
+pub(crate) mod backends;
 pub mod file_system;
 pub mod file_system_builder;
+pub mod storage_backend;
@@ -0,0 +1,388 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::{Arc, RwLock};
+use async_trait::async_trait;
+use regex::Regex;
+use crate::errors::HikyakuError::{InvalidArgumentError, UnsupportedError};
+use crate::errors::HikyakuResult;
+use crate::services::file_system::ChunkData;
+use crate::types::FileInfo;
+use crate::utils::credential::Credential;
+use crate::utils::parser::BUILTIN_PREFIXES;
+use crate::utils::region::NoneRegion;
+
+// The scheme of URL(RFC 3986) in lowercase.
+const SCHEME_REGEX: &str = r"^[a-z][a-z0-9+.-]*$";
+
+/// The state of the path which the backend points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BackendStat {
+    /// The size of the file, or [None] if the file does not exist, the path is a directory or the size is unknown.
+    pub file_size: Option<u64>,
+    /// Whether the path is a directory(or a prefix of the object storage).
+    pub is_dir: bool,
+    /// Whether the file is read only from the beginning by [StorageBackend::open_stream] instead of
+    /// [StorageBackend::read_range](e.x. the exported Google Workspace document).
+    pub is_streamed: bool,
+}
+
+/// The storage which Hikyaku transfers the files from and to.
+///
+/// The built-in file systems implement it too, and the in-house storages can be added
+/// by implementing it and registering the [BackendFactory] in [BackendRegistry].
+/// The [Display] of the backend is used in the messages of the errors and the logs.
+#[async_trait]
+pub trait StorageBackend: Any + Display + Send + Sync {
+    /// Get the size of the file, or whether the path is a directory.
+    async fn stat(&self) -> HikyakuResult<BackendStat>;
+
+    /// Read the bytes from `start` to `end`(inclusive) of the file.
+    ///
+    /// It is called in parallel for the chunks of the file, and must return exactly `end - start + 1` bytes.
+    async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>>;
+
+    /// Start to write the file at the path.
+    ///
+    /// Each backend decides what happens to the existing file at the path. The local file system refuses it
+    /// with an `InvalidArgumentError`, Google Drive adds another file of the same name, Box uploads
+    /// the new version of the file, and the others overwrite it. The implementors should document their choice.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - The size of the chunks except for the last one.
+    async fn start_write(&self, chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>>;
+
+    /// List the file paths under the path.
+    async fn list(&self) -> HikyakuResult<Vec<String>>;
+
+    /// Delete the file of the path.
+    async fn delete(&self) -> HikyakuResult<()>;
+
+    /// Open the file to read it from the beginning to the end.
+    ///
+    /// It is called instead of [StorageBackend::read_range] when [BackendStat::is_streamed] is true.
+    async fn open_stream(&self) -> HikyakuResult<Box<dyn ReadStream>> {
+        Err(UnsupportedError(format!("Stream is not supported. File system object: {}", self)))
+    }
+
+    /// Create the directory at the path with its missing parents.
+    ///
+    /// The existing directory is treated as success.
+    async fn mkdir(&self) -> HikyakuResult<()> {
+        Err(UnsupportedError(format!("Mkdir is not supported yet. File system object: {}", self)))
+    }
+
+    /// Copy the `source` to the path inside the storage service.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The backend of the copy source.
+    /// * `chunk_size` - The size of the parts if the copy is split into the parts.
+    async fn copy_from(&self, source: &dyn StorageBackend, _chunk_size: u64) -> HikyakuResult<()> {
+        Err(UnsupportedError(format!("Server-side copy is not supported from {} to {}", source, self)))
+    }
+
+    /// Move(rename) the file of the path to the `destination` inside the server.
+    async fn move_to(&self, destination: &dyn StorageBackend) -> HikyakuResult<()> {
+        Err(UnsupportedError(format!("Move is not supported yet. Source: {}, destination: {}", self, destination)))
+    }
+}
+
+/// The write of a file which was started by [StorageBackend::start_write].
+#[async_trait]
+pub trait WriteSession: Send {
+    /// Write the chunk. The chunks are written in order of the offset.
+    async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()>;
+
+    /// Complete the file after the last chunk was written.
+    async fn finish(self: Box<Self>) -> HikyakuResult<()>;
+
+    /// Cancel the write after the failure not to leave the written parts on the storage.
+    async fn abort(self: Box<Self>) {}
+}
+
+/// The file which is read from the beginning by [StorageBackend::open_stream].
+#[async_trait]
+pub trait ReadStream: Send {
+    /// Read the next bytes of the file, or [None] at the end of the file.
+    async fn next_bytes(&mut self) -> HikyakuResult<Option<Vec<u8>>>;
+}
+
+/// Creates the [StorageBackend] for the paths of the registered scheme.
+#[async_trait]
+pub trait BackendFactory: Send + Sync {
+    async fn create(&self, location: &BackendLocation) -> HikyakuResult<Arc<dyn StorageBackend>>;
+}
+
+/// The parsed path of the registered scheme(e.x. `acme://vault/datasets/train.csv`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendLocation {
+    prefix: String,
+    namespace: String,
+    path: String,
+}
+
+impl BackendLocation {
+    pub fn new(prefix: &str, namespace: &str, path: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            namespace: namespace.to_string(),
+            path: path.to_string(),
+        }
+    }
+}
+
+impl FileInfo for BackendLocation {
+    /// Get prefix(e.x. `acme://`)
+    fn get_prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Get namespace. The registered scheme always has it.
+    fn get_namespace(&self) -> Option<&str> {
+        Some(&self.namespace)
+    }
+
+    /// Get path except for namespace
+    fn get_path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// The map from the URL schemes to the factories of the backends.
+///
+/// The registry is a cheap handle, so the clones share the same factories.
+/// The builder created from it accepts the paths of the registered schemes.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::fmt::{Display, Formatter};
+/// use std::sync::{Arc, Mutex};
+/// use async_trait::async_trait;
+/// use hikyaku::errors::HikyakuResult;
+/// use hikyaku::services::file_system::ChunkData;
+/// use hikyaku::services::file_system_builder::FileSystemBuilder;
+/// use hikyaku::services::storage_backend::{BackendFactory, BackendLocation, BackendRegistry, BackendStat, StorageBackend, WriteSession};
+/// use hikyaku::types::FileInfo;
+///
+/// type Files = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+///
+/// struct AcmeBackend {
+///     files: Files,
+///     path: String,
+/// }
+///
+/// impl Display for AcmeBackend {
+///     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "Acme: path: {}", self.path)
+///     }
+/// }
+///
+/// #[async_trait]
+/// impl StorageBackend for AcmeBackend {
+///     async fn stat(&self) -> HikyakuResult<BackendStat> {
+///         let file_size = self.files.lock().unwrap().get(&self.path).map(|data| data.len() as u64);
+///         Ok(BackendStat { file_size, ..BackendStat::default() })
+///     }
+///
+///     async fn read_range(&self, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+///         Ok(self.files.lock().unwrap()[&self.path][start as usize..=end as usize].to_vec())
+///     }
+///
+///     async fn start_write(&self, _chunk_size: u64) -> HikyakuResult<Box<dyn WriteSession>> {
+///         Ok(Box::new(AcmeWriteSession { files: Arc::clone(&self.files), path: self.path.clone(), data: vec![] }))
+///     }
+///
+///     async fn list(&self) -> HikyakuResult<Vec<String>> {
+///         Ok(self.files.lock().unwrap().keys().filter(|path| path.starts_with(&self.path)).cloned().collect())
+///     }
+///
+///     async fn delete(&self) -> HikyakuResult<()> {
+///         self.files.lock().unwrap().remove(&self.path);
+///         Ok(())
+///     }
+/// }
+///
+/// struct AcmeWriteSession {
+///     files: Files,
+///     path: String,
+///     data: Vec<u8>,
+/// }
+///
+/// #[async_trait]
+/// impl WriteSession for AcmeWriteSession {
+///     async fn write_chunk(&mut self, chunk_data: ChunkData) -> HikyakuResult<()> {
+///         self.data.extend_from_slice(chunk_data.get_data());
+///         Ok(())
+///     }
+///
+///     async fn finish(self: Box<Self>) -> HikyakuResult<()> {
+///         self.files.lock().unwrap().insert(self.path, self.data);
+///         Ok(())
+///     }
+/// }
+///
+/// struct AcmeFactory {
+///     files: Files,
+/// }
+///
+/// #[async_trait]
+/// impl BackendFactory for AcmeFactory {
+///     async fn create(&self, location: &BackendLocation) -> HikyakuResult<Arc<dyn StorageBackend>> {
+///         Ok(Arc::new(AcmeBackend { files: Arc::clone(&self.files), path: location.get_path().to_string() }))
+///     }
+/// }
+///
+/// async fn example() {
+///     let registry = BackendRegistry::new();
+///     registry.register("acme", AcmeFactory { files: Files::default() }).unwrap();
+///
+///     let file_obj = FileSystemBuilder::from(registry)
+///         .set_file_path("acme://vault/datasets/train.csv")
+///         .unwrap()
+///         .build()
+///         .await
+///         .unwrap();
+///
+///     assert!(file_obj.to_string().contains("Acme"));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct BackendRegistry {
+    // prefix(e.x. `acme://`) -> factory
+    factories: Arc<RwLock<HashMap<String, Arc<dyn BackendFactory>>>>,
+}
+
+impl Debug for BackendRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BackendRegistry {{ prefixes: {:?} }}", self.registered_prefixes())
+    }
+}
+
+impl BackendRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the factory of the backend for the scheme.
+    ///
+    /// # Arguments
+    ///
+    /// * `scheme` - The scheme of the paths without `://`(e.x. `acme` for `acme://vault/path`).
+    /// * `factory` - The factory which creates the backend for the paths.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the scheme is invalid, built-in or already registered.
+    /// The built-in schemes stay reserved even if their cargo features are disabled.
+    pub fn register(&self, scheme: &str, factory: impl BackendFactory + 'static) -> HikyakuResult<()> {
+        // SAFETY: The regex statement is const string so this is always Ok().
+        if !Regex::new(SCHEME_REGEX).unwrap().is_match(scheme) {
+            return Err(InvalidArgumentError(format!("Scheme {} is invalid. It must be lowercase like 'acme'", scheme)));
+        }
+        let prefix = format!("{}://", scheme);
+        if BUILTIN_PREFIXES.contains(&prefix.as_str()) {
+            return Err(InvalidArgumentError(format!("Scheme {} is built-in", scheme)));
+        }
+
+        let mut factories = self.factories.write().unwrap_or_else(|e| e.into_inner());
+        if factories.contains_key(&prefix) {
+            return Err(InvalidArgumentError(format!("Scheme {} is already registered", scheme)));
+        }
+        factories.insert(prefix, Arc::new(factory));
+
+        Ok(())
+    }
+
+    /// Get the factory of the prefix(e.x. `acme://`).
+    pub(crate) fn factory(&self, prefix: &str) -> Option<Arc<dyn BackendFactory>> {
+        self.factories.read().unwrap_or_else(|e| e.into_inner()).get(prefix).cloned()
+    }
+}
+
+impl Credential for BackendRegistry {
+    type CredentialType = BackendRegistry;
+    type RegionType = NoneRegion;
+    type Options = ();
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+
+    fn registered_prefixes(&self) -> Vec<String> {
+        let mut prefixes = self.factories
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        prefixes.sort();
+        prefixes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::HikyakuError::UnknownError;
+    use crate::services::file_system_builder::FileSystemBuilder;
+    use crate::utils::memory_store::MemoryStore;
+    use super::*;
+
+    struct NoopFactory;
+
+    #[async_trait]
+    impl BackendFactory for NoopFactory {
+        async fn create(&self, _location: &BackendLocation) -> HikyakuResult<Arc<dyn StorageBackend>> {
+            Err(UnknownError("Not used".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        let registry = BackendRegistry::new();
+        registry.register("acme", NoopFactory).unwrap();
+        registry.clone().register("acme+v2", NoopFactory).unwrap();
+        assert_eq!(registry.registered_prefixes(), vec!["acme+v2://", "acme://"]);
+        assert!(registry.factory("acme://").is_some());
+
+        for scheme in ["acme", "s3", "Acme", "acme://", "", "2acme"] {
+            assert!(matches!(registry.register(scheme, NoopFactory), Err(InvalidArgumentError(_))), "{}", scheme);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_system_object_backend() {
+        let store = MemoryStore::new();
+        let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+        store.insert("bucket", "source.bin", data.clone());
+        store.insert("bucket", "dir/file.bin", data.clone());
+        let build = |path: &str| {
+            FileSystemBuilder::from(store.clone()).set_file_path(path).unwrap().build().unwrap()
+        };
+
+        let backend = build("mem://bucket/source.bin").storage_backend();
+        assert_eq!(backend.stat().await.unwrap(), BackendStat { file_size: Some(1000), is_dir: false, is_streamed: false });
+        assert_eq!(backend.read_range(60, 199).await.unwrap(), data[60..=199]);
+        assert_eq!(backend.read_range(999, 999).await.unwrap(), data[999..]);
+
+        let backend = build("mem://bucket/dir").storage_backend();
+        assert_eq!(backend.stat().await.unwrap(), BackendStat { file_size: None, is_dir: true, is_streamed: false });
+        assert!(matches!(backend.start_write(400).await, Err(InvalidArgumentError(_))));
+
+        let backend = build("mem://bucket/copied.bin").storage_backend();
+        assert_eq!(backend.stat().await.unwrap(), BackendStat::default());
+        let mut session = backend.start_write(400).await.unwrap();
+        for (offset, chunk) in data.chunks(400).enumerate() {
+            session.write_chunk(ChunkData::new(chunk.to_vec(), offset as u64, offset == 2)).await.unwrap();
+        }
+        session.finish().await.unwrap();
+        assert_eq!(store.get("bucket", "copied.bin").unwrap(), data);
+    }
+}
@@ -0,0 +1,773 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Display, Formatter};
+use std::time::SystemTime;
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use aws_sdk_s3::types::{CompletedPart, ObjectCannedAcl, StorageClass, Tier};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use md5::{Digest, Md5};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use crate::errors::HikyakuError::InvalidArgumentError;
+use crate::errors::HikyakuResult;
+use crate::utils::file_type::FileType;
+
+// The characters which are not encoded in the query string of the object tags.
+const TAGGING_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Amazon S3 numbers the parts of the multipart upload from 1 to 10,000.
+pub(crate) const MAX_S3_PARTS: u64 = 10_000;
+/// Every part of the multipart upload except the last must be 5 MiB or larger.
+pub(crate) const MIN_S3_PART_SIZE: u64 = 5 * 1024 * 1024;
+/// A part of the multipart upload must not be larger than 5 GiB.
+pub(crate) const MAX_S3_PART_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Server-side encryption setting for the objects which Hikyaku writes to Amazon S3.
+///
+/// # Variants
+///
+/// * `S3Managed` - SSE-S3. The objects are encrypted with the Amazon S3 managed keys.
+/// * `Kms` - SSE-KMS. The objects are encrypted with the AWS KMS key. When `key_id` is [None],
+///   the AWS managed key(`aws/s3`) is used. `bucket_key_enabled` toggles the S3 Bucket Key.
+/// * `CustomerKey` - SSE-C. The objects are encrypted with the key which the caller provides.
+///   The same key is also required to read the objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum S3Encryption {
+    S3Managed,
+    Kms {
+        key_id: Option<String>,
+        encryption_context: BTreeMap<String, String>,
+        bucket_key_enabled: Option<bool>,
+    },
+    CustomerKey(S3CustomerKey),
+}
+
+/// The customer provided key for SSE-C.
+///
+/// The key is held as the base64 encoded string with its MD5 digest which Amazon S3 requires
+/// to verify the key integrity.
+#[derive(Clone, PartialEq, Eq)]
+pub struct S3CustomerKey {
+    key: String,
+    key_md5: String,
+}
+
+impl S3CustomerKey {
+    /// Creates the customer key from the raw 256-bit key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the key is not 32 bytes.
+    pub fn new(key: &[u8]) -> HikyakuResult<Self> {
+        if key.len() != 32 {
+            return Err(InvalidArgumentError(format!("SSE-C key must be 256-bit(32 bytes) but got {} bytes", key.len())));
+        }
+
+        Ok(Self {
+            key: STANDARD.encode(key),
+            key_md5: STANDARD.encode(Md5::digest(key)),
+        })
+    }
+
+    pub(crate) fn algorithm(&self) -> &str {
+        "AES256"
+    }
+
+    pub(crate) fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub(crate) fn key_md5(&self) -> &str {
+        &self.key_md5
+    }
+}
+
+impl Debug for S3CustomerKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "S3CustomerKey {{ key: *****, key_md5: {} }}", self.key_md5)
+    }
+}
+
+/// Storage class of the objects in Amazon S3.
+///
+/// `Glacier`(S3 Glacier Flexible Retrieval) and `DeepArchive` are the archive storage classes.
+/// The objects in them must be restored before downloading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum S3StorageClass {
+    Standard,
+    ReducedRedundancy,
+    StandardIa,
+    OnezoneIa,
+    IntelligentTiering,
+    GlacierIr,
+    Glacier,
+    DeepArchive,
+    ExpressOnezone,
+    Other(String),
+}
+
+impl S3StorageClass {
+    pub(crate) fn from_name(name: &str) -> Self {
+        match name {
+            "STANDARD" => Self::Standard,
+            "REDUCED_REDUNDANCY" => Self::ReducedRedundancy,
+            "STANDARD_IA" => Self::StandardIa,
+            "ONEZONE_IA" => Self::OnezoneIa,
+            "INTELLIGENT_TIERING" => Self::IntelligentTiering,
+            "GLACIER_IR" => Self::GlacierIr,
+            "GLACIER" => Self::Glacier,
+            "DEEP_ARCHIVE" => Self::DeepArchive,
+            "EXPRESS_ONEZONE" => Self::ExpressOnezone,
+            other => Self::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Standard => "STANDARD",
+            Self::ReducedRedundancy => "REDUCED_REDUNDANCY",
+            Self::StandardIa => "STANDARD_IA",
+            Self::OnezoneIa => "ONEZONE_IA",
+            Self::IntelligentTiering => "INTELLIGENT_TIERING",
+            Self::GlacierIr => "GLACIER_IR",
+            Self::Glacier => "GLACIER",
+            Self::DeepArchive => "DEEP_ARCHIVE",
+            Self::ExpressOnezone => "EXPRESS_ONEZONE",
+            Self::Other(name) => name,
+        }
+    }
+
+    /// Returns `true` if the objects in this storage class must be restored before reading.
+    pub fn is_archive(&self) -> bool {
+        matches!(self, Self::Glacier | Self::DeepArchive)
+    }
+
+    pub(crate) fn to_sdk(&self) -> StorageClass {
+        StorageClass::from(self.as_str())
+    }
+}
+
+impl Display for S3StorageClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Retrieval tier of the restore request for the archived objects.
+///
+/// `Expedited` is not available for `DeepArchive`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum S3RestoreTier {
+    Expedited,
+    Standard,
+    Bulk,
+}
+
+impl S3RestoreTier {
+    pub(crate) fn to_sdk(self) -> Tier {
+        match self {
+            Self::Expedited => Tier::Expedited,
+            Self::Standard => Tier::Standard,
+            Self::Bulk => Tier::Bulk,
+        }
+    }
+}
+
+/// Restore status of the object in Amazon S3.
+///
+/// # Variants
+///
+/// * `NotArchived` - The object is not in an archive storage class and can be downloaded.
+/// * `Archived` - The object is archived and no restore is requested.
+/// * `InProgress` - The restore is requested but not completed yet.
+/// * `Restored` - The temporary copy is available until `expiry_date`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum S3RestoreStatus {
+    NotArchived,
+    Archived,
+    InProgress,
+    Restored {
+        expiry_date: Option<SystemTime>,
+    },
+}
+
+impl S3RestoreStatus {
+    /// Returns `true` if the object data can be read now.
+    pub fn is_readable(&self) -> bool {
+        matches!(self, Self::NotArchived | Self::Restored {..})
+    }
+
+    /// Resolve the status from the storage class and the `x-amz-restore` header of HeadObject
+    /// like `ongoing-request="false", expiry-date="Fri, 21 Dec 2012 00:00:00 GMT"`.
+    pub(crate) fn from_restore_header(storage_class: Option<&str>, restore: Option<&str>) -> Self {
+        if !storage_class.is_some_and(|storage_class| S3StorageClass::from_name(storage_class).is_archive()) {
+            return Self::NotArchived;
+        }
+
+        match restore {
+            None => Self::Archived,
+            Some(restore) if restore.contains("ongoing-request=\"true\"") => Self::InProgress,
+            Some(restore) => {
+                let expiry_date = restore
+                    .split_once("expiry-date=\"")
+                    .and_then(|(_, rest)| rest.split_once('"'))
+                    .and_then(|(date, _)| DateTime::from_str(date, DateTimeFormat::HttpDate).ok())
+                    .and_then(|date| SystemTime::try_from(date).ok());
+
+                Self::Restored { expiry_date }
+            },
+        }
+    }
+}
+
+/// Canned ACL applied to the objects written to Amazon S3.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum S3CannedAcl {
+    Private,
+    PublicRead,
+    PublicReadWrite,
+    AuthenticatedRead,
+    AwsExecRead,
+    BucketOwnerRead,
+    BucketOwnerFullControl,
+}
+
+impl S3CannedAcl {
+    pub(crate) fn to_sdk(self) -> ObjectCannedAcl {
+        match self {
+            Self::Private => ObjectCannedAcl::Private,
+            Self::PublicRead => ObjectCannedAcl::PublicRead,
+            Self::PublicReadWrite => ObjectCannedAcl::PublicReadWrite,
+            Self::AuthenticatedRead => ObjectCannedAcl::AuthenticatedRead,
+            Self::AwsExecRead => ObjectCannedAcl::AwsExecRead,
+            Self::BucketOwnerRead => ObjectCannedAcl::BucketOwnerRead,
+            Self::BucketOwnerFullControl => ObjectCannedAcl::BucketOwnerFullControl,
+        }
+    }
+}
+
+/// Object headers sent with the writes to Amazon S3.
+///
+/// The options are carried through PutObject, multipart upload and server-side copy.
+/// When the Content-Type is not set, it is derived from the extension of the object key
+/// (e.x. `index.html` is `text/html`).
+///
+/// # Example
+///
+/// ```
+/// use hikyaku::types::amazon_s3::{S3CannedAcl, S3UploadOptions};
+///
+/// let options = S3UploadOptions::new()
+///     .set_metadata("project", "hikyaku")
+///     .set_tag("team", "data")
+///     .set_cache_control("max-age=3600")
+///     .set_acl(S3CannedAcl::BucketOwnerFullControl);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct S3UploadOptions {
+    metadata: BTreeMap<String, String>,
+    tags: BTreeMap<String, String>,
+    content_type: Option<String>,
+    content_disposition: Option<String>,
+    cache_control: Option<String>,
+    acl: Option<S3CannedAcl>,
+}
+
+impl S3UploadOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the user metadata sent as the `x-amz-meta-{key}` header.
+    pub fn set_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds the object tag.
+    pub fn set_tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sets the Content-Type explicitly instead of deriving it from the object key.
+    pub fn set_content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.to_string());
+        self
+    }
+
+    /// Sets the Content-Disposition like `attachment; filename="report.pdf"`.
+    pub fn set_content_disposition(mut self, content_disposition: &str) -> Self {
+        self.content_disposition = Some(content_disposition.to_string());
+        self
+    }
+
+    /// Sets the Cache-Control like `max-age=3600`.
+    pub fn set_cache_control(mut self, cache_control: &str) -> Self {
+        self.cache_control = Some(cache_control.to_string());
+        self
+    }
+
+    /// Sets the canned ACL.
+    pub fn set_acl(mut self, acl: S3CannedAcl) -> Self {
+        self.acl = Some(acl);
+        self
+    }
+
+    pub(crate) fn metadata(&self) -> Option<HashMap<String, String>> {
+        if self.metadata.is_empty() {
+            return None;
+        }
+
+        Some(self.metadata.iter().map(|(key, value)| (key.clone(), value.clone())).collect())
+    }
+
+    /// The tags in the query string format which Amazon S3 requires.
+    pub(crate) fn tagging(&self) -> Option<String> {
+        encode_tagging(self.tags.iter().map(|(key, value)| (key.as_str(), value.as_str())))
+    }
+
+    /// Get the Content-Type. If it is not set, derive it from the extension of the `key`.
+    pub(crate) fn content_type(&self, key: &str) -> String {
+        match &self.content_type {
+            Some(content_type) => content_type.clone(),
+            None => FileType::from_filename(key).mime().to_string(),
+        }
+    }
+
+    pub(crate) fn content_disposition(&self) -> Option<String> {
+        self.content_disposition.clone()
+    }
+
+    pub(crate) fn cache_control(&self) -> Option<String> {
+        self.cache_control.clone()
+    }
+
+    pub(crate) fn acl(&self) -> Option<ObjectCannedAcl> {
+        self.acl.map(S3CannedAcl::to_sdk)
+    }
+
+    /// Returns `true` if the options replace the object metadata of the copy source.
+    pub(crate) fn replaces_metadata(&self) -> bool {
+        !self.metadata.is_empty() ||
+            self.content_type.is_some() ||
+            self.content_disposition.is_some() ||
+            self.cache_control.is_some()
+    }
+
+    /// Returns `true` if the options replace the object tags of the copy source.
+    pub(crate) fn replaces_tags(&self) -> bool {
+        !self.tags.is_empty()
+    }
+}
+
+/// A presigned request which can be sent without the AWS credentials until it expires.
+///
+/// The holder must send the request with the `method` to the `url` and attach all `headers`
+/// because they are included in the signature(e.x. Content-Type and SSE-C key).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3PresignedRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+impl S3PresignedRequest {
+    pub(crate) fn new(method: &str, url: &str, headers: Vec<(String, String)>) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            headers,
+        }
+    }
+
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn get_headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+}
+
+/// A multipart upload whose parts are uploaded by the presigned UploadPart requests.
+///
+/// The part `i` of `get_parts` is the part number `i + 1`. The holder uploads the parts and
+/// returns the ETag response headers, then the credential owner completes the upload with them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3PresignedMultipartUpload {
+    upload_id: String,
+    parts: Vec<S3PresignedRequest>,
+}
+
+impl S3PresignedMultipartUpload {
+    pub(crate) fn new(upload_id: &str, parts: Vec<S3PresignedRequest>) -> Self {
+        Self {
+            upload_id: upload_id.to_string(),
+            parts,
+        }
+    }
+
+    pub fn get_upload_id(&self) -> &str {
+        &self.upload_id
+    }
+
+    pub fn get_parts(&self) -> &[S3PresignedRequest] {
+        &self.parts
+    }
+}
+
+/// What the S3 path points to.
+///
+/// # Variants
+///
+/// * `Object` - The object exists at the key.
+/// * `Prefix` - No object exists at the key but the objects exist under `key/`(a.k.a. directory).
+/// * `Absent` - Neither the object nor the children exist.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum S3PathKind {
+    Object,
+    Prefix,
+    Absent,
+}
+
+/// The attributes of the S3 object returned by HeadObject.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3ObjectAttributes {
+    pub(crate) e_tag: Option<String>,
+    pub(crate) content_type: Option<String>,
+    pub(crate) last_modified: Option<SystemTime>,
+    pub(crate) storage_class: Option<S3StorageClass>,
+    pub(crate) restore_status: S3RestoreStatus,
+}
+
+impl S3ObjectAttributes {
+    pub fn get_e_tag(&self) -> Option<&str> {
+        self.e_tag.as_deref()
+    }
+
+    pub fn get_content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn get_last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    /// Get the storage class. Amazon S3 omits it for `STANDARD`, so [None] means `STANDARD`.
+    pub fn get_storage_class(&self) -> Option<&S3StorageClass> {
+        self.storage_class.as_ref()
+    }
+
+    /// Get the restore status at the time of the build. `Restore::restore_status` gets the latest one.
+    pub fn get_restore_status(&self) -> &S3RestoreStatus {
+        &self.restore_status
+    }
+}
+
+/// A version or a delete marker of the object in the versioned bucket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3ObjectVersion {
+    pub(crate) key: String,
+    pub(crate) version_id: String,
+    pub(crate) is_latest: bool,
+    pub(crate) is_delete_marker: bool,
+    pub(crate) size: Option<u64>,
+    pub(crate) last_modified: Option<SystemTime>,
+    pub(crate) e_tag: Option<String>,
+}
+
+impl S3ObjectVersion {
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn get_version_id(&self) -> &str {
+        &self.version_id
+    }
+
+    /// Returns `true` if this is the current version(or the current delete marker).
+    pub fn is_latest(&self) -> bool {
+        self.is_latest
+    }
+
+    /// Returns `true` if this is the delete marker which has no data.
+    pub fn is_delete_marker(&self) -> bool {
+        self.is_delete_marker
+    }
+
+    /// Get the object size. The delete marker returns [None].
+    pub fn get_size(&self) -> Option<u64> {
+        self.size
+    }
+
+    pub fn get_last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    /// Get the ETag. The delete marker returns [None].
+    pub fn get_e_tag(&self) -> Option<&str> {
+        self.e_tag.as_deref()
+    }
+}
+
+/// Applies the upload options to the fluent builders which create the object
+/// (PutObject, CreateMultipartUpload and CopyObject).
+macro_rules! apply_upload_options {
+    ($upload_options:expr, $key:expr, $builder:expr) => {
+        $builder
+            .set_metadata($upload_options.metadata())
+            .set_tagging($upload_options.tagging())
+            .content_type($upload_options.content_type($key))
+            .set_content_disposition($upload_options.content_disposition())
+            .set_cache_control($upload_options.cache_control())
+            .set_acl($upload_options.acl())
+    };
+}
+
+/// Applies the encryption setting to the fluent builders which accept the full SSE parameters
+/// (PutObject, CreateMultipartUpload and CopyObject).
+macro_rules! apply_encryption {
+    ($encryption:expr, $builder:expr) => {
+        match $encryption {
+            None => $builder,
+            Some($crate::types::amazon_s3::S3Encryption::S3Managed) => $builder
+                .server_side_encryption(aws_sdk_s3::types::ServerSideEncryption::Aes256),
+            Some($crate::types::amazon_s3::S3Encryption::Kms { key_id, encryption_context, bucket_key_enabled }) => $builder
+                .server_side_encryption(aws_sdk_s3::types::ServerSideEncryption::AwsKms)
+                .set_ssekms_key_id(key_id.clone())
+                .set_ssekms_encryption_context($crate::types::amazon_s3::encode_encryption_context(encryption_context))
+                .set_bucket_key_enabled(*bucket_key_enabled),
+            Some($crate::types::amazon_s3::S3Encryption::CustomerKey(customer_key)) => $builder
+                .sse_customer_algorithm(customer_key.algorithm())
+                .sse_customer_key(customer_key.key())
+                .sse_customer_key_md5(customer_key.key_md5()),
+        }
+    };
+}
+
+/// Applies only the SSE-C key to the fluent builders which read or write the encrypted data
+/// of the existing object or upload (GetObject, HeadObject and UploadPart).
+macro_rules! apply_customer_key {
+    ($encryption:expr, $builder:expr) => {
+        match $encryption {
+            Some($crate::types::amazon_s3::S3Encryption::CustomerKey(customer_key)) => $builder
+                .sse_customer_algorithm(customer_key.algorithm())
+                .sse_customer_key(customer_key.key())
+                .sse_customer_key_md5(customer_key.key_md5()),
+            _ => $builder,
+        }
+    };
+}
+
+/// Applies the SSE-C key of the copy source to the CopyObject and UploadPartCopy builders.
+macro_rules! apply_copy_source_customer_key {
+    ($encryption:expr, $builder:expr) => {
+        match $encryption {
+            Some($crate::types::amazon_s3::S3Encryption::CustomerKey(customer_key)) => $builder
+                .copy_source_sse_customer_algorithm(customer_key.algorithm())
+                .copy_source_sse_customer_key(customer_key.key())
+                .copy_source_sse_customer_key_md5(customer_key.key_md5()),
+            _ => $builder,
+        }
+    };
+}
+
+pub(crate) use {apply_copy_source_customer_key, apply_customer_key, apply_encryption, apply_upload_options};
+
+/// Encode the SSE-KMS encryption context to the base64 encoded JSON which Amazon S3 requires.
+/// If the context is empty, return [None] to omit the header.
+pub(crate) fn encode_encryption_context(encryption_context: &BTreeMap<String, String>) -> Option<String> {
+    if encryption_context.is_empty() {
+        return None;
+    }
+
+    // SAFETY: The map of strings can always serialize to JSON.
+    let context = serde_json::to_string(encryption_context).unwrap();
+    Some(STANDARD.encode(context))
+}
+
+/// Amazon S3 specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct S3Options {
+    pub(crate) encryption: Option<S3Encryption>,
+    pub(crate) storage_class: Option<S3StorageClass>,
+    pub(crate) upload_options: S3UploadOptions,
+    pub(crate) version_id: Option<String>,
+}
+
+impl S3Options {
+    /// Check the object can be written. The specific version is immutable,
+    /// so the object which has the version id cannot be the write destination.
+    pub(crate) fn ensure_writable(&self, bucket: &str, key: &str) -> HikyakuResult<()> {
+        match &self.version_id {
+            Some(version_id) => Err(InvalidArgumentError(format!(
+                "Cannot write to the specific version {} of s3://{}/{}. Remove the version id to write a new version",
+                version_id, bucket, key))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Encode the tags to the query string format(`key1=value1&key2=value2`) which Amazon S3 requires.
+/// Returns [None] if there is no tag.
+pub(crate) fn encode_tagging<'a>(tags: impl Iterator<Item = (&'a str, &'a str)>) -> Option<String> {
+    let tagging = tags
+        .map(|(key, value)| format!("{}={}",
+                                    utf8_percent_encode(key, TAGGING_ENCODE_SET),
+                                    utf8_percent_encode(value, TAGGING_ENCODE_SET)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    (!tagging.is_empty()).then_some(tagging)
+}
+
+/// Check the chunk size can be the part size of the multipart upload.
+///
+/// # Errors
+///
+/// Returns an `InvalidArgumentError` if the chunk size is out of 5 MiB to 5 GiB.
+pub(crate) fn validate_s3_part_size(chunk_size: u64) -> HikyakuResult<()> {
+    if !(MIN_S3_PART_SIZE..=MAX_S3_PART_SIZE).contains(&chunk_size) {
+        return Err(InvalidArgumentError(format!(
+            "The multipart upload of Amazon S3 needs the chunk size from {} to {} bytes but it is {} bytes",
+            MIN_S3_PART_SIZE, MAX_S3_PART_SIZE, chunk_size)));
+    }
+
+    Ok(())
+}
+
+/// Decide the part size of the multipart copy of the object.
+///
+/// The whole size is known before the copy, so the chunk size is raised to the minimum part size
+/// or to the size which fits the object in 10,000 parts.
+///
+/// # Errors
+///
+/// Returns an `InvalidArgumentError` if the object does not fit in 10,000 parts of 5 GiB.
+pub(crate) fn s3_copy_part_size(file_size: u64, chunk_size: u64) -> HikyakuResult<u64> {
+    let part_size = chunk_size
+        .max(MIN_S3_PART_SIZE)
+        .max(file_size.div_ceil(MAX_S3_PARTS))
+        .min(MAX_S3_PART_SIZE);
+    if file_size.div_ceil(part_size) > MAX_S3_PARTS {
+        return Err(InvalidArgumentError(format!(
+            "{} bytes cannot be copied in {} parts of {} bytes", file_size, MAX_S3_PARTS, MAX_S3_PART_SIZE)));
+    }
+
+    Ok(part_size)
+}
+
+/// The state of the multipart upload in progress.
+#[derive(Debug)]
+pub struct S3MultipartUpload {
+    upload_id: String,
+    completed_parts: Vec<CompletedPart>,
+    total_parts: Option<usize>,
+}
+
+impl S3MultipartUpload {
+    pub(crate) fn new(upload_id: &str) -> Self {
+        Self {
+            upload_id: upload_id.to_string(),
+            completed_parts: vec![],
+            total_parts: None,
+        }
+    }
+
+    pub(crate) fn get_upload_id(&self) -> &str {
+        &self.upload_id
+    }
+
+    pub(crate) fn add_part(&mut self, part: CompletedPart, is_last: bool) {
+        if is_last {
+            // The part number is the 1-based offset so the last part number equals the total.
+            self.total_parts = part.part_number().map(|number| number as usize);
+        }
+        self.completed_parts.push(part);
+    }
+
+    pub(crate) fn is_completed(&self) -> bool {
+        self.total_parts == Some(self.completed_parts.len())
+    }
+
+    /// Take the completed parts sorted by the part number which CompleteMultipartUpload requires.
+    pub(crate) fn take_sorted_parts(&mut self) -> Vec<CompletedPart> {
+        let mut parts = std::mem::take(&mut self.completed_parts);
+        parts.sort_by_key(|part| part.part_number());
+        parts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_s3_customer_key() {
+        let customer_key = S3CustomerKey::new(&[0u8; 32]).unwrap();
+        assert_eq!(customer_key.key(), "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=");
+        assert_eq!(customer_key.key_md5(), "cLyPS3KoaSFGi/joRB3OUQ==");
+        assert!(!format!("{:?}", customer_key).contains(customer_key.key()));
+
+        assert!(S3CustomerKey::new(&[0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_s3_part_size() {
+        assert!(validate_s3_part_size(MIN_S3_PART_SIZE).is_ok());
+        assert!(validate_s3_part_size(MIN_S3_PART_SIZE - 1).is_err());
+        assert!(validate_s3_part_size(MAX_S3_PART_SIZE + 1).is_err());
+
+        let gib = 1024 * 1024 * 1024;
+        assert_eq!(s3_copy_part_size(6 * gib, 1024).unwrap(), MIN_S3_PART_SIZE);
+        assert_eq!(s3_copy_part_size(6 * gib, 64 * 1024 * 1024).unwrap(), 64 * 1024 * 1024);
+        // 100 GiB in 5 MiB parts needs more than 10,000 parts.
+        let part_size = s3_copy_part_size(100 * gib, MIN_S3_PART_SIZE).unwrap();
+        assert_eq!(part_size, (100 * gib).div_ceil(MAX_S3_PARTS));
+        assert!((100 * gib).div_ceil(part_size) <= MAX_S3_PARTS);
+        assert_eq!(s3_copy_part_size(6 * gib, 10 * gib).unwrap(), MAX_S3_PART_SIZE);
+        assert!(s3_copy_part_size(MAX_S3_PARTS * MAX_S3_PART_SIZE + 1, MIN_S3_PART_SIZE).is_err());
+    }
+
+    #[test]
+    fn test_s3_upload_options() {
+        let options = S3UploadOptions::new()
+            .set_tag("team", "data & ml")
+            .set_tag("env", "prod");
+        assert_eq!(options.tagging(), Some("env=prod&team=data%20%26%20ml".to_string()));
+        assert_eq!(options.content_type("site/index.html"), "text/html");
+        assert_eq!(options.content_type("data/file.unknown"), "application/octet-stream");
+        assert!(!options.replaces_metadata());
+        assert!(options.replaces_tags());
+
+        let options = options.set_content_type("text/plain");
+        assert_eq!(options.content_type("site/index.html"), "text/plain");
+        assert!(options.replaces_metadata());
+    }
+
+    #[test]
+    fn test_s3_restore_status_from_restore_header() {
+        assert_eq!(S3RestoreStatus::from_restore_header(None, None), S3RestoreStatus::NotArchived);
+        assert_eq!(S3RestoreStatus::from_restore_header(Some("GLACIER_IR"), None), S3RestoreStatus::NotArchived);
+        assert_eq!(S3RestoreStatus::from_restore_header(Some("GLACIER"), None), S3RestoreStatus::Archived);
+        assert_eq!(
+            S3RestoreStatus::from_restore_header(Some("DEEP_ARCHIVE"), Some("ongoing-request=\"true\"")),
+            S3RestoreStatus::InProgress);
+
+        let restored = S3RestoreStatus::from_restore_header(
+            Some("GLACIER"),
+            Some("ongoing-request=\"false\", expiry-date=\"Fri, 21 Dec 2012 00:00:00 GMT\""));
+        assert_eq!(restored, S3RestoreStatus::Restored {
+            expiry_date: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1356048000)),
+        });
+        assert!(restored.is_readable());
+    }
+}
@@ -0,0 +1,462 @@
+use std::fmt::{Debug, Formatter};
+use std::time::{Duration, SystemTime};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, KeyInit, Mac};
+use log::error;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Client, Request, RequestBuilder, Response};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use serde::Deserialize;
+use sha2::Sha256;
+use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use xmlparser::{ElementEnd, Token, Tokenizer};
+use crate::errors::HikyakuError::{AzureBlobError, ConnectionError};
+use crate::errors::HikyakuResult;
+use crate::utils::credential::oauth2_tokens::OAuth2Tokens;
+use crate::utils::xml::unescape_text;
+
+/// The version of the Blob service REST API which this crate speaks.
+pub(crate) const AZURE_STORAGE_VERSION: &str = "2021-08-06";
+/// A block blob can have up to 50,000 committed blocks.
+pub(crate) const MAX_AZURE_BLOCKS: u64 = 50_000;
+const STORAGE_SCOPE: &str = "https://storage.azure.com/.default";
+// The Entra ID token is requested again when it expires within this margin.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+// The blob name is put in the URL path, so the reserved characters except the separator are escaped.
+const BLOB_NAME_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Base URL of the Blob service of a storage account.
+///
+/// The default is `https://{account}.blob.core.windows.net`. The emulator and the private endpoints
+/// address the account by the path(e.x. `http://127.0.0.1:10000/devstoreaccount1`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AzureBlobEndpoint {
+    base_url: String,
+}
+
+impl AzureBlobEndpoint {
+    /// Creates the endpoint from the base URL of the account(e.x. `https://myaccount.blob.core.windows.net`).
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Creates the public endpoint of the storage account.
+    pub fn from_account(account_name: &str) -> Self {
+        Self::new(&format!("https://{}.blob.core.windows.net", account_name))
+    }
+
+    /// Creates the endpoint of the Azurite emulator running on the local machine with the default port.
+    pub fn azurite() -> Self {
+        Self::new("http://127.0.0.1:10000/devstoreaccount1")
+    }
+
+    pub(crate) fn container_url(&self, container: &str) -> String {
+        format!("{}/{}", self.base_url, container)
+    }
+
+    pub(crate) fn blob_url(&self, container: &str, blob: &str) -> String {
+        let blob = blob
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, BLOB_NAME_ENCODE_SET).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{}/{}/{}", self.base_url, container, blob)
+    }
+}
+
+/// Azure Blob specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct AzureBlobOptions {
+    // [None] means the public endpoint of the account in the credential.
+    pub(crate) endpoint: Option<AzureBlobEndpoint>,
+}
+
+/// The client of Microsoft Entra ID which requests the token by the client credentials flow.
+#[derive(Clone)]
+pub(crate) struct EntraIdClient {
+    pub(crate) token_url: String,
+    pub(crate) client_id: String,
+    pub(crate) client_secret: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+impl EntraIdClient {
+    /// Request the token of the storage scope.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConnectionError` if the request cannot be sent, or an `AzureBlobError`
+    /// if Entra ID rejects the client.
+    async fn request_token(&self) -> HikyakuResult<OAuth2Tokens> {
+        let response = Client::new()
+            .post(&self.token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("scope", STORAGE_SCOPE),
+            ])
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to send request to Microsoft Entra ID: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Microsoft Entra ID: {:?}", e))
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AzureBlobError(format!("Failed to get the token from {}: {}, {}", self.token_url, status, body)));
+        }
+        let token = response
+            .json::<TokenResponse>()
+            .await
+            .map_err(|e| AzureBlobError(format!("Failed to parse the token response: {:?}", e)))?;
+        let expires_at = OffsetDateTime::now_utc() + Duration::from_secs(token.expires_in.unwrap_or(3600));
+
+        Ok(OAuth2Tokens::new(&token.access_token, None, expires_at))
+    }
+}
+
+pub(crate) enum AzureBlobAuthorization {
+    SharedKey(Vec<u8>),
+    Sas(String),
+    Bearer {
+        client: EntraIdClient,
+        tokens: Mutex<OAuth2Tokens>,
+    },
+}
+
+impl AzureBlobAuthorization {
+    /// The authorization by the Entra ID token. The first token is requested here,
+    /// so the wrong client secret fails when the file system object is built.
+    pub(crate) async fn entra_id(client: EntraIdClient) -> HikyakuResult<Self> {
+        let tokens = client.request_token().await?;
+
+        Ok(Self::Bearer {
+            client,
+            tokens: Mutex::new(tokens),
+        })
+    }
+}
+
+/// Authorizes the requests to the Blob service by the account key, the SAS token or the Entra ID token.
+pub struct AzureBlobAuthorizer {
+    account_name: String,
+    authorization: AzureBlobAuthorization,
+}
+
+impl Debug for AzureBlobAuthorizer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.authorization {
+            AzureBlobAuthorization::SharedKey(_) => "SharedKey",
+            AzureBlobAuthorization::Sas(_) => "Sas",
+            AzureBlobAuthorization::Bearer {..} => "Bearer",
+        };
+        write!(f, "AzureBlobAuthorizer {{ account_name: {}, authorization: {} }}", self.account_name, kind)
+    }
+}
+
+impl AzureBlobAuthorizer {
+    pub(crate) fn new(account_name: &str, authorization: AzureBlobAuthorization) -> Self {
+        Self {
+            account_name: account_name.to_string(),
+            authorization,
+        }
+    }
+
+    /// Sends the request with the date, the version and the authorization.
+    ///
+    /// # Returns
+    ///
+    /// `HikyakuResult<Response>` - The response whatever the status is, or a `ConnectionError`
+    /// if the request cannot be sent.
+    pub(crate) async fn send(&self, client: &Client, request: RequestBuilder) -> HikyakuResult<Response> {
+        self.sign(client, request)
+            .await?
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to send request to Azure Blob Storage: {:#?}", e);
+                ConnectionError(format!("Failed to send request to Azure Blob Storage: {:?}", e))
+            })
+    }
+
+    /// Adds the date, the version and the authorization to the request, so it can be sent as it is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `AzureBlobError` if the request cannot be built or signed,
+    /// or the error of Entra ID if the expiring token cannot be refreshed.
+    pub(crate) async fn sign(&self, client: &Client, request: RequestBuilder) -> HikyakuResult<RequestBuilder> {
+        let mut request = request
+            .header("x-ms-date", httpdate::fmt_http_date(SystemTime::now()))
+            .header("x-ms-version", AZURE_STORAGE_VERSION)
+            .build()
+            .map_err(|e| AzureBlobError(format!("Failed to build the request: {:?}", e)))?;
+        self.authorize(&mut request).await?;
+
+        Ok(RequestBuilder::from_parts(client.clone(), request))
+    }
+
+    async fn authorize(&self, request: &mut Request) -> HikyakuResult<()> {
+        let header_value = match &self.authorization {
+            AzureBlobAuthorization::Sas(sas) => {
+                let query = match request.url().query() {
+                    Some(query) => format!("{}&{}", query, sas),
+                    None => sas.to_string(),
+                };
+                request.url_mut().set_query(Some(&query));
+                return Ok(());
+            },
+            AzureBlobAuthorization::SharedKey(key) => {
+                // SAFETY: HMAC accepts the key of any length.
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+                mac.update(string_to_sign(request, &self.account_name).as_bytes());
+                let signature = STANDARD.encode(mac.finalize().into_bytes());
+                format!("SharedKey {}:{}", self.account_name, signature)
+            },
+            AzureBlobAuthorization::Bearer {client, tokens} => {
+                let mut tokens = tokens.lock().await;
+                if tokens.get_expires_at() - TOKEN_REFRESH_MARGIN <= OffsetDateTime::now_utc() {
+                    *tokens = client.request_token().await?;
+                }
+                format!("Bearer {}", tokens.get_access_token())
+            },
+        };
+
+        let mut header_value = HeaderValue::from_str(&header_value)
+            .map_err(|e| AzureBlobError(format!("Failed to parse the authorization header: {:?}", e)))?;
+        header_value.set_sensitive(true);
+        request.headers_mut().insert(AUTHORIZATION, header_value);
+
+        Ok(())
+    }
+}
+
+/// Build the string to sign of the Shared Key authorization for the Blob service.
+pub(crate) fn string_to_sign(request: &Request, account_name: &str) -> String {
+    let header = |name: &str| request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    // The zero length is signed as the empty string since the version 2015-02-21.
+    let content_length = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .map(|body| body.len())
+        .filter(|length| *length > 0)
+        .map(|length| length.to_string())
+        .unwrap_or_default();
+
+    let mut ms_headers = request
+        .headers()
+        .iter()
+        .filter(|(name, _)| name.as_str().starts_with("x-ms-"))
+        .map(|(name, value)| format!("{}:{}\n", name.as_str(), value.to_str().unwrap_or_default().trim()))
+        .collect::<Vec<_>>();
+    ms_headers.sort();
+
+    let mut resource = format!("/{}{}", account_name, request.url().path());
+    let mut params = request
+        .url()
+        .query_pairs()
+        .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+        .collect::<Vec<_>>();
+    params.sort();
+    let mut names = params.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    names.dedup();
+    for name in names {
+        let values = params.iter().filter(|(n, _)| n == name).map(|(_, value)| value.as_str()).collect::<Vec<_>>();
+        resource.push_str(&format!("\n{}:{}", name, values.join(",")));
+    }
+
+    [
+        request.method().as_str().to_string(),
+        header("content-encoding"),
+        header("content-language"),
+        content_length,
+        header("content-md5"),
+        header("content-type"),
+        // The date is sent as `x-ms-date`.
+        String::new(),
+        header("if-modified-since"),
+        header("if-match"),
+        header("if-none-match"),
+        header("if-unmodified-since"),
+        header("range"),
+        format!("{}{}", ms_headers.concat(), resource),
+    ].join("\n")
+}
+
+/// The state of the block upload.
+///
+/// The blocks can be put in any order, so the block list is committed when all blocks are put.
+#[derive(Debug)]
+pub struct AzureBlockUpload {
+    put_blocks: u64,
+    total_blocks: Option<u64>,
+}
+
+impl AzureBlockUpload {
+    pub(crate) fn new() -> Self {
+        Self {
+            put_blocks: 0,
+            total_blocks: None,
+        }
+    }
+
+    pub(crate) fn add_block(&mut self, offset: u64, is_last: bool) {
+        if is_last {
+            self.total_blocks = Some(offset + 1);
+        }
+        self.put_blocks += 1;
+    }
+
+    pub(crate) fn is_completed(&self) -> bool {
+        self.total_blocks == Some(self.put_blocks)
+    }
+
+    pub(crate) fn get_total_blocks(&self) -> Option<u64> {
+        self.total_blocks
+    }
+}
+
+/// Get the block id of the chunk offset. All block ids of a blob must have the same length.
+pub(crate) fn block_id(offset: u64) -> String {
+    STANDARD.encode(format!("{:032}", offset))
+}
+
+/// Build the body of Put Block List which commits the blocks in the offset order.
+pub(crate) fn block_list_body(total_blocks: u64) -> String {
+    let blocks = (0..total_blocks)
+        .map(|offset| format!("<Latest>{}</Latest>", block_id(offset)))
+        .collect::<String>();
+    format!("<?xml version=\"1.0\" encoding=\"utf-8\"?><BlockList>{}</BlockList>", blocks)
+}
+
+/// A page of List Blobs.
+#[derive(Debug, Default)]
+pub(crate) struct AzureBlobList {
+    pub(crate) blobs: Vec<String>,
+    pub(crate) prefixes: Vec<String>,
+    pub(crate) next_marker: Option<String>,
+}
+
+/// Parse the XML of List Blobs to the names of the blobs and the prefixes.
+///
+/// # Errors
+///
+/// Returns a `AzureBlobError` if the response is not well-formed XML.
+pub(crate) fn parse_blob_list(xml: &str) -> HikyakuResult<AzureBlobList> {
+    let mut list = AzureBlobList::default();
+    let mut elements: Vec<String> = vec![];
+    let mut text = String::new();
+
+    for token in Tokenizer::from(xml) {
+        let token = token.map_err(|e| AzureBlobError(format!("Failed to parse the blob list: {}", e)))?;
+        match token {
+            Token::ElementStart {local, ..} => {
+                elements.push(local.to_string());
+                text.clear();
+            },
+            Token::ElementEnd {end: ElementEnd::Empty, ..} => {
+                elements.pop();
+            },
+            Token::ElementEnd {end: ElementEnd::Close(_, local), ..} => {
+                let element = elements.pop();
+                if element.as_deref() != Some(local.as_str()) {
+                    return Err(AzureBlobError(format!("Failed to parse the blob list: unexpected </{}>", local)));
+                }
+                let parent = elements.last().map(String::as_str);
+                match (element.as_deref(), parent) {
+                    (Some("Name"), Some("Blob")) => list.blobs.push(std::mem::take(&mut text)),
+                    (Some("Name"), Some("BlobPrefix")) => list.prefixes.push(std::mem::take(&mut text)),
+                    (Some("NextMarker"), _) if !text.is_empty() => list.next_marker = Some(std::mem::take(&mut text)),
+                    _ => text.clear(),
+                }
+            },
+            Token::Text {text: part} => text.push_str(&unescape_text(part.as_str())),
+            Token::Cdata {text: part, ..} => text.push_str(part.as_str()),
+            _ => {},
+        }
+    }
+    if let Some(element) = elements.last() {
+        return Err(AzureBlobError(format!("Failed to parse the blob list: <{}> is not closed", element)));
+    }
+
+    Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Method;
+    use super::*;
+
+    #[test]
+    fn test_string_to_sign() {
+        let request = Client::new()
+            .put("http://127.0.0.1:10000/devstoreaccount1/datas/train%20set.csv")
+            .query(&[("comp", "block"), ("blockid", "MDA=")])
+            .header("x-ms-version", AZURE_STORAGE_VERSION)
+            .header("x-ms-date", "Sun, 11 Oct 2009 21:49:13 GMT")
+            .body(vec![0; 10])
+            .build()
+            .unwrap();
+        assert_eq!(
+            string_to_sign(&request, "devstoreaccount1"),
+            "PUT\n\n\n10\n\n\n\n\n\n\n\n\n\
+            x-ms-date:Sun, 11 Oct 2009 21:49:13 GMT\nx-ms-version:2021-08-06\n\
+            /devstoreaccount1/devstoreaccount1/datas/train%20set.csv\nblockid:MDA=\ncomp:block");
+
+        let request = Client::new().request(Method::GET, "https://myaccount.blob.core.windows.net/datas").build().unwrap();
+        assert!(string_to_sign(&request, "myaccount").ends_with("\n\n/myaccount/datas"));
+    }
+
+    #[test]
+    fn test_block_list_body() {
+        assert_eq!(block_id(1), STANDARD.encode("00000000000000000000000000000001"));
+        assert_eq!(
+            block_list_body(2),
+            format!("<?xml version=\"1.0\" encoding=\"utf-8\"?><BlockList><Latest>{}</Latest><Latest>{}</Latest></BlockList>", block_id(0), block_id(1)));
+    }
+
+    #[test]
+    fn test_parse_blob_list() {
+        let xml = "<?xml version=\"1.0\" encoding=\"utf-8\"?><EnumerationResults><Blobs>\
+            <Blob><Name>datas/a&amp;b.csv</Name><Properties><Content-Length>5</Content-Length></Properties></Blob>\
+            <BlobPrefix><Name>datas/titanic/</Name></BlobPrefix></Blobs><NextMarker>2!abc</NextMarker></EnumerationResults>";
+        let list = parse_blob_list(xml).unwrap();
+        assert_eq!(list.blobs, vec!["datas/a&b.csv"]);
+        assert_eq!(list.prefixes, vec!["datas/titanic/"]);
+        assert_eq!(list.next_marker.as_deref(), Some("2!abc"));
+
+        let list = parse_blob_list("<EnumerationResults><Blobs /><NextMarker /></EnumerationResults>").unwrap();
+        assert!(list.blobs.is_empty() && list.next_marker.is_none());
+
+        // The metadata named `Name` is not taken as the blob name.
+        let xml = "<EnumerationResults><Blobs><Blob><Name><![CDATA[datas/<raw>.csv]]></Name>\
+            <Metadata><Name>author</Name></Metadata></Blob></Blobs><NextMarker/></EnumerationResults>";
+        assert_eq!(parse_blob_list(xml).unwrap().blobs, vec!["datas/<raw>.csv"]);
+
+        assert!(matches!(parse_blob_list("<EnumerationResults><Blobs></EnumerationResults>"), Err(AzureBlobError(_))));
+        // The truncated response is not taken as the last page.
+        assert!(matches!(parse_blob_list("<EnumerationResults><Blobs><Blob><Name>a</Name></Blob>"), Err(AzureBlobError(_))));
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::{Debug, Formatter};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use crate::types::FileInfo;
+use crate::utils::parser::FileSystemParseResult;
+
+/// The id of the root folder(All Files) in Box.
+pub(crate) const BOX_ROOT_FOLDER_ID: &str = "0";
+
+/// Represents different types of file information for Box.
+///
+/// The file can be specified by the parsed `box://` path, the folder id with the path
+/// under the folder, or the file id.
+pub enum BoxFileInfo {
+    Parsed(FileSystemParseResult),
+    FolderId {
+        folder_id: String,
+        file_path: String,
+    },
+    FileId(String),
+}
+
+impl FileInfo for BoxFileInfo {
+    fn get_prefix(&self) -> &str {
+        match self {
+            BoxFileInfo::Parsed(parsed) => parsed.get_prefix(),
+            BoxFileInfo::FolderId {..} => "",
+            BoxFileInfo::FileId(_) => "",
+        }
+    }
+
+    fn get_namespace(&self) -> Option<&str> {
+        match self {
+            BoxFileInfo::Parsed(parsed) => parsed.get_namespace(),
+            BoxFileInfo::FolderId {..} => None,
+            BoxFileInfo::FileId(_) => None,
+        }
+    }
+
+    fn get_path(&self) -> &str {
+        match self {
+            BoxFileInfo::Parsed(parsed) => parsed.get_path(),
+            BoxFileInfo::FolderId {file_path, ..} => file_path,
+            BoxFileInfo::FileId(_) => "",
+        }
+    }
+}
+
+impl From<FileSystemParseResult> for BoxFileInfo {
+    fn from(value: FileSystemParseResult) -> Self {
+        BoxFileInfo::Parsed(value)
+    }
+}
+
+/// Base URLs of the Box API.
+///
+/// The default points to `https://api.box.com/2.0` for the metadata and download and
+/// `https://upload.box.com/api/2.0` for the upload. This can be replaced with a mock server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoxEndpoint {
+    api_base_url: String,
+    upload_base_url: String,
+}
+
+impl BoxEndpoint {
+    /// Creates the endpoint from the API base URL(e.x. `https://api.box.com/2.0`)
+    /// and the upload API base URL(e.x. `https://upload.box.com/api/2.0`).
+    pub fn new(api_base_url: &str, upload_base_url: &str) -> Self {
+        Self {
+            api_base_url: api_base_url.trim_end_matches('/').to_string(),
+            upload_base_url: upload_base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub(crate) fn folder_items_url(&self, folder_id: &str) -> String {
+        format!("{}/folders/{}/items", self.api_base_url, folder_id)
+    }
+
+    pub(crate) fn folders_url(&self) -> String {
+        format!("{}/folders", self.api_base_url)
+    }
+
+    pub(crate) fn folder_url(&self, folder_id: &str) -> String {
+        format!("{}/folders/{}", self.api_base_url, folder_id)
+    }
+
+    pub(crate) fn file_url(&self, file_id: &str) -> String {
+        format!("{}/files/{}", self.api_base_url, file_id)
+    }
+
+    pub(crate) fn file_content_url(&self, file_id: &str) -> String {
+        format!("{}/files/{}/content", self.api_base_url, file_id)
+    }
+
+    pub(crate) fn upload_url(&self, file_id: Option<&str>) -> String {
+        match file_id {
+            Some(file_id) => format!("{}/files/{}/content", self.upload_base_url, file_id),
+            None => format!("{}/files/content", self.upload_base_url),
+        }
+    }
+
+    pub(crate) fn upload_sessions_url(&self, file_id: Option<&str>) -> String {
+        match file_id {
+            Some(file_id) => format!("{}/files/{}/upload_sessions", self.upload_base_url, file_id),
+            None => format!("{}/files/upload_sessions", self.upload_base_url),
+        }
+    }
+}
+
+impl Default for BoxEndpoint {
+    fn default() -> Self {
+        Self::new("https://api.box.com/2.0", "https://upload.box.com/api/2.0")
+    }
+}
+
+/// Box specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct BoxOptions {
+    pub(crate) endpoint: BoxEndpoint,
+    pub(crate) upload_size: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct BoxItem {
+    #[serde(rename = "type")]
+    pub(crate) item_type: String,
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) size: Option<u64>,
+}
+
+impl BoxItem {
+    pub(crate) fn is_folder(&self) -> bool {
+        self.item_type == "folder"
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BoxItemsResponse {
+    pub(crate) entries: Vec<BoxItem>,
+    pub(crate) total_count: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BoxSessionEndpoints {
+    pub(crate) upload_part: String,
+    pub(crate) commit: String,
+    pub(crate) abort: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BoxUploadSessionResponse {
+    pub(crate) part_size: u64,
+    pub(crate) session_endpoints: BoxSessionEndpoints,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BoxUploadPart {
+    pub(crate) part_id: String,
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+    pub(crate) sha1: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct BoxUploadPartResponse {
+    pub(crate) part: BoxUploadPart,
+}
+
+/// The state of the upload which is kept between the chunks.
+///
+/// Box decides the part size of the upload session, so the chunks are re-split to the parts
+/// in `buffer`. The small file is kept in `buffer` until the last chunk and uploaded at once.
+pub enum BoxUpload {
+    Buffered(Vec<u8>),
+    Session {
+        upload_part_url: String,
+        commit_url: String,
+        abort_url: String,
+        part_size: u64,
+        file_size: u64,
+        buffer: Vec<u8>,
+        uploaded_size: u64,
+        parts: Vec<BoxUploadPart>,
+        // The SHA-1 of the whole file which the commit requires.
+        // It is boxed not to make the buffered upload as large as the session.
+        sha1: Box<Sha1>,
+    },
+}
+
+impl Debug for BoxUpload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoxUpload::Buffered(buffer) => write!(f, "BoxUpload::Buffered({} bytes)", buffer.len()),
+            BoxUpload::Session {upload_part_url, uploaded_size, file_size, ..} => {
+                write!(f, "BoxUpload::Session {{ upload_part_url: {}, uploaded: {}/{} }}", upload_part_url, uploaded_size, file_size)
+            },
+        }
+    }
+}
+
+/// Get the `Digest` header value(`sha=<base64 SHA-1>`) of the data.
+pub(crate) fn sha1_digest(data: &[u8]) -> String {
+    format!("sha={}", STANDARD.encode(Sha1::digest(data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha1_digest() {
+        assert_eq!(sha1_digest(b"hikyaku"), format!("sha={}", STANDARD.encode(Sha1::digest(b"hikyaku"))));
+        assert_eq!(sha1_digest(b""), "sha=2jmj7l5rSw0yVb/vlWAYkK/YBwk=");
+    }
+}
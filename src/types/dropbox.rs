@@ -0,0 +1,164 @@
+use serde::Deserialize;
+
+/// The header which carries the JSON arguments of the content endpoints.
+pub(crate) const DROPBOX_API_ARG: &str = "Dropbox-API-Arg";
+
+/// Base URLs of the Dropbox API.
+///
+/// The default points to `https://api.dropboxapi.com/2` for the RPC endpoints and
+/// `https://content.dropboxapi.com/2` for the content(upload and download) endpoints.
+/// This can be replaced with a mock server or a private endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DropboxEndpoint {
+    api_base_url: String,
+    content_base_url: String,
+}
+
+impl DropboxEndpoint {
+    /// Creates the endpoint from the RPC API base URL(e.x. `https://api.dropboxapi.com/2`)
+    /// and the content API base URL(e.x. `https://content.dropboxapi.com/2`).
+    pub fn new(api_base_url: &str, content_base_url: &str) -> Self {
+        Self {
+            api_base_url: api_base_url.trim_end_matches('/').to_string(),
+            content_base_url: content_base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub(crate) fn api_url(&self, route: &str) -> String {
+        format!("{}/{}", self.api_base_url, route)
+    }
+
+    pub(crate) fn content_url(&self, route: &str) -> String {
+        format!("{}/{}", self.content_base_url, route)
+    }
+}
+
+impl Default for DropboxEndpoint {
+    fn default() -> Self {
+        Self::new("https://api.dropboxapi.com/2", "https://content.dropboxapi.com/2")
+    }
+}
+
+/// Dropbox specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct DropboxOptions {
+    pub(crate) endpoint: DropboxEndpoint,
+}
+
+/// The upload session which is opened by the first chunk and closed by the last chunk.
+#[derive(Debug)]
+pub struct DropboxUploadSession {
+    session_id: String,
+    // The bytes which the session has received.
+    offset: u64,
+}
+
+impl DropboxUploadSession {
+    pub(crate) fn new(session_id: &str, offset: u64) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            offset,
+        }
+    }
+
+    pub(crate) fn get_session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub(crate) fn get_offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub(crate) fn add_offset(&mut self, size: u64) {
+        self.offset += size;
+    }
+}
+
+/// The metadata of the file or folder which `files/get_metadata` and `files/list_folder` return.
+#[derive(Deserialize, Debug)]
+pub(crate) struct DropboxMetadata {
+    #[serde(rename = ".tag")]
+    tag: String,
+    path_display: Option<String>,
+    size: Option<u64>,
+}
+
+impl DropboxMetadata {
+    pub(crate) fn is_folder(&self) -> bool {
+        self.tag == "folder"
+    }
+
+    pub(crate) fn is_deleted(&self) -> bool {
+        self.tag == "deleted"
+    }
+
+    pub(crate) fn get_path_display(&self) -> Option<&str> {
+        self.path_display.as_deref()
+    }
+
+    pub(crate) fn get_size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct DropboxListFolderResponse {
+    pub(crate) entries: Vec<DropboxMetadata>,
+    pub(crate) cursor: String,
+    pub(crate) has_more: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct DropboxUploadSessionStartResponse {
+    pub(crate) session_id: String,
+}
+
+/// Convert the parsed path(without the head slash) to the Dropbox path.
+/// The root folder is represented by the empty string in the Dropbox API.
+pub(crate) fn to_dropbox_path(path: &str) -> String {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", path)
+    }
+}
+
+/// The JSON in the `Dropbox-API-Arg` header must be ASCII, so escape the non-ASCII characters as `\uXXXX`.
+pub(crate) fn to_api_arg(value: &serde_json::Value) -> String {
+    let mut arg = String::new();
+    for c in value.to_string().chars() {
+        if c.is_ascii() {
+            arg.push(c);
+        } else {
+            let mut buf = [0u16; 2];
+            for unit in c.encode_utf16(&mut buf) {
+                arg.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+
+    arg
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn test_to_dropbox_path() {
+        assert_eq!(to_dropbox_path(""), "");
+        assert_eq!(to_dropbox_path("/"), "");
+        assert_eq!(to_dropbox_path("datas/train.csv"), "/datas/train.csv");
+    }
+
+    #[test]
+    fn test_to_api_arg() {
+        let arg = to_api_arg(&json!({"path": "/データ/😀.txt"}));
+        assert!(arg.is_ascii());
+        assert!(arg.contains(r"\ud83d\ude00"));
+        let value: serde_json::Value = serde_json::from_str(&arg).unwrap();
+        assert_eq!(value["path"], "/データ/😀.txt");
+    }
+}
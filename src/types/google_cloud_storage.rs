@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use md5::{Digest, Md5};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use crate::errors::HikyakuError::GcsError;
+use crate::errors::HikyakuResult;
+
+// The object name is put in a path segment of the JSON API, so `/` is escaped too.
+const OBJECT_NAME_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// The unit of the chunk size of the resumable upload. Every chunk except the last
+/// must be a multiple of 256 KiB.
+pub(crate) const RESUMABLE_CHUNK_UNIT: u64 = 256 * 1024;
+
+/// Base URL of the Cloud Storage JSON API.
+///
+/// The default points to `https://storage.googleapis.com`.
+/// This can be replaced with a local emulator like fake-gcs-server or a private endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GcsEndpoint {
+    base_url: String,
+}
+
+impl GcsEndpoint {
+    /// Creates the endpoint from the base URL(e.x. `https://storage.googleapis.com`).
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Creates the endpoint of fake-gcs-server running on the local machine
+    /// with the default port and `-scheme http`.
+    pub fn fake_gcs_server() -> Self {
+        Self::new("http://localhost:4443")
+    }
+
+    pub(crate) fn objects_url(&self, bucket: &str) -> String {
+        format!("{}/storage/v1/b/{}/o", self.base_url, bucket)
+    }
+
+    pub(crate) fn object_url(&self, bucket: &str, object: &str) -> String {
+        format!("{}/{}", self.objects_url(bucket), utf8_percent_encode(object, OBJECT_NAME_ENCODE_SET))
+    }
+
+    pub(crate) fn upload_url(&self, bucket: &str) -> String {
+        format!("{}/upload/storage/v1/b/{}/o", self.base_url, bucket)
+    }
+}
+
+impl Default for GcsEndpoint {
+    fn default() -> Self {
+        Self::new("https://storage.googleapis.com")
+    }
+}
+
+/// Google Cloud Storage specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct GcsOptions {
+    pub(crate) endpoint: GcsEndpoint,
+}
+
+/// The object resource which has only the fields used in this crate.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GcsObject {
+    pub(crate) name: String,
+    // The JSON API returns the 64-bit integers as the strings.
+    size: String,
+    crc32c: Option<String>,
+    md5_hash: Option<String>,
+}
+
+impl GcsObject {
+    pub(crate) fn get_size(&self) -> HikyakuResult<u64> {
+        self.size
+            .parse::<u64>()
+            .map_err(|e| GcsError(format!("Failed to parse the size of {}: {:?}", self.name, e)))
+    }
+
+    /// Get the CRC32C of the object. Every object has it, even the composite object which has no MD5.
+    pub(crate) fn get_crc32c(&self) -> Option<u32> {
+        let bytes = STANDARD.decode(self.crc32c.as_ref()?).ok()?;
+        Some(u32::from_be_bytes(bytes.try_into().ok()?))
+    }
+
+    pub(crate) fn get_md5_hash(&self) -> Option<&str> {
+        self.md5_hash.as_deref()
+    }
+}
+
+/// A page of the objects and the prefixes of `objects.list`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GcsObjectList {
+    #[serde(default)]
+    pub(crate) items: Vec<GcsObject>,
+    #[serde(default)]
+    pub(crate) prefixes: Vec<String>,
+    pub(crate) next_page_token: Option<String>,
+}
+
+/// Encode the CRC32C in the base64 of the big-endian bytes like the JSON API.
+pub(crate) fn encode_crc32c(crc32c: u32) -> String {
+    STANDARD.encode(crc32c.to_be_bytes())
+}
+
+/// Calculate the CRC32C and the base64 encoded MD5 of the data.
+pub(crate) fn calculate_checksums(data: &[u8]) -> (u32, String) {
+    (crc32c::crc32c(data), STANDARD.encode(Md5::digest(data)))
+}
+
+/// The CRC32C of the chunks which are downloaded in parallel.
+///
+/// The CRC32C of the chunks are combined in the offset order when all chunks are downloaded,
+/// so the whole object is verified without holding the data.
+#[derive(Debug, Default)]
+pub struct GcsDownloadChecksum {
+    // start -> (CRC32C, length)
+    parts: BTreeMap<u64, (u32, usize)>,
+}
+
+impl GcsDownloadChecksum {
+    /// Add the range of the object from `start` and get the CRC32C of the whole object
+    /// if the added ranges cover the `file_size` bytes.
+    pub(crate) fn add_part(&mut self, start: u64, data: &[u8], file_size: u64) -> Option<u32> {
+        self.parts.insert(start, (crc32c::crc32c(data), data.len()));
+        let mut covered = 0;
+        for (start, (_, len)) in &self.parts {
+            if *start != covered {
+                return None;
+            }
+            covered += *len as u64;
+        }
+        if covered != file_size {
+            return None;
+        }
+
+        let crc32c = std::mem::take(&mut self.parts)
+            .into_values()
+            .fold(0, |crc, (part_crc, len)| crc32c::crc32c_combine(crc, part_crc, len));
+        Some(crc32c)
+    }
+}
+
+/// The state of the resumable upload session.
+///
+/// The chunks are uploaded in order, so the CRC32C and the MD5 are calculated along the way
+/// and compared with the ones which Cloud Storage calculated.
+pub struct GcsResumableUpload {
+    session_url: String,
+    uploaded_size: u64,
+    crc32c: u32,
+    md5: Md5,
+}
+
+impl Debug for GcsResumableUpload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The session URL authorizes the upload without the token.
+        write!(f, "GcsResumableUpload {{ session_url: *****, uploaded_size: {} }}", self.uploaded_size)
+    }
+}
+
+impl GcsResumableUpload {
+    pub(crate) fn new(session_url: &str) -> Self {
+        Self {
+            session_url: session_url.to_string(),
+            uploaded_size: 0,
+            crc32c: 0,
+            md5: Md5::new(),
+        }
+    }
+
+    pub(crate) fn get_session_url(&self) -> &str {
+        &self.session_url
+    }
+
+    pub(crate) fn get_uploaded_size(&self) -> u64 {
+        self.uploaded_size
+    }
+
+    pub(crate) fn add_chunk(&mut self, data: &[u8]) {
+        self.uploaded_size += data.len() as u64;
+        self.crc32c = crc32c::crc32c_append(self.crc32c, data);
+        self.md5.update(data);
+    }
+
+    /// Get the CRC32C and the base64 encoded MD5 of the uploaded data.
+    pub(crate) fn finalize(self) -> (u32, String) {
+        (self.crc32c, STANDARD.encode(self.md5.finalize()))
+    }
+}
+
+/// Verify the checksums which Cloud Storage calculated for the uploaded object.
+///
+/// # Errors
+///
+/// Returns a `GcsError` if the CRC32C or the MD5 does not match.
+pub(crate) fn verify_checksums(object: &GcsObject, crc32c: u32, md5_hash: &str) -> HikyakuResult<()> {
+    if object.get_crc32c().is_some_and(|actual| actual != crc32c) {
+        return Err(GcsError(format!(
+            "CRC32C of {} does not match: expected {}, actual {:?}", object.name, encode_crc32c(crc32c), object.crc32c)));
+    }
+    if object.get_md5_hash().is_some_and(|actual| actual != md5_hash) {
+        return Err(GcsError(format!(
+            "MD5 of {} does not match: expected {}, actual {:?}", object.name, md5_hash, object.md5_hash)));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_url() {
+        let endpoint = GcsEndpoint::fake_gcs_server();
+        assert_eq!(endpoint.object_url("datas", "titanic/train set.csv"),
+                   "http://localhost:4443/storage/v1/b/datas/o/titanic%2Ftrain%20set.csv");
+        assert_eq!(endpoint.upload_url("datas"), "http://localhost:4443/upload/storage/v1/b/datas/o");
+    }
+
+    #[test]
+    fn test_checksums() {
+        let data = b"The quick brown fox jumps over the lazy dog".to_vec();
+        // The well-known CRC32C of the empty data and the base64 of the JSON API.
+        assert_eq!(encode_crc32c(crc32c::crc32c(b"")), "AAAAAA==");
+
+        let mut checksum = GcsDownloadChecksum::default();
+        assert_eq!(checksum.add_part(20, &data[20..], data.len() as u64), None);
+        assert_eq!(checksum.add_part(0, &data[..10], data.len() as u64), None);
+        assert_eq!(checksum.add_part(10, &data[10..20], data.len() as u64), Some(crc32c::crc32c(&data)));
+
+        let mut upload = GcsResumableUpload::new("http://localhost:4443/upload");
+        upload.add_chunk(&data[..10]);
+        upload.add_chunk(&data[10..]);
+        assert_eq!(upload.get_uploaded_size(), data.len() as u64);
+        let (crc32c, md5_hash) = upload.finalize();
+        assert_eq!(crc32c, crc32c::crc32c(&data));
+        assert_eq!(md5_hash, "nhB9nTcrtoJr2B01QqQZ1g==");
+
+        let object = GcsObject {
+            name: "fox.txt".to_string(),
+            size: data.len().to_string(),
+            crc32c: Some(encode_crc32c(crc32c)),
+            md5_hash: Some(md5_hash.clone()),
+        };
+        assert_eq!(object.get_crc32c(), Some(crc32c));
+        assert!(verify_checksums(&object, crc32c, &md5_hash).is_ok());
+        assert!(verify_checksums(&object, crc32c + 1, &md5_hash).is_err());
+        assert!(verify_checksums(&object, crc32c, "AAAA").is_err());
+    }
+}
@@ -1,9 +1,20 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::SystemTime;
 use serde::Deserialize;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 use crate::types::FileInfo;
 use crate::utils::file_type::FileType;
 use crate::utils::parser::FileSystemParseResult;
 
 
+// The max size of the file which is converted to Google Docs.
+const MAX_DOCS_CONVERSION_SIZE: u64 = 50 * 1024 * 1024;
+// The max size of the file which is converted to Google Sheets or Google Slides.
+const MAX_SHEETS_AND_SLIDES_CONVERSION_SIZE: u64 = 100 * 1024 * 1024;
+
 /// Represents different types of file information for Google Drive.
 ///
 /// The `GoogleDriveFileInfo` enum encapsulates variations in the way
@@ -51,24 +62,393 @@ impl From<FileSystemParseResult> for GoogleDriveFileInfo {
     }
 }
 
+/// Base URLs of the Google Drive API.
+///
+/// The default points to `https://www.googleapis.com`. This can be replaced with a mock server
+/// (e.x. `FakeGoogleDrive` of the `fake_google_drive` feature) or a private endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoogleDriveEndpoint {
+    api_base_url: String,
+    upload_base_url: String,
+}
+
+impl GoogleDriveEndpoint {
+    /// Creates the endpoint from the metadata API base URL(e.x. `https://www.googleapis.com/drive/v3`)
+    /// and the upload API base URL(e.x. `https://www.googleapis.com/upload/drive/v3`).
+    pub fn new(api_base_url: &str, upload_base_url: &str) -> Self {
+        Self {
+            api_base_url: api_base_url.trim_end_matches('/').to_string(),
+            upload_base_url: upload_base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Creates the endpoint from the server root URL(e.x. `http://localhost:8080`) which serves
+    /// the same paths as `https://www.googleapis.com`.
+    pub fn from_root_url(root_url: &str) -> Self {
+        let root_url = root_url.trim_end_matches('/');
+        Self::new(&format!("{}/drive/v3", root_url), &format!("{}/upload/drive/v3", root_url))
+    }
+
+    pub(crate) fn files_url(&self) -> String {
+        format!("{}/files", self.api_base_url)
+    }
+
+    pub(crate) fn file_url(&self, file_id: &str) -> String {
+        format!("{}/files/{}", self.api_base_url, file_id)
+    }
+
+    pub(crate) fn export_url(&self, file_id: &str) -> String {
+        format!("{}/files/{}/export", self.api_base_url, file_id)
+    }
+
+    pub(crate) fn drives_url(&self) -> String {
+        format!("{}/drives", self.api_base_url)
+    }
+
+    pub(crate) fn drive_url(&self, drive_id: &str) -> String {
+        format!("{}/drives/{}", self.api_base_url, drive_id)
+    }
+
+    pub(crate) fn upload_files_url(&self) -> String {
+        format!("{}/files", self.upload_base_url)
+    }
+}
+
+impl Default for GoogleDriveEndpoint {
+    fn default() -> Self {
+        Self::from_root_url("https://www.googleapis.com")
+    }
+}
+
+/// The format which Google Docs are exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocsExportFormat {
+    #[default]
+    Docx,
+    Pdf,
+    Markdown,
+}
+
+impl DocsExportFormat {
+    pub(crate) fn file_type(&self) -> FileType {
+        match self {
+            Self::Docx => FileType::MSWord,
+            Self::Pdf => FileType::Pdf,
+            Self::Markdown => FileType::Markdown,
+        }
+    }
+}
+
+/// The format which Google Sheets are exported to. CSV has only the first sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SheetsExportFormat {
+    #[default]
+    Xlsx,
+    Csv,
+}
+
+impl SheetsExportFormat {
+    pub(crate) fn file_type(&self) -> FileType {
+        match self {
+            Self::Xlsx => FileType::MSExcel,
+            Self::Csv => FileType::Csv,
+        }
+    }
+}
+
+/// The format which Google Slides are exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlidesExportFormat {
+    #[default]
+    Pptx,
+    Pdf,
+}
+
+impl SlidesExportFormat {
+    pub(crate) fn file_type(&self) -> FileType {
+        match self {
+            Self::Pptx => FileType::MSPowerPoint,
+            Self::Pdf => FileType::Pdf,
+        }
+    }
+}
+
+/// The format which Google Drawings are exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrawingsExportFormat {
+    #[default]
+    Png,
+    Svg,
+    Pdf,
+}
+
+impl DrawingsExportFormat {
+    pub(crate) fn file_type(&self) -> FileType {
+        match self {
+            Self::Png => FileType::Png,
+            Self::Svg => FileType::Svg,
+            Self::Pdf => FileType::Pdf,
+        }
+    }
+}
+
+/// The export formats of each type of Google Workspace documents.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GoogleWorkspaceExportFormats {
+    pub(crate) docs: DocsExportFormat,
+    pub(crate) sheets: SheetsExportFormat,
+    pub(crate) slides: SlidesExportFormat,
+    pub(crate) drawings: DrawingsExportFormat,
+}
+
+impl GoogleWorkspaceExportFormats {
+    /// Get the type which the Google Workspace document is exported to,
+    /// or [None] if the file cannot be exported.
+    pub(crate) fn export_type(&self, file_type: FileType) -> Option<FileType> {
+        match file_type {
+            FileType::GoogleDocs => Some(self.docs.file_type()),
+            FileType::GoogleSheets => Some(self.sheets.file_type()),
+            FileType::GoogleSlides => Some(self.slides.file_type()),
+            FileType::GoogleDrawing => Some(self.drawings.file_type()),
+            _ => None,
+        }
+    }
+}
+
+/// How the Google Workspace document is downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoogleDriveExport {
+    pub(crate) file_type: FileType,
+    /// The link of `exportLinks` for the file over the size limit of `files.export`.
+    pub(crate) export_link: Option<String>,
+}
+
+impl GoogleDriveExport {
+    /// Get the filename with the extension of the exported type(e.x. `Report` to `Report.docx`).
+    pub(crate) fn filename(&self, name: &str) -> String {
+        match self.file_type.extension() {
+            Some(extension) if FileType::from_filename(name) != self.file_type => format!("{}.{}", name, extension),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// The conversion of the uploaded file to Google Workspace document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GoogleDriveConversion {
+    pub(crate) source: FileType,
+    pub(crate) target: FileType,
+}
+
+impl GoogleDriveConversion {
+    /// Get the conversion of the file which has the `filename`, or [None] if Google Drive cannot convert it.
+    pub(crate) fn from_filename(filename: &str) -> Option<Self> {
+        let source = FileType::from_filename(filename);
+        source.google_workspace_type().map(|target| Self {
+            source,
+            target,
+        })
+    }
+
+    /// Get the max size of the file which Google Drive can convert to the target type.
+    pub(crate) fn size_limit(&self) -> u64 {
+        match self.target {
+            FileType::GoogleDocs => MAX_DOCS_CONVERSION_SIZE,
+            _ => MAX_SHEETS_AND_SLIDES_CONVERSION_SIZE,
+        }
+    }
+}
+
+/// Google Drive specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct GoogleDriveOptions {
+    pub(crate) endpoint: GoogleDriveEndpoint,
+    pub(crate) export_formats: GoogleWorkspaceExportFormats,
+    /// Whether the shortcut at the end of the path is kept instead of followed to its target.
+    pub(crate) keep_shortcuts: bool,
+    /// Whether the uploaded file is converted to Google Workspace document.
+    pub(crate) convert_to_google_workspace: bool,
+    pub(crate) ambiguity_strategy: AmbiguityStrategy,
+    /// The size of the uploaded file, which is checked against the size limit of the conversion.
+    pub(crate) upload_size: Option<u64>,
+}
+
+/// The file which is one of the files of the same name at a level of the path.
+#[derive(Debug, Clone, Default)]
+pub struct GoogleDriveCandidate {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) owners: Vec<String>,
+    pub(crate) owned_by_me: bool,
+    pub(crate) created_time: Option<SystemTime>,
+    pub(crate) modified_time: Option<SystemTime>,
+}
+
+impl GoogleDriveCandidate {
+    /// Get the id of the file.
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the name of the file.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the email addresses of the owners. The files in the shared drives have no owner.
+    pub fn get_owners(&self) -> &[String] {
+        &self.owners
+    }
+
+    /// Get whether the authorized user owns the file.
+    pub fn is_owned_by_me(&self) -> bool {
+        self.owned_by_me
+    }
+
+    /// Get the created time of the file.
+    pub fn get_created_time(&self) -> Option<SystemTime> {
+        self.created_time
+    }
+
+    /// Get the last modified time of the file.
+    pub fn get_modified_time(&self) -> Option<SystemTime> {
+        self.modified_time
+    }
+}
+
+/// The callback which gets the candidates and returns the index of the chosen one, or [None] to give up.
+pub type AmbiguityCallback = dyn Fn(&[GoogleDriveCandidate]) -> Option<usize> + Send + Sync;
+
+/// How the file is chosen when Google Drive has the files of the same name at a level of the path.
+///
+/// The strategy is applied at every level of the path, so the shared drive of `gds://` and
+/// the intermediate folders are chosen by the same strategy as the last file. The shared drives
+/// have only the created time, so `MostRecentlyModified` and `OwnedByMe` cannot choose one of them.
+#[derive(Clone, Default)]
+pub enum AmbiguityStrategy {
+    /// Fails with `InvalidArgumentError`.
+    #[default]
+    Error,
+    /// Chooses the file modified most recently.
+    MostRecentlyModified,
+    /// Chooses the file created first.
+    OldestCreated,
+    /// Chooses the file which the authorized user owns. Fails if the user owns none or more than one.
+    OwnedByMe,
+    /// Asks the callback, e.x. to let the user choose interactively.
+    Callback(Arc<AmbiguityCallback>),
+}
+
+impl Debug for AmbiguityStrategy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "Error"),
+            Self::MostRecentlyModified => write!(f, "MostRecentlyModified"),
+            Self::OldestCreated => write!(f, "OldestCreated"),
+            Self::OwnedByMe => write!(f, "OwnedByMe"),
+            Self::Callback(_) => write!(f, "Callback"),
+        }
+    }
+}
+
+impl AmbiguityStrategy {
+    /// Create the strategy which asks the `callback`.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - The function which gets the candidates and returns the index of the chosen one,
+    ///   or [None] to fail with `InvalidArgumentError`.
+    pub fn callback(callback: impl Fn(&[GoogleDriveCandidate]) -> Option<usize> + Send + Sync + 'static) -> Self {
+        Self::Callback(Arc::new(callback))
+    }
+
+    /// Get the index of the chosen candidate, or [None] if the strategy cannot choose one.
+    ///
+    /// The candidates of the same time are ordered by the id to choose the same one every time.
+    pub(crate) fn choose(&self, candidates: &[GoogleDriveCandidate]) -> Option<usize> {
+        let indexed = || candidates.iter().enumerate();
+        match self {
+            Self::Error => None,
+            Self::MostRecentlyModified => indexed()
+                .filter(|(_, candidate)| candidate.modified_time.is_some())
+                .max_by(|(_, a), (_, b)| a.modified_time.cmp(&b.modified_time).then_with(|| b.id.cmp(&a.id)))
+                .map(|(index, _)| index),
+            Self::OldestCreated => indexed()
+                .filter(|(_, candidate)| candidate.created_time.is_some())
+                .min_by(|(_, a), (_, b)| a.created_time.cmp(&b.created_time).then_with(|| a.id.cmp(&b.id)))
+                .map(|(index, _)| index),
+            Self::OwnedByMe => {
+                let mut owned = indexed().filter(|(_, candidate)| candidate.owned_by_me);
+                match (owned.next(), owned.next()) {
+                    (Some((index, _)), None) => Some(index),
+                    _ => None,
+                }
+            },
+            Self::Callback(callback) => callback(candidates).filter(|index| *index < candidates.len()),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct DriveOwner {
+    #[serde(rename = "emailAddress", default)]
+    pub(crate) email_address: String,
+}
+
+/// The target of a Google Drive shortcut.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GoogleDriveShortcut {
+    #[serde(rename = "targetId")]
+    pub(crate) target_id: String,
+    #[serde(rename = "targetMimeType", default)]
+    pub(crate) target_mime_type: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct SharedDriveInfo {
     pub(crate) id: String,
+    #[serde(default)]
+    pub(crate) name: String,
+    #[serde(rename = "createdTime")]
+    pub(crate) created_time: Option<String>,
+}
+
+impl SharedDriveInfo {
+    pub(crate) fn candidate(&self) -> GoogleDriveCandidate {
+        GoogleDriveCandidate {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            created_time: parse_drive_time(&self.created_time),
+            ..GoogleDriveCandidate::default()
+        }
+    }
+}
+
+/// Parse the time of Google Drive API, which is returned in RFC 3339.
+fn parse_drive_time(time: &Option<String>) -> Option<SystemTime> {
+    time
+        .as_ref()
+        .and_then(|time| OffsetDateTime::parse(time, &Rfc3339).ok())
+        .map(SystemTime::from)
 }
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct SharedDriveQueryResponse {
+    #[serde(default)]
     drives: Vec<SharedDriveInfo>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
 }
 
 impl SharedDriveQueryResponse {
-    pub(crate) fn is_empty(&self) -> bool {
-        self.drives.is_empty()
-    }
-
     pub(crate) fn get_drives(&self) -> &[SharedDriveInfo] {
         &self.drives
     }
+
+    /// Get the token of the next page, or [None] if this is the last page.
+    pub(crate) fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -78,6 +458,20 @@ pub(crate) struct DriveFileInfo {
     pub(crate) mime_type: String,
     size: Option<String>,
     pub(crate) name: String,
+    /// The export MIME type -> the download link. Only Google Workspace documents have them.
+    #[serde(rename = "exportLinks", default)]
+    pub(crate) export_links: HashMap<String, String>,
+    /// Only shortcuts have it.
+    #[serde(rename = "shortcutDetails")]
+    pub(crate) shortcut_details: Option<GoogleDriveShortcut>,
+    #[serde(rename = "createdTime")]
+    pub(crate) created_time: Option<String>,
+    #[serde(rename = "modifiedTime")]
+    pub(crate) modified_time: Option<String>,
+    #[serde(rename = "ownedByMe", default)]
+    pub(crate) owned_by_me: bool,
+    #[serde(default)]
+    pub(crate) owners: Vec<DriveOwner>,
 }
 
 impl DriveFileInfo {
@@ -86,17 +480,137 @@ impl DriveFileInfo {
             .as_ref()
             .map(|s| s.parse::<i64>().unwrap_or(-1))
     }
+
+    pub(crate) fn candidate(&self) -> GoogleDriveCandidate {
+        GoogleDriveCandidate {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            owners: self.owners.iter().map(|owner| owner.email_address.clone()).collect(),
+            owned_by_me: self.owned_by_me,
+            created_time: parse_drive_time(&self.created_time),
+            modified_time: parse_drive_time(&self.modified_time),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct DriveFileQueryResponse {
-    files: Vec<DriveFileInfo>
+    #[serde(default)]
+    files: Vec<DriveFileInfo>,
+    #[serde(rename = "nextPageToken")]
+    next_page_token: Option<String>,
 }
 
 impl DriveFileQueryResponse {
     pub(crate) fn files(&self) -> &[DriveFileInfo] {
         &self.files
     }
+
+    /// Get the token of the next page, or [None] if this is the last page.
+    pub(crate) fn next_page_token(&self) -> Option<&str> {
+        self.next_page_token.as_deref()
+    }
+}
+
+/// Escape the value of the query of Google Drive API to put it in the single quotes.
+pub(crate) fn escape_query_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// The typed query of `files.list` of Google Drive API.
+///
+/// The values are escaped, so the names which have an apostrophe or a backslash match exactly.
+/// The trashed files are excluded unless [DriveQuery::set_include_trashed] is set.
+///
+/// # Example
+///
+/// ```rust
+/// use hikyaku::types::google_drive::DriveQuery;
+///
+/// let query = DriveQuery::new()
+///     .set_name("Tom's notes")
+///     .set_parents(&["folder_id"])
+///     .set_mime_type("text/csv")
+///     .build();
+///
+/// assert_eq!(query, "name = 'Tom\\'s notes' and ('folder_id' in parents) and mimeType = 'text/csv' and trashed = false");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DriveQuery {
+    clauses: Vec<String>,
+    include_trashed: bool,
+}
+
+impl DriveQuery {
+    /// Create the query which matches all files which are not trashed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match the file which has the `name`.
+    pub fn set_name(mut self, name: &str) -> Self {
+        self.clauses.push(format!("name = '{}'", escape_query_value(name)));
+        self
+    }
+
+    /// Match the file in any of the `parent_ids`. Nothing is added if the `parent_ids` is empty.
+    pub fn set_parents(mut self, parent_ids: &[&str]) -> Self {
+        if !parent_ids.is_empty() {
+            let parents = parent_ids
+                .iter()
+                .map(|id| format!("'{}' in parents", escape_query_value(id)))
+                .collect::<Vec<_>>();
+            self.clauses.push(format!("({})", parents.join(" or ")));
+        }
+        self
+    }
+
+    /// Match the file which has the `mime_type`(e.x. `application/vnd.google-apps.folder`).
+    pub fn set_mime_type(mut self, mime_type: &str) -> Self {
+        self.clauses.push(format!("mimeType = '{}'", escape_query_value(mime_type)));
+        self
+    }
+
+    /// Match the file modified after the `time`.
+    pub fn set_modified_after(mut self, time: SystemTime) -> Self {
+        self.clauses.push(format!("modifiedTime > '{}'", format_query_time(time)));
+        self
+    }
+
+    /// Match the file modified before the `time`.
+    pub fn set_modified_before(mut self, time: SystemTime) -> Self {
+        self.clauses.push(format!("modifiedTime < '{}'", format_query_time(time)));
+        self
+    }
+
+    /// Match the file which the user of the `email` owns.
+    pub fn set_owner(mut self, email: &str) -> Self {
+        self.clauses.push(format!("'{}' in owners", escape_query_value(email)));
+        self
+    }
+
+    /// Match the trashed files too. The trashed files are excluded by default.
+    pub fn set_include_trashed(mut self, include_trashed: bool) -> Self {
+        self.include_trashed = include_trashed;
+        self
+    }
+
+    /// Get the query statement joined by `and`.
+    pub fn build(&self) -> String {
+        let mut clauses = self.clauses.clone();
+        if !self.include_trashed {
+            clauses.push("trashed = false".to_string());
+        }
+
+        clauses.join(" and ")
+    }
+}
+
+/// Format the time in RFC 3339 of UTC which the query of Google Drive API accepts.
+fn format_query_time(time: SystemTime) -> String {
+    let time = OffsetDateTime::from(time);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            time.year(), u8::from(time.month()), time.day(), time.hour(), time.minute(), time.second())
 }
 
 #[derive(Debug)]
@@ -104,6 +618,9 @@ pub(crate) struct GoogleDriveFile {
     id: String,
     mime_type: FileType,
     size: Option<u64>,
+    export_links: HashMap<String, String>,
+    shortcut: Option<GoogleDriveShortcut>,
+    candidate: GoogleDriveCandidate,
 }
 
 impl GoogleDriveFile {
@@ -114,21 +631,68 @@ impl GoogleDriveFile {
             id: id.to_string(),
             mime_type,
             size,
+            export_links: HashMap::new(),
+            shortcut: None,
+            candidate: GoogleDriveCandidate {
+                id: id.to_string(),
+                ..GoogleDriveCandidate::default()
+            },
+        }
+    }
+
+    /// Create from the metadata of the shared drive returned by Google Drive API.
+    pub(crate) fn from_shared_drive(info: &SharedDriveInfo) -> Self {
+        let mut file = Self::new(&info.id, "application/vnd.google-apps.folder", None);
+        file.candidate = info.candidate();
+
+        file
+    }
+
+    /// Create from the metadata of the file returned by Google Drive API.
+    pub(crate) fn from_info(info: &DriveFileInfo, size: Option<u64>) -> Self {
+        let mut file = Self::new(&info.id, &info.mime_type, size).with_export_links(info.export_links.clone());
+        if file.mime_type == FileType::GoogleShortcut {
+            file.shortcut = info.shortcut_details.clone();
         }
+        file.candidate = info.candidate();
+
+        file
+    }
+
+    pub(crate) fn with_export_links(mut self, export_links: HashMap<String, String>) -> Self {
+        self.export_links = export_links;
+        self
     }
 
     pub (crate) fn is_invalid(&self) -> bool {
         matches!(self.mime_type, FileType::GoogleShortcut | FileType::GoogleDriveThirdPartyShortcut)
     }
 
+    /// Get the target of the shortcut, or [None] if the file is not shortcut.
+    pub(crate) fn get_shortcut(&self) -> Option<&GoogleDriveShortcut> {
+        self.shortcut.as_ref()
+    }
+
     pub(crate) fn is_google_workspace_file(&self) -> bool {
-        matches!(self.mime_type, FileType::GoogleDriveFile)
+        self.mime_type.is_google_apps() && self.mime_type != FileType::GoogleDriveFolder
+    }
+
+    /// Get how the file is exported to the type, or [None] if the file is not Google Workspace document.
+    pub(crate) fn export(&self, formats: &GoogleWorkspaceExportFormats) -> Option<GoogleDriveExport> {
+        formats.export_type(self.mime_type).map(|file_type| GoogleDriveExport {
+            file_type,
+            export_link: self.export_links.get(file_type.mime()).cloned(),
+        })
     }
 
     pub(crate) fn get_id(&self) -> &str {
         &self.id
     }
 
+    pub(crate) fn get_candidate(&self) -> &GoogleDriveCandidate {
+        &self.candidate
+    }
+
     pub(crate) fn get_mime(&self) -> &str {
         self.mime_type.mime()
     }
@@ -148,3 +712,45 @@ impl FileId {
         self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drive_query() {
+        let query = DriveQuery::new()
+            .set_name("it's a \\ path")
+            .set_parents(&["a", "b"])
+            .set_owner("me@example.com")
+            .set_modified_after(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000))
+            .build();
+        assert_eq!(query, "name = 'it\\'s a \\\\ path' and ('a' in parents or 'b' in parents) and 'me@example.com' in owners and modifiedTime > '2023-11-14T22:13:20Z' and trashed = false");
+
+        assert_eq!(DriveQuery::new().set_parents(&[]).set_include_trashed(true).build(), "");
+    }
+
+    #[test]
+    fn test_google_drive_export() {
+        let formats = GoogleWorkspaceExportFormats {
+            docs: DocsExportFormat::Markdown,
+            ..GoogleWorkspaceExportFormats::default()
+        };
+        let links = HashMap::from([("text/markdown".to_string(), "https://docs.google.com/export?format=md".to_string())]);
+        let docs = GoogleDriveFile::new("id", "application/vnd.google-apps.document", None).with_export_links(links);
+        let export = docs.export(&formats).unwrap();
+        assert_eq!(export.file_type, FileType::Markdown);
+        assert_eq!(export.export_link.as_deref(), Some("https://docs.google.com/export?format=md"));
+        assert_eq!(export.filename("Report"), "Report.md");
+        assert_eq!(export.filename("README.md"), "README.md");
+
+        let sheets = GoogleDriveFile::new("id", "application/vnd.google-apps.spreadsheet", None);
+        let export = sheets.export(&formats).unwrap();
+        assert_eq!((export.filename("Budget 2025.v2"), export.export_link), ("Budget 2025.v2.xlsx".to_string(), None));
+
+        let form = GoogleDriveFile::new("id", "application/vnd.google-apps.form", None);
+        assert!(form.export(&formats).is_none());
+        assert!(form.is_google_workspace_file());
+        assert!(!GoogleDriveFile::new("id", "application/vnd.google-apps.folder", None).is_google_workspace_file());
+    }
+}
@@ -0,0 +1,66 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::errors::HikyakuError::InvalidArgumentError;
+use crate::errors::HikyakuResult;
+
+/// HTTP(S) specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct HttpOptions {
+    /// The headers which are sent on every request(e.x. the API key of the dataset server).
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+impl HttpOptions {
+    /// Convert the headers to the header map.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the name or the value is not allowed in the header.
+    pub(crate) fn header_map(&self) -> HikyakuResult<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| InvalidArgumentError(format!("Header name {} is invalid: {:?}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| InvalidArgumentError(format!("Header value of {} is invalid: {:?}", name, e)))?;
+            headers.append(name, value);
+        }
+
+        Ok(headers)
+    }
+}
+
+/// Get the complete length from `Content-Range: bytes {start}-{end}/{length}`.
+/// The unknown length(`*`) is [None].
+pub(crate) fn parse_content_range_length(content_range: &str) -> Option<u64> {
+    let (unit, range) = content_range.trim().split_once(' ')?;
+    if unit != "bytes" {
+        return None;
+    }
+    range.split_once('/')?.1.parse::<u64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_content_range_length() {
+        assert_eq!(parse_content_range_length("bytes 0-0/1000"), Some(1000));
+        assert_eq!(parse_content_range_length("bytes */1000"), Some(1000));
+        assert_eq!(parse_content_range_length("bytes 0-0/*"), None);
+        assert_eq!(parse_content_range_length("items 0-0/1000"), None);
+    }
+
+    #[test]
+    fn test_header_map() {
+        let options = HttpOptions {
+            headers: vec![("X-Api-Key".to_string(), "hikyaku".to_string())],
+        };
+        assert_eq!(options.header_map().unwrap().get("x-api-key").unwrap(), "hikyaku");
+
+        let options = HttpOptions {
+            headers: vec![("X Api Key".to_string(), "hikyaku".to_string())],
+        };
+        assert!(matches!(options.header_map(), Err(InvalidArgumentError(_))));
+    }
+}
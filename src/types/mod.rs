@@ -1,4 +1,23 @@
+#[cfg(feature = "amazon_s3")]
+pub mod amazon_s3;
+#[cfg(feature = "google_drive")]
 pub mod google_drive;
+#[cfg(feature = "dropbox")]
+pub mod dropbox;
+#[cfg(feature = "box_storage")]
+pub mod box_storage;
+#[cfg(feature = "onedrive")]
+pub mod onedrive;
+#[cfg(feature = "azure_blob")]
+pub mod azure_blob;
+#[cfg(feature = "google_cloud_storage")]
+pub mod google_cloud_storage;
+#[cfg(feature = "sftp")]
+pub mod sftp;
+#[cfg(feature = "webdav")]
+pub mod webdav;
+#[cfg(feature = "http")]
+pub mod http;
 
 pub trait FileInfo {
     /// Get prefix(e.x. `s3://`, `file://`, and so)
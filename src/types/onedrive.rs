@@ -0,0 +1,193 @@
+use std::fmt::{Debug, Formatter};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::Client;
+use serde::Deserialize;
+
+// The path segments are put in the URL as `root:/{path}:`, so the reserved characters are escaped.
+const GRAPH_PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// The unit of the fragment size of the upload session. Every fragment except the last
+/// must be a multiple of 320 KiB.
+pub(crate) const UPLOAD_FRAGMENT_UNIT: u64 = 320 * 1024;
+// The upload session accepts up to 60 MiB per request.
+const MAX_UPLOAD_FRAGMENT_SIZE: u64 = 60 * 1024 * 1024;
+
+/// Base URL of the Microsoft Graph API.
+///
+/// The default points to `https://graph.microsoft.com/v1.0`.
+/// This can be replaced with a mock server or a national cloud(e.x. `https://microsoftgraph.chinacloudapi.cn/v1.0`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OneDriveEndpoint {
+    base_url: String,
+}
+
+impl OneDriveEndpoint {
+    /// Creates the endpoint from the Graph API base URL(e.x. `https://graph.microsoft.com/v1.0`).
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub(crate) fn my_drive_url(&self) -> String {
+        format!("{}/me/drive", self.base_url)
+    }
+
+    pub(crate) fn sites_url(&self) -> String {
+        format!("{}/sites", self.base_url)
+    }
+
+    pub(crate) fn site_drive_url(&self, site_id: &str) -> String {
+        format!("{}/sites/{}/drive", self.base_url, site_id)
+    }
+
+    pub(crate) fn site_drives_url(&self, site_id: &str) -> String {
+        format!("{}/sites/{}/drives", self.base_url, site_id)
+    }
+
+    /// Get the URL of the item at the path in the drive, or of its `action`(e.x. `content`, `children`).
+    ///
+    /// The empty path points to the root folder of the drive.
+    pub(crate) fn item_url(&self, drive_id: &str, path: &str, action: Option<&str>) -> String {
+        let item_url = if path.is_empty() {
+            format!("{}/drives/{}/root", self.base_url, drive_id)
+        } else {
+            format!("{}/drives/{}/root:/{}", self.base_url, drive_id, encode_path(path))
+        };
+
+        match action {
+            Some(action) if path.is_empty() => format!("{}/{}", item_url, action),
+            Some(action) => format!("{}:/{}", item_url, action),
+            None => item_url,
+        }
+    }
+}
+
+impl Default for OneDriveEndpoint {
+    fn default() -> Self {
+        Self::new("https://graph.microsoft.com/v1.0")
+    }
+}
+
+/// OneDrive specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct OneDriveOptions {
+    pub(crate) endpoint: OneDriveEndpoint,
+    pub(crate) drive_id: Option<String>,
+    pub(crate) document_library: Option<String>,
+    pub(crate) upload_size: Option<u64>,
+}
+
+/// The driveItem resource which has only the fields used in this crate.
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct OneDriveItem {
+    pub(crate) name: String,
+    pub(crate) size: Option<u64>,
+    // The facet which exists only on the folders.
+    pub(crate) folder: Option<serde_json::Value>,
+}
+
+impl OneDriveItem {
+    pub(crate) fn is_folder(&self) -> bool {
+        self.folder.is_some()
+    }
+}
+
+/// A page of the collection response of Microsoft Graph.
+#[derive(Deserialize, Debug)]
+pub(crate) struct OneDriveCollection<T> {
+    pub(crate) value: Vec<T>,
+    #[serde(rename = "@odata.nextLink")]
+    pub(crate) next_link: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct OneDriveDrive {
+    pub(crate) id: String,
+    pub(crate) name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct OneDriveSite {
+    pub(crate) id: String,
+    pub(crate) name: Option<String>,
+    #[serde(rename = "displayName")]
+    pub(crate) display_name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct OneDriveUploadSessionResponse {
+    #[serde(rename = "uploadUrl")]
+    pub(crate) upload_url: String,
+}
+
+/// The state of the upload which is kept between the chunks.
+///
+/// `createUploadSession` needs the file size in every fragment, so the chunks are kept in
+/// `Buffered` until the last chunk if the size is not specified.
+pub enum OneDriveUpload {
+    Buffered(Vec<u8>),
+    Session {
+        // The upload URL is pre-authenticated, so the client must not send the token.
+        client: Client,
+        upload_url: String,
+        file_size: u64,
+        buffer: Vec<u8>,
+        uploaded_size: u64,
+    },
+}
+
+impl Debug for OneDriveUpload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OneDriveUpload::Buffered(buffer) => write!(f, "OneDriveUpload::Buffered({} bytes)", buffer.len()),
+            OneDriveUpload::Session {uploaded_size, file_size, ..} => {
+                // The upload URL is not shown because it works as a credential.
+                write!(f, "OneDriveUpload::Session {{ uploaded: {}/{} }}", uploaded_size, file_size)
+            },
+        }
+    }
+}
+
+/// Percent-encode each segment of the path.
+pub(crate) fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| utf8_percent_encode(segment, GRAPH_PATH_ENCODE_SET).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Get the fragment size of the upload session from the chunk size.
+///
+/// The fragment is the largest multiple of 320 KiB within the chunk size and 60 MiB,
+/// and at least 320 KiB.
+pub(crate) fn fragment_size(chunk_size: u64) -> u64 {
+    let size = chunk_size.min(MAX_UPLOAD_FRAGMENT_SIZE) / UPLOAD_FRAGMENT_UNIT * UPLOAD_FRAGMENT_UNIT;
+    size.max(UPLOAD_FRAGMENT_UNIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_item_url() {
+        let endpoint = OneDriveEndpoint::new("https://graph.microsoft.com/v1.0/");
+        assert_eq!(endpoint.item_url("d1", "", None), "https://graph.microsoft.com/v1.0/drives/d1/root");
+        assert_eq!(endpoint.item_url("d1", "", Some("children")), "https://graph.microsoft.com/v1.0/drives/d1/root/children");
+        assert_eq!(
+            endpoint.item_url("d1", "data sets/train#1.csv", Some("content")),
+            "https://graph.microsoft.com/v1.0/drives/d1/root:/data%20sets/train%231.csv:/content");
+    }
+
+    #[test]
+    fn test_fragment_size() {
+        assert_eq!(fragment_size(8 * 1024 * 1024), 25 * UPLOAD_FRAGMENT_UNIT);
+        assert_eq!(fragment_size(1024), UPLOAD_FRAGMENT_UNIT);
+        assert_eq!(fragment_size(1024 * 1024 * 1024), 192 * UPLOAD_FRAGMENT_UNIT);
+    }
+}
@@ -0,0 +1,397 @@
+use std::fmt::{Debug, Formatter};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use log::{error, warn};
+use ssh2::{CheckResult, ErrorCode, HostKeyType, KnownHostFileKind, KnownHosts, Session, Sftp};
+use crate::errors::HikyakuError::{ConnectionError, InvalidArgumentError, SftpError, UnknownError};
+use crate::errors::HikyakuResult;
+use crate::utils::credential::sftp_credential::SftpAuth;
+
+pub(crate) const DEFAULT_SFTP_PORT: u16 = 22;
+// The blocking call on the dead connection waits forever without the timeout.
+const SFTP_TIMEOUT_MILLIS: u32 = 60_000;
+// `LIBSSH2_FX_NO_SUCH_FILE` of the SFTP status code.
+const SFTP_NO_SUCH_FILE: i32 = 2;
+// The permissions of the created directory and file before the umask of the server.
+const DIRECTORY_MODE: i32 = 0o755;
+pub(crate) const FILE_MODE: i32 = 0o644;
+
+/// The user, the host and the port of `sftp://user@host[:port]/path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SftpAuthority {
+    pub(crate) user: String,
+    pub(crate) host: String,
+    pub(crate) port: u16,
+}
+
+impl SftpAuthority {
+    /// Parse the namespace of the path. The IPv6 address is written in the brackets like `[::1]:22`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the user or the host is missing, or the port is not a number.
+    pub(crate) fn parse(authority: &str) -> HikyakuResult<Self> {
+        let (user, host_port) = authority
+            .rsplit_once('@')
+            .filter(|(user, host)| !user.is_empty() && !host.is_empty())
+            .ok_or_else(|| InvalidArgumentError(format!("SFTP path must have user@host: {}", authority)))?;
+
+        let (host, port) = match host_port.strip_prefix('[') {
+            Some(bracketed) => {
+                let (host, rest) = bracketed
+                    .split_once(']')
+                    .ok_or_else(|| InvalidArgumentError(format!("IPv6 address is not closed: {}", authority)))?;
+                (host, rest.strip_prefix(':'))
+            },
+            None => match host_port.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            },
+        };
+        let port = match port {
+            Some(port) => port
+                .parse::<u16>()
+                .map_err(|e| InvalidArgumentError(format!("Port of {} is invalid: {:?}", authority, e)))?,
+            None => DEFAULT_SFTP_PORT,
+        };
+        if host.is_empty() {
+            return Err(InvalidArgumentError(format!("SFTP path must have user@host: {}", authority)));
+        }
+
+        Ok(Self {
+            user: user.to_string(),
+            host: host.to_string(),
+            port,
+        })
+    }
+
+    /// The host name in the known_hosts file. The non-default port is written like `[host]:port`.
+    pub(crate) fn known_hosts_name(&self) -> String {
+        if self.port == DEFAULT_SFTP_PORT {
+            self.host.clone()
+        } else {
+            format!("[{}]:{}", self.host, self.port)
+        }
+    }
+}
+
+/// How the host key of the server is verified with the known_hosts file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SftpHostKeyCheck {
+    /// Connects only to the server whose key is in the known_hosts file.
+    #[default]
+    Strict,
+    /// Adds the key of the unknown server to the known_hosts file like `StrictHostKeyChecking=accept-new`.
+    /// The server whose key is changed is still refused.
+    AcceptNew,
+}
+
+/// SFTP specific settings of the builder.
+#[derive(Debug, Clone, Default)]
+pub struct SftpOptions {
+    /// The known_hosts file. [None] means `~/.ssh/known_hosts`.
+    pub(crate) known_hosts_file: Option<PathBuf>,
+    pub(crate) host_key_check: SftpHostKeyCheck,
+}
+
+impl SftpOptions {
+    fn get_known_hosts_file(&self) -> HikyakuResult<PathBuf> {
+        match &self.known_hosts_file {
+            Some(path) => Ok(path.clone()),
+            None => std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".ssh").join("known_hosts"))
+                .ok_or_else(|| InvalidArgumentError("HOME is not set, so known_hosts file cannot be found".to_string())),
+        }
+    }
+}
+
+/// Convert the path of `sftp://` to the path on the server.
+/// The path starting with `~/` is relative to the home directory, and others are absolute.
+pub(crate) fn remote_path(path: &str) -> PathBuf {
+    match path {
+        "~" => PathBuf::from("."),
+        _ => match path.strip_prefix("~/") {
+            Some(relative) => PathBuf::from(relative),
+            None => PathBuf::from(format!("/{}", path)),
+        },
+    }
+}
+
+pub(crate) fn sftp_error(message: &str, e: ssh2::Error) -> crate::errors::HikyakuError {
+    error!("{}: {}", message, e);
+    SftpError(format!("{}: {}", message, e))
+}
+
+/// Whether the error is that the file does not exist on the server.
+pub(crate) fn is_not_found(e: &ssh2::Error) -> bool {
+    e.code() == ErrorCode::SFTP(SFTP_NO_SUCH_FILE)
+}
+
+/// Create the directory and its missing parents like `mkdir -p`. The existing directory is left as is.
+///
+/// # Errors
+///
+/// Returns an `SftpError` if the directory cannot be created, or the path exists as a file.
+pub(crate) fn create_dirs(sftp: &Sftp, dir: &Path) -> HikyakuResult<()> {
+    let mut ancestors = dir
+        .ancestors()
+        .filter(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.parent().is_some())
+        .collect::<Vec<_>>();
+    ancestors.reverse();
+
+    for ancestor in ancestors {
+        match sftp.stat(ancestor) {
+            Ok(stat) if stat.is_dir() => continue,
+            Ok(_) => return Err(SftpError(format!("{} is not a directory", ancestor.display()))),
+            Err(e) if is_not_found(&e) => {
+                sftp.mkdir(ancestor, DIRECTORY_MODE)
+                    .map_err(|e| sftp_error(&format!("Failed to create {}", ancestor.display()), e))?;
+            },
+            Err(e) => return Err(sftp_error(&format!("Failed to stat {}", ancestor.display()), e)),
+        }
+    }
+
+    Ok(())
+}
+
+fn key_type_name(key_type: HostKeyType) -> Option<&'static str> {
+    match key_type {
+        HostKeyType::Rsa => Some("ssh-rsa"),
+        HostKeyType::Dss => Some("ssh-dss"),
+        HostKeyType::Ecdsa256 => Some("ecdsa-sha2-nistp256"),
+        HostKeyType::Ecdsa384 => Some("ecdsa-sha2-nistp384"),
+        HostKeyType::Ecdsa521 => Some("ecdsa-sha2-nistp521"),
+        HostKeyType::Ed25519 => Some("ssh-ed25519"),
+        HostKeyType::Unknown => None,
+    }
+}
+
+/// Verify the host key of the server with the known_hosts file.
+///
+/// # Arguments
+///
+/// * `known_hosts` - The known hosts collection of the session.
+/// * `known_hosts_file` - The known_hosts file. The missing file is treated as empty.
+/// * `authority` - The host and the port of the server.
+/// * `key` - The host key which the server sent.
+/// * `key_type` - The type of the host key.
+/// * `host_key_check` - How the unknown host is treated.
+///
+/// # Errors
+///
+/// Returns an `SftpError` if the key is changed, or the host is unknown in [SftpHostKeyCheck::Strict].
+pub(crate) fn verify_host_key(known_hosts: &mut KnownHosts,
+                              known_hosts_file: &Path,
+                              authority: &SftpAuthority,
+                              key: &[u8],
+                              key_type: HostKeyType,
+                              host_key_check: SftpHostKeyCheck) -> HikyakuResult<()> {
+    if known_hosts_file.exists() {
+        known_hosts
+            .read_file(known_hosts_file, KnownHostFileKind::OpenSSH)
+            .map_err(|e| sftp_error(&format!("Failed to read {}", known_hosts_file.display()), e))?;
+    }
+
+    match known_hosts.check_port(&authority.host, authority.port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => Err(SftpError(format!(
+            "Host key of {} does not match the one in {}. It may be a man-in-the-middle attack",
+            authority.known_hosts_name(), known_hosts_file.display()))),
+        CheckResult::NotFound if host_key_check == SftpHostKeyCheck::AcceptNew => {
+            let key_type_name = key_type_name(key_type)
+                .ok_or_else(|| SftpError(format!("Host key type of {} is unknown", authority.known_hosts_name())))?;
+            warn!("Add the host key of {} to {}", authority.known_hosts_name(), known_hosts_file.display());
+
+            // Append the line like OpenSSH instead of rewriting the file, so the existing lines are kept as is.
+            if let Some(parent) = known_hosts_file.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| SftpError(format!("Failed to create {}: {:?}", parent.display(), e)))?;
+            }
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(known_hosts_file)
+                .map_err(|e| SftpError(format!("Failed to open {}: {:?}", known_hosts_file.display(), e)))?;
+            writeln!(file, "{} {} {}", authority.known_hosts_name(), key_type_name, STANDARD.encode(key))
+                .map_err(|e| SftpError(format!("Failed to write {}: {:?}", known_hosts_file.display(), e)))
+        },
+        CheckResult::NotFound => Err(SftpError(format!(
+            "Host key of {} is not found in {}", authority.known_hosts_name(), known_hosts_file.display()))),
+        CheckResult::Failure => Err(SftpError(format!(
+            "Failed to check the host key of {}", authority.known_hosts_name()))),
+    }
+}
+
+/// Everything to open a new connection to the server.
+pub(crate) struct SftpConnector {
+    authority: SftpAuthority,
+    auth: SftpAuth,
+    options: SftpOptions,
+}
+
+impl SftpConnector {
+    pub(crate) fn new(authority: SftpAuthority, auth: SftpAuth, options: SftpOptions) -> Self {
+        Self {
+            authority,
+            auth,
+            options,
+        }
+    }
+
+    /// Open the SSH session, verify the host key, authenticate the user and start the SFTP subsystem.
+    fn connect(&self) -> HikyakuResult<(Session, Sftp)> {
+        let authority = &self.authority;
+        let stream = TcpStream::connect((authority.host.as_str(), authority.port))
+            .map_err(|e| {
+                error!("Failed to connect to {}: {:?}", authority.known_hosts_name(), e);
+                ConnectionError(format!("Failed to connect to {}: {:?}", authority.known_hosts_name(), e))
+            })?;
+
+        let mut session = Session::new().map_err(|e| sftp_error("Failed to create the session", e))?;
+        session.set_timeout(SFTP_TIMEOUT_MILLIS);
+        session.set_tcp_stream(stream);
+        session
+            .handshake()
+            .map_err(|e| sftp_error(&format!("Failed to handshake with {}", authority.known_hosts_name()), e))?;
+
+        let (key, key_type) = session
+            .host_key()
+            .ok_or_else(|| SftpError(format!("{} sent no host key", authority.known_hosts_name())))?;
+        let mut known_hosts = session
+            .known_hosts()
+            .map_err(|e| sftp_error("Failed to initialize the known hosts", e))?;
+        verify_host_key(&mut known_hosts,
+                        &self.options.get_known_hosts_file()?,
+                        authority,
+                        key,
+                        key_type,
+                        self.options.host_key_check)?;
+
+        self.auth.authenticate(&session, &authority.user)?;
+        let sftp = session
+            .sftp()
+            .map_err(|e| sftp_error("Failed to start the sftp subsystem", e))?;
+
+        Ok((session, sftp))
+    }
+}
+
+/// The connection(the SSH session and its SFTP channel) which is opened on the first use.
+///
+/// The file system object has the connections as many as the concurrency,
+/// so the chunks are transferred in parallel over the sessions.
+pub struct SftpConnection {
+    connector: Arc<SftpConnector>,
+    channel: Mutex<Option<(Session, Sftp)>>,
+}
+
+impl Debug for SftpConnection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SftpConnection {{ authority: {:?} }}", self.connector.authority)
+    }
+}
+
+impl SftpConnection {
+    pub(crate) fn new(connector: Arc<SftpConnector>) -> Self {
+        Self {
+            connector,
+            channel: Mutex::new(None),
+        }
+    }
+
+    /// Run the blocking SFTP operation on the blocking thread pool.
+    /// The connection is opened if it is not opened yet, and it is closed if the operation fails
+    /// so that the next operation reconnects.
+    pub(crate) async fn run<T, F>(self: &Arc<Self>, operation: F) -> HikyakuResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Sftp) -> HikyakuResult<T> + Send + 'static,
+    {
+        let connection = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            let mut channel = connection
+                .channel
+                .lock()
+                .map_err(|e| UnknownError(format!("SFTP connection is poisoned: {}", e)))?;
+            if channel.is_none() {
+                *channel = Some(connection.connector.connect()?);
+            }
+
+            // SAFETY: The channel is always set above.
+            let (_, sftp) = channel.as_ref().unwrap();
+            let result = operation(sftp);
+            if matches!(result, Err(SftpError(_))) {
+                *channel = None;
+            }
+            result
+        })
+            .await
+            .map_err(|e| UnknownError(format!("SFTP task is failed: {:?}", e)))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_authority() {
+        let authority = SftpAuthority::parse("hikyaku@example.com").unwrap();
+        assert_eq!(authority, SftpAuthority { user: "hikyaku".to_string(), host: "example.com".to_string(), port: 22 });
+        assert_eq!(authority.known_hosts_name(), "example.com");
+
+        let authority = SftpAuthority::parse("hikyaku@example.com:2222").unwrap();
+        assert_eq!(authority.port, 2222);
+        assert_eq!(authority.known_hosts_name(), "[example.com]:2222");
+
+        let authority = SftpAuthority::parse("hikyaku@[::1]:2222").unwrap();
+        assert_eq!((authority.host.as_str(), authority.port), ("::1", 2222));
+
+        assert!(SftpAuthority::parse("example.com").is_err());
+        assert!(SftpAuthority::parse("hikyaku@").is_err());
+        assert!(SftpAuthority::parse("hikyaku@example.com:ssh").is_err());
+    }
+
+    #[test]
+    fn test_remote_path() {
+        assert_eq!(remote_path("~/titanic/train.csv"), PathBuf::from("titanic/train.csv"));
+        assert_eq!(remote_path("~"), PathBuf::from("."));
+        assert_eq!(remote_path("var/data/train.csv"), PathBuf::from("/var/data/train.csv"));
+        assert_eq!(remote_path(""), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn test_verify_host_key() {
+        let dir = std::env::temp_dir().join(format!("hikyaku_known_hosts_{}", std::process::id()));
+        let known_hosts_file = dir.join("known_hosts");
+        let authority = SftpAuthority::parse("hikyaku@localhost:2222").unwrap();
+        let key = (0..=255u8).collect::<Vec<_>>();
+        let other_key = (0..=255u8).rev().collect::<Vec<_>>();
+        let session = Session::new().unwrap();
+        let verify = |key: &[u8], check| {
+            let mut known_hosts = session.known_hosts().unwrap();
+            verify_host_key(&mut known_hosts, &known_hosts_file, &authority, key, HostKeyType::Ed25519, check)
+        };
+
+        // The unknown host is refused in the strict mode.
+        assert!(matches!(verify(&key, SftpHostKeyCheck::Strict), Err(SftpError(_))));
+        assert!(!known_hosts_file.exists());
+
+        // The unknown host is added once, then it is known.
+        verify(&key, SftpHostKeyCheck::AcceptNew).unwrap();
+        let content = std::fs::read_to_string(&known_hosts_file).unwrap();
+        assert_eq!(content, format!("[localhost]:2222 ssh-ed25519 {}\n", STANDARD.encode(&key)));
+        verify(&key, SftpHostKeyCheck::Strict).unwrap();
+
+        // The changed key is always refused.
+        assert!(matches!(verify(&other_key, SftpHostKeyCheck::AcceptNew), Err(SftpError(_))));
+        assert_eq!(std::fs::read_to_string(&known_hosts_file).unwrap(), content);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
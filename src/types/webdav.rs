@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use xmlparser::{ElementEnd, Token, Tokenizer};
+use crate::errors::HikyakuError::WebDavError;
+use crate::errors::HikyakuResult;
+use crate::utils::xml::unescape_text;
+
+// The characters which are escaped in a path segment of the URL.
+const PATH_SEGMENT_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/');
+
+/// The request body of PROPFIND which asks only the properties used in this crate.
+pub(crate) const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getcontentlength/></d:prop></d:propfind>"#;
+
+/// Nextcloud chunked upload v2 names the chunks from 1 to 10000.
+pub(crate) const MAX_NEXTCLOUD_CHUNKS: u64 = 10000;
+
+/// Base URL of the WebDAV server(e.x. `https://cloud.example.com`).
+///
+/// `dav://` is served over HTTP and `davs://` over HTTPS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebDavEndpoint {
+    base_url: String,
+}
+
+impl WebDavEndpoint {
+    /// Creates the endpoint from the prefix and `host[:port]` of the path.
+    pub(crate) fn new(prefix: &str, host: &str) -> Self {
+        let scheme = if prefix == "davs://" {
+            "https"
+        } else {
+            "http"
+        };
+
+        Self {
+            base_url: format!("{}://{}", scheme, host),
+        }
+    }
+
+    /// The URL of the resource. The path is the one of `dav://` except the host.
+    pub(crate) fn url(&self, path: &str) -> String {
+        let encoded = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT_ENCODE_SET).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        format!("{}/{}", self.base_url, encoded)
+    }
+
+    /// The URL of the collection, which ends with `/` as the servers redirect without it.
+    pub(crate) fn collection_url(&self, path: &str) -> String {
+        let url = self.url(path);
+        if url.ends_with('/') {
+            url
+        } else {
+            format!("{}/", url)
+        }
+    }
+}
+
+/// WebDAV specific settings of the builder.
+#[derive(Debug, Clone)]
+pub struct WebDavOptions {
+    /// Use Nextcloud chunked upload v2 when the server supports it.
+    pub(crate) chunked_upload: bool,
+}
+
+impl Default for WebDavOptions {
+    fn default() -> Self {
+        Self {
+            chunked_upload: true,
+        }
+    }
+}
+
+/// A resource in the multi-status response of PROPFIND.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct WebDavResource {
+    /// The decoded path without the host and the leading and trailing `/`.
+    pub(crate) path: String,
+    pub(crate) is_collection: bool,
+    pub(crate) content_length: Option<u64>,
+}
+
+/// Convert the href(the absolute path or the absolute URL) to the path of `dav://`.
+fn href_to_path(href: &str) -> String {
+    let path = match href.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|index| &rest[index..]).unwrap_or(""),
+        None => href,
+    };
+    percent_decode_str(path)
+        .decode_utf8_lossy()
+        .trim_matches('/')
+        .to_string()
+}
+
+/// Parse the multi-status response of PROPFIND.
+///
+/// The elements are matched by the local name, so any namespace prefix(`d:`, `D:` or the default namespace)
+/// which the servers use is accepted.
+///
+/// # Errors
+///
+/// Returns a `WebDavError` if the response is not well-formed XML.
+pub(crate) fn parse_multistatus(xml: &str) -> HikyakuResult<Vec<WebDavResource>> {
+    let mut resources = vec![];
+    let mut elements: Vec<String> = vec![];
+    let mut resource = WebDavResource::default();
+    let mut href = String::new();
+
+    for token in Tokenizer::from(xml) {
+        let token = token.map_err(|e| WebDavError(format!("Failed to parse the multi-status response: {}", e)))?;
+        match token {
+            Token::ElementStart {local, ..} => {
+                match local.as_str() {
+                    "response" => {
+                        resource = WebDavResource::default();
+                        href.clear();
+                    },
+                    "collection" if elements.iter().any(|element| element == "resourcetype") => {
+                        resource.is_collection = true;
+                    },
+                    _ => {},
+                }
+                elements.push(local.to_string());
+            },
+            Token::ElementEnd {end: ElementEnd::Empty, ..} => {
+                elements.pop();
+            },
+            Token::ElementEnd {end: ElementEnd::Close(_, local), ..} => {
+                elements.pop();
+                if local.as_str() == "response" {
+                    resource.path = href_to_path(&href);
+                    resources.push(std::mem::take(&mut resource));
+                }
+            },
+            Token::Text {text} => {
+                match elements.last().map(String::as_str) {
+                    Some("href") => href.push_str(&unescape_text(text.as_str().trim())),
+                    Some("getcontentlength") => resource.content_length = text.as_str().trim().parse::<u64>().ok(),
+                    _ => {},
+                }
+            },
+            _ => {},
+        }
+    }
+
+    Ok(resources)
+}
+
+/// The URL of the uploads collection of Nextcloud(`/remote.php/dav/uploads/{user}`)
+/// if the path is in the files collection of the user(`/remote.php/dav/files/{user}/...`).
+pub(crate) fn nextcloud_uploads_url(endpoint: &WebDavEndpoint, path: &str) -> Option<String> {
+    let rest = path.strip_prefix("remote.php/dav/files/")?;
+    let (user, _) = rest.split_once('/')?;
+    Some(endpoint.url(&format!("remote.php/dav/uploads/{}", user)))
+}
+
+/// The state of the upload.
+///
+/// When Nextcloud chunked upload v2 is available, the chunks are put in the transfer collection
+/// in parallel and they are assembled by MOVE. Otherwise the chunks are held until all arrive
+/// and put at once, because WebDAV has no standard way to write at the offset.
+#[derive(Debug, Default)]
+pub struct WebDavUpload {
+    transfer_url: Option<String>,
+    chunks: BTreeMap<u64, Vec<u8>>,
+}
+
+impl WebDavUpload {
+    pub(crate) fn new(transfer_url: Option<String>) -> Self {
+        Self {
+            transfer_url,
+            chunks: BTreeMap::new(),
+        }
+    }
+
+    pub(crate) fn get_transfer_url(&self) -> Option<&str> {
+        self.transfer_url.as_deref()
+    }
+
+    pub(crate) fn add_chunk(&mut self, offset: u64, data: Vec<u8>) {
+        self.chunks.insert(offset, data);
+    }
+
+    /// Concatenate the held chunks in the offset order.
+    pub(crate) fn into_data(self) -> Vec<u8> {
+        self.chunks.into_values().flatten().collect()
+    }
+}
+
+/// The URL of the chunk in the transfer collection. The names are zero-padded to be sorted as numbers.
+pub(crate) fn chunk_url(transfer_url: &str, offset: u64) -> String {
+    format!("{}/{:05}", transfer_url, offset + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url() {
+        let endpoint = WebDavEndpoint::new("davs://", "cloud.example.com");
+        assert_eq!(endpoint.url("remote.php/dav/files/alice/train set#1.csv"),
+                   "https://cloud.example.com/remote.php/dav/files/alice/train%20set%231.csv");
+        assert_eq!(endpoint.collection_url(""), "https://cloud.example.com/");
+        assert_eq!(nextcloud_uploads_url(&endpoint, "remote.php/dav/files/alice/train.csv"),
+                   Some("https://cloud.example.com/remote.php/dav/uploads/alice".to_string()));
+        assert_eq!(nextcloud_uploads_url(&WebDavEndpoint::new("dav://", "nas.local:8080"), "share/train.csv"), None);
+    }
+
+    #[test]
+    fn test_parse_multistatus() {
+        // Apache mod_dav style with the upper case prefix and the absolute URL.
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <D:multistatus xmlns:D="DAV:">
+              <D:response xmlns:lp1="DAV:">
+                <D:href>http://nas.local/share/titanic/</D:href>
+                <D:propstat><D:prop><lp1:resourcetype><D:collection/></lp1:resourcetype></D:prop></D:propstat>
+              </D:response>
+              <D:response>
+                <D:href>/share/titanic/train%20set.csv</D:href>
+                <D:propstat><D:prop><lp1:resourcetype/><lp1:getcontentlength>5</lp1:getcontentlength></D:prop></D:propstat>
+              </D:response>
+            </D:multistatus>"#;
+        assert_eq!(parse_multistatus(xml).unwrap(), vec![
+            WebDavResource { path: "share/titanic".to_string(), is_collection: true, content_length: None },
+            WebDavResource { path: "share/titanic/train set.csv".to_string(), is_collection: false, content_length: Some(5) },
+        ]);
+
+        // The default namespace without the prefix.
+        let xml = r#"<multistatus xmlns="DAV:"><response><href>/a&amp;b.txt</href>
+            <propstat><prop><getcontentlength>0</getcontentlength></prop></propstat></response></multistatus>"#;
+        assert_eq!(parse_multistatus(xml).unwrap(), vec![
+            WebDavResource { path: "a&b.txt".to_string(), is_collection: false, content_length: Some(0) },
+        ]);
+
+        assert!(matches!(parse_multistatus("<d:multistatus a=b/>"), Err(WebDavError(_))));
+    }
+
+    #[test]
+    fn test_upload() {
+        let mut upload = WebDavUpload::new(None);
+        upload.add_chunk(1, b"def".to_vec());
+        upload.add_chunk(0, b"abc".to_vec());
+        assert_eq!(upload.get_transfer_url(), None);
+        assert_eq!(upload.into_data(), b"abcdef".to_vec());
+        assert_eq!(chunk_url("https://cloud.example.com/remote.php/dav/uploads/alice/t1", 0),
+                   "https://cloud.example.com/remote.php/dav/uploads/alice/t1/00001");
+    }
+}
@@ -0,0 +1,280 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use aws_config::BehaviorVersion;
+use aws_credential_types::provider::error::CredentialsError;
+use aws_credential_types::provider::{future, ProvideCredentials, SharedCredentialsProvider};
+use aws_credential_types::Credentials;
+use aws_sdk_sts::config::Region as StsRegion;
+use aws_sdk_sts::Client as StsClient;
+use log::{debug, error};
+use tokio::sync::Mutex;
+use crate::errors::HikyakuResult;
+
+// The credentials are refreshed when they expire within this margin.
+const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_SESSION_NAME: &str = "hikyaku";
+
+/// A callback which returns the current MFA token code(e.x. 6 digits TOTP code).
+///
+/// The callback is called every time the role is assumed, that is at the start and
+/// when the temporary credentials expire during the long transfer.
+pub type MfaTokenProvider = Arc<dyn Fn() -> HikyakuResult<String> + Send + Sync>;
+
+/// Options of the STS AssumeRole for [S3Credential::assume_role](crate::utils::credential::s3_credential::S3Credential::assume_role).
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use hikyaku::utils::credential::aws_assume_role::AssumeRoleOptions;
+///
+/// let options = AssumeRoleOptions::new("arn:aws:iam::123456789012:role/transfer")
+///     .set_external_id("partner-id")
+///     .set_session_name("nightly-transfer")
+///     .set_duration(Duration::from_secs(3600));
+/// ```
+#[derive(Clone)]
+pub struct AssumeRoleOptions {
+    role_arn: String,
+    session_name: String,
+    external_id: Option<String>,
+    duration: Option<Duration>,
+    mfa: Option<(String, MfaTokenProvider)>,
+    sts_endpoint_url: Option<String>,
+}
+
+impl AssumeRoleOptions {
+    pub fn new(role_arn: &str) -> Self {
+        Self {
+            role_arn: role_arn.to_string(),
+            session_name: DEFAULT_SESSION_NAME.to_string(),
+            external_id: None,
+            duration: None,
+            mfa: None,
+            sts_endpoint_url: None,
+        }
+    }
+
+    pub(crate) fn get_role_arn(&self) -> &str {
+        &self.role_arn
+    }
+
+    /// Sets the role session name. The default is `hikyaku`.
+    pub fn set_session_name(mut self, session_name: &str) -> Self {
+        self.session_name = session_name.to_string();
+        self
+    }
+
+    /// Sets the external id which the trust policy of the role requires.
+    pub fn set_external_id(mut self, external_id: &str) -> Self {
+        self.external_id = Some(external_id.to_string());
+        self
+    }
+
+    /// Sets the duration of the role session. The default is 1 hour.
+    pub fn set_duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Sets the MFA device and the callback which returns the token code.
+    ///
+    /// # Arguments
+    ///
+    /// * `serial_number` - The serial number or ARN of the MFA device.
+    /// * `token_provider` - The callback which returns the current token code.
+    pub fn set_mfa<F>(mut self, serial_number: &str, token_provider: F) -> Self
+    where
+        F: Fn() -> HikyakuResult<String> + Send + Sync + 'static,
+    {
+        self.mfa = Some((serial_number.to_string(), Arc::new(token_provider)));
+        self
+    }
+
+    /// Sets the custom STS endpoint URL(e.x. VPC endpoint or the local stand-in).
+    pub fn set_sts_endpoint_url(mut self, sts_endpoint_url: &str) -> Self {
+        self.sts_endpoint_url = Some(sts_endpoint_url.to_string());
+        self
+    }
+}
+
+impl Debug for AssumeRoleOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssumeRoleOptions")
+            .field("role_arn", &self.role_arn)
+            .field("session_name", &self.session_name)
+            .field("external_id", &self.external_id)
+            .field("duration", &self.duration)
+            .field("mfa_serial_number", &self.mfa.as_ref().map(|(serial_number, _)| serial_number))
+            .field("sts_endpoint_url", &self.sts_endpoint_url)
+            .finish()
+    }
+}
+
+/// The credentials provider which assumes the role with the base credentials and
+/// assumes it again when the temporary credentials are about to expire.
+///
+/// The credentials are cached here because the MFA token code cannot be reused,
+/// so the role must not be assumed more than needed.
+pub(crate) struct AssumeRoleCredentialsProvider {
+    client: StsClient,
+    options: AssumeRoleOptions,
+    cache: Mutex<Option<Credentials>>,
+}
+
+impl AssumeRoleCredentialsProvider {
+    pub(crate) fn new(base: SharedCredentialsProvider, region: &str, options: AssumeRoleOptions) -> Self {
+        let mut config = aws_sdk_sts::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(StsRegion::new(region.to_string()))
+            .credentials_provider(base);
+        config.set_endpoint_url(options.sts_endpoint_url.clone());
+        let config = config.build();
+
+        Self {
+            client: StsClient::from_conf(config),
+            options,
+            cache: Mutex::new(None),
+        }
+    }
+
+    async fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let mut cache = self.cache.lock().await;
+        if let Some(credentials) = cache.as_ref() {
+            let is_fresh = credentials
+                .expiry()
+                .is_none_or(|expiry| expiry > SystemTime::now() + REFRESH_MARGIN);
+            if is_fresh {
+                return Ok(credentials.clone());
+            }
+        }
+
+        debug!("Assume role: {}", self.options.role_arn);
+        let mut request = self.client
+            .assume_role()
+            .role_arn(&self.options.role_arn)
+            .role_session_name(&self.options.session_name)
+            .set_external_id(self.options.external_id.clone())
+            .set_duration_seconds(self.options.duration.map(|duration| duration.as_secs() as i32));
+        if let Some((serial_number, token_provider)) = &self.options.mfa {
+            let token_code = token_provider()
+                .map_err(|e| CredentialsError::provider_error(format!("Failed to get the MFA token code: {}", e)))?;
+            request = request
+                .serial_number(serial_number)
+                .token_code(token_code);
+        }
+
+        let output = request
+            .send()
+            .await
+            .map_err(|e| {
+                error!("Failed to assume role: {:#?}", e);
+                CredentialsError::provider_error(e)
+            })?;
+        let sts_credentials = output
+            .credentials()
+            .ok_or_else(|| CredentialsError::invalid_configuration("AssumeRole returned no credentials"))?;
+
+        let credentials = Credentials::new(
+            sts_credentials.access_key_id(),
+            sts_credentials.secret_access_key(),
+            Some(sts_credentials.session_token().to_string()),
+            SystemTime::try_from(*sts_credentials.expiration()).ok(),
+            "HikyakuAssumeRole");
+        *cache = Some(credentials.clone());
+
+        Ok(credentials)
+    }
+}
+
+impl Debug for AssumeRoleCredentialsProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssumeRoleCredentialsProvider")
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
+impl ProvideCredentials for AssumeRoleCredentialsProvider {
+    fn provide_credentials<'a>(&'a self) -> future::ProvideCredentials<'a>
+    where
+        Self: 'a,
+    {
+        future::ProvideCredentials::new(self.credentials())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+    use axum::extract::State;
+    use axum::routing::post;
+    use axum::Router;
+    use tokio::net::TcpListener;
+    use super::*;
+
+    // Fake STS which returns the credentials expiring in 1 minute, so every call refreshes them.
+    async fn start_fake_sts(requests: Arc<std::sync::Mutex<Vec<String>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint_url = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route("/", post(|State(requests): State<Arc<std::sync::Mutex<Vec<String>>>>, body: String| async move {
+                let number = {
+                    let mut requests = requests.lock().unwrap();
+                    requests.push(body);
+                    requests.len()
+                };
+                let expiration = DateTime::from(SystemTime::now() + Duration::from_secs(60))
+                    .fmt(DateTimeFormat::DateTime)
+                    .unwrap();
+                let body = format!(
+                    "<AssumeRoleResponse xmlns=\"https://sts.amazonaws.com/doc/2011-06-15/\"><AssumeRoleResult>\
+                    <Credentials><AccessKeyId>ASIA{}</AccessKeyId><SecretAccessKey>secret</SecretAccessKey>\
+                    <SessionToken>token</SessionToken><Expiration>{}</Expiration></Credentials>\
+                    <AssumedRoleUser><AssumedRoleId>id:hikyaku</AssumedRoleId><Arn>arn:aws:sts::123456789012:assumed-role/transfer/hikyaku</Arn></AssumedRoleUser>\
+                    </AssumeRoleResult></AssumeRoleResponse>", number, expiration);
+
+                ([("content-type", "text/xml")], body)
+            }))
+            .with_state(requests);
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+
+        endpoint_url
+    }
+
+    #[tokio::test]
+    async fn test_assume_role_with_mfa_refresh() {
+        let requests = Arc::new(std::sync::Mutex::new(vec![]));
+        let endpoint_url = start_fake_sts(Arc::clone(&requests)).await;
+        let token_calls = Arc::new(AtomicU32::new(0));
+        let token_calls_clone = Arc::clone(&token_calls);
+
+        let options = AssumeRoleOptions::new("arn:aws:iam::123456789012:role/transfer")
+            .set_external_id("partner-id")
+            .set_session_name("nightly")
+            .set_mfa("arn:aws:iam::123456789012:mfa/user", move || {
+                token_calls_clone.fetch_add(1, Ordering::SeqCst);
+                Ok("123456".to_string())
+            })
+            .set_sts_endpoint_url(&endpoint_url);
+        let base = SharedCredentialsProvider::new(Credentials::new("base", "base_secret", None, None, "test"));
+        let provider = AssumeRoleCredentialsProvider::new(base, "ap-northeast-1", options);
+
+        let first = provider.provide_credentials().await.unwrap();
+        assert_eq!(first.access_key_id(), "ASIA1");
+        let second = provider.provide_credentials().await.unwrap();
+        assert_eq!(second.access_key_id(), "ASIA2");
+        assert_eq!(token_calls.load(Ordering::SeqCst), 2);
+
+        let body = requests.lock().unwrap()[0].clone();
+        assert!(body.contains("Action=AssumeRole"));
+        assert!(body.contains("ExternalId=partner-id"));
+        assert!(body.contains("RoleSessionName=nightly"));
+        assert!(body.contains("TokenCode=123456"));
+        assert!(body.contains("SerialNumber=arn%3Aaws%3Aiam%3A%3A123456789012%3Amfa%2Fuser"));
+    }
+}
@@ -0,0 +1,188 @@
+use std::env;
+use std::fmt::{Debug, Formatter};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use crate::errors::HikyakuError::{EnvCredentialError, InvalidArgumentError};
+use crate::errors::HikyakuResult;
+use crate::types::azure_blob::{AzureBlobAuthorization, AzureBlobAuthorizer, AzureBlobOptions, EntraIdClient};
+use crate::utils::credential::Credential;
+use crate::utils::region::NoneRegion;
+
+// The well-known account of the Azurite emulator.
+const AZURITE_ACCOUNT_NAME: &str = "devstoreaccount1";
+const AZURITE_ACCOUNT_KEY: &str = "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==";
+const DEFAULT_AUTHORITY_HOST: &str = "https://login.microsoftonline.com";
+
+#[derive(Clone)]
+enum AzureBlobAuth {
+    AccountKey(Vec<u8>),
+    Sas(String),
+    ClientSecret {
+        tenant_id: String,
+        client_id: String,
+        client_secret: String,
+        authority_host: String,
+    },
+}
+
+#[derive(Clone)]
+pub struct AzureBlobTokens {
+    account_name: String,
+    auth: AzureBlobAuth,
+}
+
+impl Debug for AzureBlobTokens {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let auth = match &self.auth {
+            AzureBlobAuth::AccountKey(_) => "AccountKey".to_string(),
+            AzureBlobAuth::Sas(_) => "Sas".to_string(),
+            AzureBlobAuth::ClientSecret {tenant_id, client_id, ..} => {
+                format!("ClientSecret {{ tenant_id: {}, client_id: {} }}", tenant_id, client_id)
+            },
+        };
+        write!(f, "AzureBlobTokens {{ account_name: {}, auth: {} }}", self.account_name, auth)
+    }
+}
+
+impl AzureBlobTokens {
+    pub(crate) fn get_account_name(&self) -> &str {
+        &self.account_name
+    }
+
+    /// Get the authorizer of the requests. The Entra ID token is requested by the client credentials flow.
+    pub(crate) async fn get_authorizer(&self) -> HikyakuResult<AzureBlobAuthorizer> {
+        let authorization = match &self.auth {
+            AzureBlobAuth::AccountKey(key) => AzureBlobAuthorization::SharedKey(key.clone()),
+            AzureBlobAuth::Sas(sas) => AzureBlobAuthorization::Sas(sas.clone()),
+            AzureBlobAuth::ClientSecret {tenant_id, client_id, client_secret, authority_host} => {
+                AzureBlobAuthorization::entra_id(EntraIdClient {
+                    token_url: format!("{}/{}/oauth2/v2.0/token", authority_host, tenant_id),
+                    client_id: client_id.to_string(),
+                    client_secret: client_secret.to_string(),
+                }).await?
+            },
+        };
+
+        Ok(AzureBlobAuthorizer::new(&self.account_name, authorization))
+    }
+}
+
+/// The credential for Azure Blob Storage.
+///
+/// The requests are authorized by the account key(Shared Key), the SAS token or
+/// the Microsoft Entra ID token of the service principal(client credentials flow).
+/// The Entra ID token is requested when the file system object is built, and it is requested again
+/// shortly before it expires, so the long transfers outlive the token of about an hour.
+pub struct AzureBlobCredential {
+    credential: AzureBlobTokens,
+}
+
+impl AzureBlobCredential {
+    /// Creates the credential with the account key.
+    ///
+    /// # Arguments
+    ///
+    /// * `account_name` - The name of the storage account.
+    /// * `account_key` - The base64 encoded account key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the account key is not base64.
+    pub fn from_account_key(account_name: &str, account_key: &str) -> HikyakuResult<Self> {
+        let key = STANDARD
+            .decode(account_key.trim())
+            .map_err(|e| InvalidArgumentError(format!("The account key is not base64: {:?}", e)))?;
+
+        Ok(Self::with_auth(account_name, AzureBlobAuth::AccountKey(key)))
+    }
+
+    /// Creates the credential with the SAS token(e.x. `sv=2021-08-06&ss=b&...&sig=...`).
+    ///
+    /// The SAS token must allow the operations(read, write, list and delete) which are used.
+    pub fn from_sas(account_name: &str, sas_token: &str) -> Self {
+        Self::with_auth(account_name, AzureBlobAuth::Sas(sas_token.trim_start_matches('?').to_string()))
+    }
+
+    /// Creates the credential with the client secret of the service principal in Microsoft Entra ID.
+    ///
+    /// The service principal needs a data role like `Storage Blob Data Contributor`.
+    pub fn from_client_secret(account_name: &str, tenant_id: &str, client_id: &str, client_secret: &str) -> Self {
+        Self::with_auth(account_name, AzureBlobAuth::ClientSecret {
+            tenant_id: tenant_id.to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            authority_host: DEFAULT_AUTHORITY_HOST.to_string(),
+        })
+    }
+
+    /// Creates the credential of the well-known account of the Azurite emulator.
+    ///
+    /// Use it with `AzureBlobEndpoint::azurite()`.
+    pub fn azurite() -> Self {
+        // SAFETY: The well-known key is always base64.
+        Self::from_account_key(AZURITE_ACCOUNT_NAME, AZURITE_ACCOUNT_KEY).unwrap()
+    }
+
+    /// Creates the credential from the environment variables.
+    ///
+    /// `AZURE_STORAGE_ACCOUNT` is required, then the first found of `AZURE_STORAGE_KEY`,
+    /// `AZURE_STORAGE_SAS_TOKEN` and `AZURE_TENANT_ID`/`AZURE_CLIENT_ID`/`AZURE_CLIENT_SECRET` is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvCredentialError` if the variables are not set.
+    pub fn from_env() -> HikyakuResult<Self> {
+        let account_name = env::var("AZURE_STORAGE_ACCOUNT")
+            .map_err(|_| EnvCredentialError("AZURE_STORAGE_ACCOUNT is not set".to_string()))?;
+
+        if let Ok(account_key) = env::var("AZURE_STORAGE_KEY") {
+            return Self::from_account_key(&account_name, &account_key);
+        }
+        if let Ok(sas_token) = env::var("AZURE_STORAGE_SAS_TOKEN") {
+            return Ok(Self::from_sas(&account_name, &sas_token));
+        }
+        match (env::var("AZURE_TENANT_ID"), env::var("AZURE_CLIENT_ID"), env::var("AZURE_CLIENT_SECRET")) {
+            (Ok(tenant_id), Ok(client_id), Ok(client_secret)) => {
+                Ok(Self::from_client_secret(&account_name, &tenant_id, &client_id, &client_secret))
+            },
+            _ => Err(EnvCredentialError(
+                "Any of AZURE_STORAGE_KEY, AZURE_STORAGE_SAS_TOKEN or AZURE_TENANT_ID/AZURE_CLIENT_ID/AZURE_CLIENT_SECRET is not set".to_string())),
+        }
+    }
+
+    /// Sets the authority host of Microsoft Entra ID for the national clouds
+    /// (e.x. `https://login.chinacloudapi.cn`). This is used only by the client secret.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the credential instance for further chaining of method calls.
+    pub fn set_authority_host(mut self, host: &str) -> Self {
+        if let AzureBlobAuth::ClientSecret {authority_host, ..} = &mut self.credential.auth {
+            *authority_host = host.trim_end_matches('/').to_string();
+        }
+        self
+    }
+
+    fn with_auth(account_name: &str, auth: AzureBlobAuth) -> Self {
+        Self {
+            credential: AzureBlobTokens {
+                account_name: account_name.to_string(),
+                auth,
+            },
+        }
+    }
+}
+
+impl Credential for AzureBlobCredential {
+    type CredentialType = AzureBlobTokens;
+    type RegionType = NoneRegion;
+    type Options = AzureBlobOptions;
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.credential.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
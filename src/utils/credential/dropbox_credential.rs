@@ -0,0 +1,37 @@
+use time::OffsetDateTime;
+use crate::types::dropbox::DropboxOptions;
+use crate::utils::credential::Credential;
+use crate::utils::credential::oauth2_tokens::OAuth2Tokens;
+use crate::utils::region::NoneRegion;
+
+/// The credential for Dropbox.
+///
+/// The tokens can be got by the OAuth2 flow with
+/// `get_dropbox_oauth2_secret`(the `google_oauth2` feature).
+pub struct DropboxCredential {
+    credential: OAuth2Tokens,
+}
+
+impl DropboxCredential {
+    pub fn new(access_token: &str, refresh_token: &str, expires_at: OffsetDateTime) -> Self {
+        let credential = OAuth2Tokens::new(access_token, Some(refresh_token), expires_at);
+
+        Self {
+            credential,
+        }
+    }
+}
+
+impl Credential for DropboxCredential {
+    type CredentialType = OAuth2Tokens;
+    type RegionType = NoneRegion;
+    type Options = DropboxOptions;
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.credential.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
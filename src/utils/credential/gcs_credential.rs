@@ -0,0 +1,203 @@
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use log::error;
+use reqwest::Client;
+use ring::rand::SystemRandom;
+use ring::signature::{RsaKeyPair, RSA_PKCS1_SHA256};
+use serde::Deserialize;
+use serde_json::json;
+use time::OffsetDateTime;
+use crate::errors::HikyakuError::{ConnectionError, FileOperationError, GcsError, InvalidArgumentError};
+use crate::errors::HikyakuResult;
+use crate::types::google_cloud_storage::GcsOptions;
+use crate::utils::credential::Credential;
+use crate::utils::region::NoneRegion;
+
+/// The scope which allows to read and write the objects.
+pub const GCS_READ_WRITE_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+// The assertion is valid for an hour at most.
+const ASSERTION_LIFETIME_SECONDS: i64 = 3600;
+
+/// The fields of the service account key file which are used to get the token.
+#[derive(Clone, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    private_key_id: Option<String>,
+    token_uri: String,
+}
+
+#[derive(Clone)]
+enum GcsAuth {
+    OAuth2 {
+        access_token: String,
+        #[allow(dead_code)]
+        refresh_token: Option<String>,
+        #[allow(dead_code)]
+        expires_at: OffsetDateTime,
+    },
+    ServiceAccount(ServiceAccountKey),
+    Anonymous,
+}
+
+#[derive(Clone)]
+pub struct GcsTokens {
+    auth: GcsAuth,
+}
+
+impl Debug for GcsTokens {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let auth = match &self.auth {
+            GcsAuth::OAuth2 {expires_at, ..} => format!("OAuth2 {{ expires_at: {} }}", expires_at),
+            GcsAuth::ServiceAccount(key) => format!("ServiceAccount {{ client_email: {} }}", key.client_email),
+            GcsAuth::Anonymous => "Anonymous".to_string(),
+        };
+        write!(f, "GcsTokens {{ auth: {} }}", auth)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+impl GcsTokens {
+    /// Get the access token. The token of the service account is requested by the signed JWT.
+    ///
+    /// # Returns
+    ///
+    /// `HikyakuResult<Option<String>>` - The access token, or [None] for the anonymous access.
+    pub(crate) async fn get_access_token(&self) -> HikyakuResult<Option<String>> {
+        match &self.auth {
+            GcsAuth::OAuth2 {access_token, ..} => Ok(Some(access_token.to_string())),
+            GcsAuth::ServiceAccount(key) => {
+                let assertion = sign_assertion(key, OffsetDateTime::now_utc())?;
+                let response = Client::new()
+                    .post(&key.token_uri)
+                    .form(&[("grant_type", JWT_BEARER_GRANT_TYPE), ("assertion", assertion.as_str())])
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to send request to Google OAuth2 API: {:#?}", e);
+                        ConnectionError(format!("Failed to send request to Google OAuth2 API: {:?}", e))
+                    })?;
+
+                if !response.status().is_success() {
+                    let status = response.status();
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(GcsError(format!("Failed to get the token of {}: {}, {}", key.client_email, status, body)));
+                }
+                let token = response
+                    .json::<TokenResponse>()
+                    .await
+                    .map_err(|e| GcsError(format!("Failed to parse the token response: {:?}", e)))?;
+
+                Ok(Some(token.access_token))
+            },
+            GcsAuth::Anonymous => Ok(None),
+        }
+    }
+}
+
+/// Sign the JWT assertion of the service account by RS256.
+fn sign_assertion(key: &ServiceAccountKey, now: OffsetDateTime) -> HikyakuResult<String> {
+    let header = json!({"alg": "RS256", "typ": "JWT", "kid": key.private_key_id});
+    let claims = json!({
+        "iss": key.client_email,
+        "scope": GCS_READ_WRITE_SCOPE,
+        "aud": key.token_uri,
+        "iat": now.unix_timestamp(),
+        "exp": now.unix_timestamp() + ASSERTION_LIFETIME_SECONDS,
+    });
+    let message = format!("{}.{}",
+                          URL_SAFE_NO_PAD.encode(header.to_string()),
+                          URL_SAFE_NO_PAD.encode(claims.to_string()));
+
+    // The private key is PEM of PKCS#8.
+    let der = STANDARD
+        .decode(key.private_key.lines().filter(|line| !line.starts_with("-----")).collect::<String>())
+        .map_err(|e| InvalidArgumentError(format!("The private key is not PEM: {:?}", e)))?;
+    let key_pair = RsaKeyPair::from_pkcs8(&der)
+        .map_err(|e| InvalidArgumentError(format!("The private key is not RSA of PKCS#8: {}", e)))?;
+    let mut signature = vec![0; key_pair.public().modulus_len()];
+    key_pair
+        .sign(&RSA_PKCS1_SHA256, &SystemRandom::new(), message.as_bytes(), &mut signature)
+        .map_err(|e| GcsError(format!("Failed to sign the assertion: {}", e)))?;
+
+    Ok(format!("{}.{}", message, URL_SAFE_NO_PAD.encode(signature)))
+}
+
+/// The credential for Google Cloud Storage.
+///
+/// The requests are authorized by the service account key, or the OAuth2 token which can be got
+/// by `get_google_oauth2_secret` with the [GCS_READ_WRITE_SCOPE] scope.
+/// The token of the service account is requested when the file system object is built
+/// and it is valid for an hour.
+pub struct GcsCredential {
+    credential: GcsTokens,
+}
+
+impl GcsCredential {
+    pub fn new(access_token: &str, refresh_token: &str, expires_at: OffsetDateTime) -> Self {
+        Self::with_auth(GcsAuth::OAuth2 {
+            access_token: access_token.to_string(),
+            refresh_token: Some(refresh_token.to_string()),
+            expires_at,
+        })
+    }
+
+    /// Creates the credential from the JSON of the service account key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the JSON is not the service account key.
+    pub fn from_service_account_key(key_json: &str) -> HikyakuResult<Self> {
+        let key = serde_json::from_str::<ServiceAccountKey>(key_json)
+            .map_err(|e| InvalidArgumentError(format!("The JSON is not the service account key: {:?}", e)))?;
+
+        Ok(Self::with_auth(GcsAuth::ServiceAccount(key)))
+    }
+
+    /// Creates the credential from the service account key file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `FileOperationError` if the file cannot be read, or an `InvalidArgumentError`
+    /// if the file is not the service account key.
+    pub fn from_service_account_file<P: AsRef<Path>>(key_path: P) -> HikyakuResult<Self> {
+        let key_json = std::fs::read_to_string(key_path.as_ref())
+            .map_err(|e| FileOperationError(format!("Failed to read {}: {:?}", key_path.as_ref().display(), e)))?;
+
+        Self::from_service_account_key(&key_json)
+    }
+
+    /// Creates the credential without the authorization for the public buckets and fake-gcs-server.
+    pub fn anonymous() -> Self {
+        Self::with_auth(GcsAuth::Anonymous)
+    }
+
+    fn with_auth(auth: GcsAuth) -> Self {
+        Self {
+            credential: GcsTokens {
+                auth,
+            },
+        }
+    }
+}
+
+impl Credential for GcsCredential {
+    type CredentialType = GcsTokens;
+    type RegionType = NoneRegion;
+    type Options = GcsOptions;
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.credential.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
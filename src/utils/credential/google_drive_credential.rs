@@ -1,31 +1,19 @@
 use time::OffsetDateTime;
+use crate::types::google_drive::GoogleDriveOptions;
 use crate::utils::credential::Credential;
+use crate::utils::credential::oauth2_tokens::OAuth2Tokens;
 use crate::utils::region::NoneRegion;
 
-#[derive(Debug, Clone)]
-pub struct GoogleDriveTokens {
-    access_token: String,
-    refresh_token: Option<String>,
-    expires_at: OffsetDateTime,
-}
-
-impl GoogleDriveTokens {
-    pub(crate) fn get_access_token(&self) -> &str {
-        &self.access_token
-    }
-}
+/// The tokens of Google Drive, which are the same as the other OAuth2 storages.
+pub type GoogleDriveTokens = OAuth2Tokens;
 
 pub struct GoogleDriveCredential {
-    credential: GoogleDriveTokens,
+    credential: OAuth2Tokens,
 }
 
 impl GoogleDriveCredential {
     pub fn new(access_token: &str, refresh_token: &str, expires_at: OffsetDateTime) -> Self {
-        let credential = GoogleDriveTokens {
-            access_token: access_token.to_string(),
-            refresh_token: Some(refresh_token.to_string()),
-            expires_at,
-        };
+        let credential = OAuth2Tokens::new(access_token, Some(refresh_token), expires_at);
         
         Self {
            credential, 
@@ -34,8 +22,9 @@ impl GoogleDriveCredential {
 }
 
 impl Credential for GoogleDriveCredential {
-    type CredentialType = GoogleDriveTokens;
+    type CredentialType = OAuth2Tokens;
     type RegionType = NoneRegion;
+    type Options = GoogleDriveOptions;
 
     fn get_credential(&self) -> Self::CredentialType {
         self.credential.clone()
@@ -0,0 +1,109 @@
+use std::fmt::{Debug, Formatter};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use crate::errors::HikyakuError::{BuilderError, ParseError};
+use crate::errors::HikyakuResult;
+use crate::types::http::HttpOptions;
+use crate::utils::credential::Credential;
+use crate::utils::region::NoneRegion;
+
+#[derive(Clone)]
+enum HttpAuthMethod {
+    Basic {
+        user: String,
+        password: String,
+    },
+    Bearer(String),
+    Anonymous,
+}
+
+#[derive(Clone)]
+pub struct HttpAuth {
+    method: HttpAuthMethod,
+}
+
+impl Debug for HttpAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let method = match &self.method {
+            HttpAuthMethod::Basic {user, ..} => format!("Basic {{ user: {} }}", user),
+            HttpAuthMethod::Bearer(_) => "Bearer".to_string(),
+            HttpAuthMethod::Anonymous => "Anonymous".to_string(),
+        };
+        write!(f, "HttpAuth {{ method: {} }}", method)
+    }
+}
+
+impl HttpAuth {
+    /// Create the client which sends the custom headers and the `Authorization` header on every request.
+    pub(crate) fn create_client(&self, mut headers: HeaderMap) -> HikyakuResult<Client> {
+        let authorization = match &self.method {
+            HttpAuthMethod::Basic {user, password} => {
+                Some(format!("Basic {}", STANDARD.encode(format!("{}:{}", user, password))))
+            },
+            HttpAuthMethod::Bearer(token) => Some(format!("Bearer {}", token)),
+            HttpAuthMethod::Anonymous => None,
+        };
+        if let Some(authorization) = authorization {
+            let mut header_value = HeaderValue::from_str(&authorization)
+                .map_err(|e| ParseError(format!("Failed to parse header value: {:#?}", e)))?;
+            header_value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, header_value);
+        }
+
+        Client::builder()
+            .default_headers(headers)
+            .build()
+            .map_err(|e| BuilderError(format!("Failed to build client: {:#?}", e)))
+    }
+}
+
+/// The credential for the HTTP(S) server.
+///
+/// The public URL needs no credential, so [HttpCredential::anonymous] is used for it.
+pub struct HttpCredential {
+    credential: HttpAuth,
+}
+
+impl HttpCredential {
+    /// Creates the credential of the basic authentication.
+    pub fn from_basic(user: &str, password: &str) -> Self {
+        Self::with_method(HttpAuthMethod::Basic {
+            user: user.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Creates the credential which sends the bearer token.
+    pub fn from_bearer(token: &str) -> Self {
+        Self::with_method(HttpAuthMethod::Bearer(token.to_string()))
+    }
+
+    /// Creates the credential without the authorization for the public URLs.
+    pub fn anonymous() -> Self {
+        Self::with_method(HttpAuthMethod::Anonymous)
+    }
+
+    fn with_method(method: HttpAuthMethod) -> Self {
+        Self {
+            credential: HttpAuth {
+                method,
+            },
+        }
+    }
+}
+
+impl Credential for HttpCredential {
+    type CredentialType = HttpAuth;
+    type RegionType = NoneRegion;
+    type Options = HttpOptions;
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.credential.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
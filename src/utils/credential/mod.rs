@@ -1,14 +1,42 @@
 use crate::utils::region::{NoneRegion, Region};
 
+#[cfg(feature = "amazon_s3")]
 pub mod s3_credential;
+#[cfg(feature = "amazon_s3")]
+pub mod aws_assume_role;
+pub mod oauth2_tokens;
+#[cfg(feature = "google_drive")]
 pub mod google_drive_credential;
+#[cfg(feature = "dropbox")]
+pub mod dropbox_credential;
+#[cfg(feature = "box_storage")]
+pub mod box_credential;
+#[cfg(feature = "onedrive")]
+pub mod onedrive_credential;
+#[cfg(feature = "azure_blob")]
+pub mod azure_blob_credential;
+#[cfg(feature = "google_cloud_storage")]
+pub mod gcs_credential;
+#[cfg(feature = "sftp")]
+pub mod sftp_credential;
+#[cfg(feature = "webdav")]
+pub mod webdav_credential;
+#[cfg(feature = "http")]
+pub mod http_credential;
 
 pub trait Credential {
     type CredentialType;
     type RegionType: Region;
+    /// The settings of the file system which the builder holds until the build(e.x. the endpoint).
+    type Options: Default;
     
     fn get_credential(&self) -> Self::CredentialType;
     fn get_region(&self) -> Self::RegionType;
+
+    /// Get the prefixes which the credential serves besides the built-in ones(e.x. `acme://`).
+    fn registered_prefixes(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 pub(crate) struct NoCredential;
@@ -16,10 +44,9 @@ pub(crate) struct NoCredential;
 impl Credential for NoCredential {
     type CredentialType = ();
     type RegionType = NoneRegion;
+    type Options = ();
 
-    fn get_credential(&self) -> Self::CredentialType {
-        ()
-    }
+    fn get_credential(&self) -> Self::CredentialType {}
 
     fn get_region(&self) -> Self::RegionType {
         NoneRegion
@@ -0,0 +1,34 @@
+use time::OffsetDateTime;
+
+/// The tokens of OAuth2 which the storages authorizing the requests by the bearer token
+/// (Google Drive, Dropbox, Box and OneDrive) share. The Entra ID token of Azure Blob Storage is kept in it too.
+#[derive(Debug, Clone)]
+pub struct OAuth2Tokens {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: OffsetDateTime,
+}
+
+impl OAuth2Tokens {
+    pub fn new(access_token: &str, refresh_token: Option<&str>, expires_at: OffsetDateTime) -> Self {
+        Self {
+            access_token: access_token.to_string(),
+            refresh_token: refresh_token.map(str::to_string),
+            expires_at,
+        }
+    }
+
+    pub(crate) fn get_access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    /// Get the refresh token, or [None] if the provider did not issue it.
+    pub fn get_refresh_token(&self) -> Option<&str> {
+        self.refresh_token.as_deref()
+    }
+
+    /// Get the time when the access token expires.
+    pub fn get_expires_at(&self) -> OffsetDateTime {
+        self.expires_at
+    }
+}
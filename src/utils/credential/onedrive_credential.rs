@@ -0,0 +1,38 @@
+use time::OffsetDateTime;
+use crate::types::onedrive::OneDriveOptions;
+use crate::utils::credential::Credential;
+use crate::utils::credential::oauth2_tokens::OAuth2Tokens;
+use crate::utils::region::NoneRegion;
+
+/// The credential for OneDrive and SharePoint via Microsoft Graph.
+///
+/// The tokens can be got by the OAuth2 flow with `get_microsoft_oauth2_secret`
+/// for both the single tenant and the multi tenant applications.
+/// The token needs the `Files.ReadWrite.All` scope, and `Sites.Read.All` to find the SharePoint sites.
+pub struct OneDriveCredential {
+    credential: OAuth2Tokens,
+}
+
+impl OneDriveCredential {
+    pub fn new(access_token: &str, refresh_token: &str, expires_at: OffsetDateTime) -> Self {
+        let credential = OAuth2Tokens::new(access_token, Some(refresh_token), expires_at);
+
+        Self {
+            credential,
+        }
+    }
+}
+
+impl Credential for OneDriveCredential {
+    type CredentialType = OAuth2Tokens;
+    type RegionType = NoneRegion;
+    type Options = OneDriveOptions;
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.credential.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
@@ -1,17 +1,33 @@
+use std::path::Path;
 use std::time::SystemTime;
 use aws_config::meta::credentials::CredentialsProviderChain;
-use aws_config::meta::region::{RegionProviderChain};
-use aws_sdk_s3::config::{Credentials, ProvideCredentials};
+use aws_config::meta::region::{ProvideRegion, RegionProviderChain};
+use aws_runtime::env_config::file::EnvConfigFiles;
+use aws_config::profile::{ProfileFileCredentialsProvider, ProfileFileRegionProvider};
+use aws_config::provider_config::ProviderConfig;
+use aws_config::sso::SsoCredentialsProvider;
+use aws_config::web_identity_token::{StaticConfiguration, WebIdentityTokenCredentialsProvider};
+use aws_config::Region as AwsConfigRegion;
+use aws_sdk_s3::config::{Credentials, ProvideCredentials, SharedCredentialsProvider};
 use time::OffsetDateTime;
-use crate::errors::HikyakuError::EnvCredentialError;
+use crate::errors::HikyakuError::{EnvCredentialError, InvalidArgumentError};
 use crate::errors::HikyakuResult;
+use crate::types::amazon_s3::S3Options;
+use crate::utils::credential::aws_assume_role::{AssumeRoleCredentialsProvider, AssumeRoleOptions};
 use crate::utils::credential::Credential;
-use crate::utils::region::aws::AWSRegion;
+use crate::utils::region::aws::{AWSPartition, AWSRegion};
 use crate::utils::region::Region;
 
+/// The credential for Amazon S3 and S3-compatible storages.
+///
+/// The credential is held as a credentials provider, so the temporary credentials(AssumeRole,
+/// web identity, SSO and so on) are refreshed before they expire even during the long transfer.
 pub struct S3Credential<AR: Region = AWSRegion> {
-    credential: Credentials,
+    credential: SharedCredentialsProvider,
     region: AR,
+    endpoint_url: Option<String>,
+    force_path_style: bool,
+    checksum_compat: bool,
 }
 
 impl <AR: Region> S3Credential<AR> {
@@ -25,10 +41,114 @@ impl <AR: Region> S3Credential<AR> {
             "HikyakuCredential");
         
         Self {
-            credential,
+            credential: SharedCredentialsProvider::new(credential),
             region,
+            endpoint_url: None,
+            force_path_style: false,
+            checksum_compat: false,
         }
     }
+
+    /// Sets the custom endpoint URL for S3-compatible storages(e.x. MinIO, LocalStack, Ceph, R2, Wasabi).
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint_url` - A string slice of the endpoint URL like `http://localhost:9000`.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the credential instance for further chaining of method calls.
+    pub fn set_endpoint_url(mut self, endpoint_url: &str) -> Self {
+        self.endpoint_url = Some(endpoint_url.to_string());
+        self
+    }
+
+    /// Sets whether the bucket is addressed by the path(`http://endpoint/bucket/key`) instead of
+    /// the virtual-hosted style(`http://bucket.endpoint/key`).
+    ///
+    /// Most of the S3-compatible storages and local stand-ins require the path style.
+    ///
+    /// # Arguments
+    ///
+    /// * `force_path_style` - If `true`, the path style addressing is used.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the credential instance for further chaining of method calls.
+    pub fn set_force_path_style(mut self, force_path_style: bool) -> Self {
+        self.force_path_style = force_path_style;
+        self
+    }
+
+    /// Sets the checksum compatibility mode.
+    ///
+    /// When it is enabled, the request checksums are calculated and the response checksums are
+    /// validated only if the operation requires them. Some S3-compatible storages reject the
+    /// default checksum headers which the AWS SDK sends.
+    ///
+    /// # Arguments
+    ///
+    /// * `checksum_compat` - If `true`, the checksum compatibility mode is enabled.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the credential instance for further chaining of method calls.
+    pub fn set_checksum_compat(mut self, checksum_compat: bool) -> Self {
+        self.checksum_compat = checksum_compat;
+        self
+    }
+
+    /// Assumes the IAM role with this credential as the base credential.
+    ///
+    /// The role is assumed again when the temporary credentials are about to expire.
+    /// The endpoint settings of this credential are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - An [AssumeRoleOptions] which has the role ARN, external id, session name and MFA.
+    ///
+    /// # Returns
+    ///
+    /// * `HikyakuResult<Self>` - The credential of the assumed role.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the role ARN belongs to the other partition than the region,
+    /// or an `EnvCredentialError` if the role cannot be assumed.
+    pub async fn assume_role(self, options: AssumeRoleOptions) -> HikyakuResult<Self> {
+        let partition = AWSPartition::from_region(self.region.get_region());
+        let role_partition = AWSPartition::from_arn(options.get_role_arn())?;
+        if role_partition != partition {
+            return Err(InvalidArgumentError(format!(
+                "The role {} is in the {} partition but the region {} is in the {} partition",
+                options.get_role_arn(), role_partition, self.region.get_region(), partition)));
+        }
+
+        let provider = AssumeRoleCredentialsProvider::new(self.credential, self.region.get_region(), options);
+        // Assume the role once to fail fast with the wrong settings.
+        // The credentials are cached in the provider so the MFA token is not consumed twice.
+        provider
+            .provide_credentials()
+            .await
+            .map_err(|e| EnvCredentialError(format!("Failed to assume role: {}", e)))?;
+
+        Ok(Self {
+            credential: SharedCredentialsProvider::new(provider),
+            ..self
+        })
+    }
+
+    pub(crate) fn get_endpoint_url(&self) -> Option<&str> {
+        self.endpoint_url.as_deref()
+    }
+
+    pub(crate) fn is_force_path_style(&self) -> bool {
+        self.force_path_style
+    }
+
+    pub(crate) fn is_checksum_compat(&self) -> bool {
+        self.checksum_compat
+    }
 }
 
 impl S3Credential {
@@ -40,28 +160,176 @@ impl S3Credential {
             .ok_or(EnvCredentialError("Failed to get region from environment".to_string()))?;
         let region = AWSRegion::try_from(env_region)?;
 
-        let credential = CredentialsProviderChain::default_provider()
-            .await
+        let provider = CredentialsProviderChain::default_provider().await;
+        // Resolve once to report the missing credentials here instead of at the first request.
+        provider
             .provide_credentials()
             .await
             .map_err(|e| EnvCredentialError(e.to_string()))?;
 
-        Ok(S3Credential::<AWSRegion> {
+        Ok(Self::from_provider(SharedCredentialsProvider::new(provider), region))
+    }
+
+    /// Creates the credential from the named profile in `~/.aws/config` and `~/.aws/credentials`.
+    ///
+    /// The profile can use the static keys, `role_arn`(AssumeRole), `sso_*` settings and
+    /// `credential_process`. The region is also read from the profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `EnvCredentialError` if the profile has no region or the credentials cannot be resolved.
+    pub async fn from_profile(profile_name: &str) -> HikyakuResult<Self> {
+        Self::from_profile_files(profile_name, EnvConfigFiles::default()).await
+    }
+
+    pub(crate) async fn from_profile_files(profile_name: &str, profile_files: EnvConfigFiles) -> HikyakuResult<Self> {
+        let profile_region = ProfileFileRegionProvider::builder()
+            .profile_name(profile_name)
+            .profile_files(profile_files.clone())
+            .build()
+            .region()
+            .await
+            .ok_or(EnvCredentialError(format!("Failed to get region from profile: {}", profile_name)))?;
+        let region = AWSRegion::try_from(profile_region.clone())?;
+
+        let provider = ProfileFileCredentialsProvider::builder()
+            .configure(&ProviderConfig::default().with_region(Some(profile_region)))
+            .profile_name(profile_name)
+            .profile_files(profile_files)
+            .build();
+        provider
+            .provide_credentials()
+            .await
+            .map_err(|e| EnvCredentialError(format!("Failed to get credentials from profile {}: {}", profile_name, e)))?;
+
+        Ok(Self::from_provider(SharedCredentialsProvider::new(provider), region))
+    }
+
+    /// Creates the credential which assumes the role with the OIDC web identity token
+    /// (e.x. GitHub Actions or the other OIDC providers).
+    ///
+    /// # Arguments
+    ///
+    /// * `role_arn` - The ARN of the role to assume.
+    /// * `token_file` - The path of the file which has the web identity token. The file is read
+    ///   again on every refresh, so the rotated token is used.
+    /// * `session_name` - The role session name.
+    /// * `region` - The region of the bucket.
+    pub fn from_web_identity<P: AsRef<Path>>(role_arn: &str, token_file: P, session_name: &str, region: AWSRegion) -> Self {
+        let provider = WebIdentityTokenCredentialsProvider::builder()
+            .configure(&Self::provider_config(&region))
+            .static_configuration(StaticConfiguration {
+                web_identity_token_file: token_file.as_ref().to_path_buf(),
+                role_arn: role_arn.to_string(),
+                session_name: session_name.to_string(),
+            })
+            .build();
+
+        Self::from_provider(SharedCredentialsProvider::new(provider), region)
+    }
+
+    /// Creates the credential from the web identity environment variables(`AWS_ROLE_ARN`,
+    /// `AWS_WEB_IDENTITY_TOKEN_FILE` and `AWS_ROLE_SESSION_NAME`) which IRSA(IAM Roles for
+    /// Service Accounts) of Amazon EKS sets.
+    pub fn from_web_identity_env(region: AWSRegion) -> Self {
+        let provider = WebIdentityTokenCredentialsProvider::builder()
+            .configure(&Self::provider_config(&region))
+            .build();
+
+        Self::from_provider(SharedCredentialsProvider::new(provider), region)
+    }
+
+    /// Creates the credential of IAM Identity Center(SSO).
+    ///
+    /// The SSO access token must be cached by `aws sso login` in advance.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_url` - The start URL of the AWS access portal.
+    /// * `sso_region` - The region where IAM Identity Center is enabled.
+    /// * `account_id` - The AWS account id.
+    /// * `role_name` - The permission set name.
+    /// * `session_name` - The `sso-session` name if the token is cached for the SSO session.
+    /// * `region` - The region of the bucket.
+    pub fn from_sso(start_url: &str,
+                    sso_region: AWSRegion,
+                    account_id: &str,
+                    role_name: &str,
+                    session_name: Option<&str>,
+                    region: AWSRegion) -> Self {
+        let mut builder = SsoCredentialsProvider::builder()
+            .configure(&Self::provider_config(&region))
+            .start_url(start_url)
+            .region(AwsConfigRegion::new(sso_region.get_region().to_string()))
+            .account_id(account_id)
+            .role_name(role_name);
+        builder.set_session_name(session_name.map(String::from));
+
+        Self::from_provider(SharedCredentialsProvider::new(builder.build()), region)
+    }
+
+    fn from_provider(credential: SharedCredentialsProvider, region: AWSRegion) -> Self {
+        Self {
             credential,
             region,
-        })
+            endpoint_url: None,
+            force_path_style: false,
+            checksum_compat: false,
+        }
+    }
+
+    fn provider_config(region: &AWSRegion) -> ProviderConfig {
+        ProviderConfig::default().with_region(Some(AwsConfigRegion::new(region.get_region().to_string())))
     }
 }
 
 impl Credential for S3Credential {
-    type CredentialType = Credentials;
+    type CredentialType = SharedCredentialsProvider;
     type RegionType = AWSRegion;
+    type Options = S3Options;
 
     fn get_credential(&self) -> Self::CredentialType {
         self.credential.clone()
     }
 
     fn get_region(&self) -> Self::RegionType {
-        self.region
+        self.region.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_runtime::env_config::file::EnvConfigFileKind;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_s3_credential_from_profile() {
+        let dir = std::env::temp_dir().join(format!("hikyaku-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("config");
+        let credentials_path = dir.join("credentials");
+        std::fs::write(&config_path, "[profile transfer]\nregion = ap-northeast-1\n").unwrap();
+        std::fs::write(&credentials_path, "[transfer]\naws_access_key_id = AKIATEST\naws_secret_access_key = secret\n").unwrap();
+
+        let profile_files = EnvConfigFiles::builder()
+            .with_file(EnvConfigFileKind::Config, &config_path)
+            .with_file(EnvConfigFileKind::Credentials, &credentials_path)
+            .build();
+        let credential = S3Credential::from_profile_files("transfer", profile_files.clone()).await.unwrap();
+        assert_eq!(credential.get_region(), AWSRegion::Tokyo);
+        let credentials = credential.get_credential().provide_credentials().await.unwrap();
+        assert_eq!(credentials.access_key_id(), "AKIATEST");
+
+        assert!(S3Credential::from_profile_files("missing", profile_files).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_s3_credential_assume_role_partition_mismatch() {
+        let credential = S3Credential::new("access_key", "secret_key", None, None, AWSRegion::Beijing);
+        let options = AssumeRoleOptions::new("arn:aws:iam::123456789012:role/transfer");
+
+        let result = credential.assume_role(options).await;
+        assert!(matches!(result, Err(InvalidArgumentError(_))));
     }
 }
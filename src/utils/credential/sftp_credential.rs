@@ -0,0 +1,112 @@
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+use ssh2::Session;
+use crate::errors::HikyakuError::SftpError;
+use crate::errors::HikyakuResult;
+use crate::types::sftp::{sftp_error, SftpOptions};
+use crate::utils::credential::Credential;
+use crate::utils::region::NoneRegion;
+
+#[derive(Clone)]
+enum SftpAuthMethod {
+    Password(String),
+    PrivateKey {
+        path: PathBuf,
+        passphrase: Option<String>,
+    },
+    Agent,
+}
+
+#[derive(Clone)]
+pub struct SftpAuth {
+    method: SftpAuthMethod,
+}
+
+impl Debug for SftpAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let method = match &self.method {
+            SftpAuthMethod::Password(_) => "Password".to_string(),
+            SftpAuthMethod::PrivateKey {path, ..} => format!("PrivateKey {{ path: {} }}", path.display()),
+            SftpAuthMethod::Agent => "Agent".to_string(),
+        };
+        write!(f, "SftpAuth {{ method: {} }}", method)
+    }
+}
+
+impl SftpAuth {
+    /// Authenticate the user on the session whose handshake is done.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `SftpError` if the server refuses the user.
+    pub(crate) fn authenticate(&self, session: &Session, user: &str) -> HikyakuResult<()> {
+        let result = match &self.method {
+            SftpAuthMethod::Password(password) => session.userauth_password(user, password),
+            SftpAuthMethod::PrivateKey {path, passphrase} => {
+                session.userauth_pubkey_file(user, None, path, passphrase.as_deref())
+            },
+            SftpAuthMethod::Agent => session.userauth_agent(user),
+        };
+        result.map_err(|e| sftp_error(&format!("Failed to authenticate {} by {:?}", user, self), e))?;
+
+        if !session.authenticated() {
+            return Err(SftpError(format!("{} is not authenticated by {:?}", user, self)));
+        }
+        Ok(())
+    }
+}
+
+/// The credential for the SFTP server.
+///
+/// The user is authenticated by the password, the private key file or the keys of ssh-agent.
+/// The user name is written in the path like `sftp://user@host/path`.
+pub struct SftpCredential {
+    credential: SftpAuth,
+}
+
+impl SftpCredential {
+    /// Creates the credential which authenticates the user by the password.
+    pub fn from_password(password: &str) -> Self {
+        Self::with_method(SftpAuthMethod::Password(password.to_string()))
+    }
+
+    /// Creates the credential which authenticates the user by the private key file(e.x. `~/.ssh/id_ed25519`).
+    ///
+    /// # Arguments
+    ///
+    /// * `private_key_path` - The private key file. The public key is derived from it.
+    /// * `passphrase` - The passphrase of the encrypted private key.
+    pub fn from_private_key<P: AsRef<Path>>(private_key_path: P, passphrase: Option<&str>) -> Self {
+        Self::with_method(SftpAuthMethod::PrivateKey {
+            path: private_key_path.as_ref().to_path_buf(),
+            passphrase: passphrase.map(|passphrase| passphrase.to_string()),
+        })
+    }
+
+    /// Creates the credential which authenticates the user by the keys of ssh-agent(`SSH_AUTH_SOCK`).
+    pub fn from_agent() -> Self {
+        Self::with_method(SftpAuthMethod::Agent)
+    }
+
+    fn with_method(method: SftpAuthMethod) -> Self {
+        Self {
+            credential: SftpAuth {
+                method,
+            },
+        }
+    }
+}
+
+impl Credential for SftpCredential {
+    type CredentialType = SftpAuth;
+    type RegionType = NoneRegion;
+    type Options = SftpOptions;
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.credential.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
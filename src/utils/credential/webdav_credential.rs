@@ -0,0 +1,98 @@
+use std::fmt::{Debug, Formatter};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::Client;
+use crate::errors::HikyakuResult;
+use crate::types::webdav::WebDavOptions;
+use crate::utils::credential::Credential;
+use crate::utils::region::NoneRegion;
+use crate::utils::reqwest::AuthType::{Basic, Bearer};
+use crate::utils::reqwest::get_client_with_token;
+
+#[derive(Clone)]
+enum WebDavAuthMethod {
+    Basic {
+        user: String,
+        password: String,
+    },
+    Bearer(String),
+    Anonymous,
+}
+
+#[derive(Clone)]
+pub struct WebDavAuth {
+    method: WebDavAuthMethod,
+}
+
+impl Debug for WebDavAuth {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let method = match &self.method {
+            WebDavAuthMethod::Basic {user, ..} => format!("Basic {{ user: {} }}", user),
+            WebDavAuthMethod::Bearer(_) => "Bearer".to_string(),
+            WebDavAuthMethod::Anonymous => "Anonymous".to_string(),
+        };
+        write!(f, "WebDavAuth {{ method: {} }}", method)
+    }
+}
+
+impl WebDavAuth {
+    /// Create the client which sends the `Authorization` header on every request.
+    pub(crate) fn create_client(&self) -> HikyakuResult<Client> {
+        match &self.method {
+            WebDavAuthMethod::Basic {user, password} => {
+                get_client_with_token(&STANDARD.encode(format!("{}:{}", user, password)), Basic)
+            },
+            WebDavAuthMethod::Bearer(token) => get_client_with_token(token, Bearer),
+            WebDavAuthMethod::Anonymous => Ok(Client::new()),
+        }
+    }
+}
+
+/// The credential for the WebDAV server.
+///
+/// Nextcloud and ownCloud accept the app password of the user by the basic authentication.
+pub struct WebDavCredential {
+    credential: WebDavAuth,
+}
+
+impl WebDavCredential {
+    /// Creates the credential of the basic authentication.
+    pub fn from_basic(user: &str, password: &str) -> Self {
+        Self::with_method(WebDavAuthMethod::Basic {
+            user: user.to_string(),
+            password: password.to_string(),
+        })
+    }
+
+    /// Creates the credential which sends the bearer token(e.x. the OAuth2 access token of Nextcloud).
+    pub fn from_bearer(token: &str) -> Self {
+        Self::with_method(WebDavAuthMethod::Bearer(token.to_string()))
+    }
+
+    /// Creates the credential without the authorization for the public shares.
+    pub fn anonymous() -> Self {
+        Self::with_method(WebDavAuthMethod::Anonymous)
+    }
+
+    fn with_method(method: WebDavAuthMethod) -> Self {
+        Self {
+            credential: WebDavAuth {
+                method,
+            },
+        }
+    }
+}
+
+impl Credential for WebDavCredential {
+    type CredentialType = WebDavAuth;
+    type RegionType = NoneRegion;
+    type Options = WebDavOptions;
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.credential.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
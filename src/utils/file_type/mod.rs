@@ -47,6 +47,7 @@ pub(crate) enum FileType {
     Css,
     Javascript,
     Csv,
+    Markdown,
     GoogleAudio,
     GoogleDocs,
     GoogleDriveThirdPartyShortcut,
@@ -115,6 +116,7 @@ impl FileType {
             FileType::Css => "text/css",
             FileType::Javascript => "application/javascript",
             FileType::Csv => "text/csv",
+            FileType::Markdown => "text/markdown",
             FileType::GoogleAudio => "application/vnd.google-apps.audio",
             FileType::GoogleDocs => "application/vnd.google-apps.document",
             FileType::GoogleDriveThirdPartyShortcut => "application/vnd.google-apps.drive-sdk",
@@ -139,7 +141,76 @@ impl FileType {
         }
     }
 
-    #[allow(dead_code)]
+    /// Get the usual filename extension without the dot. Google Workspace and unknown files have no extension.
+    pub(crate) fn extension(&self) -> Option<&str> {
+        let extension = match self {
+            FileType::Json => "json",
+            FileType::Xml => "xml",
+            FileType::Gzip => "gz",
+            FileType::Zip => "zip",
+            FileType::Tar => "tar",
+            FileType::Rar => "rar",
+            FileType::SevenZip => "7z",
+            FileType::Bzip => "bz",
+            FileType::Bzip2 => "bz2",
+            FileType::Xz => "xz",
+            FileType::Pdf => "pdf",
+            FileType::Jar => "jar",
+            FileType::Epub => "epub",
+            FileType::RichText => "rtf",
+            FileType::MSWord => "docx",
+            FileType::LegacyMSWord => "doc",
+            FileType::OpenDocumentText => "odt",
+            FileType::MSExcel => "xlsx",
+            FileType::LegacyMSExcel => "xls",
+            FileType::OpenDocumentSpreadsheet => "ods",
+            FileType::MSPowerPoint => "pptx",
+            FileType::LegacyMSPowerPoint => "ppt",
+            FileType::OpenDocumentPresentation => "odp",
+            FileType::Mp3 => "mp3",
+            FileType::Midi => "mid",
+            FileType::WebMAudio => "weba",
+            FileType::Wav => "wav",
+            FileType::Aac => "aac",
+            FileType::Mp4 => "mp4",
+            FileType::WebMVideo => "webm",
+            FileType::Avi => "avi",
+            FileType::Jpeg => "jpg",
+            FileType::Png => "png",
+            FileType::Gif => "gif",
+            FileType::Svg => "svg",
+            FileType::Tiff => "tiff",
+            FileType::Webp => "webp",
+            FileType::PlainText => "txt",
+            FileType::Html => "html",
+            FileType::Css => "css",
+            FileType::Javascript => "js",
+            FileType::Csv => "csv",
+            FileType::Markdown => "md",
+            _ => return None,
+        };
+
+        Some(extension)
+    }
+
+    /// Whether the file is the native file of Google Drive(e.x. Google Docs, folder and shortcut).
+    pub(crate) fn is_google_apps(&self) -> bool {
+        self.mime().starts_with("application/vnd.google-apps.")
+    }
+
+    /// Get the Google Workspace type which Google Drive can convert the file to on upload.
+    pub(crate) fn google_workspace_type(&self) -> Option<FileType> {
+        match self {
+            FileType::MSWord | FileType::LegacyMSWord | FileType::OpenDocumentText | FileType::RichText |
+            FileType::PlainText | FileType::Html | FileType::Markdown => Some(FileType::GoogleDocs),
+            FileType::MSExcel | FileType::LegacyMSExcel | FileType::OpenDocumentSpreadsheet |
+            FileType::Csv => Some(FileType::GoogleSheets),
+            FileType::MSPowerPoint | FileType::LegacyMSPowerPoint |
+            FileType::OpenDocumentPresentation => Some(FileType::GoogleSlides),
+            _ => None,
+        }
+    }
+
     pub(crate) fn from_filename(filename: &str) -> Self {
         let path = Path::new(filename)
             .extension()
@@ -193,6 +264,7 @@ impl FileType {
             "css" => FileType::Css,
             "js" | "mjs" => FileType::Javascript,
             "csv" => FileType::Csv,
+            "md" | "markdown" => FileType::Markdown,
             _ => FileType::Unknown
         }
     }
@@ -246,6 +318,7 @@ impl FileType {
             "text/css" => FileType::Css,
             "application/javascript" | "text/javascript" => FileType::Javascript,
             "text/csv" => FileType::Csv,
+            "text/markdown" => FileType::Markdown,
             "application/vnd.google-apps.audio" => FileType::GoogleAudio,
             "application/vnd.google-apps.document" => FileType::GoogleDocs,
             "application/vnd.google-apps.drive-sdk" => FileType::GoogleDriveThirdPartyShortcut,
@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use log::debug;
+use crate::errors::HikyakuError::{ConnectionError, NotExistFileError};
+use crate::errors::HikyakuResult;
+use crate::utils::credential::Credential;
+use crate::utils::region::NoneRegion;
+
+/// Operations of the [MemoryStore] which can be delayed or failed by the hooks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryOperation {
+    Read,
+    Write,
+    List,
+    Delete,
+}
+
+#[derive(Default)]
+struct MemoryStoreInner {
+    // namespace -> path -> object data
+    objects: HashMap<String, BTreeMap<String, Vec<u8>>>,
+    latency: HashMap<MemoryOperation, Duration>,
+    failures: HashMap<MemoryOperation, u32>,
+}
+
+/// In-process object store backing the `mem://namespace/path` file system.
+///
+/// The store is a cheap handle, so the clones share the same objects. This makes it possible
+/// to seed the store in a test, transfer files through Hikyaku and inspect the result afterward.
+/// The latency and failure hooks are useful to exercise retry and resume logic without
+/// any real cloud storage.
+///
+/// # Example
+///
+/// ```
+/// use hikyaku::services::file_system_builder::FileSystemBuilder;
+/// use hikyaku::utils::memory_store::MemoryStore;
+///
+/// let store = MemoryStore::new();
+/// store.insert("bucket", "path/to/file.txt", b"hello".to_vec());
+///
+/// let file_obj = FileSystemBuilder::from(store.clone())
+///     .set_file_path("mem://bucket/path/to/file.txt")
+///     .unwrap()
+///     .build()
+///     .unwrap();
+///
+/// assert!(file_obj.to_string().contains("Memory"));
+/// ```
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    inner: Arc<Mutex<MemoryStoreInner>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert (or overwrite) an object into the store directly without any hooks.
+    pub fn insert(&self, namespace: &str, path: &str, data: Vec<u8>) {
+        self.lock()
+            .objects
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(path.to_string(), data);
+    }
+
+    /// Get a copy of the object data directly without any hooks.
+    pub fn get(&self, namespace: &str, path: &str) -> Option<Vec<u8>> {
+        self.lock()
+            .objects
+            .get(namespace)
+            .and_then(|objects| objects.get(path))
+            .cloned()
+    }
+
+    /// Remove the object directly without any hooks and return its data.
+    pub fn remove(&self, namespace: &str, path: &str) -> Option<Vec<u8>> {
+        self.lock()
+            .objects
+            .get_mut(namespace)
+            .and_then(|objects| objects.remove(path))
+    }
+
+    /// Get the object size. If the object does not exist, return [None].
+    pub fn size(&self, namespace: &str, path: &str) -> Option<u64> {
+        self.lock()
+            .objects
+            .get(namespace)
+            .and_then(|objects| objects.get(path))
+            .map(|data| data.len() as u64)
+    }
+
+    /// Check whether the path is a directory, which has the objects under it.
+    /// The empty path is the root directory of the namespace.
+    pub fn is_dir(&self, namespace: &str, path: &str) -> bool {
+        if path.is_empty() {
+            return true;
+        }
+        let prefix = format!("{}/", path.trim_end_matches('/'));
+        self.lock()
+            .objects
+            .get(namespace)
+            .is_some_and(|objects| objects.range(prefix.clone()..).next().is_some_and(|(key, _)| key.starts_with(&prefix)))
+    }
+
+    /// Delay every call of the `operation` by the `latency`.
+    pub fn set_latency(&self, operation: MemoryOperation, latency: Duration) {
+        self.lock().latency.insert(operation, latency);
+    }
+
+    /// Make the next `times` calls of the `operation` fail with [ConnectionError].
+    pub fn fail_next(&self, operation: MemoryOperation, times: u32) {
+        self.lock().failures.insert(operation, times);
+    }
+
+    /// Remove all latency and failure hooks.
+    pub fn clear_hooks(&self) {
+        let mut inner = self.lock();
+        inner.latency.clear();
+        inner.failures.clear();
+    }
+
+    pub(crate) async fn read_range(&self, namespace: &str, path: &str, start: u64, end: u64) -> HikyakuResult<Vec<u8>> {
+        self.apply_hooks(MemoryOperation::Read).await?;
+
+        let inner = self.lock();
+        let data = inner.objects
+            .get(namespace)
+            .and_then(|objects| objects.get(path))
+            .ok_or_else(|| NotExistFileError(format!("mem://{}/{} does not exist", namespace, path)))?;
+
+        let start = (start as usize).min(data.len());
+        let end = (end as usize + 1).min(data.len());
+
+        Ok(data[start..end].to_vec())
+    }
+
+    /// Write the `data` at `start`. When `is_last` is true, the object is truncated at the end of the data.
+    pub(crate) async fn write_at(&self, namespace: &str, path: &str, start: u64, data: &[u8], is_last: bool) -> HikyakuResult<()> {
+        self.apply_hooks(MemoryOperation::Write).await?;
+
+        let mut inner = self.lock();
+        let object = inner.objects
+            .entry(namespace.to_string())
+            .or_default()
+            .entry(path.to_string())
+            .or_default();
+
+        let start = start as usize;
+        let end = start + data.len();
+        if object.len() < end {
+            object.resize(end, 0);
+        }
+        object[start..end].copy_from_slice(data);
+        if is_last {
+            object.truncate(end);
+        }
+
+        Ok(())
+    }
+
+    /// List the object paths under the `prefix`. The empty prefix lists all objects in the namespace.
+    pub(crate) async fn list(&self, namespace: &str, prefix: &str) -> HikyakuResult<Vec<String>> {
+        self.apply_hooks(MemoryOperation::List).await?;
+
+        let inner = self.lock();
+        let paths = match inner.objects.get(namespace) {
+            Some(objects) => objects
+                .keys()
+                .filter(|path| {
+                    prefix.is_empty() ||
+                        path.as_str() == prefix ||
+                        path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+                })
+                .cloned()
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(paths)
+    }
+
+    pub(crate) async fn delete(&self, namespace: &str, path: &str) -> HikyakuResult<()> {
+        self.apply_hooks(MemoryOperation::Delete).await?;
+
+        self.lock()
+            .objects
+            .get_mut(namespace)
+            .and_then(|objects| objects.remove(path))
+            .map(|_| ())
+            .ok_or_else(|| NotExistFileError(format!("mem://{}/{} does not exist", namespace, path)))
+    }
+
+    async fn apply_hooks(&self, operation: MemoryOperation) -> HikyakuResult<()> {
+        let (latency, fail) = {
+            let mut inner = self.lock();
+            let latency = inner.latency.get(&operation).copied();
+            let fail = match inner.failures.get_mut(&operation) {
+                Some(remain) if *remain > 0 => {
+                    *remain -= 1;
+                    true
+                },
+                _ => false,
+            };
+            (latency, fail)
+        };
+
+        if let Some(latency) = latency {
+            tokio::time::sleep(latency).await;
+        }
+
+        if fail {
+            debug!("Injected failure on memory store operation: {:?}", operation);
+            return Err(ConnectionError(format!("Injected failure on memory store operation: {:?}", operation)));
+        }
+
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryStoreInner> {
+        // The store is mainly used in tests, so a panic in the other test must not poison it.
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Credential for MemoryStore {
+    type CredentialType = MemoryStore;
+    type RegionType = NoneRegion;
+    type Options = ();
+
+    fn get_credential(&self) -> Self::CredentialType {
+        self.clone()
+    }
+
+    fn get_region(&self) -> Self::RegionType {
+        NoneRegion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_store_write_and_read() {
+        let store = MemoryStore::new();
+        store.write_at("ns", "dir/file", 4, b"5678", true).await.unwrap();
+        store.write_at("ns", "dir/file", 0, b"1234", false).await.unwrap();
+
+        assert_eq!(store.get("ns", "dir/file").unwrap(), b"12345678".to_vec());
+        assert_eq!(store.read_range("ns", "dir/file", 2, 5).await.unwrap(), b"3456".to_vec());
+        assert_eq!(store.size("ns", "dir/file"), Some(8));
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_list_and_delete() {
+        let store = MemoryStore::new();
+        store.insert("ns", "dir/a", vec![]);
+        store.insert("ns", "dir/b", vec![]);
+        store.insert("ns", "dir_other/c", vec![]);
+
+        assert_eq!(store.list("ns", "dir").await.unwrap(), vec!["dir/a", "dir/b"]);
+        assert_eq!(store.list("ns", "").await.unwrap().len(), 3);
+
+        store.delete("ns", "dir/a").await.unwrap();
+        assert!(store.delete("ns", "dir/a").await.is_err());
+        assert_eq!(store.list("ns", "dir").await.unwrap(), vec!["dir/b"]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_failure_hook() {
+        let store = MemoryStore::new();
+        store.insert("ns", "file", b"data".to_vec());
+        store.fail_next(MemoryOperation::Read, 2);
+
+        assert!(store.read_range("ns", "file", 0, 3).await.is_err());
+        assert!(store.read_range("ns", "file", 0, 3).await.is_err());
+        assert_eq!(store.read_range("ns", "file", 0, 3).await.unwrap(), b"data".to_vec());
+    }
+}
@@ -2,6 +2,13 @@
 pub mod oauth2;
 pub mod region;
 pub(crate) mod parser;
+#[cfg(any(feature = "azure_blob", feature = "webdav"))]
+pub(crate) mod xml;
 pub mod credential;
+pub mod memory_store;
+#[cfg(any(test, feature = "fake_google_drive"))]
+pub(crate) mod test_support;
+#[cfg(all(any(test, feature = "fake_google_drive"), feature = "google_drive"))]
+pub use test_support::fake_google_drive;
 pub(crate) mod file_type;
 pub(crate) mod reqwest;
\ No newline at end of file
@@ -7,6 +7,32 @@ use crate::types::FileInfo;
 
 // This regex is used to parse the input path into namespace, and path components.
 const FILE_SYSTEM_NAMESPACE_PATH_REGEX: &str = r"^/*([^/]+)/?(.*?[^/])?/*$";
+// The prefixes of the built-in file systems. The registered backends cannot use them even if
+// the cargo feature of the file system is disabled, so a path never changes its backend by the features.
+pub(crate) const BUILTIN_PREFIXES: [&str; 16] = [
+    "file://", "s3://", "gd://", "gds://", "mem://", "dbx://", "box://", "od://", "sp://", "az://", "gs://",
+    "sftp://", "dav://", "davs://", "http://", "https://",
+];
+
+/// Get the cargo feature which enables the built-in prefix if it is disabled.
+/// `file://` and `mem://` are always enabled, and the other prefixes return [None].
+pub(crate) fn missing_feature(prefix: &str) -> Option<&'static str> {
+    let (feature, is_enabled) = match prefix {
+        "s3://" => ("amazon_s3", cfg!(feature = "amazon_s3")),
+        "gd://" | "gds://" => ("google_drive", cfg!(feature = "google_drive")),
+        "dbx://" => ("dropbox", cfg!(feature = "dropbox")),
+        "box://" => ("box_storage", cfg!(feature = "box_storage")),
+        "od://" | "sp://" => ("onedrive", cfg!(feature = "onedrive")),
+        "az://" => ("azure_blob", cfg!(feature = "azure_blob")),
+        "gs://" => ("google_cloud_storage", cfg!(feature = "google_cloud_storage")),
+        "sftp://" => ("sftp", cfg!(feature = "sftp")),
+        "dav://" | "davs://" => ("webdav", cfg!(feature = "webdav")),
+        "http://" | "https://" => ("http", cfg!(feature = "http")),
+        _ => return None,
+    };
+
+    (!is_enabled).then_some(feature)
+}
 
 #[derive(Debug)]
 /// File path parser result.
@@ -38,21 +64,39 @@ impl FileInfo for FileSystemParseResult {
 /// 
 /// # Arguments
 /// - `input`: User input path. This should be start with valid prefixes.
+/// - `registered_prefixes`: The prefixes of the backends registered in `BackendRegistry`(e.x. `acme://`).
 /// 
 /// # Prefixes
 /// - `file://`: Local file system path
 /// - `s3://`: Amazon S3 path
 /// - `gd://`: Google Drive MyDrive path
 /// - `gds://`: Google Drive Shared path (The first path is treated as SharedDrive name)  
-/// ※ Originally, Google Drive has no concept of the path. In a pseudo manner, 
-/// the file parent-child relationship uses as the path.
+///   ※ Originally, Google Drive has no concept of the path. In a pseudo manner, 
+///   the file parent-child relationship uses as the path.
+/// - `mem://`: In-memory store path (The first path is treated as namespace)
+/// - `dbx://`: Dropbox path
+/// - `box://`: Box path
+/// - `od://`: OneDrive path of the signed-in user
+/// - `sp://`: SharePoint document library path (The first path is treated as the site name)
+/// - `az://`: Azure Blob Storage path (The first path is treated as the container name)
+/// - `gs://`: Google Cloud Storage path (The first path is treated as the bucket name)
+/// - `sftp://`: SFTP server path (The first path is treated as `user@host[:port]`)
+/// - `dav://`, `davs://`: WebDAV server path over HTTP and HTTPS (The first path is treated as `host[:port]`)
+/// - `http://`, `https://`: Read-only URL (The host is treated as namespace and the rest is kept as is)
+/// 
+/// The registered prefixes are accepted too, and their first path is treated as namespace.
+/// The built-in prefix whose cargo feature is disabled is refused with the name of the feature.
 /// 
 /// # Returns
 /// - HikyakuResult<[FileSystemParseResult]>: `FileSystemParseResult` has the prefix, 
-/// [Option] of namespace(a.k.a Amazon S3 bucket or Google Drive SharedDrive), path except the namespace.
-/// When the path is invalid, returns [InvalidArgumentError].
-pub(crate) fn file_system_prefix_parser(input: &str) -> HikyakuResult<FileSystemParseResult> {
-    let (prefix, path) = if input.starts_with("file://") {
+///   [Option] of namespace(a.k.a Amazon S3 bucket or Google Drive SharedDrive), path except the namespace.
+///   When the path is invalid, returns [InvalidArgumentError].
+pub(crate) fn file_system_prefix_parser(input: &str, registered_prefixes: &[String]) -> HikyakuResult<FileSystemParseResult> {
+    let registered_prefix = registered_prefixes.iter().find(|prefix| input.starts_with(prefix.as_str()));
+    let (prefix, path) = if let Some(prefix) = registered_prefix {
+        (prefix.as_str(), &input[prefix.len()..])
+    }
+    else if input.starts_with("file://") {
         // SAFETY: In this branch, the input always has 'file://' so the result is always Some.
         let (_, path) = input.split_once("file://").unwrap();
 
@@ -73,13 +117,104 @@ pub(crate) fn file_system_prefix_parser(input: &str) -> HikyakuResult<FileSystem
 
         ("gds://", path)
     }
+    else if input.starts_with("mem://") {
+        let (_, path) = input.split_once("mem://").unwrap();
+
+        ("mem://", path)
+    }
+    else if input.starts_with("dbx://") {
+        let (_, path) = input.split_once("dbx://").unwrap();
+
+        ("dbx://", path)
+    }
+    else if input.starts_with("box://") {
+        let (_, path) = input.split_once("box://").unwrap();
+
+        ("box://", path)
+    }
+    else if input.starts_with("od://") {
+        let (_, path) = input.split_once("od://").unwrap();
+
+        ("od://", path)
+    }
+    else if input.starts_with("sp://") {
+        let (_, path) = input.split_once("sp://").unwrap();
+
+        ("sp://", path)
+    }
+    else if input.starts_with("az://") {
+        let (_, path) = input.split_once("az://").unwrap();
+
+        ("az://", path)
+    }
+    else if input.starts_with("gs://") {
+        let (_, path) = input.split_once("gs://").unwrap();
+
+        ("gs://", path)
+    }
+    else if input.starts_with("sftp://") {
+        let (_, path) = input.split_once("sftp://").unwrap();
+
+        ("sftp://", path)
+    }
+    else if input.starts_with("dav://") {
+        let (_, path) = input.split_once("dav://").unwrap();
+
+        ("dav://", path)
+    }
+    else if input.starts_with("davs://") {
+        let (_, path) = input.split_once("davs://").unwrap();
+
+        ("davs://", path)
+    }
+    else if input.starts_with("http://") {
+        let (_, path) = input.split_once("http://").unwrap();
+
+        ("http://", path)
+    }
+    else if input.starts_with("https://") {
+        let (_, path) = input.split_once("https://").unwrap();
+
+        ("https://", path)
+    }
     else {
         error!("Input path is invalid: {}", input);
-        return Err(InvalidArgumentError(format!("Invalid Path: {} is invalid prefix. Support only 'file://', 's3://', 'gd://', 'gds://'", input)))
+        let supported = BUILTIN_PREFIXES
+            .iter()
+            .filter(|prefix| missing_feature(prefix).is_none())
+            .map(|prefix| prefix.to_string())
+            .chain(registered_prefixes.iter().cloned())
+            .map(|prefix| format!("'{}'", prefix))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(InvalidArgumentError(format!("Invalid Path: {} is invalid prefix. Support only {}", input, supported)))
     };
+    // The registered prefixes are never built-in, so only the built-in ones can miss the feature.
+    if let Some(feature) = missing_feature(prefix) {
+        error!("Input path needs the disabled feature {}: {}", feature, input);
+        return Err(InvalidArgumentError(
+            format!("Invalid Path: {} needs the cargo feature '{}' of hikyaku for '{}'", input, feature, prefix)))
+    }
+
+    // The URL is kept as is, because the path and the query of the web servers can have any characters.
+    if ["http://", "https://"].contains(&prefix) {
+        let (host, path) = path.split_once('/').unwrap_or((path, ""));
+        if host.is_empty() {
+            error!("Input path is invalid due to not have host: {}", input);
+            return Err(InvalidArgumentError(
+                format!("Invalid Path: {} is invalid path. 'http://' and 'https://' must have host", input)))
+        }
+
+        return Ok(FileSystemParseResult {
+            prefix: prefix.to_string(),
+            namespace: Some(host.to_string()),
+            path: path.to_string(),
+        })
+    }
 
-    // s3 and SharedDrive needs namespace
-    if ["s3://", "gds://"].contains(&prefix) {
+    // s3, SharedDrive, in-memory store, SharePoint site, Azure container, GCS bucket, SFTP, WebDAV server
+    // and the registered backends needs namespace
+    if ["s3://", "gds://", "mem://", "sp://", "az://", "gs://", "sftp://", "dav://", "davs://"].contains(&prefix) || registered_prefix.is_some() {
         // SAFETY: The regex statement is const string so this is always Ok().
         let regex = Regex::new(FILE_SYSTEM_NAMESPACE_PATH_REGEX).unwrap();
 
@@ -87,13 +222,13 @@ pub(crate) fn file_system_prefix_parser(input: &str) -> HikyakuResult<FileSystem
             .ok_or_else(|| {
                 error!("Input path is invalid due to not have namespace: {}", path);
                 InvalidArgumentError(
-                    format!("Invalid Path: {} is invalid path. 's3://' and 'gds://' must have namespace", input))
+                    format!("Invalid Path: {} is invalid path. '{}' must have namespace", input, prefix))
             })?;
         let namespace = path_capture.get(1)
             .ok_or_else(|| {
                 error!("Input path is invalid due to not have namespace: {}", path);
                 InvalidArgumentError(
-                    format!("Invalid Path: {} is invalid path. 's3://' and 'gds://' must have namespace", input))
+                    format!("Invalid Path: {} is invalid path. '{}' must have namespace", input, prefix))
             })?
             .as_str()
             .to_string();
@@ -179,49 +314,119 @@ pub(crate) fn path_to_names_vec(path: &str, allow_metacharacter: bool) -> Hikyak
 mod tests {
     use crate::errors::HikyakuError::InvalidArgumentError;
     use crate::types::FileInfo;
-    use super::file_system_prefix_parser;
+    use super::{file_system_prefix_parser, missing_feature};
     
     #[test]
     fn test_file_system_prefix_parser_no_namespace() {
-        let result = file_system_prefix_parser("file:///test/test1/test2").unwrap();
+        let result = file_system_prefix_parser("file:///test/test1/test2", &[]).unwrap();
         assert_eq!(result.get_prefix(), "file://");
         assert_eq!(result.get_namespace(), None);
         assert_eq!(result.get_path(), "test/test1/test2");
         
-        let result = file_system_prefix_parser("gd://test1/test2/").unwrap();
+        let result = file_system_prefix_parser("gd://test1/test2/", &[]).unwrap();
         assert_eq!(result.get_prefix(), "gd://");
         assert_eq!(result.get_namespace(), None);
         assert_eq!(result.get_path(), "test1/test2");
+
+        let result = file_system_prefix_parser("dbx:///datas/train.csv", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "dbx://");
+        assert_eq!(result.get_namespace(), None);
+        assert_eq!(result.get_path(), "datas/train.csv");
     }
     
     #[test]
     fn test_file_system_prefix_parser_with_namespace() {
-        let result = file_system_prefix_parser("s3://test/test1/test2/").unwrap();
+        let result = file_system_prefix_parser("s3://test/test1/test2/", &[]).unwrap();
         assert_eq!(result.get_prefix(), "s3://");
         assert_eq!(result.get_namespace(), Some("test"));
         assert_eq!(result.get_path(), "test1/test2");
         
-        let result = file_system_prefix_parser("gds:///test_gd/test1/test2").unwrap();
+        let result = file_system_prefix_parser("gds:///test_gd/test1/test2", &[]).unwrap();
         assert_eq!(result.get_prefix(), "gds://");
         assert_eq!(result.get_namespace(), Some("test_gd"));
         assert_eq!(result.get_path(), "test1/test2");
+
+        let result = file_system_prefix_parser("mem://test_mem/test1/test2", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "mem://");
+        assert_eq!(result.get_namespace(), Some("test_mem"));
+        assert_eq!(result.get_path(), "test1/test2");
+
+        let result = file_system_prefix_parser("sp://Marketing/plans/2024.xlsx", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "sp://");
+        assert_eq!(result.get_namespace(), Some("Marketing"));
+        assert_eq!(result.get_path(), "plans/2024.xlsx");
+
+        let result = file_system_prefix_parser("az://datas/titanic/train.csv", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "az://");
+        assert_eq!(result.get_namespace(), Some("datas"));
+        assert_eq!(result.get_path(), "titanic/train.csv");
+
+        let result = file_system_prefix_parser("gs://datas/titanic/train.csv", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "gs://");
+        assert_eq!(result.get_namespace(), Some("datas"));
+        assert_eq!(result.get_path(), "titanic/train.csv");
+
+        let result = file_system_prefix_parser("sftp://hikyaku@example.com:2222/~/titanic/train.csv", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "sftp://");
+        assert_eq!(result.get_namespace(), Some("hikyaku@example.com:2222"));
+        assert_eq!(result.get_path(), "~/titanic/train.csv");
+
+        let result = file_system_prefix_parser("davs://cloud.example.com/remote.php/dav/files/alice/train.csv", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "davs://");
+        assert_eq!(result.get_namespace(), Some("cloud.example.com"));
+        assert_eq!(result.get_path(), "remote.php/dav/files/alice/train.csv");
+
+        let result = file_system_prefix_parser("https://example.com:8443/datasets//titanic.csv?token=a/b", &[]).unwrap();
+        assert_eq!(result.get_prefix(), "https://");
+        assert_eq!(result.get_namespace(), Some("example.com:8443"));
+        assert_eq!(result.get_path(), "datasets//titanic.csv?token=a/b");
+        assert!(file_system_prefix_parser("http:///titanic.csv", &[]).is_err());
     }
     
     #[test]
     fn test_file_system_prefix_parser_invalid_prefix() {
-        let result = file_system_prefix_parser("invalid_prefix:///test/test1/test2");
+        let result = file_system_prefix_parser("invalid_prefix:///test/test1/test2", &[]);
         assert!(result.is_err());
         let error = result.unwrap_err();
         assert_eq!(
             error.to_string(), 
             InvalidArgumentError(
                 "Invalid Path: invalid_prefix:///test/test1/test2 is invalid prefix. \
-                Support only 'file://', 's3://', 'gd://', 'gds://'".to_string()).to_string());
+                Support only 'file://', 's3://', 'gd://', 'gds://', 'mem://', 'dbx://', 'box://', 'od://', 'sp://', 'az://', 'gs://', 'sftp://', 'dav://', 'davs://', 'http://', 'https://'".to_string()).to_string());
     }
     
+    #[test]
+    fn test_file_system_prefix_parser_registered_prefix() {
+        let registered = vec!["acme://".to_string()];
+        let result = file_system_prefix_parser("acme://vault/datasets/titanic.csv", &registered).unwrap();
+        assert_eq!(result.get_prefix(), "acme://");
+        assert_eq!(result.get_namespace(), Some("vault"));
+        assert_eq!(result.get_path(), "datasets/titanic.csv");
+
+        let result = file_system_prefix_parser("acme:///", &registered);
+        assert!(matches!(result, Err(InvalidArgumentError(_))));
+
+        let result = file_system_prefix_parser("acme://vault/datasets/titanic.csv", &[]);
+        assert!(result.unwrap_err().to_string().ends_with("'http://', 'https://'"));
+        let result = file_system_prefix_parser("other://vault/titanic.csv", &registered);
+        assert!(result.unwrap_err().to_string().ends_with("'https://', 'acme://'"));
+    }
+
+    #[test]
+    fn test_file_system_prefix_parser_disabled_feature() {
+        assert_eq!(missing_feature("file://"), None);
+        assert_eq!(missing_feature("mem://"), None);
+
+        let result = file_system_prefix_parser("s3://bucket/key", &[]);
+        match missing_feature("s3://") {
+            Some(_) => assert!(result.unwrap_err().to_string().contains("'amazon_s3'")),
+            None => assert!(result.is_ok()),
+        }
+    }
+
     #[test]
     fn test_file_system_prefix_parser_invalid_path() {
-        let result = file_system_prefix_parser("file:///test/test1//test2/");
+        let result = file_system_prefix_parser("file:///test/test1//test2/", &[]);
         assert!(result.is_err());
         let error = result.unwrap_err();
         assert_eq!(
@@ -230,7 +435,7 @@ mod tests {
                 "Invalid Path: file:///test/test1//test2/ is invalid path. \
                 To avoid ambiguous process, '//' or more chain slash cannot use.".to_string()).to_string());
         
-        let result = file_system_prefix_parser("s3:///test//test1/test2");
+        let result = file_system_prefix_parser("s3:///test//test1/test2", &[]);
         assert!(result.is_err());
         let error = result.unwrap_err();
         assert_eq!(
@@ -239,7 +444,7 @@ mod tests {
                 "Invalid Path: s3:///test//test1/test2 is invalid path. \
                 To avoid ambiguous process, '//' or more chain slash cannot use.".to_string()).to_string());
         
-        let result = file_system_prefix_parser("file://////");
+        let result = file_system_prefix_parser("file://////", &[]);
         assert!(result.is_err());
         let error = result.unwrap_err();
         assert_eq!(
@@ -1,7 +1,8 @@
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use aws_config::meta::region::ProvideRegion;
 use aws_config::{Region as AwsConfigRegion};
-use log::error;
+use log::{error, warn};
 use crate::errors::{HikyakuError, HikyakuResult};
 use crate::errors::HikyakuError::InvalidArgumentError;
 use crate::utils::region::Region;
@@ -45,7 +46,11 @@ use crate::utils::region::Region;
 /// * `SaoPaulo` - sa-east-1
 /// * `USEastGovernment` - us-gov-east-1
 /// * `USWestGovernment` - us-gov-west-1
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+/// * `Beijing` - cn-north-1
+/// * `Ningxia` - cn-northwest-1
+/// * `Custom` - Any other region code(e.x. the newly launched region or the region name of
+///   the S3-compatible storage). Use [AWSRegion::custom] to create it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum AWSRegion {
     Ohio,
     NVirginia,
@@ -79,6 +84,107 @@ pub enum AWSRegion {
     SaoPaulo,
     USEastGovernment,
     USWestGovernment,
+    Beijing,
+    Ningxia,
+    Custom(String),
+}
+
+/// AWSPartition represents the isolated group of the AWS regions.
+///
+/// The credentials of a partition cannot be used in the other partitions, so the partition part of
+/// the role ARN is checked against the region before assuming the role. The endpoints are not
+/// derived from the partition, and the AWS SDK selects them from the region code.
+///
+/// # Variants
+///
+/// * `Aws` - The commercial regions(`aws`).
+/// * `AwsCn` - The China regions(`aws-cn`).
+/// * `AwsUsGov` - The AWS GovCloud (US) regions(`aws-us-gov`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AWSPartition {
+    Aws,
+    AwsCn,
+    AwsUsGov,
+}
+
+impl AWSPartition {
+    /// Get the partition name used in the ARNs.
+    pub fn as_str(&self) -> &str {
+        match self {
+            AWSPartition::Aws => "aws",
+            AWSPartition::AwsCn => "aws-cn",
+            AWSPartition::AwsUsGov => "aws-us-gov",
+        }
+    }
+
+    /// Get the partition which the region code belongs to.
+    ///
+    /// The partition is decided by the prefix of the region code, `cn-` for `aws-cn`, `us-gov-` for
+    /// `aws-us-gov` and the others for `aws`. It is the same rule as the region resolver of
+    /// the AWS SDK, which selects the endpoints(e.x. `s3.cn-north-1.amazonaws.com.cn`).
+    pub fn from_region(region: &str) -> Self {
+        if region.starts_with("cn-") {
+            AWSPartition::AwsCn
+        } else if region.starts_with("us-gov-") {
+            AWSPartition::AwsUsGov
+        } else {
+            AWSPartition::Aws
+        }
+    }
+
+    /// Get the partition from the ARN.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the string is not an ARN or the partition is unknown.
+    pub fn from_arn(arn: &str) -> HikyakuResult<Self> {
+        let mut parts = arn.splitn(6, ':');
+        if parts.next() != Some("arn") || arn.split(':').count() < 6 {
+            return Err(InvalidArgumentError(format!("{} is not an ARN", arn)));
+        }
+
+        match parts.next() {
+            Some("aws") => Ok(AWSPartition::Aws),
+            Some("aws-cn") => Ok(AWSPartition::AwsCn),
+            Some("aws-us-gov") => Ok(AWSPartition::AwsUsGov),
+            _ => Err(InvalidArgumentError(format!("Unknown partition in ARN: {}", arn))),
+        }
+    }
+}
+
+impl Display for AWSPartition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl AWSRegion {
+    /// Create the region from the region code.
+    ///
+    /// The known region code is converted to the named variant, and the other code
+    /// becomes `Custom`, so the newly launched regions and the made-up region names of
+    /// S3-compatible storages can be used. It must still be usable in the host name of the endpoints.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the region is not a DNS label(e.x. empty or has spaces).
+    pub fn custom(region: &str) -> HikyakuResult<Self> {
+        if let Ok(known_region) = get_aws_region_from_str(region) {
+            return Ok(known_region);
+        }
+        if !is_region_code(region) {
+            error!("{} is not a valid region code", region);
+            return Err(InvalidArgumentError(format!("{} is not a valid region code", region)));
+        }
+
+        warn!("{} is not a known AWS region, so it is used as a custom region", region);
+        Ok(AWSRegion::Custom(region.to_string()))
+    }
+
+    /// Get the partition which the region belongs to. See [AWSPartition::from_region].
+    pub fn get_partition(&self) -> AWSPartition {
+        AWSPartition::from_region(self.get_region())
+    }
 }
 
 impl Region for AWSRegion {
@@ -117,6 +223,9 @@ impl Region for AWSRegion {
             AWSRegion::SaoPaulo => "sa-east-1",
             AWSRegion::USEastGovernment => "us-gov-east-1",
             AWSRegion::USWestGovernment => "us-gov-west-1",
+            AWSRegion::Beijing => "cn-north-1",
+            AWSRegion::Ningxia => "cn-northwest-1",
+            AWSRegion::Custom(region) => region.as_str(),
         }
     }
 }
@@ -166,6 +275,8 @@ fn get_aws_region_from_str(region_str: &str) -> HikyakuResult<AWSRegion> {
         "sa-east-1" | "saopaulo" => Ok(AWSRegion::SaoPaulo),
         "us-gov-east-1" => Ok(AWSRegion::USEastGovernment),
         "us-gov-west-1" => Ok(AWSRegion::USWestGovernment),
+        "cn-north-1" | "beijing" => Ok(AWSRegion::Beijing),
+        "cn-northwest-1" | "ningxia" => Ok(AWSRegion::Ningxia),
         _ => {
             error!("{} not exist in AWS region", region_str);
             Err(InvalidArgumentError(format!("{} not exist in AWS region", region_str)))
@@ -173,8 +284,18 @@ fn get_aws_region_from_str(region_str: &str) -> HikyakuResult<AWSRegion> {
     }
 }
 
+/// Whether the region code can be a label of the host names(alphanumerics and hyphens up to 63 characters,
+/// which does not start or end with a hyphen).
+fn is_region_code(region: &str) -> bool {
+    !region.is_empty()
+        && region.len() <= 63
+        && !region.starts_with('-')
+        && !region.ends_with('-')
+        && region.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
 impl ProvideRegion for AWSRegion {
-    fn region(&self) -> aws_config::meta::region::future::ProvideRegion {
+    fn region(&self) -> aws_config::meta::region::future::ProvideRegion<'_> {
         aws_config::meta::region::future::ProvideRegion::new(async { 
             Some(AwsConfigRegion::new(self.get_region().to_string()))
         })
@@ -184,11 +305,20 @@ impl ProvideRegion for AWSRegion {
 impl TryFrom<AwsConfigRegion> for AWSRegion {
     type Error = HikyakuError;
     
+    /// The region resolved by the AWS config(environment variables or profiles) can be
+    /// the newly launched region or the region of the S3-compatible storage, so the unknown region
+    /// becomes `Custom` instead of an error like [AWSRegion::custom].
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgumentError` if the region is not a DNS label(e.x. empty or has spaces).
     fn try_from(value: AwsConfigRegion) -> Result<Self, Self::Error> {
-        get_aws_region_from_str(value.as_ref())
+        AWSRegion::custom(value.as_ref())
     }
 }
 
+// Kept as the manual impl of the released versions instead of `#[default]` on the variant.
+#[allow(clippy::derivable_impls)]
 impl Default for AWSRegion {
     fn default() -> Self {
         AWSRegion::Ohio
@@ -199,9 +329,10 @@ impl Default for AWSRegion {
 mod tests {
     use std::str::FromStr;
     use crate::utils::region::Region;
-    use super::AWSRegion;
+    use aws_config::Region as AwsConfigRegion;
+    use super::{AWSPartition, AWSRegion, InvalidArgumentError};
     
-    const AWS_REGION: [(&str, AWSRegion, &str); 32] = [
+    const AWS_REGION: [(&str, AWSRegion, &str); 34] = [
         ("ohio", AWSRegion::Ohio, "us-east-2"),
         ("virginia", AWSRegion::NVirginia, "us-east1"),
         ("california", AWSRegion::NCalifornia, "us-west-1"),
@@ -234,6 +365,8 @@ mod tests {
         ("saopaulo", AWSRegion::SaoPaulo, "sa-east-1"),
         ("us-gov-east-1", AWSRegion::USEastGovernment, "us-gov-east-1"),
         ("us-gov-west-1", AWSRegion::USWestGovernment, "us-gov-west-1"),
+        ("beijing", AWSRegion::Beijing, "cn-north-1"),
+        ("ningxia", AWSRegion::Ningxia, "cn-northwest-1"),
     ];
 
     #[test]
@@ -260,4 +393,31 @@ mod tests {
         let region = AWSRegion::default();
         assert_eq!(region.get_region(), "us-east-2");
     }
+
+    #[test]
+    fn test_region_custom() {
+        let region = AWSRegion::try_from(AwsConfigRegion::new("ap-east-9")).unwrap();
+        assert_eq!(region, AWSRegion::Custom("ap-east-9".to_string()));
+        assert_eq!(region.get_region(), "ap-east-9");
+        assert_eq!(AWSRegion::try_from(AwsConfigRegion::new("ap-northeast-1")).unwrap(), AWSRegion::Tokyo);
+        assert_eq!(AWSRegion::custom("garage").unwrap().get_region(), "garage");
+        assert_eq!(AWSRegion::custom("tokyo").unwrap(), AWSRegion::Tokyo);
+        for invalid in ["", "ap east 9", "-garage", "s3.garage"] {
+            assert!(AWSRegion::try_from(AwsConfigRegion::new(invalid)).is_err(), "{}", invalid);
+            assert!(matches!(AWSRegion::custom(invalid), Err(InvalidArgumentError(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_region_partition() {
+        assert_eq!(AWSRegion::Tokyo.get_partition(), AWSPartition::Aws);
+        assert_eq!(AWSRegion::Beijing.get_partition(), AWSPartition::AwsCn);
+        assert_eq!(AWSRegion::USWestGovernment.get_partition(), AWSPartition::AwsUsGov);
+        assert_eq!(AWSRegion::custom("cn-south-1").unwrap().get_partition(), AWSPartition::AwsCn);
+        assert_eq!(AWSPartition::from_region("us-gov-central-1"), AWSPartition::AwsUsGov);
+
+        assert_eq!(AWSPartition::from_arn("arn:aws-us-gov:iam::123456789012:role/transfer").unwrap(), AWSPartition::AwsUsGov);
+        assert!(AWSPartition::from_arn("arn:aws-iso:iam::123456789012:role/transfer").is_err());
+        assert!(AWSPartition::from_arn("role/transfer").is_err());
+    }
 }
@@ -1,3 +1,4 @@
+#[cfg(feature = "amazon_s3")]
 pub mod aws;
 
 pub trait Region {
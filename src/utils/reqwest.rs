@@ -7,12 +7,14 @@ use crate::errors::HikyakuResult;
 #[derive(Debug, Clone, Copy)]
 pub(crate) enum AuthType {
     Bearer,
+    Basic,
 }
 
 impl Display for AuthType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Bearer => write!(f, "Bearer"),
+            Self::Basic => write!(f, "Basic"),
         }
     }
 }
@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::http::header::{AUTHORIZATION, CONTENT_LENGTH};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hmac::{Hmac, KeyInit, Mac};
+use percent_encoding::percent_decode_str;
+use serde_json::json;
+use sha2::Sha256;
+use crate::types::azure_blob::AzureBlobEndpoint;
+use crate::utils::test_support::{lock, ranged_response, FakeServer};
+
+// The well-known account of the Azurite emulator which `AzureBlobCredential::azurite()` uses.
+const ACCOUNT_NAME: &str = "devstoreaccount1";
+const ACCOUNT_KEY: &str = "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==";
+const SAS_SIGNATURE: &str = "fake-signature";
+const ACCESS_TOKEN: &str = "fake-access-token";
+// The items per page of List Blobs to exercise `NextMarker`.
+const PAGE_SIZE: usize = 2;
+
+#[derive(Default)]
+struct FakeAzureBlobState {
+    // (container, blob) -> data
+    blobs: BTreeMap<(String, String), Vec<u8>>,
+    // (container, blob, block id) -> data
+    uncommitted_blocks: HashMap<(String, String, String), Vec<u8>>,
+    put_blocks: usize,
+    issued_tokens: usize,
+    // [None] means the usual lifetime of about an hour.
+    token_expires_in: Option<u64>,
+}
+
+impl FakeAzureBlobState {
+    fn has_container(&self, container: &str) -> bool {
+        self.blobs.keys().any(|(c, _)| c == container)
+    }
+
+    /// List the blobs and the virtual directories under the prefix with the `/` delimiter.
+    fn list(&self, container: &str, prefix: &str) -> Vec<(bool, String)> {
+        let mut entries = vec![];
+        for (c, blob) in self.blobs.keys() {
+            let Some(rest) = blob.strip_prefix(prefix).filter(|_| c == container) else {
+                continue;
+            };
+            let entry = match rest.split_once('/') {
+                Some((name, _)) => (true, format!("{}{}/", prefix, name)),
+                None => (false, blob.to_string()),
+            };
+            if !entries.contains(&entry) {
+                entries.push(entry);
+            }
+        }
+
+        entries
+    }
+}
+
+type SharedState = Arc<Mutex<FakeAzureBlobState>>;
+
+/// A small local fake of the Blob service of Azure Storage for the tests.
+///
+/// It addresses the account by the path like Azurite, and the containers exist implicitly
+/// as the parents of the blobs. The requests are authorized independently of the crate by
+/// the Shared Key of the well-known account, the SAS token from `sas_token` or the Entra ID token
+/// which the token endpoint under `authority_host` issues.
+pub(crate) struct FakeAzureBlob {
+    server: FakeServer<FakeAzureBlobState>,
+}
+
+impl FakeAzureBlob {
+    pub(crate) async fn start() -> Self {
+        let router = Router::new()
+            .route("/login/:tenant_id/oauth2/v2.0/token", post(issue_token))
+            .route(&format!("/{}/:container", ACCOUNT_NAME), get(list_blobs))
+            .route(&format!("/{}/:container/*blob", ACCOUNT_NAME),
+                   get(handle_blob).head(handle_blob).put(handle_blob).delete(handle_blob))
+            // The blocks exceed the default body limit.
+            .layer(DefaultBodyLimit::disable());
+
+        Self {
+            server: FakeServer::start(|_| FakeAzureBlobState::default(), router).await.unwrap(),
+        }
+    }
+
+    pub(crate) fn endpoint(&self) -> AzureBlobEndpoint {
+        AzureBlobEndpoint::new(&format!("{}/{}", self.server.root_url(), ACCOUNT_NAME))
+    }
+
+    pub(crate) fn authority_host(&self) -> String {
+        format!("{}/login", self.server.root_url())
+    }
+
+    pub(crate) fn sas_token(&self) -> String {
+        format!("sv=2021-08-06&ss=b&srt=sco&sp=rwdlc&sig={}", SAS_SIGNATURE)
+    }
+
+    pub(crate) fn add_blob(&self, container: &str, blob: &str, data: Vec<u8>) {
+        self.server.lock().blobs.insert((container.to_string(), blob.to_string()), data);
+    }
+
+    pub(crate) fn get_blob(&self, container: &str, blob: &str) -> Option<Vec<u8>> {
+        self.server.lock().blobs.get(&(container.to_string(), blob.to_string())).cloned()
+    }
+
+    pub(crate) fn put_blocks(&self) -> usize {
+        self.server.lock().put_blocks
+    }
+
+    pub(crate) fn issued_tokens(&self) -> usize {
+        self.server.lock().issued_tokens
+    }
+
+    /// Set the lifetime in seconds of the tokens issued after this.
+    pub(crate) fn set_token_expires_in(&self, expires_in: u64) {
+        self.server.lock().token_expires_in = Some(expires_in);
+    }
+}
+
+fn error_response(status: StatusCode, code: &str) -> Response {
+    let body = format!("<?xml version=\"1.0\" encoding=\"utf-8\"?><Error><Code>{}</Code></Error>", code);
+    (status, body).into_response()
+}
+
+fn query_params(uri: &Uri) -> Vec<(String, String)> {
+    uri.query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |s: &str| percent_decode_str(&s.replace('+', " ")).decode_utf8_lossy().to_string();
+            (decode(name), decode(value))
+        })
+        .collect()
+}
+
+fn query_param(uri: &Uri, name: &str) -> Option<String> {
+    query_params(uri).into_iter().find(|(n, _)| n == name).map(|(_, value)| value)
+}
+
+/// Verify the authorization of the request by the Shared Key, the SAS signature or the bearer token.
+fn is_authorized(method: &Method, uri: &Uri, headers: &HeaderMap) -> bool {
+    let authorization = headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok());
+    match authorization {
+        Some(authorization) if authorization.starts_with("Bearer ") => {
+            authorization == format!("Bearer {}", ACCESS_TOKEN)
+        },
+        Some(authorization) => {
+            let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).unwrap_or_default().to_string();
+            let content_length = Some(header("content-length")).filter(|length| length != "0").unwrap_or_default();
+            let mut ms_headers = headers
+                .iter()
+                .filter(|(name, _)| name.as_str().starts_with("x-ms-"))
+                .map(|(name, value)| format!("{}:{}\n", name, value.to_str().unwrap_or_default()))
+                .collect::<Vec<_>>();
+            ms_headers.sort();
+            let mut params = query_params(uri);
+            params.sort();
+            let resource = params
+                .iter()
+                .fold(format!("/{}{}", ACCOUNT_NAME, uri.path()), |resource, (name, value)| format!("{}\n{}:{}", resource, name, value));
+
+            let string_to_sign = [
+                method.to_string(), header("content-encoding"), header("content-language"), content_length,
+                header("content-md5"), header("content-type"), header("date"), header("if-modified-since"),
+                header("if-match"), header("if-none-match"), header("if-unmodified-since"), header("range"),
+                format!("{}{}", ms_headers.concat(), resource),
+            ].join("\n");
+            let mut mac = Hmac::<Sha256>::new_from_slice(&STANDARD.decode(ACCOUNT_KEY).unwrap()).unwrap();
+            mac.update(string_to_sign.as_bytes());
+
+            authorization == format!("SharedKey {}:{}", ACCOUNT_NAME, STANDARD.encode(mac.finalize().into_bytes()))
+        },
+        None => query_param(uri, "sig").as_deref() == Some(SAS_SIGNATURE),
+    }
+}
+
+async fn issue_token(State(state): State<SharedState>, Form(params): Form<HashMap<String, String>>) -> Response {
+    let is_valid = params.get("grant_type").map(String::as_str) == Some("client_credentials")
+        && params.get("scope").map(String::as_str) == Some("https://storage.azure.com/.default")
+        && params.contains_key("client_id")
+        && params.contains_key("client_secret");
+    if !is_valid {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid_request"}))).into_response();
+    }
+
+    let mut state = lock(&state);
+    state.issued_tokens += 1;
+    let expires_in = state.token_expires_in.unwrap_or(3599);
+    Json(json!({"token_type": "Bearer", "expires_in": expires_in, "access_token": ACCESS_TOKEN})).into_response()
+}
+
+async fn list_blobs(State(state): State<SharedState>,
+                    Path(container): Path<String>,
+                    method: Method,
+                    uri: Uri,
+                    headers: HeaderMap) -> Response {
+    if !is_authorized(&method, &uri, &headers) {
+        return error_response(StatusCode::FORBIDDEN, "AuthenticationFailed");
+    }
+    if query_param(&uri, "restype").as_deref() != Some("container") || query_param(&uri, "comp").as_deref() != Some("list") {
+        return error_response(StatusCode::BAD_REQUEST, "UnsupportedQueryParameter");
+    }
+
+    let state = lock(&state);
+    if !state.has_container(&container) {
+        return error_response(StatusCode::NOT_FOUND, "ContainerNotFound");
+    }
+    let prefix = query_param(&uri, "prefix").unwrap_or_default();
+    let entries = state.list(&container, &prefix);
+    let skip = query_param(&uri, "marker").and_then(|marker| marker.parse::<usize>().ok()).unwrap_or(0);
+    let page_size = query_param(&uri, "maxresults")
+        .and_then(|max_results| max_results.parse::<usize>().ok())
+        .unwrap_or(PAGE_SIZE)
+        .min(PAGE_SIZE);
+    let end = (skip + page_size).min(entries.len());
+
+    let items = entries[skip.min(end)..end]
+        .iter()
+        .map(|(is_prefix, name)| if *is_prefix {
+            format!("<BlobPrefix><Name>{}</Name></BlobPrefix>", name.replace('&', "&amp;"))
+        } else {
+            format!("<Blob><Name>{}</Name><Properties /></Blob>", name.replace('&', "&amp;"))
+        })
+        .collect::<String>();
+    let next_marker = if end < entries.len() { end.to_string() } else { String::new() };
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><EnumerationResults><Blobs>{}</Blobs><NextMarker>{}</NextMarker></EnumerationResults>",
+        items, next_marker);
+
+    (StatusCode::OK, body).into_response()
+}
+
+async fn handle_blob(State(state): State<SharedState>,
+                     Path((container, blob)): Path<(String, String)>,
+                     method: Method,
+                     uri: Uri,
+                     headers: HeaderMap,
+                     body: Bytes) -> Response {
+    if !is_authorized(&method, &uri, &headers) {
+        return error_response(StatusCode::FORBIDDEN, "AuthenticationFailed");
+    }
+
+    let mut state = lock(&state);
+    let key = (container.clone(), blob.clone());
+    match method {
+        Method::HEAD => match state.blobs.get(&key) {
+            Some(data) => (StatusCode::OK, [(CONTENT_LENGTH, data.len().to_string())]).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        },
+        Method::GET => {
+            let Some(data) = state.blobs.get(&key) else {
+                return error_response(StatusCode::NOT_FOUND, "BlobNotFound");
+            };
+            ranged_response(&headers, "x-ms-range", data)
+        },
+        Method::PUT => match query_param(&uri, "comp").as_deref() {
+            Some("block") => {
+                let Some(block_id) = query_param(&uri, "blockid") else {
+                    return error_response(StatusCode::BAD_REQUEST, "InvalidQueryParameterValue");
+                };
+                state.uncommitted_blocks.insert((container, blob, block_id), body.to_vec());
+                state.put_blocks += 1;
+                StatusCode::CREATED.into_response()
+            },
+            Some("blocklist") => {
+                let body = String::from_utf8_lossy(&body).to_string();
+                let mut data = vec![];
+                for element in body.split("<Latest>").skip(1) {
+                    let block_id = element.split("</Latest>").next().unwrap_or_default().to_string();
+                    match state.uncommitted_blocks.get(&(container.clone(), blob.clone(), block_id)) {
+                        Some(block) => data.extend_from_slice(block),
+                        None => return error_response(StatusCode::BAD_REQUEST, "InvalidBlockList"),
+                    }
+                }
+                state.uncommitted_blocks.retain(|(c, b, _), _| !(c == &container && b == &blob));
+                state.blobs.insert(key, data);
+                StatusCode::CREATED.into_response()
+            },
+            Some(_) => error_response(StatusCode::BAD_REQUEST, "UnsupportedQueryParameter"),
+            None => {
+                if headers.get("x-ms-blob-type").and_then(|value| value.to_str().ok()) != Some("BlockBlob") {
+                    return error_response(StatusCode::BAD_REQUEST, "MissingRequiredHeader");
+                }
+                state.blobs.insert(key, body.to_vec());
+                StatusCode::CREATED.into_response()
+            },
+        },
+        Method::DELETE => match state.blobs.remove(&key) {
+            Some(_) => StatusCode::ACCEPTED.into_response(),
+            None => error_response(StatusCode::NOT_FOUND, "BlobNotFound"),
+        },
+        _ => error_response(StatusCode::METHOD_NOT_ALLOWED, "UnsupportedHttpVerb"),
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::http::header::CONTENT_TYPE;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use crate::types::box_storage::{sha1_digest, BoxEndpoint, BOX_ROOT_FOLDER_ID};
+use crate::utils::test_support::{chunk_range, lock, ranged_response, FakeServer};
+
+// The part size of the upload session which Box decides for the files up to about 500 MiB.
+const DEFAULT_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+/// A file(or folder) stored in the [FakeBox].
+#[derive(Debug, Clone)]
+pub(crate) struct FakeBoxItem {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) parent_id: String,
+    pub(crate) is_folder: bool,
+    pub(crate) data: Vec<u8>,
+}
+
+impl FakeBoxItem {
+    fn to_json(&self) -> Value {
+        let mut value = json!({
+            "type": if self.is_folder { "folder" } else { "file" },
+            "id": self.id,
+            "name": self.name,
+        });
+        if !self.is_folder {
+            value["size"] = json!(self.data.len());
+        }
+
+        value
+    }
+}
+
+struct FakeBoxSession {
+    folder_id: Option<String>,
+    file_id: Option<String>,
+    file_name: Option<String>,
+    file_size: u64,
+    data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct FakeBoxState {
+    items: Vec<FakeBoxItem>,
+    sessions: HashMap<String, FakeBoxSession>,
+    uploaded_parts: usize,
+    next_id: u64,
+    root_url: String,
+}
+
+impl FakeBoxState {
+    fn generate_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("{}", 1000 + self.next_id)
+    }
+
+    fn store_file(&mut self, parent_id: &str, name: &str, data: Vec<u8>) -> FakeBoxItem {
+        let id = self.generate_id();
+        let item = FakeBoxItem {
+            id,
+            name: name.to_string(),
+            parent_id: parent_id.to_string(),
+            is_folder: false,
+            data,
+        };
+        self.items.push(item.clone());
+
+        item
+    }
+
+    fn update_file(&mut self, file_id: &str, data: Vec<u8>) -> Option<FakeBoxItem> {
+        let item = self.items.iter_mut().find(|item| item.id == file_id)?;
+        item.data = data;
+
+        Some(item.clone())
+    }
+}
+
+type SharedState = Arc<Mutex<FakeBoxState>>;
+
+/// A small local fake of the Box API for the tests.
+///
+/// The API routes are served under `/2.0` and the upload routes under `/upload/2.0`.
+pub(crate) struct FakeBox {
+    server: FakeServer<FakeBoxState>,
+}
+
+impl FakeBox {
+    pub(crate) async fn start() -> Self {
+        let router = Router::new()
+            .route("/2.0/folders", post(create_folder))
+            .route("/2.0/folders/:folder_id", delete(delete_item))
+            .route("/2.0/folders/:folder_id/items", get(list_items))
+            .route("/2.0/files/:file_id", get(get_file).delete(delete_item))
+            .route("/2.0/files/:file_id/content", get(download))
+            .route("/upload/2.0/files/content", post(upload))
+            .route("/upload/2.0/files/:file_id/content", post(upload_version))
+            .route("/upload/2.0/files/upload_sessions", post(create_session))
+            .route("/upload/2.0/files/:file_id/upload_sessions", post(create_version_session))
+            .route("/upload/2.0/sessions/:session_id", put(upload_part).delete(abort_session))
+            .route("/upload/2.0/sessions/:session_id/commit", post(commit))
+            // The parts of the upload session(8 MiB) exceed the default body limit.
+            .layer(DefaultBodyLimit::disable());
+        let state = |root_url: &str| FakeBoxState {
+            root_url: root_url.to_string(),
+            ..FakeBoxState::default()
+        };
+
+        Self {
+            server: FakeServer::start(state, router).await.unwrap(),
+        }
+    }
+
+    pub(crate) fn endpoint(&self) -> BoxEndpoint {
+        BoxEndpoint::new(&format!("{}/2.0", self.server.root_url()), &format!("{}/upload/2.0", self.server.root_url()))
+    }
+
+    pub(crate) fn add_folder(&self, name: &str, parent_id: &str) -> String {
+        let mut state = self.server.lock();
+        let id = state.generate_id();
+        state.items.push(FakeBoxItem {
+            id: id.clone(),
+            name: name.to_string(),
+            parent_id: parent_id.to_string(),
+            is_folder: true,
+            data: vec![],
+        });
+
+        id
+    }
+
+    pub(crate) fn add_file(&self, name: &str, parent_id: &str, data: Vec<u8>) -> String {
+        self.server.lock().store_file(parent_id, name, data).id
+    }
+
+    pub(crate) fn find_file(&self, name: &str) -> Option<FakeBoxItem> {
+        self.server.lock().items.iter().find(|item| item.name == name).cloned()
+    }
+
+    pub(crate) fn find_files_by_parent(&self, parent_id: &str) -> Vec<FakeBoxItem> {
+        self.server.lock().items.iter().filter(|item| item.parent_id == parent_id).cloned().collect()
+    }
+
+    pub(crate) fn uploaded_parts(&self) -> usize {
+        self.server.lock().uploaded_parts
+    }
+
+    /// Get the number of the upload sessions which are neither committed nor aborted.
+    pub(crate) fn open_sessions(&self) -> usize {
+        self.server.lock().sessions.len()
+    }
+}
+
+fn not_found() -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({"type": "error", "status": 404, "code": "not_found"}))).into_response()
+}
+
+async fn list_items(State(state): State<SharedState>,
+                    Path(folder_id): Path<String>,
+                    Query(params): Query<HashMap<String, String>>) -> Response {
+    let state = lock(&state);
+    if folder_id != BOX_ROOT_FOLDER_ID && !state.items.iter().any(|item| item.id == folder_id && item.is_folder) {
+        return not_found();
+    }
+    let offset = params.get("offset").and_then(|offset| offset.parse::<usize>().ok()).unwrap_or(0);
+    let limit = params.get("limit").and_then(|limit| limit.parse::<usize>().ok()).unwrap_or(100);
+    let items = state.items.iter().filter(|item| item.parent_id == folder_id).collect::<Vec<_>>();
+    let entries = items.iter().skip(offset).take(limit).map(|item| item.to_json()).collect::<Vec<_>>();
+
+    Json(json!({"total_count": items.len(), "entries": entries, "offset": offset, "limit": limit})).into_response()
+}
+
+async fn get_file(State(state): State<SharedState>, Path(file_id): Path<String>) -> Response {
+    match lock(&state).items.iter().find(|item| item.id == file_id && !item.is_folder) {
+        Some(item) => Json(item.to_json()).into_response(),
+        None => not_found(),
+    }
+}
+
+async fn delete_item(State(state): State<SharedState>, Path(item_id): Path<String>) -> Response {
+    let mut state = lock(&state);
+    if state.items.iter().any(|item| item.parent_id == item_id) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"code": "folder_not_empty"}))).into_response();
+    }
+    let count = state.items.len();
+    state.items.retain(|item| item.id != item_id);
+    if state.items.len() == count {
+        return not_found();
+    }
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+async fn create_folder(State(state): State<SharedState>, Json(request): Json<Value>) -> Response {
+    let mut state = lock(&state);
+    let name = request["name"].as_str().unwrap_or_default().to_string();
+    let parent_id = request["parent"]["id"].as_str().unwrap_or_default().to_string();
+    if let Some(conflict) = state.items.iter().find(|item| item.parent_id == parent_id && item.name == name) {
+        let conflicts = json!({"context_info": {"conflicts": [{"id": conflict.id}]}});
+        return (StatusCode::CONFLICT, Json(conflicts)).into_response();
+    }
+    let id = state.generate_id();
+    let folder = FakeBoxItem {
+        id,
+        name,
+        parent_id,
+        is_folder: true,
+        data: vec![],
+    };
+    state.items.push(folder.clone());
+
+    (StatusCode::CREATED, Json(folder.to_json())).into_response()
+}
+
+async fn download(State(state): State<SharedState>, Path(file_id): Path<String>, headers: HeaderMap) -> Response {
+    let Some(item) = lock(&state).items.iter().find(|item| item.id == file_id).cloned() else {
+        return not_found();
+    };
+    ranged_response(&headers, "range", &item.data)
+}
+
+/// Split the `multipart/form-data` body to the `attributes` JSON and the file data.
+fn parse_multipart(headers: &HeaderMap, body: &[u8]) -> Option<(Value, Vec<u8>)> {
+    let boundary = headers
+        .get(CONTENT_TYPE)?
+        .to_str()
+        .ok()?
+        .split_once("boundary=")?
+        .1
+        .to_string();
+    let delimiter = format!("\r\n--{}", boundary);
+    // Prepend CRLF so every part starts with the same delimiter.
+    let body = [b"\r\n".as_slice(), body].concat();
+    let find = |data: &[u8], pattern: &[u8], from: usize| {
+        data[from..].windows(pattern.len()).position(|window| window == pattern).map(|position| position + from)
+    };
+
+    let mut parts = vec![];
+    let mut position = find(&body, delimiter.as_bytes(), 0)? + delimiter.len();
+    while let Some(next) = find(&body, delimiter.as_bytes(), position) {
+        let part = &body[position..next];
+        let header_end = find(part, b"\r\n\r\n", 0)?;
+        parts.push(part[header_end + 4..].to_vec());
+        position = next + delimiter.len();
+    }
+
+    let attributes = serde_json::from_slice(parts.first()?).ok()?;
+    Some((attributes, parts.get(1)?.clone()))
+}
+
+async fn upload(State(state): State<SharedState>, headers: HeaderMap, body: Bytes) -> Response {
+    let Some((attributes, data)) = parse_multipart(&headers, &body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let item = lock(&state).store_file(
+        attributes["parent"]["id"].as_str().unwrap_or_default(),
+        attributes["name"].as_str().unwrap_or_default(),
+        data);
+
+    (StatusCode::CREATED, Json(json!({"total_count": 1, "entries": [item.to_json()]}))).into_response()
+}
+
+async fn upload_version(State(state): State<SharedState>,
+                        Path(file_id): Path<String>,
+                        headers: HeaderMap,
+                        body: Bytes) -> Response {
+    let Some((_, data)) = parse_multipart(&headers, &body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    match lock(&state).update_file(&file_id, data) {
+        Some(item) => (StatusCode::CREATED, Json(json!({"total_count": 1, "entries": [item.to_json()]}))).into_response(),
+        None => not_found(),
+    }
+}
+
+fn start_session(state: &SharedState, session: FakeBoxSession) -> Response {
+    let mut state = lock(state);
+    let session_id = state.generate_id();
+    let upload_part = format!("{}/upload/2.0/sessions/{}", state.root_url, session_id);
+    let commit = format!("{}/commit", upload_part);
+    let total_parts = session.file_size.div_ceil(DEFAULT_PART_SIZE);
+    state.sessions.insert(session_id.clone(), session);
+
+    (StatusCode::CREATED, Json(json!({
+        "id": session_id,
+        "part_size": DEFAULT_PART_SIZE,
+        "total_parts": total_parts,
+        "session_endpoints": {"upload_part": upload_part.clone(), "commit": commit, "abort": upload_part},
+    }))).into_response()
+}
+
+async fn abort_session(State(state): State<SharedState>, Path(session_id): Path<String>) -> Response {
+    match lock(&state).sessions.remove(&session_id) {
+        Some(_) => StatusCode::NO_CONTENT.into_response(),
+        None => not_found(),
+    }
+}
+
+async fn create_session(State(state): State<SharedState>, Json(request): Json<Value>) -> Response {
+    start_session(&state, FakeBoxSession {
+        folder_id: request["folder_id"].as_str().map(String::from),
+        file_id: None,
+        file_name: request["file_name"].as_str().map(String::from),
+        file_size: request["file_size"].as_u64().unwrap_or_default(),
+        data: vec![],
+    })
+}
+
+async fn create_version_session(State(state): State<SharedState>,
+                                Path(file_id): Path<String>,
+                                Json(request): Json<Value>) -> Response {
+    start_session(&state, FakeBoxSession {
+        folder_id: None,
+        file_id: Some(file_id),
+        file_name: None,
+        file_size: request["file_size"].as_u64().unwrap_or_default(),
+        data: vec![],
+    })
+}
+
+async fn upload_part(State(state): State<SharedState>,
+                     Path(session_id): Path<String>,
+                     headers: HeaderMap,
+                     body: Bytes) -> Response {
+    let digest = headers.get("Digest").and_then(|digest| digest.to_str().ok()).unwrap_or_default();
+    if digest != sha1_digest(&body) {
+        return (StatusCode::PRECONDITION_FAILED, "Digest mismatch").into_response();
+    }
+    let offset = chunk_range(&headers).map(|(start, _, _)| start);
+
+    let mut state = lock(&state);
+    state.uploaded_parts += 1;
+    let Some(session) = state.sessions.get_mut(&session_id) else {
+        return not_found();
+    };
+    let Some(offset) = offset.filter(|offset| *offset == session.data.len()) else {
+        return (StatusCode::RANGE_NOT_SATISFIABLE, "Invalid Content-Range").into_response();
+    };
+    let part_id = format!("{:08X}", offset);
+    let size = body.len();
+    session.data.extend_from_slice(&body);
+
+    Json(json!({"part": {"part_id": part_id, "offset": offset, "size": size, "sha1": digest}})).into_response()
+}
+
+async fn commit(State(state): State<SharedState>,
+                Path(session_id): Path<String>,
+                headers: HeaderMap,
+                Json(request): Json<Value>) -> Response {
+    let mut state = lock(&state);
+    let Some(session) = state.sessions.remove(&session_id) else {
+        return not_found();
+    };
+    let digest = headers.get("Digest").and_then(|digest| digest.to_str().ok()).unwrap_or_default();
+    let parts = request["parts"].as_array().map(Vec::len).unwrap_or_default();
+    if digest != sha1_digest(&session.data) || session.data.len() as u64 != session.file_size || parts == 0 {
+        return (StatusCode::PRECONDITION_FAILED, "Invalid commit").into_response();
+    }
+
+    let item = match (&session.file_id, &session.folder_id) {
+        (Some(file_id), _) => state.update_file(file_id, session.data),
+        (None, Some(folder_id)) => Some(state.store_file(folder_id, session.file_name.as_deref().unwrap_or_default(), session.data)),
+        (None, None) => None,
+    };
+    match item {
+        Some(item) => (StatusCode::CREATED, Json(json!({"total_count": 1, "entries": [item.to_json()]}))).into_response(),
+        None => not_found(),
+    }
+}
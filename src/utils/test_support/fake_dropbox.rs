@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use crate::types::dropbox::{DropboxEndpoint, DROPBOX_API_ARG};
+use crate::utils::test_support::{lock, ranged_response, FakeServer};
+
+// The entries per page of `files/list_folder` to exercise `files/list_folder/continue`.
+const LIST_PAGE_SIZE: usize = 1;
+
+#[derive(Default)]
+struct FakeDropboxState {
+    // path -> data
+    files: BTreeMap<String, Vec<u8>>,
+    // session id -> received data
+    sessions: HashMap<String, Vec<u8>>,
+    // route -> request count
+    requests: HashMap<String, usize>,
+    next_id: u64,
+}
+
+type SharedState = Arc<Mutex<FakeDropboxState>>;
+
+/// A small local fake of the Dropbox API v2 for the tests.
+///
+/// The API routes are served under `/api/2` and the content routes under `/content/2`.
+/// The folders exist implicitly as the parents of the files.
+pub(crate) struct FakeDropbox {
+    server: FakeServer<FakeDropboxState>,
+}
+
+impl FakeDropbox {
+    pub(crate) async fn start() -> Self {
+        let router = Router::new()
+            .route("/api/2/*route", post(handle_api))
+            .route("/content/2/*route", post(handle_content));
+
+        Self {
+            server: FakeServer::start(|_| FakeDropboxState::default(), router).await.unwrap(),
+        }
+    }
+
+    pub(crate) fn endpoint(&self) -> DropboxEndpoint {
+        DropboxEndpoint::new(&format!("{}/api/2", self.server.root_url()), &format!("{}/content/2", self.server.root_url()))
+    }
+
+    pub(crate) fn add_file(&self, path: &str, data: Vec<u8>) {
+        self.server.lock().files.insert(path.to_string(), data);
+    }
+
+    pub(crate) fn get_file(&self, path: &str) -> Option<Vec<u8>> {
+        self.server.lock().files.get(path).cloned()
+    }
+
+    /// Get the number of the requests to the route(e.x. `files/upload_session/start`).
+    pub(crate) fn requests(&self, route: &str) -> usize {
+        self.server.lock().requests.get(route).copied().unwrap_or(0)
+    }
+}
+
+fn conflict(summary: &str) -> Response {
+    (StatusCode::CONFLICT, Json(json!({"error_summary": summary}))).into_response()
+}
+
+fn file_metadata(path: &str, size: usize) -> Value {
+    json!({".tag": "file", "path_display": path, "size": size})
+}
+
+fn folder_metadata(path: &str) -> Value {
+    json!({".tag": "folder", "path_display": path})
+}
+
+/// List the direct children of the folder as the metadata.
+fn children(state: &FakeDropboxState, folder: &str) -> Vec<Value> {
+    let prefix = format!("{}/", folder);
+    let mut folders = vec![];
+    let mut entries = vec![];
+    for (path, data) in &state.files {
+        let Some(rest) = path.strip_prefix(&prefix) else {
+            continue;
+        };
+        match rest.split_once('/') {
+            Some((name, _)) => {
+                let folder = format!("{}{}", prefix, name);
+                if !folders.contains(&folder) {
+                    entries.push(folder_metadata(&folder));
+                    folders.push(folder);
+                }
+            },
+            None => entries.push(file_metadata(path, data.len())),
+        }
+    }
+
+    entries
+}
+
+async fn handle_api(State(state): State<SharedState>, Path(route): Path<String>, Json(arg): Json<Value>) -> Response {
+    let mut state = lock(&state);
+    *state.requests.entry(route.clone()).or_default() += 1;
+
+    match route.as_str() {
+        "files/get_metadata" => {
+            let path = arg["path"].as_str().unwrap_or_default();
+            if let Some(data) = state.files.get(path) {
+                return Json(file_metadata(path, data.len())).into_response();
+            }
+            if state.files.keys().any(|file| file.starts_with(&format!("{}/", path))) {
+                return Json(folder_metadata(path)).into_response();
+            }
+            conflict("path/not_found/..")
+        },
+        "files/list_folder" | "files/list_folder/continue" => {
+            // The cursor is `{folder}:{start index}`.
+            let (folder, start) = match arg["cursor"].as_str() {
+                Some(cursor) => {
+                    let (folder, start) = cursor.rsplit_once(':').unwrap();
+                    (folder.to_string(), start.parse::<usize>().unwrap())
+                },
+                None => (arg["path"].as_str().unwrap_or_default().to_string(), 0),
+            };
+            let entries = children(&state, &folder);
+            let end = (start + LIST_PAGE_SIZE).min(entries.len());
+
+            Json(json!({
+                "entries": entries[start..end],
+                "cursor": format!("{}:{}", folder, end),
+                "has_more": end < entries.len(),
+            })).into_response()
+        },
+        _ => (StatusCode::NOT_FOUND, "Unknown route").into_response(),
+    }
+}
+
+async fn handle_content(State(state): State<SharedState>,
+                        Path(route): Path<String>,
+                        headers: HeaderMap,
+                        body: Bytes) -> Response {
+    let arg = headers
+        .get(DROPBOX_API_ARG)
+        .and_then(|arg| arg.to_str().ok())
+        .and_then(|arg| serde_json::from_str::<Value>(arg).ok())
+        .unwrap_or_default();
+    let mut state = lock(&state);
+    *state.requests.entry(route.clone()).or_default() += 1;
+
+    match route.as_str() {
+        "files/download" => {
+            let path = arg["path"].as_str().unwrap_or_default();
+            let Some(data) = state.files.get(path) else {
+                return conflict("path/not_found/..");
+            };
+            ranged_response(&headers, "range", data)
+        },
+        "files/upload" => {
+            let path = arg["path"].as_str().unwrap_or_default().to_string();
+            let size = body.len();
+            state.files.insert(path.clone(), body.to_vec());
+
+            Json(file_metadata(&path, size)).into_response()
+        },
+        "files/upload_session/start" => {
+            state.next_id += 1;
+            let session_id = format!("session{:08}", state.next_id);
+            state.sessions.insert(session_id.clone(), body.to_vec());
+
+            Json(json!({"session_id": session_id})).into_response()
+        },
+        "files/upload_session/append_v2" | "files/upload_session/finish" => {
+            let session_id = arg["cursor"]["session_id"].as_str().unwrap_or_default().to_string();
+            let offset = arg["cursor"]["offset"].as_u64().unwrap_or_default() as usize;
+            let Some(data) = state.sessions.get_mut(&session_id) else {
+                return conflict("lookup_failed/not_found/..");
+            };
+            if data.len() != offset {
+                return conflict("lookup_failed/incorrect_offset/..");
+            }
+            data.extend_from_slice(&body);
+
+            if route == "files/upload_session/finish" {
+                // SAFETY: The session exists in the above.
+                let data = state.sessions.remove(&session_id).unwrap();
+                let path = arg["commit"]["path"].as_str().unwrap_or_default().to_string();
+                let size = data.len();
+                state.files.insert(path.clone(), data);
+                return Json(file_metadata(&path, size)).into_response();
+            }
+
+            Json(Value::Null).into_response()
+        },
+        _ => (StatusCode::NOT_FOUND, "Unknown route").into_response(),
+    }
+}
@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::http::header::LOCATION;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use futures_core::Stream;
+use regex::Regex;
+use serde_json::{json, Value};
+use tokio::time::{sleep, Sleep};
+use crate::errors::HikyakuError::ConnectionError;
+use crate::errors::HikyakuResult;
+use crate::types::google_drive::GoogleDriveEndpoint;
+use crate::utils::test_support::{chunk_range, empty_chunk_total, lock, ranged_response, FakeServer};
+
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+const SHORTCUT_MIME_TYPE: &str = "application/vnd.google-apps.shortcut";
+const OWNER: &str = "me@example.com";
+const GOOGLE_APPS_MIME_TYPE_PREFIX: &str = "application/vnd.google-apps.";
+// The page size which is used when the request has no `pageSize`.
+const DEFAULT_PAGE_SIZE: usize = 100;
+// The max page size of `files.list` of Google Drive API.
+const MAX_PAGE_SIZE: usize = 1000;
+// The size limit of `files.export` of Google Drive API.
+const EXPORT_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+/// A file(or folder) stored in the [FakeGoogleDrive].
+#[derive(Debug, Clone)]
+pub struct FakeDriveFile {
+    pub id: String,
+    pub name: String,
+    pub mime_type: String,
+    pub parents: Vec<String>,
+    pub data: Vec<u8>,
+    /// The exported data of Google Workspace document by the export MIME type.
+    pub exports: HashMap<String, Vec<u8>>,
+    /// The id and the MIME type of the target if the file is shortcut.
+    pub shortcut_target: Option<(String, String)>,
+    /// The created time in RFC 3339.
+    pub created_time: String,
+    /// The last modified time in RFC 3339.
+    pub modified_time: String,
+    /// The email addresses of the owners.
+    pub owners: Vec<String>,
+    pub owned_by_me: bool,
+    pub trashed: bool,
+}
+
+impl FakeDriveFile {
+    fn to_json(&self, root_url: &str) -> Value {
+        let mut value = json!({
+            "kind": "drive#file",
+            "id": self.id,
+            "name": self.name,
+            "mimeType": self.mime_type,
+            "parents": self.parents,
+            "createdTime": self.created_time,
+            "modifiedTime": self.modified_time,
+            "ownedByMe": self.owned_by_me,
+            "trashed": self.trashed,
+            "owners": self.owners.iter().map(|owner| json!({"emailAddress": owner})).collect::<Vec<_>>(),
+        });
+        // Google Workspace documents and folders have no size.
+        if !self.mime_type.starts_with(GOOGLE_APPS_MIME_TYPE_PREFIX) {
+            value["size"] = json!(self.data.len().to_string());
+        }
+        if !self.exports.is_empty() {
+            let export_links = self.exports
+                .keys()
+                .map(|mime_type| (mime_type.clone(), json!(format!("{}/export-links/{}?mimeType={}", root_url, self.id, mime_type))))
+                .collect::<serde_json::Map<_, _>>();
+            value["exportLinks"] = Value::Object(export_links);
+        }
+        if let Some((target_id, target_mime_type)) = &self.shortcut_target {
+            value["shortcutDetails"] = json!({"targetId": target_id, "targetMimeType": target_mime_type});
+        }
+
+        value
+    }
+}
+
+#[derive(Default)]
+struct FakeDriveState {
+    files: Vec<FakeDriveFile>,
+    // (id, name, created time)
+    drives: Vec<(String, String, String)>,
+    // upload id -> (file metadata, received data)
+    uploads: HashMap<String, (FakeDriveFile, Vec<u8>)>,
+    next_id: u64,
+    root_url: String,
+    export_size_limit: usize,
+    // The export responses are cut off in the middle of the body.
+    interrupt_exports: bool,
+    max_page_size: usize,
+}
+
+impl FakeDriveState {
+    fn generate_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}{:08}", prefix, self.next_id)
+    }
+
+    /// Get the time of the latest id, so the files added later are newer.
+    fn current_time(&self) -> String {
+        format!("2024-01-01T{:02}:{:02}:{:02}.000Z", self.next_id / 3600 % 24, self.next_id / 60 % 60, self.next_id % 60)
+    }
+}
+
+type SharedState = Arc<Mutex<FakeDriveState>>;
+
+/// A small local fake of the Google Drive API v3 for offline tests.
+///
+/// The server runs on `127.0.0.1` with a random port and supports the subset of the API which
+/// Hikyaku uses: paginated shared drive lookup and file query by name and parents(which excludes the
+/// trashed files by `trashed = false`), metadata and ranged media
+/// download, export of Google Workspace documents(and `exportLinks`), shortcuts, folder creation and
+/// resumable upload. The server stops when this is dropped. Seed the files with
+/// [FakeGoogleDrive::add_folder], [FakeGoogleDrive::add_file], [FakeGoogleDrive::add_workspace_file]
+/// and [FakeGoogleDrive::add_shortcut], then pass [FakeGoogleDrive::endpoint] to
+/// `FileSystemBuilder::set_endpoint`.
+///
+/// This module is available with the `fake_google_drive` feature.
+pub struct FakeGoogleDrive {
+    server: FakeServer<FakeDriveState>,
+}
+
+impl FakeGoogleDrive {
+    /// Starts the fake server.
+    pub async fn start() -> HikyakuResult<Self> {
+        let router = Router::new()
+            .route("/drive/v3/drives", get(list_drives))
+            .route("/drive/v3/drives/:drive_id", get(get_drive))
+            .route("/drive/v3/files", get(list_files).post(create_file))
+            .route("/drive/v3/files/:file_id", get(get_file))
+            .route("/drive/v3/files/:file_id/export", get(export_file))
+            .route("/export-links/:file_id", get(export_link))
+            .route("/upload/drive/v3/files", axum::routing::post(start_upload).put(put_upload).delete(cancel_upload));
+        let state = |root_url: &str| FakeDriveState {
+            root_url: root_url.to_string(),
+            export_size_limit: EXPORT_SIZE_LIMIT,
+            interrupt_exports: false,
+            max_page_size: MAX_PAGE_SIZE,
+            ..FakeDriveState::default()
+        };
+        let server = FakeServer::start(state, router)
+            .await
+            .map_err(|e| ConnectionError(format!("Failed to start fake Google Drive server: {:?}", e)))?;
+
+        Ok(Self {
+            server,
+        })
+    }
+
+    /// Get the endpoint to connect to this server.
+    pub fn endpoint(&self) -> GoogleDriveEndpoint {
+        GoogleDriveEndpoint::from_root_url(self.server.root_url())
+    }
+
+    /// Add a shared drive and return its id.
+    pub fn add_shared_drive(&self, name: &str) -> String {
+        let mut state = self.server.lock();
+        let id = state.generate_id("drive");
+        let created_time = state.current_time();
+        state.drives.push((id.clone(), name.to_string(), created_time));
+
+        id
+    }
+
+    /// Add a folder under the `parent_id`(My Drive root if [None]) and return its id.
+    pub fn add_folder(&self, name: &str, parent_id: Option<&str>) -> String {
+        self.add_file(name, parent_id, FOLDER_MIME_TYPE, vec![])
+    }
+
+    /// Add a file under the `parent_id`(My Drive root if [None]) and return its id.
+    pub fn add_file(&self, name: &str, parent_id: Option<&str>, mime_type: &str, data: Vec<u8>) -> String {
+        let mut state = self.server.lock();
+        let id = state.generate_id("file");
+        let time = state.current_time();
+        state.files.push(FakeDriveFile {
+            id: id.clone(),
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            parents: parent_id.map(|id| vec![id.to_string()]).unwrap_or_default(),
+            data,
+            exports: HashMap::new(),
+            shortcut_target: None,
+            created_time: time.clone(),
+            modified_time: time,
+            owners: vec![OWNER.to_string()],
+            owned_by_me: true,
+            trashed: false,
+        });
+
+        id
+    }
+
+    /// Change the file(e.x. its times and owners) by the `update`.
+    pub fn update_file(&self, id: &str, update: impl FnOnce(&mut FakeDriveFile)) {
+        if let Some(file) = self.server.lock().files.iter_mut().find(|file| file.id == id) {
+            update(file);
+        }
+    }
+
+    /// Add a shortcut to the `target_id` under the `parent_id`(My Drive root if [None]) and return its id.
+    pub fn add_shortcut(&self, name: &str, parent_id: Option<&str>, target_id: &str) -> String {
+        let id = self.add_file(name, parent_id, SHORTCUT_MIME_TYPE, vec![]);
+        self.set_shortcut_target(&id, target_id);
+
+        id
+    }
+
+    /// Change the target of the shortcut(e.x. to make the shortcuts which point to each other).
+    pub fn set_shortcut_target(&self, shortcut_id: &str, target_id: &str) {
+        let mut state = self.server.lock();
+        let target = shortcut_target(&state, target_id);
+        if let Some(shortcut) = state.files.iter_mut().find(|file| file.id == shortcut_id) {
+            shortcut.shortcut_target = Some(target);
+        }
+    }
+
+    /// Add a Google Workspace document(e.x. Google Docs) which is exported to the `exports`
+    /// by the MIME type under the `parent_id`(My Drive root if [None]) and return its id.
+    pub fn add_workspace_file(&self, name: &str, parent_id: Option<&str>, mime_type: &str, exports: &[(&str, Vec<u8>)]) -> String {
+        let id = self.add_file(name, parent_id, mime_type, vec![]);
+        let mut state = self.server.lock();
+        // SAFETY: The file is added in the above.
+        let file = state.files.iter_mut().find(|file| file.id == id).unwrap();
+        file.exports = exports.iter().map(|(mime_type, data)| (mime_type.to_string(), data.clone())).collect();
+
+        id
+    }
+
+    /// Set the size limit of `files.export`(10 MiB by default). The larger documents are
+    /// rejected with `exportSizeLimitExceeded` and must be downloaded from `exportLinks`.
+    pub fn set_export_size_limit(&self, bytes: usize) {
+        self.server.lock().export_size_limit = bytes;
+    }
+
+    /// Cut off the export responses in the middle of the body, like the connection lost during the export.
+    pub fn set_interrupt_exports(&self, interrupt: bool) {
+        self.server.lock().interrupt_exports = interrupt;
+    }
+
+    /// Set the max number of the files and the shared drives in a page(1000 by default)
+    /// to split the results of the queries into the small pages.
+    pub fn set_max_page_size(&self, max_page_size: usize) {
+        self.server.lock().max_page_size = max_page_size;
+    }
+
+    /// Get the files which have the `name`.
+    pub fn find_files(&self, name: &str) -> Vec<FakeDriveFile> {
+        self.server.lock()
+            .files
+            .iter()
+            .filter(|file| file.name == name)
+            .cloned()
+            .collect()
+    }
+
+    /// Get the number of the resumable uploads which are neither completed nor cancelled.
+    pub fn open_uploads(&self) -> usize {
+        self.server.lock().uploads.len()
+    }
+}
+
+fn not_found(message: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({"error": {"code": 404, "message": message}}))).into_response()
+}
+
+/// Extract the values of `name = '...'` and `'...' in parents` from the query statement.
+fn parse_query(query: &str) -> (Option<String>, Vec<String>) {
+    // SAFETY: The regex statements are const string so these are always Ok().
+    let name_regex = Regex::new(r"name = '((?:[^'\\]|\\.)*)'").unwrap();
+    let parent_regex = Regex::new(r"'((?:[^'\\]|\\.)*)' in parents").unwrap();
+    let unescape = |value: &str| value.replace("\\'", "'").replace("\\\\", "\\");
+
+    let name = name_regex
+        .captures(query)
+        .and_then(|capture| capture.get(1))
+        .map(|name| unescape(name.as_str()));
+    let parents = parent_regex
+        .captures_iter(query)
+        .filter_map(|capture| capture.get(1))
+        .map(|parent| unescape(parent.as_str()))
+        .collect();
+
+    (name, parents)
+}
+
+/// Get the items of the page and the token of the next page. The token is the offset of the next page.
+fn paginate(items: Vec<Value>, params: &HashMap<String, String>, max_page_size: usize) -> (Vec<Value>, Option<String>) {
+    let offset = params.get("pageToken").and_then(|token| token.parse::<usize>().ok()).unwrap_or(0);
+    let page_size = params
+        .get("pageSize")
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .min(max_page_size);
+    let next_offset = offset + page_size;
+    let next_page_token = (next_offset < items.len()).then(|| next_offset.to_string());
+
+    (items.into_iter().skip(offset).take(page_size).collect(), next_page_token)
+}
+
+fn page_response(key: &str, (items, next_page_token): (Vec<Value>, Option<String>)) -> Response {
+    let mut response = json!({key: items});
+    if let Some(next_page_token) = next_page_token {
+        response["nextPageToken"] = json!(next_page_token);
+    }
+
+    Json(response).into_response()
+}
+
+async fn list_drives(State(state): State<SharedState>, Query(params): Query<HashMap<String, String>>) -> Response {
+    let (name, _) = parse_query(params.get("q").map(String::as_str).unwrap_or_default());
+    let state = lock(&state);
+    let drives = state
+        .drives
+        .iter()
+        .filter(|(_, drive_name, _)| name.as_ref().is_none_or(|name| name == drive_name))
+        .map(|(id, name, created_time)| json!({"kind": "drive#drive", "id": id, "name": name, "createdTime": created_time}))
+        .collect::<Vec<_>>();
+
+    page_response("drives", paginate(drives, &params, state.max_page_size))
+}
+
+async fn get_drive(State(state): State<SharedState>, Path(drive_id): Path<String>) -> Response {
+    match lock(&state).drives.iter().find(|(id, _, _)| *id == drive_id) {
+        Some((id, name, created_time)) => Json(json!({"kind": "drive#drive", "id": id, "name": name, "createdTime": created_time})).into_response(),
+        None => not_found("Shared drive not found"),
+    }
+}
+
+async fn list_files(State(state): State<SharedState>, Query(params): Query<HashMap<String, String>>) -> Response {
+    let query = params.get("q").map(String::as_str).unwrap_or_default();
+    let (name, parents) = parse_query(query);
+    let excludes_trashed = query.contains("trashed = false");
+    let state = lock(&state);
+    let files = state
+        .files
+        .iter()
+        .filter(|file| name.as_ref().is_none_or(|name| *name == file.name))
+        .filter(|file| parents.is_empty() || file.parents.iter().any(|parent| parents.contains(parent)))
+        .filter(|file| !(excludes_trashed && file.trashed))
+        .map(|file| file.to_json(&state.root_url))
+        .collect::<Vec<_>>();
+
+    page_response("files", paginate(files, &params, state.max_page_size))
+}
+
+async fn get_file(State(state): State<SharedState>,
+                  Path(file_id): Path<String>,
+                  Query(params): Query<HashMap<String, String>>,
+                  headers: HeaderMap) -> Response {
+    let (file, root_url) = {
+        let state = lock(&state);
+        match state.files.iter().find(|file| file.id == file_id) {
+            Some(file) => (file.clone(), state.root_url.clone()),
+            None => return not_found("File not found"),
+        }
+    };
+
+    if params.get("alt").map(String::as_str) != Some("media") {
+        return Json(file.to_json(&root_url)).into_response();
+    }
+
+    ranged_response(&headers, "range", &file.data)
+}
+
+/// Find the data exported to `mimeType` and the size limit of `files.export`.
+fn find_export(state: &SharedState, file_id: &str, params: &HashMap<String, String>) -> Option<(Vec<u8>, usize)> {
+    let state = lock(state);
+    let mime_type = params.get("mimeType")?;
+    let file = state.files.iter().find(|file| file.id == file_id)?;
+
+    file.exports.get(mime_type).map(|data| (data.clone(), state.export_size_limit))
+}
+
+/// Respond the exported data, or only its first half followed by the error of the body
+/// when the exports are interrupted.
+fn export_response(state: &SharedState, data: Vec<u8>) -> Response {
+    if !lock(state).interrupt_exports {
+        return (StatusCode::OK, data).into_response();
+    }
+
+    let first_half = data[..data.len() / 2].to_vec();
+    let stream = InterruptedStream {
+        data: Some(first_half),
+        delay: Box::pin(sleep(Duration::from_millis(100))),
+    };
+    (StatusCode::OK, Body::from_stream(stream)).into_response()
+}
+
+/// The body stream which fails a moment after the data, so the client has received the response.
+struct InterruptedStream {
+    data: Option<Vec<u8>>,
+    delay: Pin<Box<Sleep>>,
+}
+
+impl Stream for InterruptedStream {
+    type Item = Result<Vec<u8>, std::io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(data) = self.data.take() {
+            return Poll::Ready(Some(Ok(data)));
+        }
+        match self.delay.as_mut().poll(cx) {
+            Poll::Ready(()) => Poll::Ready(Some(Err(std::io::Error::new(ErrorKind::ConnectionReset, "The export is interrupted")))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+async fn export_file(State(state): State<SharedState>,
+                     Path(file_id): Path<String>,
+                     Query(params): Query<HashMap<String, String>>) -> Response {
+    match find_export(&state, &file_id, &params) {
+        Some((data, limit)) if data.len() > limit => {
+            let error = json!({"error": {
+                "code": 403,
+                "message": "This file is too large to be exported.",
+                "errors": [{"reason": "exportSizeLimitExceeded"}],
+            }});
+            (StatusCode::FORBIDDEN, Json(error)).into_response()
+        },
+        Some((data, _)) => export_response(&state, data),
+        None => not_found("File or export format not found"),
+    }
+}
+
+async fn export_link(State(state): State<SharedState>,
+                     Path(file_id): Path<String>,
+                     Query(params): Query<HashMap<String, String>>) -> Response {
+    match find_export(&state, &file_id, &params) {
+        Some((data, _)) => export_response(&state, data),
+        None => not_found("File or export format not found"),
+    }
+}
+
+async fn create_file(State(state): State<SharedState>, Json(metadata): Json<Value>) -> Response {
+    let file = new_file_from_metadata(&state, &metadata);
+    let mut state = lock(&state);
+    let response = file.to_json(&state.root_url);
+    state.files.push(file);
+
+    Json(response).into_response()
+}
+
+async fn start_upload(State(state): State<SharedState>,
+                      Query(params): Query<HashMap<String, String>>,
+                      Json(metadata): Json<Value>) -> Response {
+    if params.get("uploadType").map(String::as_str) != Some("resumable") {
+        return (StatusCode::BAD_REQUEST, "Only resumable upload is supported").into_response();
+    }
+
+    let file = new_file_from_metadata(&state, &metadata);
+    let mut state = lock(&state);
+    let upload_id = state.generate_id("upload");
+    let location = format!("{}/upload/drive/v3/files?uploadType=resumable&upload_id={}", state.root_url, upload_id);
+    state.uploads.insert(upload_id, (file, vec![]));
+
+    (StatusCode::OK, [(LOCATION, location)]).into_response()
+}
+
+async fn put_upload(State(state): State<SharedState>,
+                    Query(params): Query<HashMap<String, String>>,
+                    headers: HeaderMap,
+                    body: Bytes) -> Response {
+    let upload_id = params.get("upload_id").cloned().unwrap_or_default();
+    let content_range = chunk_range(&headers)
+        .map(|(start, _, total)| (start, total))
+        .or_else(|| empty_chunk_total(&headers).map(|total| (total, Some(total))));
+    let (start, total) = match content_range {
+        Some(range) => range,
+        None => return (StatusCode::BAD_REQUEST, "Invalid Content-Range").into_response(),
+    };
+
+    let mut state = lock(&state);
+    let (_, data) = match state.uploads.get_mut(&upload_id) {
+        Some(upload) => upload,
+        None => return not_found("Upload session not found"),
+    };
+
+    let end = start + body.len();
+    if data.len() < end {
+        data.resize(end, 0);
+    }
+    data[start..end].copy_from_slice(&body);
+
+    match total {
+        Some(total) if total == data.len() => {
+            // SAFETY: The upload session exists in the above.
+            let (mut file, data) = state.uploads.remove(&upload_id).unwrap();
+            file.data = data;
+            let response = file.to_json(&state.root_url);
+            state.files.push(file);
+
+            Json(response).into_response()
+        },
+        _ => (StatusCode::PERMANENT_REDIRECT, [("Range", format!("bytes=0-{}", end.saturating_sub(1)))]).into_response(),
+    }
+}
+
+async fn cancel_upload(State(state): State<SharedState>, Query(params): Query<HashMap<String, String>>) -> Response {
+    let upload_id = params.get("upload_id").cloned().unwrap_or_default();
+    match lock(&state).uploads.remove(&upload_id) {
+        // Google Drive returns 499 Client Closed Request for the cancelled upload.
+        Some(_) => StatusCode::from_u16(499).unwrap().into_response(),
+        None => not_found("Upload session not found"),
+    }
+}
+
+/// Get the id and the MIME type of the target of the shortcut.
+fn shortcut_target(state: &FakeDriveState, target_id: &str) -> (String, String) {
+    let target_mime_type = state.files
+        .iter()
+        .find(|file| file.id == target_id)
+        .map(|file| file.mime_type.clone())
+        .unwrap_or_default();
+
+    (target_id.to_string(), target_mime_type)
+}
+
+fn new_file_from_metadata(state: &SharedState, metadata: &Value) -> FakeDriveFile {
+    let mut state = lock(state);
+    let id = state.generate_id("file");
+    let time = state.current_time();
+    FakeDriveFile {
+        id,
+        name: metadata["name"].as_str().unwrap_or_default().to_string(),
+        mime_type: metadata["mimeType"].as_str().unwrap_or("application/octet-stream").to_string(),
+        parents: metadata["parents"]
+            .as_array()
+            .map(|parents| parents.iter().filter_map(|parent| parent.as_str().map(String::from)).collect())
+            .unwrap_or_default(),
+        data: vec![],
+        exports: HashMap::new(),
+        shortcut_target: metadata["shortcutDetails"]["targetId"]
+            .as_str()
+            .map(|target_id| shortcut_target(&state, target_id)),
+        created_time: time.clone(),
+        modified_time: time,
+        owners: vec![OWNER.to_string()],
+        owned_by_me: true,
+        trashed: false,
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::http::header::{ACCEPT_RANGES, AUTHORIZATION, CONTENT_RANGE, RANGE};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use futures_core::Stream;
+use crate::utils::test_support::{lock, FakeServer};
+
+pub(crate) const API_KEY: &str = "hikyaku-key";
+pub(crate) const TOKEN: &str = "hikyaku-token";
+pub(crate) const USER: &str = "alice";
+pub(crate) const PASSWORD: &str = "secret";
+// The files under this path need the bearer token or the basic authentication.
+const PRIVATE_PREFIX: &str = "private/";
+
+/// How the fake server answers the size and the ranges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FakeHttpMode {
+    /// HEAD returns the length with `Accept-Ranges: bytes`, and GET accepts the range.
+    Ranges,
+    /// HEAD is not implemented, and GET accepts the range.
+    RangesWithoutHead,
+    /// HEAD returns only the length, and GET ignores the range.
+    NoRanges,
+    /// HEAD is not implemented, and GET ignores the range and sends the chunked body without the length.
+    Chunked,
+}
+
+struct FakeHttpState {
+    mode: FakeHttpMode,
+    // path -> data. The paths have no leading `/`.
+    files: BTreeMap<String, Vec<u8>>,
+    range_gets: usize,
+    full_gets: usize,
+    // The responses of the whole file have only the first half.
+    interrupt_full_gets: bool,
+}
+
+type SharedState = Arc<Mutex<FakeHttpState>>;
+
+/// A small local fake of the static file server for the tests.
+///
+/// Every request needs the `X-Api-Key` header of [API_KEY].
+pub(crate) struct FakeHttp {
+    server: FakeServer<FakeHttpState>,
+}
+
+impl FakeHttp {
+    pub(crate) async fn start(mode: FakeHttpMode) -> Self {
+        let router = Router::new().fallback(handle);
+        let state = |_: &str| FakeHttpState {
+            mode,
+            files: BTreeMap::new(),
+            range_gets: 0,
+            full_gets: 0,
+            interrupt_full_gets: false,
+        };
+
+        Self {
+            server: FakeServer::start(state, router).await.unwrap(),
+        }
+    }
+
+    /// `host:port` which is put in `http://` paths.
+    pub(crate) fn host(&self) -> &str {
+        self.server.root_url().trim_start_matches("http://")
+    }
+
+    pub(crate) fn add_file(&self, path: &str, data: Vec<u8>) {
+        self.server.lock().files.insert(path.to_string(), data);
+    }
+
+    pub(crate) fn range_gets(&self) -> usize {
+        self.server.lock().range_gets
+    }
+
+    pub(crate) fn full_gets(&self) -> usize {
+        self.server.lock().full_gets
+    }
+
+    /// Cut off the responses of the whole file at the half, which is shorter than the length of HEAD.
+    pub(crate) fn set_interrupt_full_gets(&self, interrupt: bool) {
+        self.server.lock().interrupt_full_gets = interrupt;
+    }
+}
+
+fn is_authorized(headers: &HeaderMap) -> bool {
+    let authorization = headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok());
+    let basic = format!("Basic {}", STANDARD.encode(format!("{}:{}", USER, PASSWORD)));
+    authorization == Some(format!("Bearer {}", TOKEN).as_str()) || authorization == Some(basic.as_str())
+}
+
+async fn handle(State(state): State<SharedState>,
+                method: Method,
+                uri: Uri,
+                headers: HeaderMap) -> Response {
+    if headers.get("X-Api-Key").and_then(|value| value.to_str().ok()) != Some(API_KEY) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let path = uri.path().trim_start_matches('/').to_string();
+    if path.starts_with(PRIVATE_PREFIX) && !is_authorized(&headers) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let mut state = lock(&state);
+    let mode = state.mode;
+    let Some(data) = state.files.get(&path).cloned() else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    match method {
+        // axum removes the body of the response to HEAD but keeps its length.
+        Method::HEAD => match mode {
+            FakeHttpMode::Ranges => (StatusCode::OK, [(ACCEPT_RANGES, "bytes")], data).into_response(),
+            FakeHttpMode::RangesWithoutHead | FakeHttpMode::Chunked => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+            FakeHttpMode::NoRanges => (StatusCode::OK, data).into_response(),
+        },
+        Method::GET => {
+            let range = headers
+                .get(RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|range| {
+                    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+                    Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+                })
+                .filter(|_| mode == FakeHttpMode::Ranges || mode == FakeHttpMode::RangesWithoutHead);
+            match range {
+                Some((start, _)) if start >= data.len() => StatusCode::RANGE_NOT_SATISFIABLE.into_response(),
+                Some((start, end)) => {
+                    state.range_gets += 1;
+                    let end = end.min(data.len() - 1);
+                    let content_range = format!("bytes {}-{}/{}", start, end, data.len());
+                    (StatusCode::PARTIAL_CONTENT, [(CONTENT_RANGE, content_range)], data[start..=end].to_vec()).into_response()
+                },
+                None if state.interrupt_full_gets => {
+                    state.full_gets += 1;
+                    (StatusCode::OK, data[..data.len() / 2].to_vec()).into_response()
+                },
+                None if mode == FakeHttpMode::Chunked => {
+                    state.full_gets += 1;
+                    (StatusCode::OK, Body::from_stream(ChunkedStream { data, offset: 0 })).into_response()
+                },
+                None => {
+                    state.full_gets += 1;
+                    (StatusCode::OK, data).into_response()
+                },
+            }
+        },
+        _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+    }
+}
+
+/// The body stream of the unknown length, which is sent by the chunked transfer encoding.
+struct ChunkedStream {
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl Stream for ChunkedStream {
+    type Item = Result<Vec<u8>, std::io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.offset >= self.data.len() {
+            return Poll::Ready(None);
+        }
+        let end = (self.offset + 100).min(self.data.len());
+        let chunk = self.data[self.offset..end].to_vec();
+        self.offset = end;
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::http::header::{AUTHORIZATION, LOCATION};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use crate::types::onedrive::{encode_path, OneDriveEndpoint, UPLOAD_FRAGMENT_UNIT};
+use crate::utils::test_support::{chunk_range, lock, ranged_response, FakeServer};
+
+// The items per page of the collections to exercise `@odata.nextLink`.
+const PAGE_SIZE: usize = 1;
+
+struct FakeUploadSession {
+    drive_id: String,
+    path: String,
+    data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct FakeOneDriveState {
+    // (drive id, path) -> data
+    files: BTreeMap<(String, String), Vec<u8>>,
+    // (site id, site name, drive id of the default document library)
+    sites: Vec<(String, String, String)>,
+    sessions: HashMap<String, FakeUploadSession>,
+    uploaded_fragments: usize,
+    next_id: u64,
+    root_url: String,
+}
+
+impl FakeOneDriveState {
+    /// Get the metadata of the file or the folder which exists implicitly as the parent of the files.
+    fn item(&self, drive_id: &str, path: &str) -> Option<Value> {
+        let name = path.rsplit('/').next().unwrap_or_default();
+        if let Some(data) = self.files.get(&(drive_id.to_string(), path.to_string())) {
+            return Some(json!({"name": name, "size": data.len(), "file": {}}));
+        }
+        let children = self.children(drive_id, path);
+        if path.is_empty() || !children.is_empty() {
+            return Some(json!({"name": if path.is_empty() { "root" } else { name }, "folder": {"childCount": children.len()}}));
+        }
+
+        None
+    }
+
+    fn children(&self, drive_id: &str, folder: &str) -> Vec<Value> {
+        let prefix = if folder.is_empty() { String::new() } else { format!("{}/", folder) };
+        let mut folders = vec![];
+        let mut entries = vec![];
+        for ((drive, path), data) in &self.files {
+            let Some(rest) = path.strip_prefix(&prefix).filter(|_| drive == drive_id) else {
+                continue;
+            };
+            match rest.split_once('/') {
+                Some((name, _)) => {
+                    if !folders.contains(&name) {
+                        entries.push(json!({"name": name, "folder": {}}));
+                        folders.push(name);
+                    }
+                },
+                None => entries.push(json!({"name": rest, "size": data.len(), "file": {}})),
+            }
+        }
+
+        entries
+    }
+
+    /// Get a page of the collection with the link to the next page.
+    fn page(&self, url: &str, values: Vec<Value>, skip: usize) -> Value {
+        let end = (skip + PAGE_SIZE).min(values.len());
+        let mut page = json!({"value": values[skip.min(end)..end]});
+        if end < values.len() {
+            let separator = if url.contains('?') { "&" } else { "?" };
+            page["@odata.nextLink"] = json!(format!("{}{}{}skip={}", self.root_url, url, separator, end));
+        }
+
+        page
+    }
+}
+
+type SharedState = Arc<Mutex<FakeOneDriveState>>;
+
+/// A small local fake of the Microsoft Graph drive API for the tests.
+///
+/// The API routes are served under `/v1.0`. The OneDrive of the signed-in user has the drive id `me`,
+/// and the folders exist implicitly as the parents of the files.
+pub(crate) struct FakeOneDrive {
+    server: FakeServer<FakeOneDriveState>,
+}
+
+impl FakeOneDrive {
+    pub(crate) async fn start() -> Self {
+        let router = Router::new()
+            .route("/v1.0/me/drive", get(|| async { Json(json!({"id": "me"})) }))
+            .route("/v1.0/sites", get(list_sites))
+            .route("/v1.0/sites/:site_id/drive", get(get_site_drive))
+            .route("/v1.0/sites/:site_id/drives", get(list_site_drives))
+            .route("/v1.0/drives/:drive_id/*route", get(handle_drive).put(handle_drive).post(handle_drive).delete(handle_drive))
+            .route("/download/:drive_id/*path", get(download))
+            .route("/upload/:session_id", put(upload_fragment).delete(cancel_session))
+            // The fragments of the upload session exceed the default body limit.
+            .layer(DefaultBodyLimit::disable());
+        let state = |root_url: &str| FakeOneDriveState {
+            root_url: root_url.to_string(),
+            ..FakeOneDriveState::default()
+        };
+
+        Self {
+            server: FakeServer::start(state, router).await.unwrap(),
+        }
+    }
+
+    pub(crate) fn endpoint(&self) -> OneDriveEndpoint {
+        OneDriveEndpoint::new(&format!("{}/v1.0", self.server.root_url()))
+    }
+
+    pub(crate) fn add_file(&self, drive_id: &str, path: &str, data: Vec<u8>) {
+        self.server.lock().files.insert((drive_id.to_string(), path.to_string()), data);
+    }
+
+    pub(crate) fn get_file(&self, drive_id: &str, path: &str) -> Option<Vec<u8>> {
+        self.server.lock().files.get(&(drive_id.to_string(), path.to_string())).cloned()
+    }
+
+    /// Add the SharePoint site whose default document library is the drive.
+    pub(crate) fn add_site(&self, name: &str, drive_id: &str) {
+        let mut state = self.server.lock();
+        state.next_id += 1;
+        let site_id = format!("contoso.sharepoint.com,{},{}", state.next_id, state.next_id);
+        state.sites.push((site_id, name.to_string(), drive_id.to_string()));
+    }
+
+    pub(crate) fn uploaded_fragments(&self) -> usize {
+        self.server.lock().uploaded_fragments
+    }
+
+    /// Get the number of the upload sessions which are neither completed nor cancelled.
+    pub(crate) fn open_sessions(&self) -> usize {
+        self.server.lock().sessions.len()
+    }
+}
+
+fn not_found() -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({"error": {"code": "itemNotFound"}}))).into_response()
+}
+
+fn get_skip(params: &HashMap<String, String>) -> usize {
+    params.get("skip").and_then(|skip| skip.parse::<usize>().ok()).unwrap_or(0)
+}
+
+async fn list_sites(State(state): State<SharedState>, Query(params): Query<HashMap<String, String>>) -> Response {
+    let state = lock(&state);
+    let search = params.get("search").cloned().unwrap_or_default();
+    let sites = state.sites
+        .iter()
+        .map(|(id, name, _)| json!({"id": id, "name": name, "displayName": name}))
+        .collect::<Vec<_>>();
+
+    Json(state.page(&format!("/v1.0/sites?search={}", search), sites, get_skip(&params))).into_response()
+}
+
+async fn get_site_drive(State(state): State<SharedState>, Path(site_id): Path<String>) -> Response {
+    match lock(&state).sites.iter().find(|(id, ..)| *id == site_id) {
+        Some((_, _, drive_id)) => Json(json!({"id": drive_id, "name": "Documents"})).into_response(),
+        None => not_found(),
+    }
+}
+
+async fn list_site_drives(State(state): State<SharedState>, Path(site_id): Path<String>) -> Response {
+    match lock(&state).sites.iter().find(|(id, ..)| *id == site_id) {
+        Some((_, _, drive_id)) => Json(json!({"value": [{"id": drive_id, "name": "Documents"}]})).into_response(),
+        None => not_found(),
+    }
+}
+
+/// Split the route(`root`, `root/{action}`, `root:/{path}` or `root:/{path}:/{action}`) to the path and the action.
+fn parse_route(route: &str) -> Option<(String, Option<String>)> {
+    let rest = route.strip_prefix("root")?;
+    if rest.is_empty() {
+        return Some((String::new(), None));
+    }
+    if let Some(action) = rest.strip_prefix('/') {
+        return Some((String::new(), Some(action.to_string())));
+    }
+    let rest = rest.strip_prefix(":/")?;
+    match rest.split_once(":/") {
+        Some((path, action)) => Some((path.to_string(), Some(action.to_string()))),
+        None => Some((rest.trim_end_matches(':').to_string(), None)),
+    }
+}
+
+async fn handle_drive(State(state): State<SharedState>,
+                      Path((drive_id, route)): Path<(String, String)>,
+                      Query(params): Query<HashMap<String, String>>,
+                      method: Method,
+                      body: Bytes) -> Response {
+    let Some((path, action)) = parse_route(&route) else {
+        return (StatusCode::BAD_REQUEST, "Invalid route").into_response();
+    };
+    let mut state = lock(&state);
+
+    match (method, action.as_deref()) {
+        (Method::GET, None) => match state.item(&drive_id, &path) {
+            Some(item) => Json(item).into_response(),
+            None => not_found(),
+        },
+        (Method::GET, Some("children")) => {
+            if state.item(&drive_id, &path).is_none() {
+                return not_found();
+            }
+            let children = state.children(&drive_id, &path);
+            let url = format!("/v1.0/drives/{}/root:/{}:/children?$select=name", drive_id, encode_path(&path));
+            Json(state.page(&url, children, get_skip(&params))).into_response()
+        },
+        (Method::GET, Some("content")) => {
+            if !state.files.contains_key(&(drive_id.clone(), path.clone())) {
+                return not_found();
+            }
+            // The content is served from the pre-authenticated URL like Microsoft Graph.
+            let location = format!("{}/download/{}/{}", state.root_url, drive_id, encode_path(&path));
+            (StatusCode::FOUND, [(LOCATION, location)]).into_response()
+        },
+        (Method::PUT, Some("content")) => {
+            state.files.insert((drive_id.clone(), path.clone()), body.to_vec());
+            // SAFETY: The file is stored in the above.
+            (StatusCode::CREATED, Json(state.item(&drive_id, &path).unwrap())).into_response()
+        },
+        (Method::POST, Some("createUploadSession")) => {
+            state.next_id += 1;
+            let session_id = format!("session{:08}", state.next_id);
+            state.sessions.insert(session_id.clone(), FakeUploadSession {
+                drive_id,
+                path,
+                data: vec![],
+            });
+
+            Json(json!({"uploadUrl": format!("{}/upload/{}", state.root_url, session_id)})).into_response()
+        },
+        (Method::DELETE, None) => {
+            let prefix = format!("{}/", path);
+            let count = state.files.len();
+            state.files.retain(|(drive, file), _| *drive != drive_id || (*file != path && !file.starts_with(&prefix)));
+            if state.files.len() == count {
+                return not_found();
+            }
+
+            StatusCode::NO_CONTENT.into_response()
+        },
+        _ => (StatusCode::BAD_REQUEST, "Unknown route").into_response(),
+    }
+}
+
+async fn download(State(state): State<SharedState>,
+                  Path((drive_id, path)): Path<(String, String)>,
+                  headers: HeaderMap) -> Response {
+    let Some(data) = lock(&state).files.get(&(drive_id, path)).cloned() else {
+        return not_found();
+    };
+    ranged_response(&headers, "range", &data)
+}
+
+async fn upload_fragment(State(state): State<SharedState>,
+                         Path(session_id): Path<String>,
+                         headers: HeaderMap,
+                         body: Bytes) -> Response {
+    // The upload URL is pre-authenticated and rejects the token.
+    if headers.contains_key(AUTHORIZATION) {
+        return (StatusCode::UNAUTHORIZED, "The upload URL does not accept Authorization").into_response();
+    }
+    let range = chunk_range(&headers);
+
+    let mut state = lock(&state);
+    state.uploaded_fragments += 1;
+    let Some(session) = state.sessions.get_mut(&session_id) else {
+        return not_found();
+    };
+    // Graph needs the total size in every fragment.
+    let Some((start, end, Some(total))) = range else {
+        return (StatusCode::BAD_REQUEST, "Invalid Content-Range").into_response();
+    };
+    let is_last = end + 1 == total;
+    if start != session.data.len() || end + 1 - start != body.len() || (!is_last && !(body.len() as u64).is_multiple_of(UPLOAD_FRAGMENT_UNIT)) {
+        return (StatusCode::RANGE_NOT_SATISFIABLE, "Invalid fragment").into_response();
+    }
+    session.data.extend_from_slice(&body);
+    if !is_last {
+        return (StatusCode::ACCEPTED, Json(json!({"nextExpectedRanges": [format!("{}-", end + 1)]}))).into_response();
+    }
+
+    // SAFETY: The session exists in the above.
+    let session = state.sessions.remove(&session_id).unwrap();
+    state.files.insert((session.drive_id.clone(), session.path.clone()), session.data);
+    // SAFETY: The file is stored in the above.
+    (StatusCode::CREATED, Json(state.item(&session.drive_id, &session.path).unwrap())).into_response()
+}
+
+async fn cancel_session(State(state): State<SharedState>, Path(session_id): Path<String>) -> Response {
+    match lock(&state).sessions.remove(&session_id) {
+        Some(_) => StatusCode::NO_CONTENT.into_response(),
+        None => not_found(),
+    }
+}
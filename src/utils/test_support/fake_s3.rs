@@ -0,0 +1,534 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use crate::utils::credential::s3_credential::S3Credential;
+use crate::utils::region::aws::AWSRegion;
+use crate::utils::test_support::{lock, FakeServer};
+
+/// An object stored in the [FakeS3].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FakeS3Object {
+    pub(crate) data: Vec<u8>,
+    pub(crate) version_id: String,
+    // The lower-case header name -> value which were sent with the object.
+    pub(crate) headers: BTreeMap<String, String>,
+}
+
+impl FakeS3Object {
+    fn is_archived(&self) -> bool {
+        let is_archive_class = self.headers
+            .get("x-amz-storage-class")
+            .is_some_and(|storage_class| storage_class == "GLACIER" || storage_class == "DEEP_ARCHIVE");
+        let is_restored = self.headers
+            .get("x-amz-restore")
+            .is_some_and(|restore| restore.contains("ongoing-request=\"false\""));
+
+        is_archive_class && !is_restored
+    }
+}
+
+/// A request which the [FakeS3] received.
+#[derive(Debug, Clone)]
+pub(crate) struct FakeS3Request {
+    pub(crate) method: Method,
+    pub(crate) path: String,
+    pub(crate) query: HashMap<String, String>,
+    pub(crate) headers: HeaderMap,
+}
+
+impl FakeS3Request {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+}
+
+struct FakeS3Upload {
+    bucket: String,
+    key: String,
+    headers: BTreeMap<String, String>,
+    // part number -> data
+    parts: BTreeMap<i32, Vec<u8>>,
+}
+
+struct FakeS3Version {
+    bucket: String,
+    key: String,
+    version_id: String,
+    // [None] is the delete marker.
+    object: Option<FakeS3Object>,
+    sequence: u64,
+}
+
+#[derive(Default)]
+struct FakeS3State {
+    // (bucket, key) -> current object
+    objects: HashMap<(String, String), FakeS3Object>,
+    // All versions in the written order. Every bucket of the fake is versioned.
+    versions: Vec<FakeS3Version>,
+    // upload id -> upload in progress
+    uploads: HashMap<String, FakeS3Upload>,
+    requests: Vec<FakeS3Request>,
+    next_id: u64,
+}
+
+impl FakeS3State {
+    /// Store the object as the new current version and return its version id.
+    fn store(&mut self, bucket: &str, key: &str, mut object: FakeS3Object) -> String {
+        self.next_id += 1;
+        let version_id = format!("version{:08}", self.next_id);
+        object.version_id = version_id.clone();
+        self.versions.push(FakeS3Version {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            version_id: version_id.clone(),
+            object: Some(object.clone()),
+            sequence: self.next_id,
+        });
+        self.objects.insert((bucket.to_string(), key.to_string()), object);
+
+        version_id
+    }
+
+    fn delete(&mut self, bucket: &str, key: &str) {
+        self.next_id += 1;
+        self.versions.push(FakeS3Version {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            version_id: format!("version{:08}", self.next_id),
+            object: None,
+            sequence: self.next_id,
+        });
+        self.objects.remove(&(bucket.to_string(), key.to_string()));
+    }
+
+    /// Find the current object or the specific version. The delete marker is returned as `Some(None)`.
+    fn find(&self, bucket: &str, key: &str, version_id: Option<&str>) -> Option<Option<&FakeS3Object>> {
+        match version_id {
+            Some(version_id) => self.versions
+                .iter()
+                .find(|version| version.bucket == bucket && version.key == key && version.version_id == version_id)
+                .map(|version| version.object.as_ref()),
+            None => self.objects.get(&(bucket.to_string(), key.to_string())).map(Some),
+        }
+    }
+}
+
+type SharedState = Arc<Mutex<FakeS3State>>;
+
+/// A small local fake of the Amazon S3 REST API for offline tests.
+///
+/// The server accepts the path style requests and supports the subset of the API which Hikyaku
+/// uses: ListObjectsV2, ListObjectVersions, GetObject, HeadObject, PutObject, CopyObject,
+/// DeleteObject, RestoreObject and the multipart upload(including UploadPartCopy and
+/// AbortMultipartUpload). Every bucket is versioned. All requests are recorded to assert the headers.
+pub(crate) struct FakeS3 {
+    server: FakeServer<FakeS3State>,
+}
+
+impl FakeS3 {
+    pub(crate) async fn start() -> Self {
+        let router = Router::new()
+            .fallback(handle_request)
+            // The parts of the multipart upload(5 MiB or larger) exceed the default body limit.
+            .layer(DefaultBodyLimit::disable());
+
+        Self {
+            server: FakeServer::start(|_| FakeS3State::default(), router).await.unwrap(),
+        }
+    }
+
+    /// Get the credential which connects to this server.
+    pub(crate) fn credential(&self) -> S3Credential {
+        S3Credential::new("access_key", "secret_key", None, None, AWSRegion::Tokyo)
+            .set_endpoint_url(self.server.root_url())
+            .set_force_path_style(true)
+            .set_checksum_compat(true)
+    }
+
+    /// Put an object and return its version id.
+    pub(crate) fn put_object(&self, bucket: &str, key: &str, data: Vec<u8>) -> String {
+        self.server.lock().store(bucket, key, FakeS3Object { data, ..FakeS3Object::default() })
+    }
+
+    /// Put an object with the headers like `x-amz-storage-class`.
+    pub(crate) fn put_object_with_headers(&self, bucket: &str, key: &str, data: Vec<u8>, headers: &[(&str, &str)]) {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), value.to_string()))
+            .collect();
+        self.server.lock().store(bucket, key, FakeS3Object { data, headers, ..FakeS3Object::default() });
+    }
+
+    pub(crate) fn delete_object(&self, bucket: &str, key: &str) {
+        self.server.lock().delete(bucket, key);
+    }
+
+    /// Complete the restore requested by RestoreObject.
+    pub(crate) fn complete_restore(&self, bucket: &str, key: &str) {
+        if let Some(object) = self.server.lock().objects.get_mut(&(bucket.to_string(), key.to_string())) {
+            object.headers.insert(
+                "x-amz-restore".to_string(),
+                "ongoing-request=\"false\", expiry-date=\"Fri, 21 Dec 2012 00:00:00 GMT\"".to_string());
+        }
+    }
+
+    pub(crate) fn get_object(&self, bucket: &str, key: &str) -> Option<FakeS3Object> {
+        self.server.lock().objects.get(&(bucket.to_string(), key.to_string())).cloned()
+    }
+
+    pub(crate) fn requests(&self) -> Vec<FakeS3Request> {
+        self.server.lock().requests.clone()
+    }
+
+    /// Forget the recorded requests(e.x. the lookup requests which the builder sends).
+    pub(crate) fn clear_requests(&self) {
+        self.server.lock().requests.clear();
+    }
+}
+
+fn xml(body: String) -> Response {
+    (StatusCode::OK, [("content-type", "application/xml")], body).into_response()
+}
+
+fn error(status: StatusCode, code: &str) -> Response {
+    let body = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?><Error><Code>{}</Code><Message>{}</Message></Error>", code, code);
+    (status, [("content-type", "application/xml")], body).into_response()
+}
+
+fn etag(data: &[u8]) -> String {
+    format!("\"{:x}\"", data.iter().fold(data.len() as u64, |acc, b| acc.wrapping_mul(31).wrapping_add(*b as u64)))
+}
+
+// Keep the headers which describe the object(content type, metadata, SSE and so on).
+fn object_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .filter(|(name, _)| {
+            let name = name.as_str();
+            (name.starts_with("x-amz-") && !name.starts_with("x-amz-copy-source") &&
+                !matches!(name, "x-amz-date" | "x-amz-content-sha256" | "x-amz-user-agent" | "x-amz-security-token")) ||
+                matches!(name, "content-type" | "content-disposition" | "cache-control" | "content-encoding" | "content-language")
+        })
+        .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.to_string(), value.to_string())))
+        .collect()
+}
+
+// Build the response of GetObjectTagging from the tags in the query string format.
+fn tagging_xml(tagging: &str) -> String {
+    let tags = tagging
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            format!("<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+                    percent_decode_str(key).decode_utf8_lossy(),
+                    percent_decode_str(value).decode_utf8_lossy())
+        })
+        .collect::<String>();
+
+    format!("<Tagging><TagSet>{}</TagSet></Tagging>", tags)
+}
+
+fn parse_range(range: &str, size: usize) -> Option<(usize, usize)> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    let start = start.parse::<usize>().ok()?;
+    let end = end.parse::<usize>().ok().unwrap_or(size.saturating_sub(1)).min(size.saturating_sub(1));
+
+    Some((start, end))
+}
+
+async fn handle_request(State(state): State<SharedState>,
+                        method: Method,
+                        uri: Uri,
+                        headers: HeaderMap,
+                        body: Bytes) -> Response {
+    let path = percent_decode_str(uri.path()).decode_utf8_lossy().to_string();
+    let query = uri.query()
+        .map(|query| query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode_str(name).decode_utf8_lossy().to_string(),
+                 percent_decode_str(value).decode_utf8_lossy().to_string())
+            })
+            .collect::<HashMap<_, _>>())
+        .unwrap_or_default();
+
+    let mut state = lock(&state);
+    state.requests.push(FakeS3Request {
+        method: method.clone(),
+        path: path.clone(),
+        query: query.clone(),
+        headers: headers.clone(),
+    });
+
+    let (bucket, key) = match path.trim_start_matches('/').split_once('/') {
+        Some((bucket, key)) => (bucket.to_string(), key.to_string()),
+        None => (path.trim_start_matches('/').to_string(), String::new()),
+    };
+
+    if key.is_empty() && method == Method::GET && query.contains_key("versions") {
+        return list_object_versions(&state, &bucket, query.get("prefix").map(String::as_str).unwrap_or_default());
+    }
+    if key.is_empty() && method == Method::GET {
+        return list_objects(&state, &bucket, query.get("prefix").map(String::as_str).unwrap_or_default());
+    }
+
+    match method {
+        Method::GET | Method::HEAD => {
+            let object = match state.find(&bucket, &key, query.get("versionId").map(String::as_str)) {
+                Some(Some(object)) => object,
+                Some(None) => return error(StatusCode::METHOD_NOT_ALLOWED, "MethodNotAllowed"),
+                None => return error(StatusCode::NOT_FOUND, "NoSuchKey"),
+            };
+            if method == Method::GET && query.contains_key("tagging") {
+                return xml(tagging_xml(object.headers.get("x-amz-tagging").map(String::as_str).unwrap_or_default()));
+            }
+            if method == Method::GET && object.is_archived() {
+                return error(StatusCode::FORBIDDEN, "InvalidObjectState");
+            }
+            let (status, data, content_range) = match headers.get("range").and_then(|range| range.to_str().ok()) {
+                Some(range) => match parse_range(range, object.data.len()) {
+                    Some((start, end)) => (
+                        StatusCode::PARTIAL_CONTENT,
+                        object.data[start..=end].to_vec(),
+                        Some(format!("bytes {}-{}/{}", start, end, object.data.len()))),
+                    None => return error(StatusCode::RANGE_NOT_SATISFIABLE, "InvalidRange"),
+                },
+                None => (StatusCode::OK, object.data.clone(), None),
+            };
+
+            let mut response = Response::builder()
+                .status(status)
+                .header("etag", etag(&object.data))
+                .header("x-amz-version-id", &object.version_id)
+                .header("content-length", data.len())
+                .header("last-modified", "Wed, 01 Jan 2025 00:00:00 GMT");
+            if let Some(content_range) = content_range {
+                response = response.header("content-range", content_range);
+            }
+            for (name, value) in &object.headers {
+                response = response.header(name, value);
+            }
+            let body = if method == Method::HEAD { vec![] } else { data };
+
+            response.body(body.into()).unwrap()
+        },
+        Method::PUT => {
+            let copy_source = headers
+                .get("x-amz-copy-source")
+                .and_then(|source| source.to_str().ok())
+                .map(|source| {
+                    let (source, version_id) = match source.split_once("?versionId=") {
+                        Some((source, version_id)) => (source, Some(version_id.to_string())),
+                        None => (source, None),
+                    };
+                    let source = percent_decode_str(source.trim_start_matches('/')).decode_utf8_lossy().to_string();
+                    let (source_bucket, source_key) = source.split_once('/').unwrap_or((&source, ""));
+
+                    (source_bucket.to_string(), source_key.to_string(), version_id)
+                });
+            let source_object = match &copy_source {
+                Some((source_bucket, source_key, version_id)) => {
+                    match state.find(source_bucket, source_key, version_id.as_deref()) {
+                        Some(Some(object)) => Some(object.clone()),
+                        _ => return error(StatusCode::NOT_FOUND, "NoSuchKey"),
+                    }
+                },
+                None => None,
+            };
+            let source_data = source_object.as_ref().map(|object| object.data.clone());
+
+            if let (Some(upload_id), Some(part_number)) = (query.get("uploadId"), query.get("partNumber")) {
+                let data = match source_data {
+                    Some(source_data) => match headers.get("x-amz-copy-source-range").and_then(|range| range.to_str().ok()) {
+                        Some(range) => match parse_range(range, source_data.len()) {
+                            Some((start, end)) => source_data[start..=end].to_vec(),
+                            None => return error(StatusCode::RANGE_NOT_SATISFIABLE, "InvalidRange"),
+                        },
+                        None => source_data,
+                    },
+                    None => body.to_vec(),
+                };
+                let part_etag = etag(&data);
+                let Some(upload) = state.uploads.get_mut(upload_id) else {
+                    return error(StatusCode::NOT_FOUND, "NoSuchUpload");
+                };
+                upload.parts.insert(part_number.parse().unwrap_or_default(), data);
+
+                return if copy_source.is_some() {
+                    xml(format!("<CopyPartResult><ETag>{}</ETag></CopyPartResult>", part_etag))
+                } else {
+                    (StatusCode::OK, [("etag", part_etag)]).into_response()
+                };
+            }
+
+            let data = source_data.unwrap_or_else(|| body.to_vec());
+            let object_etag = etag(&data);
+            let mut object_headers = object_headers(&headers);
+            if let Some(source) = &source_object {
+                // Like Amazon S3, the copy keeps the source metadata unless it is replaced.
+                if headers.get("x-amz-metadata-directive").is_none_or(|directive| directive != "REPLACE") {
+                    for (name, value) in &source.headers {
+                        if name.starts_with("x-amz-meta-") || name.starts_with("content-") || name == "cache-control" {
+                            object_headers.entry(name.clone()).or_insert_with(|| value.clone());
+                        }
+                    }
+                }
+                if headers.get("x-amz-tagging-directive").is_none_or(|directive| directive != "REPLACE") {
+                    if let Some(tagging) = source.headers.get("x-amz-tagging") {
+                        object_headers.entry("x-amz-tagging".to_string()).or_insert_with(|| tagging.clone());
+                    }
+                }
+            }
+            let version_id = state.store(&bucket, &key, FakeS3Object { data, headers: object_headers, ..FakeS3Object::default() });
+
+            if copy_source.is_some() {
+                let body = format!("<CopyObjectResult><ETag>{}</ETag></CopyObjectResult>", object_etag);
+                (StatusCode::OK, [("content-type", "application/xml".to_string()), ("x-amz-version-id", version_id)], body).into_response()
+            } else {
+                (StatusCode::OK, [("etag", object_etag), ("x-amz-version-id", version_id)]).into_response()
+            }
+        },
+        Method::POST if query.contains_key("restore") => {
+            let Some(object) = state.objects.get_mut(&(bucket, key)) else {
+                return error(StatusCode::NOT_FOUND, "NoSuchKey");
+            };
+            match object.headers.get("x-amz-restore") {
+                Some(restore) if restore.contains("ongoing-request=\"true\"") => {
+                    error(StatusCode::CONFLICT, "RestoreAlreadyInProgress")
+                },
+                Some(_) => StatusCode::OK.into_response(),
+                None => {
+                    object.headers.insert("x-amz-restore".to_string(), "ongoing-request=\"true\"".to_string());
+                    StatusCode::ACCEPTED.into_response()
+                },
+            }
+        },
+        Method::POST if query.contains_key("uploads") => {
+            state.next_id += 1;
+            let upload_id = format!("upload{:08}", state.next_id);
+            state.uploads.insert(upload_id.clone(), FakeS3Upload {
+                bucket: bucket.clone(),
+                key: key.clone(),
+                headers: object_headers(&headers),
+                parts: BTreeMap::new(),
+            });
+
+            xml(format!(
+                "<InitiateMultipartUploadResult><Bucket>{}</Bucket><Key>{}</Key><UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+                bucket, key, upload_id))
+        },
+        Method::POST if query.contains_key("uploadId") => {
+            let Some(FakeS3Upload { bucket, key, headers: object_headers, parts }) = state.uploads.remove(&query["uploadId"]) else {
+                return error(StatusCode::NOT_FOUND, "NoSuchUpload");
+            };
+            let body = String::from_utf8_lossy(&body);
+            // SAFETY: The pattern is a valid regex.
+            let part_number_regex = Regex::new(r"<PartNumber>(\d+)</PartNumber>").unwrap();
+            let mut data = vec![];
+            for capture in part_number_regex.captures_iter(&body) {
+                let part_number = capture[1].parse::<i32>().unwrap_or_default();
+                match parts.get(&part_number) {
+                    Some(part) => data.extend_from_slice(part),
+                    None => return error(StatusCode::BAD_REQUEST, "InvalidPart"),
+                }
+            }
+            let object_etag = etag(&data);
+            state.store(&bucket, &key, FakeS3Object { data, headers: object_headers, ..FakeS3Object::default() });
+
+            xml(format!(
+                "<CompleteMultipartUploadResult><Bucket>{}</Bucket><Key>{}</Key><ETag>{}</ETag></CompleteMultipartUploadResult>",
+                bucket, key, object_etag))
+        },
+        Method::DELETE if query.contains_key("uploadId") => {
+            match state.uploads.remove(&query["uploadId"]) {
+                Some(_) => StatusCode::NO_CONTENT.into_response(),
+                None => error(StatusCode::NOT_FOUND, "NoSuchUpload"),
+            }
+        },
+        Method::DELETE => {
+            state.delete(&bucket, &key);
+            StatusCode::NO_CONTENT.into_response()
+        },
+        _ => error(StatusCode::NOT_IMPLEMENTED, "NotImplemented"),
+    }
+}
+
+fn list_object_versions(state: &FakeS3State, bucket: &str, prefix: &str) -> Response {
+    let latest_sequences = state.versions
+        .iter()
+        .filter(|version| version.bucket == bucket)
+        .fold(HashMap::new(), |mut latest, version| {
+            latest.insert(version.key.as_str(), version.sequence);
+            latest
+        });
+
+    // The versions of the same key are listed from the newest like Amazon S3.
+    let entries = state.versions
+        .iter()
+        .rev()
+        .filter(|version| version.bucket == bucket && version.key.starts_with(prefix))
+        .map(|version| {
+            let is_latest = latest_sequences.get(version.key.as_str()) == Some(&version.sequence);
+            let last_modified = DateTime::from_secs(1735689600 + version.sequence as i64)
+                .fmt(DateTimeFormat::DateTime)
+                .unwrap_or_default();
+            match &version.object {
+                Some(object) => format!(
+                    "<Version><Key>{}</Key><VersionId>{}</VersionId><IsLatest>{}</IsLatest>\
+                    <LastModified>{}</LastModified><ETag>{}</ETag><Size>{}</Size><StorageClass>STANDARD</StorageClass></Version>",
+                    version.key, version.version_id, is_latest, last_modified, etag(&object.data), object.data.len()),
+                None => format!(
+                    "<DeleteMarker><Key>{}</Key><VersionId>{}</VersionId><IsLatest>{}</IsLatest>\
+                    <LastModified>{}</LastModified></DeleteMarker>",
+                    version.key, version.version_id, is_latest, last_modified),
+            }
+        })
+        .collect::<String>();
+
+    xml(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListVersionsResult><Name>{}</Name><Prefix>{}</Prefix>\
+        <MaxKeys>1000</MaxKeys><IsTruncated>false</IsTruncated>{}</ListVersionsResult>",
+        bucket, prefix, entries))
+}
+
+fn list_objects(state: &FakeS3State, bucket: &str, prefix: &str) -> Response {
+    let mut objects = state.objects
+        .iter()
+        .filter(|((object_bucket, key), _)| object_bucket == bucket && key.starts_with(prefix))
+        .collect::<Vec<_>>();
+    objects.sort_by(|((_, a), _), ((_, b), _)| a.cmp(b));
+
+    let contents = objects
+        .iter()
+        .map(|((_, key), object)| {
+            let storage_class = object.headers.get("x-amz-storage-class").map(String::as_str).unwrap_or("STANDARD");
+            let restore_status = match object.headers.get("x-amz-restore") {
+                Some(restore) if restore.contains("ongoing-request=\"true\"") => {
+                    "<RestoreStatus><IsRestoreInProgress>true</IsRestoreInProgress></RestoreStatus>"
+                },
+                Some(_) => {
+                    "<RestoreStatus><IsRestoreInProgress>false</IsRestoreInProgress>\
+                    <RestoreExpiryDate>2012-12-21T00:00:00.000Z</RestoreExpiryDate></RestoreStatus>"
+                },
+                None => "",
+            };
+            format!("<Contents><Key>{}</Key><Size>{}</Size><StorageClass>{}</StorageClass>{}</Contents>",
+                    key, object.data.len(), storage_class, restore_status)
+        })
+        .collect::<String>();
+
+    xml(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><ListBucketResult><Name>{}</Name><Prefix>{}</Prefix>\
+        <KeyCount>{}</KeyCount><MaxKeys>1000</MaxKeys><IsTruncated>false</IsTruncated>{}</ListBucketResult>",
+        bucket, prefix, objects.len(), contents))
+}
@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use crate::utils::credential::webdav_credential::WebDavCredential;
+use crate::utils::test_support::{lock, ranged_response, FakeServer};
+
+const USER: &str = "alice";
+const PASSWORD: &str = "secret";
+// The uploads collection of Nextcloud chunked upload v2.
+const UPLOADS_COLLECTION: &str = "remote.php/dav/uploads/alice";
+
+#[derive(Default)]
+struct FakeWebDavState {
+    // path -> data. The paths have no leading and trailing `/`.
+    files: BTreeMap<String, Vec<u8>>,
+    // The root collection is the empty path.
+    collections: BTreeSet<String>,
+    chunk_puts: usize,
+    file_puts: usize,
+}
+
+impl FakeWebDavState {
+    fn add_collections(&mut self, path: &str) {
+        let mut current = String::new();
+        self.collections.insert(current.clone());
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(segment);
+            self.collections.insert(current.clone());
+        }
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.contains_key(path) || self.collections.contains(path)
+    }
+
+    fn members(&self, path: &str) -> Vec<String> {
+        let is_member = |member: &&String| {
+            let rest = if path.is_empty() {
+                Some(member.as_str())
+            } else {
+                member.strip_prefix(&format!("{}/", path))
+            };
+            rest.is_some_and(|rest| !rest.is_empty() && !rest.contains('/'))
+        };
+        self.files.keys().chain(self.collections.iter()).filter(is_member).cloned().collect()
+    }
+
+    /// Remove the file or the collection with its members.
+    fn remove(&mut self, path: &str) -> bool {
+        let prefix = format!("{}/", path);
+        let removed = self.files.remove(path).is_some() | self.collections.remove(path);
+        self.files.retain(|member, _| !member.starts_with(&prefix));
+        self.collections.retain(|member| !member.starts_with(&prefix));
+        removed
+    }
+}
+
+type SharedState = Arc<Mutex<FakeWebDavState>>;
+
+/// A small local fake of the WebDAV server for the tests.
+///
+/// It accepts the basic authentication of `alice` and has the uploads collection of
+/// Nextcloud chunked upload v2, whose chunks are assembled by MOVE of `.file`.
+pub(crate) struct FakeWebDav {
+    server: FakeServer<FakeWebDavState>,
+}
+
+impl FakeWebDav {
+    pub(crate) async fn start() -> Self {
+        // WebDAV has its own methods, so all requests are dispatched by the method in one handler.
+        let router = Router::new()
+            .fallback(handle)
+            .layer(DefaultBodyLimit::disable());
+        let state = |_: &str| {
+            let mut state = FakeWebDavState::default();
+            state.add_collections(UPLOADS_COLLECTION);
+            state
+        };
+
+        Self {
+            server: FakeServer::start(state, router).await.unwrap(),
+        }
+    }
+
+    /// `host:port` which is put in `dav://` paths.
+    pub(crate) fn host(&self) -> &str {
+        self.server.root_url().trim_start_matches("http://")
+    }
+
+    pub(crate) fn credential(&self) -> WebDavCredential {
+        WebDavCredential::from_basic(USER, PASSWORD)
+    }
+
+    pub(crate) fn add_file(&self, path: &str, data: Vec<u8>) {
+        let mut state = self.server.lock();
+        if let Some((parent, _)) = path.rsplit_once('/') {
+            state.add_collections(parent);
+        }
+        state.files.insert(path.to_string(), data);
+    }
+
+    pub(crate) fn get_file(&self, path: &str) -> Option<Vec<u8>> {
+        self.server.lock().files.get(path).cloned()
+    }
+
+    pub(crate) fn is_empty_collection(&self, path: &str) -> bool {
+        let state = self.server.lock();
+        state.collections.contains(path) && state.members(path).is_empty()
+    }
+
+    pub(crate) fn chunk_puts(&self) -> usize {
+        self.server.lock().chunk_puts
+    }
+
+    pub(crate) fn file_puts(&self) -> usize {
+        self.server.lock().file_puts
+    }
+}
+
+/// Convert the path of the URL(or the absolute URL of `Destination`) to the path of the state.
+fn decode_path(url: &str) -> String {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|index| &rest[index..]).unwrap_or(""),
+        None => url,
+    };
+    percent_decode_str(path).decode_utf8_lossy().trim_matches('/').to_string()
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map(|(parent, _)| parent).unwrap_or("")
+}
+
+fn propfind_response(state: &FakeWebDavState, path: &str) -> String {
+    let href = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| format!("/{}", utf8_percent_encode(segment, NON_ALPHANUMERIC)))
+        .collect::<String>();
+    let prop = match state.files.get(path) {
+        Some(data) => format!("<d:resourcetype/><d:getcontentlength>{}</d:getcontentlength>", data.len()),
+        None => "<d:resourcetype><d:collection/></d:resourcetype>".to_string(),
+    };
+    let href = if state.collections.contains(path) {
+        format!("{}/", href)
+    } else {
+        href
+    };
+
+    format!("<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop>\
+             <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>", href, prop)
+}
+
+async fn handle(State(state): State<SharedState>,
+                method: Method,
+                uri: Uri,
+                headers: HeaderMap,
+                body: Bytes) -> Response {
+    let authorization = format!("Basic {}", STANDARD.encode(format!("{}:{}", USER, PASSWORD)));
+    if headers.get(AUTHORIZATION).and_then(|value| value.to_str().ok()) != Some(authorization.as_str()) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    let path = decode_path(uri.path());
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(|value| value.to_string());
+    let mut state = lock(&state);
+
+    match method.as_str() {
+        "PROPFIND" => {
+            if !state.exists(&path) {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+            let mut responses = propfind_response(&state, &path);
+            if header("Depth").as_deref() == Some("1") && state.collections.contains(&path) {
+                for member in state.members(&path) {
+                    responses.push_str(&propfind_response(&state, &member));
+                }
+            }
+            let xml = format!(r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:">{}</d:multistatus>"#, responses);
+            (StatusCode::MULTI_STATUS, [(CONTENT_TYPE, "application/xml; charset=utf-8")], xml).into_response()
+        },
+        "GET" => {
+            let Some(data) = state.files.get(&path) else {
+                return StatusCode::NOT_FOUND.into_response();
+            };
+            ranged_response(&headers, "range", data)
+        },
+        "PUT" => {
+            if !state.collections.contains(parent(&path)) {
+                return StatusCode::CONFLICT.into_response();
+            }
+            if parent(&path).starts_with(UPLOADS_COLLECTION) {
+                // Nextcloud requires the destination on every chunk.
+                if header("Destination").is_none() {
+                    return StatusCode::BAD_REQUEST.into_response();
+                }
+                state.chunk_puts += 1;
+            } else {
+                state.file_puts += 1;
+            }
+            state.files.insert(path, body.to_vec());
+            StatusCode::CREATED.into_response()
+        },
+        "MKCOL" => {
+            if state.exists(&path) {
+                return StatusCode::METHOD_NOT_ALLOWED.into_response();
+            }
+            if !state.collections.contains(parent(&path)) {
+                return StatusCode::CONFLICT.into_response();
+            }
+            state.collections.insert(path);
+            StatusCode::CREATED.into_response()
+        },
+        "DELETE" => {
+            if state.remove(&path) {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                StatusCode::NOT_FOUND.into_response()
+            }
+        },
+        "MOVE" => {
+            let Some(destination) = header("Destination").map(|destination| decode_path(&destination)) else {
+                return StatusCode::BAD_REQUEST.into_response();
+            };
+            if !state.collections.contains(parent(&destination)) {
+                return StatusCode::CONFLICT.into_response();
+            }
+            let overwritten = state.exists(&destination);
+            if overwritten && header("Overwrite").as_deref() == Some("F") {
+                return StatusCode::PRECONDITION_FAILED.into_response();
+            }
+
+            // `.file` of the transfer collection assembles the chunks in the name order.
+            if let Some(transfer) = path.strip_suffix("/.file").filter(|transfer| state.collections.contains(*transfer)) {
+                let transfer = transfer.to_string();
+                let data = state
+                    .members(&transfer)
+                    .iter()
+                    .filter_map(|chunk| state.files.get(chunk))
+                    .flatten()
+                    .copied()
+                    .collect::<Vec<_>>();
+                state.remove(&transfer);
+                state.remove(&destination);
+                state.files.insert(destination, data);
+            } else if let Some(data) = state.files.remove(&path) {
+                state.remove(&destination);
+                state.files.insert(destination, data);
+            } else if state.collections.contains(&path) {
+                let prefix = format!("{}/", path);
+                let moved_files = state.files
+                    .iter()
+                    .filter(|(member, _)| member.starts_with(&prefix))
+                    .map(|(member, data)| (format!("{}/{}", destination, &member[prefix.len()..]), data.clone()))
+                    .collect::<Vec<_>>();
+                let moved_collections = state.collections
+                    .iter()
+                    .filter(|member| member.starts_with(&prefix))
+                    .map(|member| format!("{}/{}", destination, &member[prefix.len()..]))
+                    .collect::<Vec<_>>();
+                state.remove(&path);
+                state.remove(&destination);
+                state.collections.insert(destination);
+                state.collections.extend(moved_collections);
+                state.files.extend(moved_files);
+            } else {
+                return StatusCode::NOT_FOUND.into_response();
+            }
+
+            if overwritten {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                StatusCode::CREATED.into_response()
+            }
+        },
+        _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+    }
+}
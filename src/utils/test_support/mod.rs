@@ -0,0 +1,188 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use axum::http::{HeaderMap, StatusCode};
+use axum::http::header::CONTENT_RANGE;
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+#[cfg(test)]
+use tokio::sync::mpsc::{Receiver, Sender};
+#[cfg(test)]
+use crate::errors::HikyakuError;
+#[cfg(test)]
+use crate::services::file_system::ChunkData;
+#[cfg(test)]
+use crate::services::storage_backend::StorageBackend;
+
+#[cfg(feature = "google_drive")]
+pub mod fake_google_drive;
+#[cfg(all(test, feature = "amazon_s3"))]
+pub(crate) mod fake_s3;
+#[cfg(all(test, feature = "dropbox"))]
+pub(crate) mod fake_dropbox;
+#[cfg(all(test, feature = "box_storage"))]
+pub(crate) mod fake_box;
+#[cfg(all(test, feature = "onedrive"))]
+pub(crate) mod fake_onedrive;
+#[cfg(all(test, feature = "azure_blob"))]
+pub(crate) mod fake_azure_blob;
+#[cfg(all(test, feature = "google_cloud_storage"))]
+pub(crate) mod fake_gcs;
+#[cfg(all(test, feature = "webdav"))]
+pub(crate) mod fake_webdav;
+#[cfg(all(test, feature = "http"))]
+pub(crate) mod fake_http;
+
+/// The local server which the fakes of the storages share.
+///
+/// It serves the routes of the fake on `127.0.0.1` with a random port, holds the state of the fake
+/// which the handlers get by `State`, and stops when it is dropped.
+pub(crate) struct FakeServer<S> {
+    state: Arc<Mutex<S>>,
+    root_url: String,
+    handle: JoinHandle<()>,
+}
+
+impl<S: Send + 'static> FakeServer<S> {
+    /// Starts the server of the routes.
+    ///
+    /// The state is made from the root URL(e.x. `http://127.0.0.1:8080`), because some responses
+    /// (e.x. the upload sessions) point at the server itself.
+    pub(crate) async fn start(state: impl FnOnce(&str) -> S, router: Router<Arc<Mutex<S>>>) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let root_url = format!("http://{}", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(state(&root_url)));
+
+        let router = router.with_state(Arc::clone(&state));
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                log::error!("Fake server stopped: {:?}", e);
+            }
+        });
+
+        Ok(Self {
+            state,
+            root_url,
+            handle,
+        })
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, S> {
+        lock(&self.state)
+    }
+
+    /// `http://host:port` of the server.
+    pub(crate) fn root_url(&self) -> &str {
+        &self.root_url
+    }
+}
+
+impl<S> Drop for FakeServer<S> {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Lock the state of the fake. The state is still used after a handler panics, so the tests report the panic.
+pub(crate) fn lock<S>(state: &Mutex<S>) -> MutexGuard<'_, S> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Respond the data, or the range of it which the header(e.x. `Range` or `x-ms-range`) requests
+/// by `bytes={start}-{end}` or `bytes={start}-`.
+///
+/// The range which ends after the data is cut at the end, and the range which starts after the data
+/// is `416 Range Not Satisfiable`, like the storages do.
+pub(crate) fn ranged_response(headers: &HeaderMap, range_header: &str, data: &[u8]) -> Response {
+    let range = headers
+        .get(range_header)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| range.split_once('-'))
+        .and_then(|(start, end)| Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok())));
+
+    match range {
+        Some((start, _)) if start >= data.len() => {
+            (StatusCode::RANGE_NOT_SATISFIABLE, [(CONTENT_RANGE, format!("bytes */{}", data.len()))]).into_response()
+        },
+        Some((start, end)) => {
+            let end = end.unwrap_or(data.len() - 1).min(data.len() - 1);
+            let content_range = format!("bytes {}-{}/{}", start, end, data.len());
+            (StatusCode::PARTIAL_CONTENT, [(CONTENT_RANGE, content_range)], data[start..=end].to_vec()).into_response()
+        },
+        None => (StatusCode::OK, data.to_vec()).into_response(),
+    }
+}
+
+/// Parse `Content-Range: bytes {start}-{end}/{total or *}` of the chunk of the upload session.
+///
+/// # Returns
+///
+/// `Option<(usize, usize, Option<usize>)>` - The first and the last byte of the chunk, and the total size
+/// if it is known.
+pub(crate) fn chunk_range(headers: &HeaderMap) -> Option<(usize, usize, Option<usize>)> {
+    let (range, total) = headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+
+    Some((start.parse().ok()?, end.parse().ok()?, total.parse().ok()))
+}
+
+/// Get the total size of the resumable upload from the `Content-Range` of the empty last chunk(e.x. `bytes */0`).
+pub(crate) fn empty_chunk_total(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes */")?
+        .parse()
+        .ok()
+}
+
+/// Collect the downloaded chunks in the offset order. The last chunk must be received.
+#[cfg(test)]
+pub(crate) async fn collect(mut receiver: Receiver<ChunkData>) -> Vec<u8> {
+    let mut chunks = vec![];
+    while let Some(chunk_data) = receiver.recv().await {
+        chunks.push(chunk_data);
+    }
+    chunks.sort_by_key(|chunk_data| chunk_data.get_offset());
+    assert!(chunks.last().unwrap().is_last());
+    chunks.into_iter().flat_map(|chunk_data| chunk_data.get_raw_data()).collect()
+}
+
+/// Send the data as the chunks of the size, like the download does.
+#[cfg(test)]
+pub(crate) async fn send_data(sender: Sender<ChunkData>, data: &[u8], chunk_size: usize) {
+    let total_chunks = data.len().div_ceil(chunk_size).max(1);
+    for offset in 0..total_chunks {
+        let chunk = data.get(offset * chunk_size..data.len().min((offset + 1) * chunk_size)).unwrap_or_default();
+        let chunk_data = ChunkData::new(chunk.to_vec(), offset as u64, offset + 1 == total_chunks);
+        sender.send(chunk_data).await.unwrap();
+    }
+}
+
+/// The file of 100 bytes which [assert_read_range] reads.
+#[cfg(test)]
+pub(crate) fn range_data() -> Vec<u8> {
+    (0..100u8).collect()
+}
+
+/// Read the ranges of the file of [range_data] through the backend.
+///
+/// The ranges inside the file are read as they are. The range which ends after the file and the range
+/// which starts after the file fail with the error of the backend, because the result is shorter than requested.
+#[cfg(test)]
+pub(crate) async fn assert_read_range(backend: Arc<dyn StorageBackend>, is_backend_error: impl Fn(&HikyakuError) -> bool) {
+    let data = range_data();
+    assert_eq!(backend.read_range(2, 5).await.unwrap(), data[2..=5]);
+    assert_eq!(backend.read_range(99, 99).await.unwrap(), data[99..]);
+    for (start, end) in [(95, 120), (200, 210)] {
+        let error = backend.read_range(start, end).await.unwrap_err();
+        assert!(is_backend_error(&error), "{}-{}: {:?}", start, end, error);
+    }
+}
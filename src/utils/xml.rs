@@ -0,0 +1,36 @@
+use xmlparser::{Reference, Stream};
+
+/// Decode the references(e.x. `&amp;`, `&#x26;`) in the text of the element.
+///
+/// The unknown entity references are kept as they are, because no DTD is read.
+pub(crate) fn unescape_text(text: &str) -> String {
+    let mut stream = Stream::from(text);
+    let mut unescaped = String::with_capacity(text.len());
+    while !stream.at_end() {
+        let start = stream.pos();
+        match stream.try_consume_reference() {
+            Some(Reference::Char(c)) => unescaped.push(c),
+            Some(Reference::Entity(_)) => unescaped.push_str(&text[start..stream.pos()]),
+            None => {
+                // SAFETY: The stream is not at the end, so the rest has at least one character.
+                let c = text[start..].chars().next().unwrap();
+                unescaped.push(c);
+                stream.advance(c.len_utf8());
+            },
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unescape_text() {
+        assert_eq!(unescape_text("a&amp;b &lt;c&gt; &quot;d&apos;"), "a&b <c> \"d'");
+        assert_eq!(unescape_text("&#x26;amp; &#12354;"), "&amp; あ");
+        assert_eq!(unescape_text("&custom; & plain"), "&custom; & plain");
+    }
+}